@@ -0,0 +1,107 @@
+//! Statistics about the account kept by the site, like forum activity.
+
+use {
+    crate::{
+        client::Session,
+        endpoints::page_url,
+        friends::{cell_text, parse_count},
+    },
+    serde::Serialize,
+    std::error::Error,
+    unhtml::scraper::{Html, Selector},
+};
+
+const ACCOUNT_ENDPOINT: &str = "account";
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct AccountStats {
+    pub posts_made: Option<usize>,
+    pub kudos_earned: Option<usize>,
+    /// How many problems the site counts as solved, to cross-check the progress page with.
+    pub problems_solved: Option<usize>,
+}
+
+impl AccountStats {
+    /// Parses the statistics of the account page, which labels each count, either in a table
+    /// row of its own or like "Posts made: 12".
+    pub fn from_page(page: &str) -> Result<Self, Box<dyn Error>> {
+        let html = Html::parse_document(page);
+        let cell_selector = Selector::parse("td, th").unwrap();
+
+        let mut counts = Vec::new();
+        for row in html.select(&Selector::parse("tr").unwrap()) {
+            let cells = row.select(&cell_selector).collect::<Vec<_>>();
+            if let [label, value, ..] = cells[..] {
+                if let Some(count) = parse_count(&cell_text(value)) {
+                    counts.push((cell_text(label), count));
+                }
+            }
+        }
+        for element in html.select(&Selector::parse("li, p, div, span").unwrap()) {
+            // Elements holding more than one label are left to the elements inside them.
+            let text = cell_text(element);
+            if text.matches(':').count() != 1 {
+                continue;
+            }
+            if let Some((label, value)) = text.split_once(':') {
+                if let Some(count) = parse_count(value) {
+                    counts.push((label.to_owned(), count));
+                }
+            }
+        }
+
+        let find = |keyword: &str| {
+            counts
+                .iter()
+                .find(|(label, _)| label.to_lowercase().contains(keyword))
+                .map(|(_, count)| *count)
+        };
+        let stats = Self {
+            posts_made: find("post"),
+            kudos_earned: find("kudos"),
+            problems_solved: find("solved"),
+        };
+        if stats == Self::default() {
+            return Err(
+                "unable to find any statistics on the account page; make sure the session is \
+                 signed in"
+                    .into(),
+            );
+        }
+        Ok(stats)
+    }
+
+    pub fn fetch(session: &mut Session) -> Result<Self, Box<dyn Error>> {
+        Self::from_page(&session.fetch_page(&page_url(ACCOUNT_ENDPOINT))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_account_statistics() {
+        let page = include_str!("../tests/fixtures/pages/account.html");
+        assert_eq!(
+            AccountStats::from_page(page).unwrap(),
+            AccountStats {
+                posts_made: Some(1042),
+                kudos_earned: Some(317),
+                problems_solved: Some(26),
+            }
+        );
+    }
+
+    #[test]
+    fn parses_account_statistics_missing_some_counts() {
+        assert_eq!(
+            AccountStats::from_page("<ul><li>Kudos: 5</li></ul>").unwrap(),
+            AccountStats {
+                kudos_earned: Some(5),
+                ..AccountStats::default()
+            }
+        );
+        assert!(AccountStats::from_page("<p>Sign in</p>").is_err());
+    }
+}
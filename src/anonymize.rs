@@ -0,0 +1,241 @@
+//! Leaving personal details out of JSON output with `--anonymize`, so that it can be shared
+//! publicly, like with sites collecting statistics.
+//!
+//! These fields of JSON output are personal, wherever they appear, and under `--anonymize`:
+//!
+//! - `username` is removed from the user's own entry (marked `"me": true`), and replaced with a
+//!   pseudonym like `user-1a2b3c4d5e6f7a8b` everywhere else, like for friends;
+//! - `country` is removed;
+//! - `paths` is removed, since local paths can name the account of the computer.
+//!
+//! Pseudonyms are hashes of usernames salted with a random salt kept per profile, so the same
+//! username gets the same pseudonym in every export of a profile, but can't be looked up
+//! without the salt.
+
+use {
+    crate::paths::{profile_data_dir, write_atomically},
+    rand::{thread_rng, RngCore},
+    serde::Serialize,
+    serde_json::Value,
+    sha2::{Digest, Sha256},
+    std::{
+        error::Error,
+        fmt::Write,
+        fs::{create_dir_all, read_to_string},
+        io::ErrorKind,
+        path::PathBuf,
+        sync::Mutex,
+    },
+};
+
+const SALT_FILE_NAME: &str = "anonymize-salt";
+/// How many bytes of salt are generated.
+const SALT_LEN: usize = 16;
+/// How many bytes of a hash make up a pseudonym.
+const PSEUDONYM_LEN: usize = 8;
+
+/// The anonymizer for JSON output, if `--anonymize` was given.
+static ANONYMIZER: Mutex<Option<Anonymizer>> = Mutex::new(None);
+
+/// What's done to a personal field.
+#[derive(Clone, Copy, Debug)]
+enum Treatment {
+    /// Replaced with a pseudonym, except in the user's own entry, where it's removed.
+    Pseudonym,
+    Remove,
+}
+
+/// The personal fields of JSON output, as documented above.
+const PERSONAL_FIELDS: &[(&str, Treatment)] = &[
+    ("username", Treatment::Pseudonym),
+    ("country", Treatment::Remove),
+    ("paths", Treatment::Remove),
+];
+
+fn hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        write!(hex, "{:02x}", byte).unwrap();
+    }
+    hex
+}
+
+#[derive(Debug)]
+pub struct Anonymizer {
+    salt: String,
+}
+
+impl Anonymizer {
+    pub fn salt_path(profile: &str) -> PathBuf {
+        profile_data_dir(profile).join(SALT_FILE_NAME)
+    }
+
+    /// Loads the salt of `profile`, generating it the first time.
+    pub fn load(profile: &str) -> Result<Self, Box<dyn Error>> {
+        let path = Self::salt_path(profile);
+        let salt = match read_to_string(&path) {
+            Ok(salt) => salt.trim().to_owned(),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                let mut salt = [0; SALT_LEN];
+                thread_rng().fill_bytes(&mut salt);
+                let salt = hex(&salt);
+                create_dir_all(profile_data_dir(profile))?;
+                write_atomically(&path, format!("{}\n", salt))
+                    .map_err(|e| format!("unable to write {}: {}", path.display(), e))?;
+                salt
+            }
+            Err(e) => return Err(format!("unable to read {}: {}", path.display(), e).into()),
+        };
+        if salt.is_empty() {
+            return Err(format!(
+                "{} is empty; remove it to generate a new salt",
+                path.display()
+            )
+            .into());
+        }
+        Ok(Self { salt })
+    }
+
+    /// The pseudonym of `username`, which ignores case like the site does.
+    pub fn pseudonym(&self, username: &str) -> String {
+        let digest = Sha256::new()
+            .chain(self.salt.as_bytes())
+            .chain(b"\0")
+            .chain(username.to_lowercase().as_bytes())
+            .finalize();
+        format!("user-{}", hex(&digest[..PSEUDONYM_LEN]))
+    }
+
+    /// Removes or replaces the personal fields of `value` and everything in it.
+    pub fn anonymize(&self, value: &mut Value) {
+        match value {
+            Value::Object(object) => {
+                let is_me = object.get("me") == Some(&Value::Bool(true));
+                for (field, treatment) in PERSONAL_FIELDS {
+                    let pseudonym = match (treatment, object.get(*field)) {
+                        (_, None) => continue,
+                        (Treatment::Pseudonym, Some(Value::String(username))) if !is_me => {
+                            Some(self.pseudonym(username))
+                        }
+                        _ => None,
+                    };
+                    match pseudonym {
+                        Some(pseudonym) => {
+                            object.insert((*field).to_owned(), Value::String(pseudonym));
+                        }
+                        None => {
+                            object.remove(*field);
+                        }
+                    }
+                }
+                object.values_mut().for_each(|value| self.anonymize(value));
+            }
+            Value::Array(values) => values.iter_mut().for_each(|value| self.anonymize(value)),
+            _ => (),
+        }
+    }
+}
+
+/// Anonymizes JSON output from now on, with the salt of `profile`.
+pub fn enable(profile: &str) -> Result<(), Box<dyn Error>> {
+    *ANONYMIZER.lock().unwrap() = Some(Anonymizer::load(profile)?);
+    Ok(())
+}
+
+/// Stops anonymizing JSON output, so that tests start without it.
+#[cfg(test)]
+pub fn disable() {
+    *ANONYMIZER.lock().unwrap() = None;
+}
+
+/// Renders `value` as pretty JSON, anonymized if `--anonymize` was given. Anonymizing sorts the
+/// keys of objects, which are otherwise kept in order.
+pub fn to_string_pretty(value: &impl Serialize) -> serde_json::Result<String> {
+    match &*ANONYMIZER.lock().unwrap() {
+        Some(anonymizer) => {
+            let mut value = serde_json::to_value(value)?;
+            anonymizer.anonymize(&mut value);
+            serde_json::to_string_pretty(&value)
+        }
+        None => serde_json::to_string_pretty(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::test_support::lock_globals, serde_json::json, std::fs::write};
+
+    fn anonymizer(salt: &str) -> Anonymizer {
+        Anonymizer {
+            salt: salt.to_owned(),
+        }
+    }
+
+    #[test]
+    fn makes_pseudonyms_that_depend_on_the_salt() {
+        let anonymizer = anonymizer("pepper");
+        let pseudonym = anonymizer.pseudonym("euler_fan");
+        assert!(pseudonym.starts_with("user-"), "{}", pseudonym);
+        assert_eq!(pseudonym.len(), "user-".len() + PSEUDONYM_LEN * 2);
+        assert_eq!(anonymizer.pseudonym("Euler_Fan"), pseudonym);
+        assert_ne!(anonymizer.pseudonym("euler_foe"), pseudonym);
+        assert_ne!(self::anonymizer("salt").pseudonym("euler_fan"), pseudonym);
+    }
+
+    #[test]
+    fn removes_and_replaces_personal_fields() {
+        let anonymizer = anonymizer("pepper");
+        let mut value = json!({
+            "friends": [
+                { "username": "euler_fan", "me": true, "country": "Canada", "solved": 26 },
+                { "username": "ace", "me": false, "country": "Sweden", "solved": 900 },
+            ],
+            "local": [{ "number": 1, "paths": ["/home/ace/euler/src/bin/p001.rs"] }],
+            "username": 42,
+        });
+        anonymizer.anonymize(&mut value);
+        assert_eq!(
+            value,
+            json!({
+                "friends": [
+                    { "me": true, "solved": 26 },
+                    { "username": anonymizer.pseudonym("ace"), "me": false, "solved": 900 },
+                ],
+                "local": [{ "number": 1 }],
+            })
+        );
+    }
+
+    #[test]
+    fn keeps_the_salt_of_a_profile() {
+        let _globals = lock_globals();
+        let pseudonym = Anonymizer::load("default").unwrap().pseudonym("ace");
+        assert_eq!(
+            Anonymizer::load("default").unwrap().pseudonym("ace"),
+            pseudonym
+        );
+        assert_ne!(
+            Anonymizer::load("work").unwrap().pseudonym("ace"),
+            pseudonym
+        );
+
+        write(Anonymizer::salt_path("default"), "\n").unwrap();
+        let e = Anonymizer::load("default").unwrap_err();
+        assert!(e.to_string().contains("is empty"), "{}", e);
+    }
+
+    #[test]
+    fn anonymizes_output_once_enabled() {
+        let _globals = lock_globals();
+        let value = json!({ "username": "ace", "country": "Sweden" });
+        assert_eq!(
+            to_string_pretty(&value).unwrap(),
+            serde_json::to_string_pretty(&value).unwrap()
+        );
+        enable("default").unwrap();
+        let anonymized = to_string_pretty(&value).unwrap();
+        disable();
+        assert!(!anonymized.contains("ace"), "{}", anonymized);
+        assert!(!anonymized.contains("Sweden"), "{}", anonymized);
+    }
+}
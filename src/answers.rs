@@ -0,0 +1,431 @@
+//! Expected answers to problems, stored locally to verify solutions against. Answers can be
+//! stored in plaintext in `answers.toml`, or as SHA-256 digests in `answers.sha256` so that
+//! plaintext answers never need to touch the disk. Hashed answers take precedence.
+
+use {
+    crate::{
+        clipboard::{Clipboard, SystemClipboard},
+        init::HASHED_ANSWERS_FILE_NAME,
+        workspace::Workspace,
+    },
+    sha2::{Digest, Sha256},
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        error::Error,
+        fmt::Write as _,
+        fs::{read_to_string, write},
+        io::{stdin, ErrorKind, Read},
+        path::{Path, PathBuf},
+    },
+    structopt::StructOpt,
+    toml::Value,
+};
+
+pub const PLAINTEXT_ANSWERS_FILE_NAME: &str = "answers.toml";
+
+/// Normalizes an answer for comparison, so that incidental whitespace (like a trailing newline)
+/// doesn't matter.
+pub fn canonicalize(answer: &str) -> &str {
+    answer.trim()
+}
+
+/// Hashes the canonical form of `answer` into the lowercase hexadecimal form used in
+/// `answers.sha256`.
+pub fn hash(answer: &str) -> String {
+    let digest = Sha256::digest(canonicalize(answer).as_bytes());
+    let mut hex = String::with_capacity(digest.len() * 2);
+    for byte in digest {
+        write!(hex, "{:02x}", byte).unwrap();
+    }
+    hex
+}
+
+#[derive(Debug)]
+pub enum Expected<'a> {
+    Plaintext(&'a str),
+    Hashed(&'a str),
+}
+
+impl Expected<'_> {
+    pub fn matches(&self, actual: &str) -> bool {
+        match self {
+            Self::Plaintext(expected) => *expected == canonicalize(actual),
+            Self::Hashed(expected) => *expected == hash(actual),
+        }
+    }
+}
+
+fn read_optional(path: &Path) -> Result<Option<String>, Box<dyn Error>> {
+    match read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("unable to read {}: {}", path.display(), e).into()),
+    }
+}
+
+fn parse_plaintext(path: &Path, contents: &str) -> Result<BTreeMap<usize, String>, Box<dyn Error>> {
+    let table = contents
+        .parse::<Value>()
+        .map_err(|e| format!("unable to parse {}: {}", path.display(), e))?;
+    let table = table
+        .as_table()
+        .ok_or_else(|| format!("expected a table in {}", path.display()))?;
+
+    let mut plaintext = BTreeMap::new();
+    for (key, value) in table {
+        let number = key.parse().map_err(|_| {
+            format!(
+                "expected a problem number as a key in {}, got {:?}",
+                path.display(),
+                key
+            )
+        })?;
+        // Answers are compared as strings so integers of any width work the same.
+        let answer = match value {
+            Value::String(s) => s.clone(),
+            Value::Integer(i) => i.to_string(),
+            Value::Float(f) => f.to_string(),
+            other => {
+                return Err(format!(
+                    "unsupported answer to problem {} in {}: {}",
+                    number,
+                    path.display(),
+                    other
+                )
+                .into())
+            }
+        };
+        plaintext.insert(number, canonicalize(&answer).to_owned());
+    }
+    Ok(plaintext)
+}
+
+/// Parses `answers.sha256`, where every line that isn't blank or a `#` comment is a problem
+/// number followed by the hexadecimal SHA-256 digest of its canonicalized answer.
+fn parse_hashed(path: &Path, contents: &str) -> Result<BTreeMap<usize, String>, Box<dyn Error>> {
+    let mut hashed = BTreeMap::new();
+    for (idx, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = || format!("invalid entry on line {} of {}", idx + 1, path.display());
+        let (number, digest) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+        let number = number.parse().map_err(|_| invalid())?;
+        let digest = digest.trim().to_ascii_lowercase();
+        if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid().into());
+        }
+        hashed.insert(number, digest);
+    }
+    Ok(hashed)
+}
+
+#[derive(Debug, Default)]
+pub struct Answers {
+    plaintext: BTreeMap<usize, String>,
+    hashed: BTreeMap<usize, String>,
+}
+
+impl Answers {
+    /// Loads the answers stored in `workspace`, which are empty if no answers files exist.
+    /// Plaintext answers are skipped entirely if `hashed_only` is set.
+    pub fn load(workspace: &Workspace, hashed_only: bool) -> Result<Self, Box<dyn Error>> {
+        let plaintext_path = workspace.root.join(PLAINTEXT_ANSWERS_FILE_NAME);
+        let plaintext = match hashed_only {
+            true => None,
+            false => read_optional(&plaintext_path)?,
+        };
+        let hashed_path = workspace.root.join(HASHED_ANSWERS_FILE_NAME);
+        Ok(Self {
+            plaintext: match plaintext {
+                Some(contents) => parse_plaintext(&plaintext_path, &contents)?,
+                None => BTreeMap::new(),
+            },
+            hashed: match read_optional(&hashed_path)? {
+                Some(contents) => parse_hashed(&hashed_path, &contents)?,
+                None => BTreeMap::new(),
+            },
+        })
+    }
+
+    pub fn get(&self, number: usize) -> Option<Expected<'_>> {
+        self.hashed
+            .get(&number)
+            .map(|digest| Expected::Hashed(digest))
+            .or_else(|| {
+                self.plaintext
+                    .get(&number)
+                    .map(|answer| Expected::Plaintext(answer))
+            })
+    }
+
+    /// The numbers of the problems with stored answers, in ascending order.
+    pub fn numbers(&self) -> impl Iterator<Item = usize> + '_ {
+        self.hashed
+            .keys()
+            .chain(self.plaintext.keys())
+            .copied()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+    }
+
+    /// The plaintext answer to `number`, if one is stored.
+    pub fn plaintext(&self, number: usize) -> Option<&str> {
+        self.plaintext.get(&number).map(String::as_str)
+    }
+}
+
+/// Inserts or replaces the entry for `number` in the contents of an `answers.sha256` file,
+/// preserving every other line.
+fn upsert_hashed(contents: &str, number: usize, digest: &str) -> String {
+    let entry = format!("{} {}", number, digest);
+    let mut replaced = false;
+    let mut updated = String::with_capacity(contents.len() + entry.len() + 1);
+    for line in contents.lines() {
+        let is_entry = line
+            .split_whitespace()
+            .next()
+            .and_then(|n| n.parse::<usize>().ok())
+            == Some(number);
+        if is_entry && !line.trim_start().starts_with('#') {
+            if !replaced {
+                updated.push_str(&entry);
+                updated.push('\n');
+                replaced = true;
+            }
+        } else {
+            updated.push_str(line);
+            updated.push('\n');
+        }
+    }
+    if !replaced {
+        updated.push_str(&entry);
+        updated.push('\n');
+    }
+    updated
+}
+
+/// Stores the hash of `answer` to `number` in the `answers.sha256` of `workspace`, replacing any
+/// existing entry, and returns where.
+pub fn store_hashed(
+    workspace: &Workspace,
+    number: usize,
+    answer: &str,
+) -> Result<PathBuf, Box<dyn Error>> {
+    if canonicalize(answer).is_empty() {
+        return Err("refusing to store an empty answer".into());
+    }
+    let path = workspace.root.join(HASHED_ANSWERS_FILE_NAME);
+    let existing = read_optional(&path)?.unwrap_or_default();
+    write(&path, upsert_hashed(&existing, number, &hash(answer)))
+        .map_err(|e| format!("unable to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Manage stored answers.
+#[derive(Debug, StructOpt)]
+pub enum AnswersCommand {
+    /// Store the hash of an answer in `answers.sha256`, replacing any existing entry.
+    Hash {
+        /// The problem the answer is for.
+        number: usize,
+        /// The answer to hash. Read from standard input if omitted.
+        answer: Option<String>,
+    },
+    /// Copy the plaintext answer stored in `answers.toml` to the clipboard.
+    Copy {
+        /// The problem the answer is for.
+        number: usize,
+    },
+}
+
+impl AnswersCommand {
+    pub fn run(self) -> Result<(), Box<dyn Error>> {
+        match self {
+            Self::Hash { number, answer } => {
+                let answer = match answer {
+                    Some(answer) => answer,
+                    None => {
+                        let mut answer = String::new();
+                        stdin().read_to_string(&mut answer)?;
+                        answer
+                    }
+                };
+                let workspace = Workspace::discover()?;
+                let path = store_hashed(&workspace, number, &answer)?;
+                println!(
+                    "Stored the hashed answer to problem {} in {}",
+                    number,
+                    workspace.display_path(&path)
+                );
+                Ok(())
+            }
+            Self::Copy { number } => {
+                let workspace = Workspace::discover()?;
+                let answers = Answers::load(&workspace, false)?;
+                copy_answer(&answers, number, &mut SystemClipboard)?;
+                println!("Copied the answer to problem {}", number);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Copies the plaintext answer to problem `number` to `clipboard`, failing if only its hash is
+/// stored.
+fn copy_answer(
+    answers: &Answers,
+    number: usize,
+    clipboard: &mut dyn Clipboard,
+) -> Result<(), Box<dyn Error>> {
+    let answer = match (answers.plaintext(number), answers.get(number)) {
+        (Some(answer), _) => answer,
+        (None, Some(Expected::Hashed(_))) => {
+            return Err(format!(
+                "only the hash of the answer to problem {} is stored, so it can't be copied",
+                number
+            )
+            .into())
+        }
+        (None, _) => return Err(format!("no answer to problem {} is stored", number).into()),
+    };
+    clipboard
+        .set_text(answer)
+        .map_err(|e| format!("unable to copy to the clipboard: {}", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            test_support::{FakeClipboard, TempDir},
+            workspace::Config,
+        },
+    };
+
+    const DIGEST_1: &str = "c0b20f4665d0388d564f0b6ecf3edc9f9480cb15fff87198b95701d9f5fe1f7b";
+    const DIGEST_2: &str = "1f5882e19314ac13acca52ad5503184b3cb1fd8dbeea82e0979d799af2361704";
+
+    #[test]
+    fn ignores_incidental_whitespace() {
+        for output in &["233168", "233168\n", "233168\r\n", "  233168\t\n\n"] {
+            assert_eq!(canonicalize(output), "233168");
+            assert_eq!(hash(output), DIGEST_1);
+            assert!(Expected::Hashed(DIGEST_1).matches(output));
+            assert!(Expected::Plaintext("233168").matches(output));
+        }
+    }
+
+    #[test]
+    fn keeps_whitespace_within_answers() {
+        assert_eq!(canonicalize(" 1 2 \n"), "1 2");
+        assert!(!Expected::Plaintext("12").matches("1 2"));
+        assert!(!Expected::Hashed(DIGEST_1).matches("2331680"));
+    }
+
+    #[test]
+    fn parses_hashed_answers() {
+        let contents = format!(
+            "# Answers\n\n1 {}\n2\t{}\n",
+            DIGEST_1,
+            DIGEST_2.to_uppercase()
+        );
+        let hashed = parse_hashed(Path::new("answers.sha256"), &contents).unwrap();
+        assert_eq!(hashed.get(&1).map(String::as_str), Some(DIGEST_1));
+        assert_eq!(hashed.get(&2).map(String::as_str), Some(DIGEST_2));
+
+        for invalid in &["1", "one abc", "1 abc", &format!("1 {}0", DIGEST_1)] {
+            let e = parse_hashed(Path::new("answers.sha256"), invalid).unwrap_err();
+            assert_eq!(e.to_string(), "invalid entry on line 1 of answers.sha256");
+        }
+    }
+
+    #[test]
+    fn compares_plaintext_answers_as_strings() {
+        let plaintext =
+            parse_plaintext(Path::new("answers.toml"), "1 = 233168\n2 = \" 4613732 \"\n").unwrap();
+        assert_eq!(plaintext.get(&1).map(String::as_str), Some("233168"));
+        assert_eq!(plaintext.get(&2).map(String::as_str), Some("4613732"));
+    }
+
+    #[test]
+    fn prefers_hashed_answers() {
+        let dir = TempDir::new();
+        write(
+            dir.path().join(PLAINTEXT_ANSWERS_FILE_NAME),
+            "1 = 1\n2 = 4613732\n",
+        )
+        .unwrap();
+        write(
+            dir.path().join(HASHED_ANSWERS_FILE_NAME),
+            format!("1 {}\n", DIGEST_1),
+        )
+        .unwrap();
+        let workspace = Workspace::new(dir.path().to_owned(), Config::default());
+
+        let answers = Answers::load(&workspace, false).unwrap();
+        assert!(answers.get(1).unwrap().matches("233168"));
+        assert!(answers.get(2).unwrap().matches("4613732"));
+        assert_eq!(answers.numbers().collect::<Vec<_>>(), [1, 2]);
+        let hashed_only = Answers::load(&workspace, true).unwrap();
+        assert!(hashed_only.get(2).is_none());
+    }
+
+    #[test]
+    fn upserts_hashed_answers_in_place() {
+        let contents = format!("# Mine\n1 {}\n# 2 is next\n3 abc\n", DIGEST_2);
+        assert_eq!(
+            upsert_hashed(&contents, 1, DIGEST_1),
+            format!("# Mine\n1 {}\n# 2 is next\n3 abc\n", DIGEST_1)
+        );
+        assert_eq!(
+            upsert_hashed(&contents, 2, DIGEST_2),
+            format!("{}2 {}\n", contents, DIGEST_2)
+        );
+    }
+
+    #[test]
+    fn refuses_to_store_empty_answers() {
+        let dir = TempDir::new();
+        let workspace = Workspace::new(dir.path().to_owned(), Config::default());
+        assert!(store_hashed(&workspace, 1, " \n").is_err());
+        let path = store_hashed(&workspace, 1, "233168\n").unwrap();
+        assert_eq!(read_to_string(path).unwrap(), format!("1 {}\n", DIGEST_1));
+    }
+
+    #[test]
+    fn copies_only_plaintext_answers() {
+        let dir = TempDir::new();
+        write(
+            dir.path().join(PLAINTEXT_ANSWERS_FILE_NAME),
+            "2 = 4613732
+",
+        )
+        .unwrap();
+        write(
+            dir.path().join(HASHED_ANSWERS_FILE_NAME),
+            format!("1 {}\n", DIGEST_1),
+        )
+        .unwrap();
+        let workspace = Workspace::new(dir.path().to_owned(), Config::default());
+        let answers = Answers::load(&workspace, false).unwrap();
+        let mut clipboard = FakeClipboard::default();
+
+        copy_answer(&answers, 2, &mut clipboard).unwrap();
+        assert_eq!(clipboard.copied, ["4613732"]);
+        let error = copy_answer(&answers, 1, &mut clipboard).unwrap_err();
+        assert!(error.to_string().contains("only the hash"), "{}", error);
+        let error = copy_answer(&answers, 3, &mut clipboard).unwrap_err();
+        assert_eq!(error.to_string(), "no answer to problem 3 is stored");
+        assert_eq!(clipboard.copied.len(), 1);
+
+        let error = copy_answer(&answers, 2, &mut FakeClipboard::unavailable()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unable to copy to the clipboard: no clipboard"
+        );
+    }
+}
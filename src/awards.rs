@@ -0,0 +1,572 @@
+//! Awards, and what's left to earn them.
+
+use {
+    crate::{
+        context::Context,
+        endpoints::page_url,
+        enrichment::Enrichment,
+        friends::cell_text,
+        progress::{ProblemStatus, Problems},
+    },
+    std::{error::Error, iter::once, ops::RangeInclusive},
+    structopt::StructOpt,
+    unhtml::scraper::{Html, Selector},
+};
+
+const AWARDS_ENDPOINT: &str = "awards";
+
+#[derive(Debug)]
+pub struct Award {
+    pub name: String,
+    /// What must be done to earn the award, like "Solve 25 consecutive problems".
+    pub description: String,
+    pub completed: bool,
+}
+
+/// Parses the awards page. Like levels on the progress page, each award is a box whose tooltip
+/// holds its name and description, and whose image only shows once it's completed.
+pub fn parse_awards(page: &str) -> Result<Vec<Award>, Box<dyn Error>> {
+    let html = Html::parse_document(page);
+    let tooltip_selector = Selector::parse("span").unwrap();
+    let name_selector = Selector::parse("div, strong").unwrap();
+    let image_selector = Selector::parse("img").unwrap();
+
+    let mut awards = Vec::new();
+    for award in html.select(&Selector::parse("div.award_box, div.info").unwrap()) {
+        let tooltip = match award.select(&tooltip_selector).next() {
+            Some(tooltip) => tooltip,
+            None => continue,
+        };
+        let name = match tooltip.select(&name_selector).next() {
+            Some(name) => cell_text(name),
+            None => continue,
+        };
+        let text = cell_text(tooltip);
+        let description = text.strip_prefix(&name).unwrap_or(&text).trim().to_owned();
+        let completed = award
+            .value()
+            .classes()
+            .any(|class| class.contains("completed"))
+            || award.select(&image_selector).any(|image| {
+                !image
+                    .ancestors()
+                    .any(|ancestor| ancestor.id() == tooltip.id())
+            });
+        awards.push(Award {
+            name,
+            description,
+            completed,
+        });
+    }
+    if awards.is_empty() {
+        return Err("unable to find any awards; make sure the session is signed in".into());
+    }
+    Ok(awards)
+}
+
+/// An award's criterion that can be evaluated from progress and cached problem metadata.
+#[derive(Debug, PartialEq)]
+pub enum Criterion {
+    /// Solve this many consecutive problems.
+    Consecutive(usize),
+    /// Solve this many problems, of at least this difficulty if any.
+    Solved {
+        count: usize,
+        min_difficulty: Option<u8>,
+    },
+    /// Solve the first this many problems.
+    First(usize),
+}
+
+/// Parses a number written in digits or in words, like "25" or "twenty five", from the start of
+/// `words`, returning it with how many words it took.
+fn parse_number(words: &[&str]) -> Option<(usize, usize)> {
+    const UNITS: &[&str] = &[
+        "zero",
+        "one",
+        "two",
+        "three",
+        "four",
+        "five",
+        "six",
+        "seven",
+        "eight",
+        "nine",
+        "ten",
+        "eleven",
+        "twelve",
+        "thirteen",
+        "fourteen",
+        "fifteen",
+        "sixteen",
+        "seventeen",
+        "eighteen",
+        "nineteen",
+    ];
+    const TENS: &[&str] = &[
+        "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+    ];
+
+    let first = words.first()?;
+    if let Ok(number) = first.replace(',', "").parse() {
+        return Some((number, 1));
+    }
+    let (mut total, mut current, mut taken) = (0, 0, 0);
+    for word in words {
+        let value = UNITS.iter().position(|unit| unit == word).or_else(|| {
+            TENS.iter()
+                .position(|ten| !ten.is_empty() && ten == word)
+                .map(|ten| ten * 10)
+        });
+        match (*word, value) {
+            (_, Some(value)) => current += value,
+            ("hundred", None) if taken > 0 => current *= 100,
+            ("thousand", None) if taken > 0 => {
+                total += current * 1000;
+                current = 0;
+            }
+            ("and", None) if taken > 0 => (),
+            _ => break,
+        }
+        taken += 1;
+    }
+    match taken {
+        0 => None,
+        taken => Some((total + current, taken)),
+    }
+}
+
+/// Recognizes the criterion of an award from its description, or returns `None` if it isn't one
+/// that can be evaluated locally.
+pub fn criterion(description: &str) -> Option<Criterion> {
+    let lowercase = description.to_lowercase();
+    // Separators of thousands are kept, so that numbers like "1,000" stay a single word.
+    let chars = lowercase.chars().collect::<Vec<_>>();
+    let lowercase = chars
+        .iter()
+        .enumerate()
+        .map(|(idx, &c)| {
+            let separates_digits = c == ','
+                && idx > 0
+                && chars[idx - 1].is_ascii_digit()
+                && chars.get(idx + 1).is_some_and(char::is_ascii_digit);
+            match c {
+                '-' | '.' | ',' if !separates_digits => ' ',
+                c => c,
+            }
+        })
+        .collect::<String>()
+        .replace('≥', " at least ");
+    let words = lowercase.split_whitespace().collect::<Vec<_>>();
+    let words = words.strip_prefix(&["solve"])?;
+    let (first, words) = match words.strip_prefix(&["the", "first"]) {
+        Some(words) => (true, words),
+        None => (false, words),
+    };
+    let (count, taken) = parse_number(words)?;
+    let rest = &words[taken..];
+
+    match rest {
+        ["problems"] | ["problem"] if first => Some(Criterion::First(count)),
+        ["consecutive", "problems"] | ["problems", "in", "a", "row"] => {
+            Some(Criterion::Consecutive(count))
+        }
+        ["problems"] | ["problem"] => Some(Criterion::Solved {
+            count,
+            min_difficulty: None,
+        }),
+        ["problems", rest @ ..] if !first && rest.contains(&"difficulty") => {
+            let at_least = rest.windows(2).any(|pair| {
+                pair == ["at", "least"] || pair == ["or", "higher"] || pair == ["or", "more"]
+            });
+            let percentage = rest
+                .iter()
+                .find_map(|word| word.strip_suffix('%')?.parse().ok())?;
+            if !at_least {
+                return None;
+            }
+            Some(Criterion::Solved {
+                count,
+                min_difficulty: Some(percentage),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// The longest run of consecutive solved problems, if any are solved. Retired problems break
+/// runs, like unsolved ones.
+pub fn longest_run(problems: &Problems) -> Option<RangeInclusive<usize>> {
+    let mut longest: Option<RangeInclusive<usize>> = None;
+    let mut start = None;
+    let solved = problems
+        .0
+        .iter()
+        .map(|status| *status == ProblemStatus::Solved)
+        .chain(once(false));
+    for (idx, solved) in solved.enumerate() {
+        let number = idx + 1;
+        match (solved, start) {
+            (true, None) => start = Some(number),
+            (false, Some(first)) => {
+                if longest
+                    .as_ref()
+                    .is_none_or(|longest| number - first > longest.clone().count())
+                {
+                    longest = Some(first..=number - 1);
+                }
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    longest
+}
+
+/// The run of `length` consecutive problems with the fewest unsolved, the earliest of those
+/// tied, along with the unsolved problems breaking it. Runs with retired problems can't be
+/// completed, so they're left out. `None` if fewer problems are published, or every run has a
+/// retired problem.
+pub fn closest_run(
+    problems: &Problems,
+    length: usize,
+) -> Option<(RangeInclusive<usize>, Vec<usize>)> {
+    if length == 0 || length > problems.0.len() {
+        return None;
+    }
+    let count = |idx: usize, status| usize::from(problems.0[idx] == status);
+    let (mut unsolved, mut retired) = (0..length).fold((0, 0), |(unsolved, retired), idx| {
+        (
+            unsolved + count(idx, ProblemStatus::Unsolved),
+            retired + count(idx, ProblemStatus::Retired),
+        )
+    });
+    let mut best = Some((0, unsolved)).filter(|_| retired == 0);
+    for start in 1..=problems.0.len() - length {
+        for (status, counted) in [
+            (ProblemStatus::Unsolved, &mut unsolved),
+            (ProblemStatus::Retired, &mut retired),
+        ] {
+            *counted += count(start + length - 1, status);
+            *counted -= count(start - 1, status);
+        }
+        if retired == 0 && best.is_none_or(|(_, best_unsolved)| unsolved < best_unsolved) {
+            best = Some((start, unsolved));
+        }
+    }
+    let (best, _) = best?;
+    let gaps = (best..best + length)
+        .filter(|idx| problems.0[*idx] == ProblemStatus::Unsolved)
+        .map(|idx| idx + 1)
+        .collect();
+    Some((best + 1..=best + length, gaps))
+}
+
+/// Problems of at least `min_difficulty` by cached difficulty ratings.
+#[derive(Debug, PartialEq)]
+pub struct Qualifying {
+    /// How many solved problems qualify.
+    pub solved: usize,
+    /// The unsolved problems that qualify, easiest first.
+    pub unsolved: Vec<usize>,
+    /// How many solved problems have no cached rating, and so might qualify too.
+    pub unrated_solved: usize,
+}
+
+pub fn qualifying(problems: &Problems, enrichment: &Enrichment, min_difficulty: u8) -> Qualifying {
+    let mut qualifying = Qualifying {
+        solved: 0,
+        unsolved: Vec::new(),
+        unrated_solved: 0,
+    };
+    let mut unsolved = Vec::new();
+    for (idx, status) in problems.0.iter().enumerate() {
+        let number = idx + 1;
+        match (
+            enrichment.get(number).and_then(|info| info.difficulty),
+            status,
+        ) {
+            (Some(difficulty), ProblemStatus::Solved) if difficulty >= min_difficulty => {
+                qualifying.solved += 1
+            }
+            (Some(difficulty), ProblemStatus::Unsolved) if difficulty >= min_difficulty => {
+                unsolved.push((difficulty, number))
+            }
+            (None, ProblemStatus::Solved) => qualifying.unrated_solved += 1,
+            _ => (),
+        }
+    }
+    unsolved.sort();
+    qualifying.unsolved = unsolved.into_iter().map(|(_, number)| number).collect();
+    qualifying
+}
+
+/// Lists `numbers`, eliding all but the first few.
+pub fn list(numbers: &[usize]) -> String {
+    const SHOWN: usize = 10;
+    let mut list = numbers
+        .iter()
+        .take(SHOWN)
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    if numbers.len() > SHOWN {
+        list.push_str(&format!(" and {} more", numbers.len() - SHOWN));
+    }
+    list
+}
+
+fn problems(count: usize) -> String {
+    match count {
+        1 => "1 problem".to_owned(),
+        count => format!("{} problems", count),
+    }
+}
+
+/// Explains what's left to meet `criterion`, as lines to print.
+fn explanation(criterion: &Criterion, problems: &Problems, enrichment: &Enrichment) -> Vec<String> {
+    match *criterion {
+        Criterion::Consecutive(length) => {
+            let mut lines = vec![match longest_run(problems) {
+                Some(run) => format!(
+                    "Your longest run is {} to {} ({})",
+                    run.start(),
+                    run.end(),
+                    self::problems(run.clone().count())
+                ),
+                None => "You haven't solved any problems yet".to_owned(),
+            }];
+            lines.push(match closest_run(problems, length) {
+                Some((run, gaps)) if gaps.is_empty() => {
+                    format!("{} to {} are all solved", run.start(), run.end())
+                }
+                Some((run, gaps)) => format!(
+                    "Solving {} would complete {} to {}",
+                    list(&gaps),
+                    run.start(),
+                    run.end()
+                ),
+                None => format!(
+                    "Only {} are published",
+                    self::problems(problems.published())
+                ),
+            });
+            lines
+        }
+        Criterion::Solved {
+            count,
+            min_difficulty: None,
+        } => vec![format!(
+            "{} more to solve",
+            self::problems(count.saturating_sub(problems.solved_count()))
+        )],
+        Criterion::Solved {
+            count,
+            min_difficulty: Some(min_difficulty),
+        } => {
+            if enrichment.is_empty() {
+                return vec![format!(
+                    "This needs difficulty ratings, but none are cached in {}; cache them with `cargo euler \
+                     prefetch --difficulty`",
+                    Enrichment::cache_path().display()
+                )];
+            }
+            let qualifying = qualifying(problems, enrichment, min_difficulty);
+            let mut lines = vec![format!(
+                "{} of at least {}% difficulty solved, {} more to solve",
+                qualifying.solved,
+                min_difficulty,
+                count.saturating_sub(qualifying.solved)
+            )];
+            if !qualifying.unsolved.is_empty() {
+                lines.push(format!(
+                    "Unsolved qualifying problems, easiest first: {}",
+                    list(&qualifying.unsolved)
+                ));
+            }
+            if qualifying.unrated_solved > 0 {
+                lines.push(format!(
+                    "{} solved have no cached rating, and may count too",
+                    self::problems(qualifying.unrated_solved)
+                ));
+            }
+            lines
+        }
+        Criterion::First(count) => {
+            let unsolved = problems
+                .unsolved()
+                .take_while(|number| *number <= count)
+                .collect::<Vec<_>>();
+            vec![format!("Left to solve: {}", list(&unsolved))]
+        }
+    }
+}
+
+/// List awards and whether they're completed.
+#[derive(Debug, StructOpt)]
+pub struct Awards {
+    /// For each incomplete award, explain what's left to earn it, where that can be worked out
+    /// from progress and cached problem metadata.
+    #[structopt(long)]
+    explain: bool,
+}
+
+impl Awards {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self { explain } = self;
+
+        let awards = parse_awards(&context.session()?.fetch_page(&page_url(AWARDS_ENDPOINT))?)?;
+        let (problems, enrichment) = if explain {
+            (
+                Some(context.progress()?.problems),
+                Enrichment::load_cached(),
+            )
+        } else {
+            (None, Enrichment::default())
+        };
+        for award in &awards {
+            println!(
+                "{} {}: {}",
+                if award.completed { "✔" } else { " " },
+                award.name,
+                award.description
+            );
+            let problems = match &problems {
+                Some(problems) if !award.completed => problems,
+                _ => continue,
+            };
+            match criterion(&award.description) {
+                Some(criterion) => {
+                    for line in explanation(&criterion, problems, &enrichment) {
+                        println!("    {}", line);
+                    }
+                }
+                None => println!("    This can't be evaluated locally"),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{enrichment::ProblemInfo, test_support::problems},
+    };
+
+    fn rated(difficulties: &[(usize, u8)]) -> Enrichment {
+        let mut enrichment = Enrichment::default();
+        for &(number, difficulty) in difficulties {
+            enrichment.insert(ProblemInfo {
+                number,
+                title: format!("Problem {}", number),
+                difficulty: Some(difficulty),
+                solved_by: None,
+            });
+        }
+        enrichment
+    }
+
+    #[test]
+    fn parses_awards() {
+        let page = r#"
+            <div class="award_box"><img src="images/awards/baby_steps.png"><span class="tooltiptext"><div>Baby Steps</div>Solve three problems</span></div>
+            <div class="award_box"><span class="tooltiptext"><div>Decathlete</div>Solve ten consecutive problems</span></div>
+        "#;
+        let awards = parse_awards(page).unwrap();
+        let summary = awards
+            .iter()
+            .map(|award| (&*award.name, &*award.description, award.completed))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                ("Baby Steps", "Solve three problems", true),
+                ("Decathlete", "Solve ten consecutive problems", false),
+            ]
+        );
+        assert!(parse_awards("<p>Sign in</p>").is_err());
+    }
+
+    #[test]
+    fn recognizes_criteria() {
+        assert_eq!(
+            criterion("Solve twenty-five consecutive problems"),
+            Some(Criterion::Consecutive(25))
+        );
+        assert_eq!(
+            criterion("Solve 100 problems in a row."),
+            Some(Criterion::Consecutive(100))
+        );
+        assert_eq!(
+            criterion("Solve one hundred and fifty problems"),
+            Some(Criterion::Solved {
+                count: 150,
+                min_difficulty: None
+            })
+        );
+        assert_eq!(
+            criterion("Solve three problems with a difficulty rating of at least 50%"),
+            Some(Criterion::Solved {
+                count: 3,
+                min_difficulty: Some(50)
+            })
+        );
+        assert_eq!(
+            criterion("Solve the first 1,000 problems"),
+            Some(Criterion::First(1000))
+        );
+        assert_eq!(criterion("Solve a problem on its release day"), None);
+        assert_eq!(
+            criterion("Solve five problems with a difficulty rating of 50%"),
+            None
+        );
+    }
+
+    #[test]
+    fn finds_runs() {
+        let problems = problems("SSU SSS RSS U");
+        assert_eq!(longest_run(&problems), Some(4..=6));
+        assert_eq!(longest_run(&self::problems("UU")), None);
+        // The first run is as short of completing as the last, but the middle has a retired
+        // problem.
+        assert_eq!(closest_run(&problems, 4), Some((1..=4, vec![3])));
+        assert_eq!(closest_run(&problems, 3), Some((4..=6, vec![])));
+        assert_eq!(closest_run(&problems, 11), None);
+        assert_eq!(closest_run(&self::problems("SRS"), 2), None);
+    }
+
+    #[test]
+    fn counts_qualifying_problems() {
+        let enrichment = rated(&[(1, 60), (2, 40), (3, 75), (4, 55)]);
+        assert_eq!(
+            qualifying(&problems("SSUUS"), &enrichment, 50),
+            Qualifying {
+                solved: 1,
+                unsolved: vec![4, 3],
+                unrated_solved: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn explains_what_is_left() {
+        let enrichment = Enrichment::default();
+        assert_eq!(
+            explanation(&Criterion::Consecutive(3), &problems("SSUS"), &enrichment),
+            [
+                "Your longest run is 1 to 2 (2 problems)",
+                "Solving 3 would complete 1 to 3"
+            ]
+        );
+        assert_eq!(
+            explanation(&Criterion::First(4), &problems("SUSUU"), &enrichment),
+            ["Left to solve: 2, 4"]
+        );
+        assert_eq!(
+            list(&(1..=12).collect::<Vec<_>>()),
+            "1, 2, 3, 4, 5, 6, 7, 8, 9, 10 and 2 more"
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use {
+    crate::{
+        output::red,
+        runner::{format_duration, Solution, TimeLimit},
+        storage,
+        workspace::Workspace,
+    },
+    chrono::{DateTime, Utc},
+    serde::{Deserialize, Serialize},
+    std::{error::Error, process::Command, time::Duration},
+    structopt::StructOpt,
+};
+
+/// Benchmark local solutions, recording their timings.
+#[derive(Debug, StructOpt)]
+pub struct Bench {
+    /// The problems whose solutions to benchmark. Every local solution is benchmarked if
+    /// omitted.
+    numbers: Vec<usize>,
+    /// How many untimed runs to do before timing.
+    #[structopt(long, default_value = "1")]
+    warmup: usize,
+    /// How many timed runs to do.
+    #[structopt(long, default_value = "5")]
+    runs: usize,
+    /// Flag solutions whose median time exceeds this many seconds. Defaults to the budget in
+    /// `euler.toml`, or 60 seconds.
+    #[structopt(long)]
+    budget: Option<f64>,
+    /// Compare each result against the previously recorded run of the same problem.
+    #[structopt(long)]
+    compare: bool,
+    /// With `--compare`, report regressions of the median time above this percentage.
+    #[structopt(long, default_value = "10")]
+    threshold: f64,
+    /// Kill a solution if any run of it takes longer than this, like `60s`. Defaults to the
+    /// time limit in `euler.toml`, if any.
+    #[structopt(long)]
+    time_limit: Option<TimeLimit>,
+    /// Use the solution file matching this glob, like `src/bin/p*.rs`, for problems with
+    /// several. Tried before the `layout.prefer` globs in `euler.toml`.
+    #[structopt(long)]
+    prefer: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BenchRecord {
+    pub problem: usize,
+    /// The commit of the workspace's git repository that was benchmarked, if any.
+    pub commit: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub runs: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+}
+
+pub fn read_history(profile: &str) -> Result<Vec<BenchRecord>, Box<dyn Error>> {
+    storage::open(profile)?.bench_records()
+}
+
+fn current_commit(workspace: &Workspace) -> Option<String> {
+    let output = Command::new("git")
+        .current_dir(&workspace.root)
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8(output.stdout).ok()?.trim().to_owned())
+}
+
+/// The duration a median time may take before it's flagged, given in seconds.
+fn budget(seconds: f64) -> Result<Duration, String> {
+    Duration::try_from_secs_f64(seconds).map_err(|_| {
+        format!(
+            "invalid budget {}; expected a non-negative number of seconds",
+            seconds
+        )
+    })
+}
+
+fn bench_one(
+    workspace: &Workspace,
+    number: usize,
+    warmup: usize,
+    runs: usize,
+    time_limit: Option<Duration>,
+) -> Result<Vec<Duration>, Box<dyn Error>> {
+    let solution = Solution::resolve(workspace, number)?;
+    let executable = solution.build(workspace, true)?;
+    for _ in 0..warmup {
+        solution.execute(workspace, &executable, &[], time_limit)?;
+    }
+    let mut timings = (0..runs)
+        .map(|_| {
+            Ok(solution
+                .execute(workspace, &executable, &[], time_limit)?
+                .elapsed)
+        })
+        .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+    timings.sort_unstable();
+    Ok(timings)
+}
+
+impl Bench {
+    pub fn run(self, profile: &str) -> Result<(), Box<dyn Error>> {
+        let Self {
+            numbers,
+            warmup,
+            runs,
+            budget,
+            compare,
+            threshold,
+            time_limit,
+            prefer,
+        } = self;
+        if runs == 0 {
+            return Err("at least one timed run is needed".into());
+        }
+
+        let mut workspace = Workspace::discover()?;
+        workspace.prefer(prefer);
+        let budget = self::budget(budget.unwrap_or(workspace.config.bench.budget))?;
+        let time_limit = time_limit
+            .or(workspace.config.run.time_limit)
+            .map(|TimeLimit(limit)| limit);
+        let numbers = if numbers.is_empty() {
+            workspace.solutions()?
+        } else {
+            numbers
+        };
+        let store = storage::open(profile)?;
+        let history = store.bench_records()?;
+        let commit = current_commit(&workspace);
+        let timestamp = Utc::now();
+
+        println!(
+            "{:>7}  {:>10}  {:>10}  {:>10}",
+            "Problem", "Min", "Median", "Max"
+        );
+        let mut records = Vec::with_capacity(numbers.len());
+        for number in numbers {
+            let timings = bench_one(&workspace, number, warmup, runs, time_limit)?;
+            let record = BenchRecord {
+                problem: number,
+                commit: commit.clone(),
+                timestamp,
+                runs,
+                min: timings[0],
+                median: timings[timings.len() / 2],
+                max: timings[timings.len() - 1],
+            };
+            let median = format!("{:>10}", format_duration(record.median));
+            println!(
+                "{:>7}  {:>10}  {}  {:>10}",
+                number,
+                format_duration(record.min),
+                if record.median > budget {
+                    red(&median)
+                } else {
+                    median
+                },
+                format_duration(record.max),
+            );
+            records.push(record);
+        }
+
+        if compare {
+            for record in &records {
+                let previous = history.iter().rev().find(|r| r.problem == record.problem);
+                let previous = match previous {
+                    Some(previous) => previous,
+                    None => {
+                        println!("Problem {}: no previous run to compare to", record.problem);
+                        continue;
+                    }
+                };
+                let change =
+                    (record.median.as_secs_f64() / previous.median.as_secs_f64() - 1.0) * 100.0;
+                let message = format!(
+                    "Problem {}: median {} -> {} ({:+.1}%)",
+                    record.problem,
+                    format_duration(previous.median),
+                    format_duration(record.median),
+                    change
+                );
+                if change > threshold {
+                    println!("{} {}", red(&message), red("regression"));
+                } else {
+                    println!("{}", message);
+                }
+            }
+        }
+
+        store.append_bench_records(&records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::test_support::{noon, with_each_backend},
+    };
+
+    #[test]
+    fn parses_budgets() {
+        assert_eq!(budget(60.0), Ok(Duration::from_secs(60)));
+        assert_eq!(budget(0.5), Ok(Duration::from_millis(500)));
+        assert_eq!(budget(0.0), Ok(Duration::ZERO));
+        for &seconds in &[-1.0, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(budget(seconds).is_err(), "{}", seconds);
+        }
+        assert_eq!(
+            budget(-1.0),
+            Err("invalid budget -1; expected a non-negative number of seconds".to_owned())
+        );
+    }
+
+    #[test]
+    fn records_benchmark_results() {
+        with_each_backend(|| {
+            assert!(read_history("default").unwrap().is_empty());
+            let records = [(1, Some("0123abc")), (2, None)]
+                .iter()
+                .map(|&(problem, commit)| BenchRecord {
+                    problem,
+                    commit: commit.map(str::to_owned),
+                    timestamp: noon(3, 1),
+                    runs: 10,
+                    min: Duration::from_micros(90),
+                    median: Duration::from_micros(100),
+                    max: Duration::from_micros(250),
+                })
+                .collect::<Vec<_>>();
+            storage::open("default")
+                .unwrap()
+                .append_bench_records(&records)
+                .unwrap();
+            assert_eq!(
+                serde_json::to_value(read_history("default").unwrap()).unwrap(),
+                serde_json::to_value(&records).unwrap()
+            );
+        });
+    }
+}
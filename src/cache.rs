@@ -0,0 +1,507 @@
+//! Managing the cache directory, everything in which can be fetched again.
+
+use {
+    crate::{
+        context::{Context, PROGRESS_CACHE_FILE_NAME},
+        enrichment::PROBLEMS_CACHE_FILE_NAME,
+        paths::{cache_dir, profile_cache_dir, CACHE_MARKER_FILE_NAME},
+        storage::STATEMENTS_DIR_NAME,
+    },
+    std::{
+        collections::BTreeMap,
+        error::Error,
+        fmt::{self, Display, Formatter},
+        fs::{metadata, read_dir, remove_file},
+        io::{self, ErrorKind},
+        path::{Path, PathBuf},
+        str::FromStr,
+        time::{Duration, SystemTime},
+    },
+    structopt::StructOpt,
+};
+
+/// The kinds of files the tool caches.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Category {
+    /// Fetched pages, like progress pages of each profile.
+    Pages,
+    Statements,
+    /// Problem metadata, like titles and difficulty ratings.
+    Metadata,
+}
+
+impl Display for Category {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.pad(match self {
+            Category::Pages => "pages",
+            Category::Statements => "statements",
+            Category::Metadata => "metadata",
+        })
+    }
+}
+
+/// A file in the cache.
+#[derive(Debug)]
+pub struct Entry {
+    pub category: Category,
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+fn entry(category: Category, path: PathBuf) -> io::Result<Option<Entry>> {
+    match metadata(&path) {
+        Ok(metadata) if metadata.is_file() => Ok(Some(Entry {
+            category,
+            size: metadata.len(),
+            modified: metadata.modified()?,
+            path,
+        })),
+        Ok(_) => Ok(None),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn subdirectories(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    match read_dir(dir) {
+        Ok(entries) => entries
+            .map(|entry| entry.map(|entry| entry.path()))
+            .filter(|path| path.as_ref().map_or(true, |path| path.is_dir()))
+            .collect(),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Lists the files in the cache directory `root` that the tool creates, by the names it gives
+/// them. Anything else in it is left out, so it's never deleted.
+pub fn entries(root: &Path) -> io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    entries.extend(entry(
+        Category::Metadata,
+        root.join(PROBLEMS_CACHE_FILE_NAME),
+    )?);
+    let statements_dir = root.join(STATEMENTS_DIR_NAME);
+    if let Ok(statements) = read_dir(&statements_dir) {
+        for statement in statements {
+            let path = statement?.path();
+            let is_statement = path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+                && path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .is_some_and(|stem| stem.parse::<usize>().is_ok());
+            if is_statement {
+                entries.extend(entry(Category::Statements, path)?);
+            }
+        }
+    }
+    for profile_dir in subdirectories(root)? {
+        if profile_dir != statements_dir {
+            entries.extend(entry(
+                Category::Pages,
+                profile_dir.join(PROGRESS_CACHE_FILE_NAME),
+            )?);
+        }
+    }
+    Ok(entries)
+}
+
+/// How many entries of each category there are, and their total size.
+pub fn totals(entries: &[Entry]) -> BTreeMap<Category, (usize, u64)> {
+    let mut totals = BTreeMap::new();
+    for entry in entries {
+        let (count, size) = totals.entry(entry.category).or_insert((0, 0));
+        *count += 1;
+        *size += entry.size;
+    }
+    totals
+}
+
+/// Selects the entries to remove to enforce a retention policy: those modified longer than
+/// `max_age` before `now`, then the oldest of the rest until they fit in `max_size`.
+pub fn select_garbage(
+    entries: &[Entry],
+    max_age: Option<Duration>,
+    max_size: Option<u64>,
+    now: SystemTime,
+) -> Vec<&Entry> {
+    let mut entries = entries.iter().collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.modified);
+    let (mut garbage, kept): (Vec<_>, Vec<_>) = entries.into_iter().partition(|entry| {
+        max_age.is_some_and(|max_age| {
+            now.duration_since(entry.modified)
+                .is_ok_and(|age| age > max_age)
+        })
+    });
+    if let Some(max_size) = max_size {
+        let mut size = kept.iter().map(|entry| entry.size).sum::<u64>();
+        for entry in kept {
+            if size <= max_size {
+                break;
+            }
+            size -= entry.size;
+            garbage.push(entry);
+        }
+    }
+    garbage
+}
+
+/// A size like `100MB` or `1.5GiB`.
+#[derive(Clone, Copy, Debug)]
+pub struct Size(pub u64);
+
+impl FromStr for Size {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let split = s
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let multiplier: u64 = match unit.trim().to_lowercase().as_str() {
+            "" | "b" => 1,
+            "kb" | "k" => 1000,
+            "mb" | "m" => 1000 * 1000,
+            "gb" | "g" => 1000 * 1000 * 1000,
+            "kib" => 1 << 10,
+            "mib" => 1 << 20,
+            "gib" => 1 << 30,
+            _ => {
+                return Err(format!(
+                    "unknown unit in size {:?}; expected like `100MB`",
+                    s
+                ))
+            }
+        };
+        let number = number
+            .parse::<f64>()
+            .map_err(|_| format!("invalid size {:?}; expected like `100MB`", s))?;
+        Ok(Self((number * multiplier as f64) as u64))
+    }
+}
+
+/// An age like `90d`, `12h` or `2w`.
+#[derive(Clone, Copy, Debug)]
+pub struct Age(pub Duration);
+
+impl FromStr for Age {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let seconds = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            "w" => 7 * 24 * 60 * 60,
+            _ => return Err(format!("invalid age {:?}; expected like `90d`", s)),
+        };
+        let number = number
+            .parse::<u64>()
+            .map_err(|_| format!("invalid age {:?}; expected like `90d`", s))?;
+        let seconds = number
+            .checked_mul(seconds)
+            .ok_or_else(|| format!("invalid age {:?}; it's too long", s))?;
+        Ok(Self(Duration::from_secs(seconds)))
+    }
+}
+
+/// Formats `bytes` like `1.2 MB`.
+fn human_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} B", bytes),
+        unit => format!("{:.1} {}", size, UNITS[unit]),
+    }
+}
+
+/// Fails unless `root` carries the marker the tool writes to the cache directories it creates.
+fn check_marker(root: &Path) -> Result<(), Box<dyn Error>> {
+    if !root.join(CACHE_MARKER_FILE_NAME).is_file() {
+        return Err(format!(
+            "{} doesn't have the {} file cargo-euler marks its cache with, so nothing will be \
+             deleted from it",
+            root.display(),
+            CACHE_MARKER_FILE_NAME
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Removes `entries`, reporting how many were removed and how much space was freed.
+fn remove(entries: &[&Entry]) -> Result<(), Box<dyn Error>> {
+    for entry in entries {
+        remove_file(&entry.path)
+            .map_err(|e| format!("unable to remove {}: {}", entry.path.display(), e))?;
+    }
+    println!(
+        "Removed {} cached files, freeing {}",
+        entries.len(),
+        human_size(entries.iter().map(|entry| entry.size).sum())
+    );
+    Ok(())
+}
+
+/// Manage cached data.
+#[derive(Debug, StructOpt)]
+pub enum CacheCommand {
+    /// Print where the profile's cached data is kept.
+    Path,
+    /// Show how many files of each kind are cached, and their total size.
+    Stats,
+    /// Remove cached data.
+    Clear {
+        /// Remove cached statements.
+        #[structopt(long)]
+        statements: bool,
+        /// Remove cached pages, like progress pages.
+        #[structopt(long)]
+        pages: bool,
+        /// Remove everything cached.
+        #[structopt(long, conflicts_with_all = &["statements", "pages"])]
+        all: bool,
+    },
+    /// Remove cached data that's too old, then the oldest until the rest is small enough.
+    Gc {
+        /// Remove files not modified in this long, like `90d`.
+        #[structopt(long)]
+        max_age: Option<Age>,
+        /// Then remove the oldest files until the rest take up no more than this, like `100MB`.
+        #[structopt(long)]
+        max_size: Option<Size>,
+    },
+}
+
+impl CacheCommand {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let root = cache_dir();
+        match self {
+            Self::Path => println!("{}", profile_cache_dir(&context.profile).display()),
+            Self::Stats => {
+                let entries = entries(&root)?;
+                let totals = totals(&entries);
+                for category in &[Category::Pages, Category::Statements, Category::Metadata] {
+                    let (count, size) = totals.get(category).copied().unwrap_or_default();
+                    println!(
+                        "{:<10}  {:>6} files  {:>9}",
+                        category,
+                        count,
+                        human_size(size)
+                    );
+                }
+                println!(
+                    "{:<10}  {:>6} files  {:>9}",
+                    "total",
+                    entries.len(),
+                    human_size(entries.iter().map(|entry| entry.size).sum())
+                );
+            }
+            Self::Clear {
+                statements,
+                pages,
+                all,
+            } => {
+                if !(statements || pages || all) {
+                    return Err("pass `--statements`, `--pages` or `--all`".into());
+                }
+                check_marker(&root)?;
+                let entries = entries(&root)?;
+                let cleared = entries
+                    .iter()
+                    .filter(|entry| match entry.category {
+                        _ if all => true,
+                        Category::Statements => statements,
+                        Category::Pages => pages,
+                        Category::Metadata => false,
+                    })
+                    .collect::<Vec<_>>();
+                remove(&cleared)?;
+            }
+            Self::Gc { max_age, max_size } => {
+                if max_age.is_none() && max_size.is_none() {
+                    return Err("pass `--max-age`, `--max-size` or both".into());
+                }
+                check_marker(&root)?;
+                let entries = entries(&root)?;
+                let garbage = select_garbage(
+                    &entries,
+                    max_age.map(|Age(age)| age),
+                    max_size.map(|Size(size)| size),
+                    SystemTime::now(),
+                );
+                remove(&garbage)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::test_support::TempDir,
+        std::fs::{create_dir_all, write},
+    };
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn at(days_ago: u64, size: u64, now: SystemTime) -> Entry {
+        Entry {
+            category: Category::Statements,
+            path: PathBuf::from(format!("{}.json", days_ago)),
+            size,
+            modified: now - DAY * days_ago as u32,
+        }
+    }
+
+    fn names(garbage: Vec<&Entry>) -> Vec<String> {
+        garbage
+            .into_iter()
+            .map(|entry| entry.path.display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn parses_sizes() {
+        for (size, bytes) in [
+            ("512", 512),
+            ("512B", 512),
+            ("100MB", 100_000_000),
+            ("100 mb", 100_000_000),
+            ("2k", 2_000),
+            ("1.5GiB", 3 << 29),
+            ("1KiB", 1024),
+        ] {
+            assert_eq!(size.parse::<Size>().unwrap().0, bytes, "{:?}", size);
+        }
+        for size in ["", "MB", "100TB", "1.2.3MB", "-5MB"] {
+            assert!(size.parse::<Size>().is_err(), "{:?}", size);
+        }
+    }
+
+    #[test]
+    fn parses_ages() {
+        for (age, seconds) in [
+            ("30s", 30),
+            ("5m", 300),
+            ("12h", 12 * 60 * 60),
+            ("90d", 90 * 24 * 60 * 60),
+            ("2w", 14 * 24 * 60 * 60),
+        ] {
+            assert_eq!(
+                age.parse::<Age>().unwrap().0,
+                Duration::from_secs(seconds),
+                "{:?}",
+                age
+            );
+        }
+        for age in ["", "90", "d", "1.5d", "90 d", "90D", "-1d"] {
+            assert!(age.parse::<Age>().is_err(), "{:?}", age);
+        }
+        assert_eq!(
+            "300000000000000d".parse::<Age>().unwrap_err(),
+            "invalid age \"300000000000000d\"; it's too long"
+        );
+    }
+
+    #[test]
+    fn selects_entries_older_than_the_maximum_age() {
+        let now = SystemTime::now();
+        let entries = [at(1, 10, now), at(100, 10, now), at(30, 10, now)];
+        assert_eq!(
+            names(select_garbage(&entries, Some(DAY * 30), None, now)),
+            ["100.json"]
+        );
+        assert!(select_garbage(&entries, Some(DAY * 365), None, now).is_empty());
+        assert!(select_garbage(&entries, None, None, now).is_empty());
+    }
+
+    #[test]
+    fn selects_the_oldest_entries_until_the_rest_fit() {
+        let now = SystemTime::now();
+        let entries = [at(3, 30, now), at(1, 10, now), at(2, 20, now)];
+        assert_eq!(
+            names(select_garbage(&entries, None, Some(25), now)),
+            ["3.json", "2.json"]
+        );
+        assert_eq!(
+            names(select_garbage(&entries, None, Some(30), now)),
+            ["3.json"]
+        );
+        assert!(select_garbage(&entries, None, Some(60), now).is_empty());
+        assert_eq!(names(select_garbage(&entries, None, Some(0), now)).len(), 3);
+    }
+
+    #[test]
+    fn applies_the_maximum_size_to_what_is_young_enough() {
+        let now = SystemTime::now();
+        let entries = [at(100, 1000, now), at(2, 20, now), at(1, 10, now)];
+        assert_eq!(
+            names(select_garbage(&entries, Some(DAY * 30), Some(15), now)),
+            ["100.json", "2.json"]
+        );
+    }
+
+    #[test]
+    fn lists_only_files_the_tool_creates() {
+        let root = TempDir::new();
+        let root = root.path();
+        create_dir_all(root.join(STATEMENTS_DIR_NAME)).unwrap();
+        create_dir_all(root.join("default")).unwrap();
+        create_dir_all(root.join("work")).unwrap();
+        for (path, contents) in [
+            (PathBuf::from(PROBLEMS_CACHE_FILE_NAME), "[]"),
+            (Path::new(STATEMENTS_DIR_NAME).join("1.json"), "{}"),
+            (Path::new(STATEMENTS_DIR_NAME).join("notes.json"), "{}"),
+            (Path::new(STATEMENTS_DIR_NAME).join("2.txt"), ""),
+            (
+                Path::new("default").join(PROGRESS_CACHE_FILE_NAME),
+                "<html>",
+            ),
+            (Path::new("work").join("todo.txt"), "mine"),
+            (PathBuf::from("unrelated.txt"), "mine"),
+        ] {
+            write(root.join(path), contents).unwrap();
+        }
+
+        let found = entries(root).unwrap();
+        assert_eq!(totals(&found)[&Category::Statements], (1, 2));
+        let mut found = found
+            .into_iter()
+            .map(|entry| {
+                (
+                    entry.category,
+                    entry.path.strip_prefix(root).unwrap().to_owned(),
+                )
+            })
+            .collect::<Vec<_>>();
+        found.sort();
+        assert_eq!(
+            found,
+            [
+                (
+                    Category::Pages,
+                    Path::new("default").join(PROGRESS_CACHE_FILE_NAME)
+                ),
+                (
+                    Category::Statements,
+                    Path::new(STATEMENTS_DIR_NAME).join("1.json")
+                ),
+                (Category::Metadata, PathBuf::from(PROBLEMS_CACHE_FILE_NAME)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,311 @@
+//! Solving the image captchas that guard signing in and submitting answers. Captchas are shown
+//! inline in terminals known to support an image protocol, and otherwise saved for the user to
+//! open themselves.
+
+use {
+    crate::client::Session,
+    base64::{engine::general_purpose::STANDARD as BASE64, Engine},
+    log::warn,
+    reqwest::Url,
+    std::{
+        env::{temp_dir, var},
+        error::Error,
+        fmt::Write as _,
+        fs::write,
+        io::{stdin, stdout, IsTerminal, Write},
+        path::Path,
+        process,
+        sync::mpsc::{channel, RecvTimeoutError},
+        thread,
+        time::Duration,
+    },
+    unhtml::scraper::{ElementRef, Selector},
+};
+
+/// How long to wait for an answer before warning that the captcha may have expired.
+const EXPIRY_WARNING_AFTER: Duration = Duration::from_secs(3 * 60);
+
+/// The most bytes of base64 the kitty graphics protocol accepts in a single escape sequence.
+const KITTY_CHUNK_LEN: usize = 4096;
+
+/// How many shades of gray captchas are rendered with over sixel. Captchas are monochrome
+/// enough that this loses nothing that matters.
+const SIXEL_SHADES: u8 = 16;
+
+/// Asks someone to solve a captcha. This is what flows needing captchas are tested through.
+pub trait CaptchaPrompt {
+    /// Returns the text shown in the captcha `image`.
+    fn solve(&mut self, image: &[u8]) -> Result<String, Box<dyn Error>>;
+}
+
+/// Finds the captcha of `form`, if it has one: the name of the field its solution goes in, and
+/// the URL of its image relative to `base`.
+pub fn find_in_form(
+    form: ElementRef<'_>,
+    base: &Url,
+) -> Result<Option<(String, Url)>, Box<dyn Error>> {
+    let field = form
+        .select(&Selector::parse("input[name]").unwrap())
+        .filter_map(|input| input.value().attr("name"))
+        .find(|name| name.contains("captcha"));
+    let field = match field {
+        Some(field) => field.to_owned(),
+        None => return Ok(None),
+    };
+    let src = form
+        .select(&Selector::parse("img[src]").unwrap())
+        .filter_map(|img| img.value().attr("src"))
+        .find(|src| src.contains("captcha"))
+        .ok_or("unable to find the image of the captcha")?;
+    Ok(Some((field, base.join(src)?)))
+}
+
+/// Downloads the captcha at `url` for `session`, and has `prompt` solve it.
+pub fn solve(
+    session: &mut Session,
+    url: &str,
+    prompt: &mut dyn CaptchaPrompt,
+) -> Result<String, Box<dyn Error>> {
+    let image = session.fetch_bytes(url)?;
+    prompt.solve(&image)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ImageProtocol {
+    Kitty,
+    ITerm2,
+    Sixel,
+}
+
+impl ImageProtocol {
+    /// Guesses which image protocol the terminal standard output goes to supports, if any.
+    fn detect() -> Option<Self> {
+        if !stdout().is_terminal() {
+            return None;
+        }
+        let term = var("TERM").unwrap_or_default();
+        let term_program = var("TERM_PROGRAM").unwrap_or_default();
+        if var("KITTY_WINDOW_ID").is_ok() || term.contains("kitty") || term_program == "ghostty" {
+            Some(Self::Kitty)
+        } else if term_program == "iTerm.app" || term_program == "WezTerm" {
+            Some(Self::ITerm2)
+        } else if term.contains("sixel") || term.starts_with("foot") || term == "mlterm" {
+            Some(Self::Sixel)
+        } else {
+            None
+        }
+    }
+
+    fn render(self, png: &[u8]) -> Result<String, Box<dyn Error>> {
+        Ok(match self {
+            Self::Kitty => {
+                let encoded = BASE64.encode(png);
+                let chunks = encoded
+                    .as_bytes()
+                    .chunks(KITTY_CHUNK_LEN)
+                    .collect::<Vec<_>>();
+                let mut rendered = String::new();
+                for (idx, chunk) in chunks.iter().enumerate() {
+                    let more = u8::from(idx + 1 < chunks.len());
+                    let chunk = std::str::from_utf8(chunk).unwrap();
+                    if idx == 0 {
+                        write!(rendered, "\x1b_Gf=100,a=T,m={};{}\x1b\\", more, chunk)?;
+                    } else {
+                        write!(rendered, "\x1b_Gm={};{}\x1b\\", more, chunk)?;
+                    }
+                }
+                rendered
+            }
+            Self::ITerm2 => format!(
+                "\x1b]1337;File=inline=1;size={}:{}\x07",
+                png.len(),
+                BASE64.encode(png)
+            ),
+            Self::Sixel => render_sixel(png)?,
+        })
+    }
+}
+
+/// Renders a PNG image as grayscale sixel graphics.
+fn render_sixel(png: &[u8]) -> Result<String, Box<dyn Error>> {
+    let mut decoder = png::Decoder::new(png);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels)?;
+    let (width, height) = (info.width as usize, info.height as usize);
+    let channels = info.color_type.samples();
+    let shade = |x: usize, y: usize| {
+        let pixel = &pixels[(y * width + x) * channels..][..channels];
+        let luminance = match channels {
+            1 | 2 => u32::from(pixel[0]),
+            _ => (u32::from(pixel[0]) * 3 + u32::from(pixel[1]) * 6 + u32::from(pixel[2])) / 10,
+        };
+        // Treat transparency as white, which is what captchas are drawn on.
+        let alpha = match channels {
+            2 => u32::from(pixel[1]),
+            4 => u32::from(pixel[3]),
+            _ => 255,
+        };
+        let luminance = (luminance * alpha + 255 * (255 - alpha)) / 255;
+        (luminance * u32::from(SIXEL_SHADES - 1) / 255) as u8
+    };
+
+    let mut rendered = String::from("\x1bPq");
+    for idx in 0..SIXEL_SHADES {
+        let percent = u32::from(idx) * 100 / u32::from(SIXEL_SHADES - 1);
+        write!(rendered, "#{};2;{};{};{}", idx, percent, percent, percent)?;
+    }
+    for band in (0..height).step_by(6) {
+        for idx in 0..SIXEL_SHADES {
+            write!(rendered, "#{}", idx)?;
+            for x in 0..width {
+                let bits = (0..6)
+                    .filter(|row| band + row < height && shade(x, band + row) == idx)
+                    .fold(0, |bits, row| bits | 1 << row);
+                rendered.push(char::from(63 + bits));
+            }
+            rendered.push('$');
+        }
+        rendered.push('-');
+    }
+    rendered.push_str("\x1b\\");
+    Ok(rendered)
+}
+
+/// Reads a line from standard input, warning once if it takes long enough that the captcha
+/// being answered may have expired.
+fn read_answer() -> Result<String, Box<dyn Error>> {
+    let (sender, receiver) = channel();
+    thread::spawn(move || {
+        let mut line = String::new();
+        let _ = sender.send(stdin().read_line(&mut line).map(|_| line));
+    });
+    let line = match receiver.recv_timeout(EXPIRY_WARNING_AFTER) {
+        Ok(line) => line,
+        Err(RecvTimeoutError::Timeout) => {
+            eprintln!();
+            eprintln!("warning: captchas expire; if this one is rejected, you'll get a new one");
+            receiver.recv()?
+        }
+        Err(RecvTimeoutError::Disconnected) => return Err("unable to read from stdin".into()),
+    }?;
+    Ok(line.trim().to_owned())
+}
+
+/// Shows captchas in the terminal and reads their solutions from standard input.
+#[derive(Debug, Default)]
+pub struct TerminalPrompt;
+
+impl TerminalPrompt {
+    fn show(image: &[u8], path: &Path) {
+        match ImageProtocol::detect().map(|protocol| protocol.render(image)) {
+            Some(Ok(rendered)) => println!("{}", rendered),
+            Some(Err(e)) => warn!("unable to display the captcha inline: {}", e),
+            None => (),
+        }
+        println!("The captcha is saved at {}", path.display());
+    }
+}
+
+impl CaptchaPrompt for TerminalPrompt {
+    fn solve(&mut self, image: &[u8]) -> Result<String, Box<dyn Error>> {
+        let path = temp_dir().join(format!("cargo-euler-captcha-{}.png", process::id()));
+        write(&path, image)
+            .map_err(|e| format!("unable to save the captcha to {}: {}", path.display(), e))?;
+        Self::show(image, &path);
+        loop {
+            print!("Captcha (leave empty to open the image): ");
+            stdout().flush()?;
+            let answer = read_answer()?;
+            if !answer.is_empty() {
+                return Ok(answer);
+            }
+            if let Err(e) = webbrowser::open(&path.to_string_lossy()) {
+                warn!("unable to open {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            endpoints::set_base_url,
+            submit::{submit_answer, SubmissionOutcome},
+            test_support::{lock_globals, MockServer, Reply, ScriptedPrompt},
+        },
+        unhtml::scraper::Html,
+    };
+
+    #[test]
+    fn finds_captchas_in_forms() {
+        let html = Html::parse_document(
+            r#"<form><input name="captcha"><img src="logo.png">
+               <img src="captcha/show_captcha.php?1"></form>"#,
+        );
+        let form = html
+            .select(&Selector::parse("form").unwrap())
+            .next()
+            .unwrap();
+        let base = Url::parse("https://projecteuler.net/sign_in").unwrap();
+        assert_eq!(
+            find_in_form(form, &base).unwrap(),
+            Some((
+                "captcha".to_owned(),
+                Url::parse("https://projecteuler.net/captcha/show_captcha.php?1").unwrap()
+            ))
+        );
+
+        let html = Html::parse_document(r#"<form><input name="username"></form>"#);
+        let form = html
+            .select(&Selector::parse("form").unwrap())
+            .next()
+            .unwrap();
+        assert_eq!(find_in_form(form, &base).unwrap(), None);
+    }
+
+    #[test]
+    fn submits_with_the_captcha_solved_by_the_prompt() {
+        let _globals = lock_globals();
+        let server = MockServer::start();
+        set_base_url(Some(Url::parse(server.url()).unwrap()));
+        let page = include_str!("../tests/fixtures/pages/problem-submittable.html");
+        server
+            .reply("GET", "/problem=42", Reply::ok(page))
+            .reply(
+                "GET",
+                "/captcha/show_captcha.php?1697120000",
+                Reply::ok(&b"\x89PNG captcha"[..]),
+            )
+            .reply(
+                "POST",
+                "/problem=42",
+                Reply::ok("<div id=\"content\">Congratulations, the answer is correct.</div>"),
+            );
+
+        let mut session = Session::new(Some("s3ss10n")).unwrap();
+        let mut prompt = ScriptedPrompt::new(&["12345"]);
+        let outcome = submit_answer(&mut session, 42, "162", &mut prompt).unwrap();
+        assert_eq!(outcome, SubmissionOutcome::Correct);
+        assert_eq!(prompt.shown, [b"\x89PNG captcha".to_vec()]);
+
+        let received = server.received();
+        assert_eq!(
+            received
+                .iter()
+                .map(|request| (&*request.method, &*request.path))
+                .collect::<Vec<_>>(),
+            [
+                ("GET", "/problem=42"),
+                ("GET", "/captcha/show_captcha.php?1697120000"),
+                ("POST", "/problem=42"),
+            ]
+        );
+        let post = &received[2];
+        assert_eq!(post.body, "csrf_token=3f9a1c&guess_42=162&captcha=12345");
+        assert_eq!(post.header("cookie"), Some("PHPSESSID=s3ss10n"));
+    }
+}
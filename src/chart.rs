@@ -0,0 +1,374 @@
+//! Charts of the solved count over time, drawn from the recorded history.
+
+use {
+    crate::{
+        history::{read_history, Snapshot},
+        paths::write_atomically,
+    },
+    chrono::{DateTime, Datelike, Duration, NaiveDate, Utc},
+    std::{error::Error, path::PathBuf, str::FromStr},
+    structopt::StructOpt,
+};
+
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// How many rows of characters a braille chart is tall.
+const BRAILLE_ROWS: usize = 4;
+
+const SVG_WIDTH: f64 = 600.0;
+const SVG_HEIGHT: f64 = 200.0;
+const SVG_MARGIN: f64 = 40.0;
+
+/// The span of time each point of a chart covers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bucket {
+    Day,
+    Week,
+    Month,
+}
+
+impl FromStr for Bucket {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "day" => Ok(Self::Day),
+            "week" => Ok(Self::Week),
+            "month" => Ok(Self::Month),
+            _ => Err(format!(
+                "unknown bucket {:?}; expected `day`, `week` or `month`",
+                s
+            )),
+        }
+    }
+}
+
+impl Bucket {
+    /// The first day of the bucket containing `date`. Weeks start on Monday.
+    pub fn start(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Day => date,
+            Self::Week => date - Duration::days(date.weekday().num_days_from_monday().into()),
+            Self::Month => date.with_day(1).unwrap(),
+        }
+    }
+
+    /// The first day of the bucket after the one starting at `start`.
+    fn next(self, start: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Day => start + Duration::days(1),
+            Self::Week => start + Duration::weeks(1),
+            Self::Month => {
+                let (year, month) = if start.month() == 12 {
+                    (start.year() + 1, 1)
+                } else {
+                    (start.year(), start.month() + 1)
+                };
+                NaiveDate::from_ymd_opt(year, month, 1).unwrap()
+            }
+        }
+    }
+}
+
+/// Buckets the solved counts of `snapshots` (oldest first) from the bucket of the first snapshot
+/// up to the bucket containing `now`. Each bucket takes the count of the last snapshot in it;
+/// buckets without a snapshot carry the previous count forward.
+pub fn bucket_counts(
+    snapshots: &[Snapshot],
+    bucket: Bucket,
+    now: DateTime<Utc>,
+) -> Vec<(NaiveDate, usize)> {
+    let first = match snapshots.first() {
+        Some(first) => first,
+        None => return Vec::new(),
+    };
+    let last = bucket.start(
+        now.date_naive().max(
+            snapshots
+                .last()
+                .map_or(first.timestamp, |last| last.timestamp)
+                .date_naive(),
+        ),
+    );
+
+    let mut counts = Vec::new();
+    let mut snapshots = snapshots.iter().peekable();
+    let mut count = first.solved_count;
+    let mut start = bucket.start(first.timestamp.date_naive());
+    while start <= last {
+        let end = bucket.next(start);
+        while let Some(snapshot) = snapshots.next_if(|s| s.timestamp.date_naive() < end) {
+            count = snapshot.solved_count;
+        }
+        counts.push((start, count));
+        start = end;
+    }
+    counts
+}
+
+/// How a chart is drawn: `sparkline`, `braille` or `svg`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChartFormat {
+    Sparkline,
+    Braille,
+    Svg,
+}
+
+impl FromStr for ChartFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sparkline" => Ok(Self::Sparkline),
+            "braille" => Ok(Self::Braille),
+            "svg" => Ok(Self::Svg),
+            _ => Err(format!(
+                "unknown chart format {:?}; expected `sparkline`, `braille` or `svg`",
+                s
+            )),
+        }
+    }
+}
+
+/// Scales `value` from the range `min..=max` onto `0..=steps - 1`.
+fn scale(value: usize, min: usize, max: usize, steps: usize) -> usize {
+    if max == min {
+        0
+    } else {
+        (value - min) * (steps - 1) / (max - min)
+    }
+}
+
+fn sparkline(counts: &[usize], min: usize, max: usize) -> String {
+    counts
+        .iter()
+        .map(|&count| SPARK_CHARS[scale(count, min, max, SPARK_CHARS.len())])
+        .collect()
+}
+
+/// Draws `counts` as a line of braille dots, two points per character, with consecutive points
+/// joined vertically.
+fn braille(counts: &[usize], min: usize, max: usize) -> Vec<String> {
+    let height = BRAILLE_ROWS * 4;
+    let width = counts.len().div_ceil(2);
+    let mut dots = vec![vec![0u8; width]; BRAILLE_ROWS];
+    let mut previous: Option<usize> = None;
+    for (x, &count) in counts.iter().enumerate() {
+        let y = height - 1 - scale(count, min, max, height);
+        let (low, high) = match previous {
+            Some(previous) if previous < y => (previous + 1, y),
+            Some(previous) => (y, previous.max(y)),
+            None => (y, y),
+        };
+        for y in low..=high {
+            let bit = match (x % 2, y % 4) {
+                (0, 3) => 6,
+                (1, 3) => 7,
+                (column, row) => column * 3 + row,
+            };
+            dots[y / 4][x / 2] |= 1 << bit;
+        }
+        previous = Some(y);
+    }
+    dots.into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|bits| char::from_u32(0x2800 + u32::from(bits)).unwrap())
+                .collect()
+        })
+        .collect()
+}
+
+fn svg(counts: &[(NaiveDate, usize)], min: usize, max: usize) -> String {
+    let span = (max - min).max(1) as f64;
+    let step = (SVG_WIDTH - 2.0 * SVG_MARGIN) / (counts.len().max(2) - 1) as f64;
+    let points = counts
+        .iter()
+        .enumerate()
+        .map(|(idx, (_, count))| {
+            let x = SVG_MARGIN + idx as f64 * step;
+            let y = SVG_HEIGHT
+                - SVG_MARGIN
+                - (count - min) as f64 / span * (SVG_HEIGHT - 2.0 * SVG_MARGIN);
+            format!("{:.1},{:.1}", x, y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let (first, _) = counts[0];
+    let (last, current) = counts[counts.len() - 1];
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="sans-serif" font-size="12">
+  <polyline fill="none" stroke="#2b7bb9" stroke-width="2" points="{points}"/>
+  <text x="{margin}" y="{max_y}" text-anchor="end" dx="-4">{max}</text>
+  <text x="{margin}" y="{min_y}" text-anchor="end" dx="-4">{min}</text>
+  <text x="{margin}" y="{date_y}">{first}</text>
+  <text x="{right}" y="{date_y}" text-anchor="end">{last} ({current} solved)</text>
+</svg>
+"##,
+        width = SVG_WIDTH,
+        height = SVG_HEIGHT,
+        points = points,
+        margin = SVG_MARGIN,
+        right = SVG_WIDTH - SVG_MARGIN,
+        max_y = SVG_MARGIN + 4.0,
+        min_y = SVG_HEIGHT - SVG_MARGIN + 4.0,
+        date_y = SVG_HEIGHT - SVG_MARGIN / 2.0,
+        max = max,
+        min = min,
+        first = first,
+        last = last,
+        current = current,
+    )
+}
+
+/// Chart the solved count over time.
+#[derive(Debug, StructOpt)]
+pub struct Chart {
+    /// The span of time each point covers: `day`, `week` or `month`.
+    #[structopt(long, default_value = "day")]
+    bucket: Bucket,
+    /// How to draw the chart: `sparkline`, `braille` or `svg`.
+    #[structopt(long, default_value = "sparkline")]
+    format: ChartFormat,
+    /// Only chart this many of the most recent buckets in the terminal.
+    #[structopt(long, default_value = "60")]
+    width: usize,
+    /// Write the chart to this file instead of standard output.
+    #[structopt(short, long, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+impl Chart {
+    pub fn run(self, profile: &str) -> Result<(), Box<dyn Error>> {
+        let Self {
+            bucket,
+            format,
+            width,
+            output,
+        } = self;
+
+        let mut counts = bucket_counts(&read_history(profile)?, bucket, Utc::now());
+        if counts.is_empty() {
+            return Err("no snapshots have been recorded yet".into());
+        }
+        if format != ChartFormat::Svg && counts.len() > width {
+            counts.drain(..counts.len() - width);
+        }
+        let values = counts.iter().map(|&(_, count)| count).collect::<Vec<_>>();
+        let min = *values.iter().min().unwrap();
+        let max = *values.iter().max().unwrap();
+        let (first, _) = counts[0];
+        let (last, current) = counts[counts.len() - 1];
+
+        let rendered = match format {
+            ChartFormat::Sparkline => format!(
+                "{} {} {}\nmin {}, max {}, now {}\n",
+                first,
+                sparkline(&values, min, max),
+                last,
+                min,
+                max,
+                current
+            ),
+            ChartFormat::Braille => {
+                let label_width = max.to_string().len();
+                let rows = braille(&values, min, max);
+                let mut rendered = String::new();
+                for (idx, row) in rows.iter().enumerate() {
+                    let label = match idx {
+                        0 => max.to_string(),
+                        _ if idx == rows.len() - 1 => min.to_string(),
+                        _ => String::new(),
+                    };
+                    rendered.push_str(&format!(
+                        "{:>width$} ┤{}\n",
+                        label,
+                        row,
+                        width = label_width
+                    ));
+                }
+                rendered.push_str(&format!(
+                    "{:>width$}  {} to {}, now {}\n",
+                    "",
+                    first,
+                    last,
+                    current,
+                    width = label_width
+                ));
+                rendered
+            }
+            ChartFormat::Svg => svg(&counts, min, max),
+        };
+
+        match output {
+            Some(path) => write_atomically(&path, rendered)?,
+            None => print!("{}", rendered),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::test_support::snapshot, chrono::TimeZone};
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn starts_buckets() {
+        // 2024-03-14 is a Thursday.
+        assert_eq!(Bucket::Day.start(date(2024, 3, 14)), date(2024, 3, 14));
+        assert_eq!(Bucket::Week.start(date(2024, 3, 14)), date(2024, 3, 11));
+        assert_eq!(Bucket::Week.start(date(2024, 3, 11)), date(2024, 3, 11));
+        assert_eq!(Bucket::Month.start(date(2024, 3, 14)), date(2024, 3, 1));
+        assert_eq!(Bucket::Month.next(date(2024, 12, 1)), date(2025, 1, 1));
+        assert_eq!(Bucket::Week.next(date(2024, 12, 30)), date(2025, 1, 6));
+    }
+
+    #[test]
+    fn buckets_counts_carrying_them_forward() {
+        let snapshots = [snapshot(3, 1, 10), snapshot(3, 1, 11), snapshot(3, 3, 15)];
+        let now = Utc.with_ymd_and_hms(2024, 3, 5, 0, 0, 0).unwrap();
+        let counts = bucket_counts(&snapshots, Bucket::Day, now);
+        assert_eq!(
+            counts,
+            [
+                (date(2024, 3, 1), 11),
+                (date(2024, 3, 2), 11),
+                (date(2024, 3, 3), 15),
+                (date(2024, 3, 4), 15),
+                (date(2024, 3, 5), 15),
+            ]
+        );
+        assert_eq!(
+            bucket_counts(&snapshots, Bucket::Month, now),
+            [(date(2024, 3, 1), 15)]
+        );
+        assert!(bucket_counts(&[], Bucket::Day, now).is_empty());
+    }
+
+    #[test]
+    fn draws_sparklines() {
+        assert_eq!(sparkline(&[0, 7, 14], 0, 14), "▁▄█");
+        assert_eq!(sparkline(&[5, 5], 5, 5), "▁▁");
+    }
+
+    #[test]
+    fn draws_braille() {
+        let rows = braille(&[0, 15], 0, 15);
+        assert_eq!(rows.len(), BRAILLE_ROWS);
+        // The first point is at the bottom left, and the second is joined to it all the way up
+        // the right column.
+        assert_eq!(rows, ["⢸", "⢸", "⢸", "⣸"]);
+        assert_eq!(braille(&[3, 3, 3], 3, 3), ["⠀⠀", "⠀⠀", "⠀⠀", "⣀⡀"]);
+    }
+
+    #[test]
+    fn draws_svg() {
+        let svg = svg(&[(date(2024, 3, 1), 10), (date(2024, 3, 2), 20)], 10, 20);
+        assert!(svg.contains(r#"points="40.0,160.0 560.0,40.0""#), "{}", svg);
+        assert!(svg.contains("2024-03-02 (20 solved)"));
+    }
+}
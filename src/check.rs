@@ -0,0 +1,218 @@
+use {
+    crate::{
+        context::Context,
+        github::{annotate, append_step_summary, Annotation},
+        new::escape_inline_markdown,
+        progress::Progress,
+        solved_set::SolvedSet,
+        status::{categorize, Category, Entry, StatusReport},
+        workspace::Workspace,
+        Failure,
+    },
+    std::{
+        error::Error,
+        fs::{read_to_string, write},
+        path::PathBuf,
+    },
+    structopt::StructOpt,
+    unhtml::FromHtml,
+};
+
+/// Describes every problem failing the checks that are required, or only the first with
+/// `fail_fast`.
+fn violations(
+    report: &StatusReport,
+    workspace: &Workspace,
+    require_local_for_solved: bool,
+    require_solved_for_local: bool,
+    fail_fast: bool,
+) -> Vec<String> {
+    let mut violations = Vec::new();
+    if require_local_for_solved {
+        for Entry { number, .. } in &report[&Category::SolvedWithoutLocal] {
+            violations.push(format!(
+                "problem {} is solved on the site, but has no local solution",
+                number
+            ));
+        }
+    }
+    if require_solved_for_local {
+        for Entry { number, paths, .. } in &report[&Category::LocalUnsolved] {
+            let paths = paths
+                .iter()
+                .map(|path| workspace.display_path(path).to_string())
+                .collect::<Vec<_>>();
+            violations.push(format!(
+                "problem {} has a local solution ({}), but isn't solved on the site",
+                number,
+                paths.join(", ")
+            ));
+        }
+    }
+    if fail_fast {
+        violations.truncate(1);
+    }
+    violations
+}
+
+/// Fails if any check did.
+fn verdict(violations: &[String], fail_fast: bool) -> Result<(), Box<dyn Error>> {
+    match violations.len() {
+        0 => Ok(()),
+        _ if fail_fast => Err(Failure("stopped at the first failed check".to_owned()).into()),
+        failed => Err(Failure(format!("{} check(s) failed", failed)).into()),
+    }
+}
+
+/// Renders the outcome of checking as Markdown, for a job summary.
+fn summary(violations: &[String]) -> String {
+    let mut markdown = String::from("### Consistency checks\n\n");
+    if violations.is_empty() {
+        markdown.push_str("Every check passed.\n");
+    }
+    for violation in violations {
+        markdown.push_str(&format!("- {}\n", escape_inline_markdown(violation)));
+    }
+    markdown
+}
+
+/// Check that local solutions and remote progress agree, for use in CI. Exits with 1 if any
+/// check fails, and 2 if checking couldn't be done at all.
+#[derive(Debug, StructOpt)]
+pub struct Check {
+    /// Fail if any problem solved on the site lacks a local solution.
+    #[structopt(long)]
+    require_local_for_solved: bool,
+    /// Fail if any local solution exists for a problem that isn't solved on the site.
+    #[structopt(long)]
+    require_solved_for_local: bool,
+    /// A copy of the progress page to check against, which can be committed so CI doesn't need
+    /// a session. Read instead of fetching with `--offline`, and updated otherwise.
+    #[structopt(long, parse(from_os_str))]
+    snapshot: Option<PathBuf>,
+    /// Check against a solved set exported with `cargo euler export` instead of the site.
+    #[structopt(long, parse(from_os_str), conflicts_with = "snapshot")]
+    baseline: Option<PathBuf>,
+    /// Stop at the first failed check, instead of reporting every one.
+    #[structopt(long)]
+    fail_fast: bool,
+}
+
+impl Check {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self {
+            mut require_local_for_solved,
+            mut require_solved_for_local,
+            snapshot,
+            baseline,
+            fail_fast,
+        } = self;
+        if !require_local_for_solved && !require_solved_for_local {
+            require_local_for_solved = true;
+            require_solved_for_local = true;
+        }
+
+        let workspace = Workspace::discover()?;
+        let local = workspace.scan_solutions()?;
+        let problems = match (&baseline, &snapshot) {
+            (Some(baseline), _) => SolvedSet::read(baseline)?.problems(),
+            (None, Some(snapshot)) if context.offline => {
+                let page = read_to_string(snapshot).map_err(|e| {
+                    format!("unable to read snapshot {}: {}", snapshot.display(), e)
+                })?;
+                Progress::from_html(&page)?.problems
+            }
+            (None, Some(snapshot)) => {
+                let (page, progress) = context.progress_page()?;
+                write(snapshot, &page)?;
+                progress.problems
+            }
+            (None, None) => context.progress_page()?.1.problems,
+        };
+        let report = categorize(&problems, local);
+
+        let violations = violations(
+            &report,
+            &workspace,
+            require_local_for_solved,
+            require_solved_for_local,
+            fail_fast,
+        );
+        for violation in &violations {
+            println!("{}", violation);
+            annotate(Annotation::Error, violation);
+        }
+        append_step_summary(&summary(&violations));
+        verdict(&violations, fail_fast)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{status::categorize, test_support::problems},
+        std::collections::BTreeMap,
+    };
+
+    fn report() -> StatusReport {
+        let local = vec![
+            (1, vec![PathBuf::from("/euler/src/bin/p001.rs")]),
+            (3, vec![PathBuf::from("/euler/src/bin/p003.rs")]),
+            (4, vec![PathBuf::from("/euler/src/bin/p004.rs")]),
+        ]
+        .into_iter()
+        .collect::<BTreeMap<_, _>>();
+        categorize(&problems("SSUU"), local)
+    }
+
+    fn workspace() -> Workspace {
+        Workspace::new(PathBuf::from("/euler"), Default::default())
+    }
+
+    #[test]
+    fn reports_every_failed_check() {
+        let all = violations(&report(), &workspace(), true, true, false);
+        assert_eq!(
+            all,
+            [
+                "problem 2 is solved on the site, but has no local solution",
+                "problem 3 has a local solution (src/bin/p003.rs), but isn't solved on the site",
+                "problem 4 has a local solution (src/bin/p004.rs), but isn't solved on the site",
+            ]
+        );
+        assert_eq!(
+            verdict(&all, false).unwrap_err().to_string(),
+            "3 check(s) failed"
+        );
+        assert_eq!(
+            violations(&report(), &workspace(), false, true, false).len(),
+            2
+        );
+    }
+
+    #[test]
+    fn stops_at_the_first_failed_check_with_fail_fast() {
+        let first = violations(&report(), &workspace(), true, true, true);
+        assert_eq!(
+            first,
+            ["problem 2 is solved on the site, but has no local solution"]
+        );
+        let e = verdict(&first, true).unwrap_err();
+        assert!(e.is::<Failure>());
+        assert_eq!(e.to_string(), "stopped at the first failed check");
+        assert!(verdict(&[], true).is_ok());
+    }
+
+    #[test]
+    fn summarizes_checks() {
+        assert_eq!(
+            summary(&[]),
+            "### Consistency checks\n\nEvery check passed.\n"
+        );
+        assert_eq!(
+            summary(&["problem 2 is *odd*".to_owned()]),
+            "### Consistency checks\n\n- problem 2 is \\*odd\\*\n"
+        );
+    }
+}
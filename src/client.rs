@@ -0,0 +1,929 @@
+use {
+    crate::{
+        endpoints::{problem_url, progress_url},
+        fixtures,
+        paths::session_id_path,
+        timings::{self, PhaseTimer},
+        user_config::UserConfig,
+    },
+    chrono::DateTime,
+    log::{debug, warn},
+    reqwest::{
+        header::{HeaderValue, COOKIE, LOCATION, RETRY_AFTER},
+        Client, ClientBuilder, Method, Proxy, RedirectPolicy, Request, RequestBuilder, Response,
+        StatusCode, Url,
+    },
+    serde::Serialize,
+    std::{
+        env::var,
+        error::Error,
+        fmt::{self, Display, Formatter},
+        fs::read_to_string,
+        io::{stderr, Write},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Mutex,
+        },
+        thread::sleep,
+        time::{Duration, Instant, SystemTime},
+    },
+};
+
+pub const SESSION_COOKIE_NAME: &str = "PHPSESSID";
+
+/// A page served in place of the site's.
+#[derive(Debug)]
+pub enum UnavailableError {
+    /// A Cloudflare challenge, which only a browser can pass.
+    Challenge,
+    Maintenance,
+    /// Rate limited, with longer to wait than is configured to be waited out.
+    RateLimited(Duration),
+}
+
+impl Display for UnavailableError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            UnavailableError::Challenge => write!(
+                f,
+                "the site is temporarily protected by a browser challenge; try again later, or \
+                 refresh your session from a browser"
+            ),
+            UnavailableError::Maintenance => {
+                write!(f, "the site is down for maintenance; try again later")
+            }
+            UnavailableError::RateLimited(wait) => write!(
+                f,
+                "the site is rate limiting requests, and asked to wait {} seconds before \
+                 retrying; that's longer than `max_retry_after` in {} allows",
+                wait.as_secs(),
+                UserConfig::path().display()
+            ),
+        }
+    }
+}
+
+impl Error for UnavailableError {}
+
+const CHALLENGE_MARKERS: &[&str] = &[
+    "cf-browser-verification",
+    "cf-challenge",
+    "cf_chl_",
+    "challenge-platform",
+    "<title>just a moment...</title>",
+    "attention required! | cloudflare",
+];
+const MAINTENANCE_MARKERS: &[&str] = &[
+    "down for maintenance",
+    "undergoing maintenance",
+    "scheduled maintenance",
+];
+
+/// Recognizes Cloudflare challenges and maintenance notices served in place of the site's pages,
+/// so that they're reported as such instead of failing to parse. Only looks at the raw text, so
+/// it can run before anything else looks at a page.
+pub fn check_available(page: &str) -> Result<(), UnavailableError> {
+    let lowercase = page.to_lowercase();
+    if CHALLENGE_MARKERS
+        .iter()
+        .any(|marker| lowercase.contains(marker))
+    {
+        return Err(UnavailableError::Challenge);
+    }
+    let title = lowercase
+        .find("<title>")
+        .and_then(|start| {
+            let title = &lowercase[start..];
+            title.find("</title>").map(|end| &title[..end])
+        })
+        .unwrap_or("");
+    if title.contains("maintenance")
+        || MAINTENANCE_MARKERS
+            .iter()
+            .any(|marker| lowercase.contains(marker))
+    {
+        return Err(UnavailableError::Maintenance);
+    }
+    Ok(())
+}
+
+/// How a dry run treats requests. Requests that would change anything on the site, like
+/// submitting a form, are never sent in a dry run.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DryRun {
+    /// Send no requests at all.
+    All,
+    /// Still send requests that only read from the site.
+    ReadsOk,
+}
+
+impl std::str::FromStr for DryRun {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reads-ok" => Ok(Self::ReadsOk),
+            _ => Err(format!("unknown dry run mode {:?}; expected `reads-ok`", s)),
+        }
+    }
+}
+
+/// The dry run requests are made in, if any. Set once at startup.
+static DRY_RUN: Mutex<Option<DryRun>> = Mutex::new(None);
+
+pub fn set_dry_run(dry_run: Option<DryRun>) {
+    *DRY_RUN.lock().unwrap() = dry_run;
+}
+
+pub fn dry_run() -> Option<DryRun> {
+    *DRY_RUN.lock().unwrap()
+}
+
+/// A request that wasn't sent, because it's a dry run.
+#[derive(Debug)]
+pub struct DryRunError {
+    pub method: Method,
+    pub url: Url,
+}
+
+impl Display for DryRunError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "stopping at {} {}, which isn't sent in a dry run",
+            self.method, self.url
+        )
+    }
+}
+
+impl Error for DryRunError {}
+
+/// In a dry run, prints the request `request` would send, and fails unless it's allowed to be
+/// sent.
+fn check_dry_run(request: &Request) -> Result<(), Box<dyn Error>> {
+    let dry_run = match dry_run() {
+        Some(dry_run) => dry_run,
+        None => return Ok(()),
+    };
+    let is_write = !matches!(*request.method(), Method::GET | Method::HEAD);
+    eprintln!(
+        "dry run: {} {} ({})",
+        request.method(),
+        request.url(),
+        if is_write { "write" } else { "read" }
+    );
+    if is_write || dry_run == DryRun::All {
+        return Err(DryRunError {
+            method: request.method().clone(),
+            url: request.url().clone(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Whether to connect directly regardless of proxies configured in the environment.
+static DIRECT: AtomicBool = AtomicBool::new(false);
+
+pub fn set_direct(direct: bool) {
+    DIRECT.store(direct, Ordering::Relaxed);
+}
+
+/// Whether `host` is exempt from proxying by a `NO_PROXY` list like `localhost,.example.com`.
+/// Like curl, an entry matches the host itself and its subdomains whether or not it starts with
+/// a `.` (or `*.`), `*` matches every host, ports are ignored and matching ignores case.
+pub fn bypasses_proxy(no_proxy: &str, host: &str) -> bool {
+    let host = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .trim_end_matches('.')
+        .to_lowercase();
+    no_proxy
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| {
+            if entry == "*" {
+                return true;
+            }
+            let entry = match entry.strip_prefix('[') {
+                // An IPv6 address, maybe with a port after it.
+                Some(rest) => rest.split(']').next().unwrap(),
+                // A port, unless it's an IPv6 address without brackets.
+                None if entry.matches(':').count() == 1 => entry.split(':').next().unwrap(),
+                None => entry,
+            };
+            let entry = entry
+                .trim_start_matches('*')
+                .trim_start_matches('.')
+                .trim_end_matches('.')
+                .to_lowercase();
+            !entry.is_empty()
+                && (host == entry
+                    || host
+                        .strip_suffix(&entry)
+                        .is_some_and(|prefix| prefix.ends_with('.')))
+        })
+}
+
+/// The first of the environment variables `names` that's set and not empty.
+fn env_var(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .filter_map(|name| var(name).ok())
+        .find(|value| !value.trim().is_empty())
+}
+
+/// The proxy the environment configures for requests to `url`, if any, like curl reads it.
+fn proxy_for(url: &Url) -> Option<Url> {
+    let proxy = match url.scheme() {
+        "https" => env_var(&["https_proxy", "HTTPS_PROXY", "all_proxy", "ALL_PROXY"]),
+        "http" => env_var(&["http_proxy", "HTTP_PROXY", "all_proxy", "ALL_PROXY"]),
+        _ => None,
+    }?;
+    if let (Some(no_proxy), Some(host)) = (env_var(&["no_proxy", "NO_PROXY"]), url.host_str()) {
+        if bypasses_proxy(&no_proxy, host) {
+            return None;
+        }
+    }
+    // Like curl, assume a proxy without a scheme is an HTTP one.
+    let proxy = if proxy.contains("://") {
+        proxy
+    } else {
+        format!("http://{}", proxy)
+    };
+    match Url::parse(&proxy) {
+        Ok(proxy) => Some(proxy),
+        Err(e) => {
+            warn!("ignoring the invalid proxy {:?}: {}", proxy, e);
+            None
+        }
+    }
+}
+
+/// Starts building a client, proxied like the environment says unless `--no-proxy` was passed.
+fn client_builder() -> ClientBuilder {
+    let builder = Client::builder();
+    if DIRECT.load(Ordering::Relaxed) {
+        return builder;
+    }
+    builder.proxy(Proxy::custom(proxy_for))
+}
+
+/// How long to wait out a `Retry-After` for, at most, unless configured otherwise.
+const DEFAULT_MAX_RETRY_AFTER: Duration = Duration::from_secs(120);
+
+/// Until when the site last asked requests to stop, with a `429 Too Many Requests` response.
+/// Every request waits for it, so that a batch of requests pauses as a whole.
+static PAUSED_UNTIL: Mutex<Option<SystemTime>> = Mutex::new(None);
+
+/// Forgets any pause the site asked for, so that tests start without one.
+#[cfg(test)]
+pub fn clear_pause() {
+    *PAUSED_UNTIL.lock().unwrap() = None;
+}
+
+fn max_retry_after() -> Duration {
+    UserConfig::load()
+        .ok()
+        .and_then(|config| config.max_retry_after)
+        .map_or(DEFAULT_MAX_RETRY_AFTER, Duration::from_secs)
+}
+
+/// Parses a `Retry-After` header, either as a number of seconds or as an HTTP date, into how
+/// long to wait from `now`.
+pub fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = SystemTime::from(DateTime::parse_from_rfc2822(value).ok()?);
+    Some(date.duration_since(now).unwrap_or_default())
+}
+
+/// How long to wait until `paused_until` from `now`, or an error if that's longer than `max`.
+pub fn pause_remaining(
+    paused_until: Option<SystemTime>,
+    now: SystemTime,
+    max: Duration,
+) -> Result<Duration, UnavailableError> {
+    let wait = paused_until
+        .and_then(|until| until.duration_since(now).ok())
+        .unwrap_or_default();
+    if wait > max {
+        return Err(UnavailableError::RateLimited(wait));
+    }
+    Ok(wait)
+}
+
+/// Sleeps for `wait`, counting down on standard error.
+fn count_down(wait: Duration) {
+    let mut stderr = stderr();
+    for remaining in (1..=wait.as_secs()).rev() {
+        let _ = write!(
+            stderr,
+            "\rRate limited by the site; retrying in {}s ",
+            remaining
+        );
+        let _ = stderr.flush();
+        sleep(Duration::from_secs(1));
+    }
+    sleep(Duration::from_nanos(wait.subsec_nanos().into()));
+    let _ = write!(stderr, "\r{:48}\r", "");
+}
+
+/// Sends the request built by `request`, first waiting out any pause the site asked for. On a
+/// `429 Too Many Requests` response with a `Retry-After` header, pauses every request for as long
+/// as it says, then retries once. Each response is logged with how long it took to `url`.
+/// Responses are recorded or replayed as fixtures if asked for.
+fn send_pausing(
+    url: &str,
+    request: impl Fn() -> RequestBuilder,
+) -> Result<Response, Box<dyn Error>> {
+    let built = request().build()?;
+    check_dry_run(&built)?;
+    if let Some(response) = fixtures::replay(built.method(), built.url())? {
+        return Ok(response);
+    }
+    let mut retried = false;
+    loop {
+        let paused_until = *PAUSED_UNTIL.lock().unwrap();
+        if paused_until.is_some() {
+            let wait = pause_remaining(paused_until, SystemTime::now(), max_retry_after())?;
+            if wait > Duration::default() {
+                count_down(wait);
+            }
+        }
+
+        let start = Instant::now();
+        let response = timings::time("request", || request().send())?;
+        let response = fixtures::record(&built, response)?;
+        let duration_ms = start.elapsed().as_millis() as u64;
+        debug!(
+            url,
+            status = response.status().as_u16(),
+            duration_ms;
+            "{} responded with {} in {}ms",
+            url,
+            response.status(),
+            duration_ms
+        );
+        if response.status() != StatusCode::TOO_MANY_REQUESTS {
+            return Ok(response);
+        }
+        let now = SystemTime::now();
+        let wait = match response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| parse_retry_after(value, now))
+        {
+            Some(wait) => wait,
+            None => return Ok(response),
+        };
+        *PAUSED_UNTIL.lock().unwrap() = Some(now + wait);
+        if retried {
+            return Err(UnavailableError::RateLimited(wait).into());
+        }
+        retried = true;
+    }
+}
+
+/// Fetches a page at `url` that doesn't require a session.
+pub fn fetch_public_page(url: &str) -> Result<String, Box<dyn Error>> {
+    let client = client_builder().build()?;
+    let mut response = send_pausing(url, || client.get(url))?;
+    let page = timings::time("transfer", || response.text())?;
+    check_available(&page)?;
+    Ok(page)
+}
+
+/// Fetches the public page of problem `number`.
+pub fn fetch_problem_page(number: usize) -> Result<String, Box<dyn Error>> {
+    fetch_public_page(&problem_url(number))
+}
+
+/// Downloads the raw contents of `url`, like a data file attached to a problem.
+pub fn fetch_bytes(url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let client = client_builder().build()?;
+    let mut response = send_pausing(url, || client.get(url))?.error_for_status()?;
+    let mut bytes = Vec::new();
+    timings::time("transfer", || response.copy_to(&mut bytes))?;
+    Ok(bytes)
+}
+
+/// POSTs `body` as JSON to `url`, like to a webhook.
+pub fn post_json(url: &str, body: &impl Serialize) -> Result<(), Box<dyn Error>> {
+    let client = client_builder().build()?;
+    send_pausing(url, || client.post(url).json(body))?.error_for_status()?;
+    Ok(())
+}
+
+/// Cleans up a session ID as it tends to be pasted into the session file: with a byte order mark
+/// and CRLF line endings from Windows editors, wrapped in quotes, or copied from a `Cookie`
+/// header along with its name and a trailing `;`. What's stripped is logged.
+pub fn sanitize_session_id(raw: &str) -> String {
+    let mut stripped = Vec::new();
+    let mut id = raw;
+    if let Some(rest) = id.strip_prefix('\u{feff}') {
+        stripped.push("a byte order mark");
+        id = rest;
+    }
+    loop {
+        let before = id;
+        if id.trim() != id {
+            stripped.push("whitespace");
+            id = id.trim();
+        }
+        for quote in &['"', '\''] {
+            if let Some(rest) = id
+                .strip_prefix(*quote)
+                .and_then(|rest| rest.strip_suffix(*quote))
+            {
+                stripped.push("quotes");
+                id = rest;
+            }
+        }
+        if let Some(rest) = id.strip_suffix(';') {
+            stripped.push("a trailing `;`");
+            id = rest;
+        }
+        if let Some(rest) = id
+            .strip_prefix(SESSION_COOKIE_NAME)
+            .and_then(|rest| rest.trim_start().strip_prefix('='))
+        {
+            stripped.push("the cookie name");
+            id = rest;
+        }
+        if id == before {
+            break;
+        }
+    }
+    if !stripped.is_empty() {
+        let mut unique = Vec::new();
+        for contamination in stripped {
+            if !unique.contains(&contamination) {
+                unique.push(contamination);
+            }
+        }
+        debug!("stripped {} from the session ID", unique.join(", "));
+    }
+    id.to_owned()
+}
+
+/// How many redirects to follow for a single request before giving up.
+const MAX_REDIRECTS: usize = 10;
+
+/// A client tracking a session of the site, optionally signed in.
+#[derive(Debug)]
+pub struct Session {
+    client: Client,
+    id: Option<String>,
+}
+
+impl Session {
+    /// Authenticates with `session_id`, or the one in the `PHPSESSID` file of the current
+    /// directory if omitted.
+    pub fn new(session_id: Option<&str>) -> Result<Self, Box<dyn Error>> {
+        let _timer = PhaseTimer::start("session");
+        let id = match session_id {
+            Some(value) => value.to_owned(),
+            None => {
+                let path = session_id_path();
+                read_to_string(&path).map_err(|e| {
+                    format!(
+                        "unable to read the session ID from {} ({}); sign in with `cargo euler \
+                         login`, or pass `--session-id`",
+                        path.display(),
+                        e
+                    )
+                })?
+            }
+        };
+        Ok(Self {
+            client: client_builder().redirect(RedirectPolicy::none()).build()?,
+            id: Some(sanitize_session_id(&id)),
+        })
+    }
+
+    /// Starts a session that isn't signed in, like for signing in with. The site assigns its ID
+    /// with the first response.
+    pub fn anonymous() -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            client: client_builder().redirect(RedirectPolicy::none()).build()?,
+            id: None,
+        })
+    }
+
+    /// The value of the session cookie, once there is one.
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Sends a request, following redirects and keeping track of the session cookie along the
+    /// way, since the site may assign a new one at any point.
+    fn send(
+        &mut self,
+        method: Method,
+        url: &str,
+        form: Option<&[(String, String)]>,
+    ) -> Result<Response, Box<dyn Error>> {
+        let mut url = Url::parse(url)?;
+        let mut method = method;
+        let mut form = form;
+        for _ in 0..=MAX_REDIRECTS {
+            let cookie = match &self.id {
+                Some(id) => Some(HeaderValue::from_str(&format!(
+                    "{}={}",
+                    SESSION_COOKIE_NAME, id
+                ))?),
+                None => None,
+            };
+            let response = send_pausing(url.as_str(), || {
+                let mut request = self.client.request(method.clone(), url.clone());
+                if let Some(cookie) = &cookie {
+                    request = request.header(COOKIE, cookie.clone());
+                }
+                if let Some(form) = form {
+                    request = request.form(form);
+                }
+                request
+            })?;
+            if let Some(cookie) = response
+                .cookies()
+                .find(|cookie| cookie.name() == SESSION_COOKIE_NAME)
+            {
+                self.id = Some(cookie.value().to_owned());
+            }
+            let status = response.status();
+            if status.is_client_error() || status.is_server_error() {
+                // Challenges and maintenance notices tend to come with error statuses.
+                let mut response = response;
+                if let Ok(page) = response.text() {
+                    check_available(&page)?;
+                }
+                return Err(response.error_for_status().unwrap_err().into());
+            }
+            if !status.is_redirection() {
+                return Ok(response);
+            }
+            let location = response
+                .headers()
+                .get(LOCATION)
+                .ok_or("redirected without a location")?
+                .to_str()?;
+            url = url.join(location)?;
+            // Like browsers, follow redirects of form submissions with a plain `GET`.
+            method = Method::GET;
+            form = None;
+        }
+        Err(format!("too many redirects requesting {}", url).into())
+    }
+
+    /// Fetches the page at `url` as seen by the user of the session.
+    pub fn fetch_page(&mut self, url: &str) -> Result<String, Box<dyn Error>> {
+        let mut response = self.send(Method::GET, url, None)?;
+        let page = timings::time("transfer", || response.text())?;
+        check_available(&page)?;
+        Ok(page)
+    }
+
+    /// Downloads the raw contents of `url` as seen by the user of the session, like a captcha.
+    pub fn fetch_bytes(&mut self, url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut bytes = Vec::new();
+        let mut response = self.send(Method::GET, url, None)?;
+        timings::time("transfer", || response.copy_to(&mut bytes))?;
+        Ok(bytes)
+    }
+
+    /// Submits a form to `url`, returning the page responded with.
+    pub fn post_form(
+        &mut self,
+        url: &str,
+        fields: &[(String, String)],
+    ) -> Result<String, Box<dyn Error>> {
+        let mut response = self.send(Method::POST, url, Some(fields))?;
+        let page = timings::time("transfer", || response.text())?;
+        check_available(&page)?;
+        Ok(page)
+    }
+}
+
+pub fn fetch_progress_page(session_id: Option<&str>) -> Result<String, Box<dyn Error>> {
+    Session::new(session_id)?.fetch_page(&progress_url())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            endpoints::set_base_url,
+            test_support::{lock_globals, MockServer, Reply},
+        },
+    };
+
+    const CHALLENGE: &str = include_str!("../tests/fixtures/pages/challenge.html");
+    const MAINTENANCE: &str = include_str!("../tests/fixtures/pages/maintenance.html");
+
+    #[test]
+    fn recognizes_pages_served_in_place_of_the_site() {
+        assert!(matches!(
+            check_available(CHALLENGE),
+            Err(UnavailableError::Challenge)
+        ));
+        assert!(matches!(
+            check_available(MAINTENANCE),
+            Err(UnavailableError::Maintenance)
+        ));
+        assert!(matches!(
+            check_available("<title>Scheduled Maintenance</title>"),
+            Err(UnavailableError::Maintenance)
+        ));
+        assert!(check_available(include_str!("../tests/fixtures/pages/progress.html")).is_ok());
+    }
+
+    #[test]
+    fn reports_challenges_served_with_error_statuses() {
+        let _globals = lock_globals();
+        let server = MockServer::start();
+        set_base_url(Some(server.url().parse().unwrap()));
+        let mut challenge = Reply::status(403);
+        challenge.body = CHALLENGE.into();
+        server.reply("GET", "/progress", challenge).reply(
+            "GET",
+            "/problem=1",
+            Reply::ok(MAINTENANCE),
+        );
+
+        let error = fetch_progress_page(Some("s3ss10n")).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(UnavailableError::Challenge)
+        ));
+        let error = fetch_public_page(&problem_url(1)).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(UnavailableError::Maintenance)
+        ));
+    }
+
+    #[test]
+    fn parses_retry_after() {
+        let now = SystemTime::from(
+            DateTime::parse_from_rfc2822("Sun, 06 Nov 1994 08:49:37 GMT").unwrap(),
+        );
+        for (value, wait) in [
+            ("120", Some(120)),
+            (" 0 ", Some(0)),
+            ("Sun, 06 Nov 1994 08:50:37 GMT", Some(60)),
+            // A date that's passed already means not waiting at all.
+            ("Sun, 06 Nov 1994 08:48:37 GMT", Some(0)),
+            ("-5", None),
+            ("1.5", None),
+            ("soon", None),
+            ("", None),
+        ] {
+            assert_eq!(
+                parse_retry_after(value, now),
+                wait.map(Duration::from_secs),
+                "{:?}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn waits_out_pauses_up_to_a_maximum() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let max = Duration::from_secs(120);
+        let after = |seconds| Some(now + Duration::from_secs(seconds));
+        assert_eq!(
+            pause_remaining(None, now, max).unwrap(),
+            Duration::default()
+        );
+        assert_eq!(
+            pause_remaining(Some(now - Duration::from_secs(5)), now, max).unwrap(),
+            Duration::default()
+        );
+        assert_eq!(
+            pause_remaining(after(120), now, max).unwrap(),
+            Duration::from_secs(120)
+        );
+        assert!(matches!(
+            pause_remaining(after(121), now, max),
+            Err(UnavailableError::RateLimited(wait)) if wait == Duration::from_secs(121)
+        ));
+    }
+
+    #[test]
+    fn pauses_every_request_for_too_long_a_retry_after() {
+        let _globals = lock_globals();
+        let server = MockServer::start();
+        set_base_url(Some(server.url().parse().unwrap()));
+        server
+            .reply(
+                "GET",
+                "/problem=1",
+                Reply::status(429).header("Retry-After", "3600"),
+            )
+            .reply("GET", "/problem=2", Reply::ok("<h2>Problem 2</h2>"));
+
+        let error = fetch_problem_page(1).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(UnavailableError::RateLimited(_))
+        ));
+        // The pause holds back requests to other pages too, rather than sending them.
+        let error = fetch_problem_page(2).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(UnavailableError::RateLimited(_))
+        ));
+        let received = server.received();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].path, "/problem=1");
+    }
+
+    #[test]
+    fn sanitizes_pasted_session_ids() {
+        for raw in [
+            "0123abcd",
+            "0123abcd\n",
+            "0123abcd\r\n",
+            "\u{feff}0123abcd\r\n",
+            "  0123abcd\t",
+            "\"0123abcd\"",
+            "'0123abcd'\n",
+            "0123abcd;",
+            "PHPSESSID=0123abcd",
+            "PHPSESSID = 0123abcd; ",
+            "\"PHPSESSID=0123abcd;\"\r\n",
+            "\u{feff}PHPSESSID='0123abcd'",
+        ] {
+            assert_eq!(sanitize_session_id(raw), "0123abcd", "{:?}", raw);
+        }
+    }
+
+    #[test]
+    fn keeps_what_session_ids_are_made_of() {
+        // Only a whole cookie name is stripped, and quotes only in pairs.
+        for id in ["PHPSESSIDX", "\"0123abcd", "0123=abcd", "a b"] {
+            assert_eq!(sanitize_session_id(id), id);
+        }
+        assert_eq!(sanitize_session_id(""), "");
+        assert_eq!(sanitize_session_id(" \r\n"), "");
+    }
+
+    #[test]
+    fn sends_sanitized_session_ids() {
+        let _globals = lock_globals();
+        let server = MockServer::start();
+        set_base_url(Some(server.url().parse().unwrap()));
+        server.reply("GET", "/progress", Reply::ok("progress"));
+
+        fetch_progress_page(Some("PHPSESSID=0123abcd;\r\n")).unwrap();
+        assert_eq!(
+            server.received()[0].header("Cookie"),
+            Some("PHPSESSID=0123abcd")
+        );
+    }
+
+    #[test]
+    fn bypasses_proxies_for_hosts_in_no_proxy() {
+        for (no_proxy, host) in [
+            ("*", "projecteuler.net"),
+            ("projecteuler.net", "projecteuler.net"),
+            ("projecteuler.net", "www.projecteuler.net"),
+            (".projecteuler.net", "www.projecteuler.net"),
+            ("*.projecteuler.net", "projecteuler.net"),
+            ("localhost, projecteuler.net", "projecteuler.net"),
+            ("localhost projecteuler.net", "projecteuler.net"),
+            ("PROJECTEULER.NET", "ProjectEuler.net."),
+            ("projecteuler.net:443", "projecteuler.net"),
+            ("127.0.0.1:8080", "127.0.0.1"),
+            ("::1", "[::1]"),
+            ("[::1]:8080", "::1"),
+        ] {
+            assert!(
+                bypasses_proxy(no_proxy, host),
+                "{:?} should bypass {:?}",
+                host,
+                no_proxy
+            );
+        }
+    }
+
+    #[test]
+    fn proxies_hosts_not_in_no_proxy() {
+        for (no_proxy, host) in [
+            ("", "projecteuler.net"),
+            (" , ", "projecteuler.net"),
+            ("euler.net", "projecteuler.net"),
+            ("www.projecteuler.net", "projecteuler.net"),
+            ("projecteuler.net", "projecteuler.network"),
+            (".", "projecteuler.net"),
+            ("*.", "projecteuler.net"),
+            ("127.0.0.1", "127.0.0.10"),
+            ("::1", "[::10]"),
+        ] {
+            assert!(
+                !bypasses_proxy(no_proxy, host),
+                "{:?} shouldn't bypass {:?}",
+                host,
+                no_proxy
+            );
+        }
+    }
+
+    #[test]
+    fn reads_proxies_from_the_environment() {
+        use std::env::{remove_var, set_var};
+
+        const VARS: &[&str] = &[
+            "https_proxy",
+            "HTTPS_PROXY",
+            "http_proxy",
+            "HTTP_PROXY",
+            "all_proxy",
+            "ALL_PROXY",
+            "no_proxy",
+            "NO_PROXY",
+        ];
+
+        let _globals = lock_globals();
+        let saved = VARS
+            .iter()
+            .map(|name| (name, std::env::var_os(name)))
+            .collect::<Vec<_>>();
+        for name in VARS {
+            remove_var(name);
+        }
+        let proxy = |url: &str| proxy_for(&url.parse().unwrap()).map(|proxy| proxy.to_string());
+
+        let site = "https://projecteuler.net/progress";
+        assert_eq!(proxy(site), None);
+        set_var("ALL_PROXY", "proxy.example:3128");
+        assert_eq!(proxy(site).as_deref(), Some("http://proxy.example:3128/"));
+        set_var("HTTPS_PROXY", "socks5://secure.example:1080");
+        set_var("http_proxy", "http://plain.example:8080");
+        assert_eq!(
+            proxy(site).as_deref(),
+            Some("socks5://secure.example:1080/")
+        );
+        assert_eq!(
+            proxy("http://projecteuler.net/").as_deref(),
+            Some("http://plain.example:8080/")
+        );
+        // An empty variable counts as unset.
+        set_var("https_proxy", "");
+        assert_eq!(
+            proxy(site).as_deref(),
+            Some("socks5://secure.example:1080/")
+        );
+        set_var("NO_PROXY", "example.com, .projecteuler.net");
+        assert_eq!(proxy(site), None);
+        assert_eq!(
+            proxy("https://mirror.example/").as_deref(),
+            Some("socks5://secure.example:1080/")
+        );
+
+        for (name, value) in saved {
+            match value {
+                Some(value) => set_var(name, value),
+                None => remove_var(name),
+            }
+        }
+    }
+
+    #[test]
+    fn posts_json_waiting_out_rate_limits() {
+        let _globals = lock_globals();
+        let server = MockServer::start();
+        server
+            .reply(
+                "POST",
+                "/hook",
+                Reply::status(429).header("Retry-After", "0"),
+            )
+            .reply("POST", "/hook", Reply::status(204));
+        let url = format!("{}hook", server.url());
+
+        post_json(&url, &serde_json::json!({ "text": "Solved 26" })).unwrap();
+        let received = server.received();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[1].body, r#"{"text":"Solved 26"}"#);
+        assert_eq!(received[1].header("Content-Type"), Some("application/json"));
+    }
+
+    #[test]
+    fn posts_no_json_in_a_dry_run() {
+        let _globals = lock_globals();
+        let server = MockServer::start();
+        set_dry_run(Some(DryRun::ReadsOk));
+
+        let error = post_json(&format!("{}hook", server.url()), &"hi").unwrap_err();
+        assert!(error.is::<DryRunError>());
+        assert!(server.received().is_empty());
+    }
+}
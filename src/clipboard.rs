@@ -0,0 +1,47 @@
+//! Copying text to the system clipboard.
+
+use {log::warn, std::error::Error};
+
+/// Somewhere text can be copied to, so it can be pasted elsewhere.
+pub trait Clipboard {
+    fn set_text(&mut self, text: &str) -> Result<(), Box<dyn Error>>;
+}
+
+/// The clipboard of the desktop, on X11, Wayland, macOS or Windows. On Linux, what's copied only
+/// outlives the tool if a clipboard manager is running to take it over.
+#[derive(Debug, Default)]
+pub struct SystemClipboard;
+
+impl Clipboard for SystemClipboard {
+    fn set_text(&mut self, text: &str) -> Result<(), Box<dyn Error>> {
+        arboard::Clipboard::new()?.set_text(text)?;
+        Ok(())
+    }
+}
+
+/// Copies `text` to `clipboard`, warning instead of failing when there's no clipboard, like on
+/// headless systems.
+pub fn copy(clipboard: &mut dyn Clipboard, text: &str) {
+    if let Err(e) = clipboard.set_text(text) {
+        warn!("unable to copy to the clipboard: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::test_support::FakeClipboard};
+
+    #[test]
+    fn copies_text() {
+        let mut clipboard = FakeClipboard::default();
+        copy(&mut clipboard, "233168");
+        assert_eq!(clipboard.copied, ["233168"]);
+    }
+
+    #[test]
+    fn copies_nothing_without_failing_when_theres_no_clipboard() {
+        let mut clipboard = FakeClipboard::unavailable();
+        copy(&mut clipboard, "233168");
+        assert!(clipboard.copied.is_empty());
+    }
+}
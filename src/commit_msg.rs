@@ -0,0 +1,151 @@
+use {
+    crate::{
+        context::Context,
+        endpoints::problem_url,
+        enrichment::Enrichment,
+        paths::config_dir,
+        statement::Statement,
+        template::Template,
+        workspace::{Layout, Workspace},
+    },
+    std::{error::Error, path::PathBuf},
+    structopt::StructOpt,
+};
+
+const USER_TEMPLATE_FILE_NAME: &str = "commit-msg.hbs";
+const BUILTIN_TEMPLATE: &str = include_str!("templates/commit-msg.hbs");
+
+/// Print a commit message for solving a problem, for use like `git commit -F <(cargo euler
+/// commit-msg 42)`.
+#[derive(Debug, StructOpt)]
+pub struct CommitMsg {
+    /// The number of the problem that was solved.
+    number: usize,
+    /// The template file to render the message from. Defaults to the template configured in
+    /// `euler.toml`, then `commit-msg.hbs` in the user's configuration directory, then the
+    /// built-in template.
+    #[structopt(long, parse(from_os_str))]
+    template: Option<PathBuf>,
+}
+
+fn resolve_template(
+    requested: Option<PathBuf>,
+    workspace: Option<&Workspace>,
+) -> Result<Template, Box<dyn Error>> {
+    if let Some(requested) = requested {
+        return Template::read(&requested);
+    }
+    if let Some(workspace) = workspace {
+        if let Some(path) = &workspace.config.templates.commit_message {
+            return Template::read(&workspace.root.join(path));
+        }
+    }
+    let user_template = config_dir().join("templates").join(USER_TEMPLATE_FILE_NAME);
+    if user_template.is_file() {
+        return Template::read(&user_template);
+    }
+    Ok(Template::new(
+        "built-in commit message template",
+        BUILTIN_TEMPLATE,
+    ))
+}
+
+/// The variables of a commit message template that describe the problem, named like those of
+/// solution templates.
+fn problem_vars(number: usize, title: &str, layout: &Layout) -> Vec<(&'static str, String)> {
+    let heading = if title.is_empty() {
+        format!("Problem {}", number)
+    } else {
+        format!("Problem {}: {}", number, title)
+    };
+    vec![
+        ("number", number.to_string()),
+        ("padded_number", layout.padded_number(number)),
+        ("title", title.to_owned()),
+        ("heading", heading),
+        ("url", problem_url(number)),
+    ]
+}
+
+impl CommitMsg {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self { number, template } = self;
+
+        let workspace = Workspace::discover().ok();
+        let template = resolve_template(template, workspace.as_ref())?;
+        let enrichment = Enrichment::load_cached();
+        let title = enrichment
+            .get(number)
+            .map(|info| info.title.clone())
+            .or_else(|| Statement::cached(number).map(|statement| statement.title))
+            .unwrap_or_default();
+        let progress = context.progress()?;
+
+        let default_layout = Layout::default();
+        let layout = workspace
+            .as_ref()
+            .map_or(&default_layout, |workspace| &workspace.config.layout);
+        let mut vars = problem_vars(number, &title, layout);
+        vars.extend(vec![
+            ("solved", progress.problems.solved_count().to_string()),
+            ("total", progress.problems.available().to_string()),
+            ("level", progress.levels.current().to_string()),
+        ]);
+        let vars = vars
+            .iter()
+            .map(|(name, value)| (*name, &**value))
+            .collect::<Vec<_>>();
+        print!("{}", template.render(&vars)?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::test_support::lock_globals};
+
+    fn render(template: &Template, vars: &[(&str, String)]) -> String {
+        let vars = vars
+            .iter()
+            .map(|(name, value)| (*name, &**value))
+            .collect::<Vec<_>>();
+        template.render(&vars).unwrap()
+    }
+
+    #[test]
+    fn renders_the_built_in_template() {
+        let template = Template::new("built-in", BUILTIN_TEMPLATE);
+        for (title, subject) in &[
+            (
+                "Coded triangle numbers",
+                "Solve Problem 42: Coded triangle numbers",
+            ),
+            ("", "Solve Problem 42"),
+        ] {
+            let mut vars = problem_vars(42, title, &Layout::default());
+            vars.extend(vec![
+                ("solved", "42".to_owned()),
+                ("total", "900".to_owned()),
+                ("level", "1".to_owned()),
+            ]);
+            assert_eq!(
+                render(&template, &vars),
+                format!(
+                    "{}\n\nSolved 42 of 900 problems, reaching level 1.\n",
+                    subject
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn names_problem_variables_like_solution_templates() {
+        let _globals = lock_globals();
+        let vars = problem_vars(7, "10001st prime", &Layout::default());
+        let template = Template::new("test", "{{padded_number}} {{heading}} <{{url}}>\n");
+        assert_eq!(
+            render(&template, &vars),
+            "007 Problem 7: 10001st prime <https://projecteuler.net/problem=7>\n"
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use {
+    crate::{
+        client::{fetch_progress_page, DryRun, Session},
+        endpoints::parse_base_url,
+        history::{append_snapshot, Snapshot},
+        output::Format,
+        paths::{create_cache_dir, profile_cache_dir},
+        progress::{self, Progress},
+        timings,
+    },
+    chrono::Utc,
+    log::{debug, warn},
+    reqwest::Url,
+    std::{
+        error::Error,
+        fs::{read_to_string, remove_file, write},
+        io::ErrorKind,
+        path::PathBuf,
+        sync::Mutex,
+    },
+    structopt::StructOpt,
+    unhtml::FromHtml,
+};
+
+pub const PROGRESS_CACHE_FILE_NAME: &str = "progress.html";
+
+/// Options shared by every command.
+#[derive(Debug, StructOpt)]
+pub struct Context {
+    /// The value of the `PHPSESSID` cookie to authenticate with. Read from the `PHPSESSID` file
+    /// in the configuration directory if omitted, or in the current directory if there's one
+    /// there.
+    #[structopt(long, global = true)]
+    pub session_id: Option<String>,
+    /// The name of the profile to store persistent data under, for keeping data from several
+    /// accounts apart.
+    #[structopt(long, global = true, default_value = "default")]
+    pub profile: String,
+    /// Never make network requests, relying on cached data instead.
+    #[structopt(long, global = true)]
+    pub offline: bool,
+    /// Don't record a snapshot of fetched progress in the history.
+    #[structopt(long, global = true)]
+    pub no_history: bool,
+    /// The format to log in: `human`, or `json` for one object per line. Which records are
+    /// logged is still controlled by `RUST_LOG`.
+    #[structopt(long, global = true, default_value = "human")]
+    pub log_format: Format,
+    /// Print the requests that would be made instead of making them, and make changes nowhere.
+    /// With `--dry-run=reads-ok`, requests that only read from the site are still made.
+    #[structopt(
+        long,
+        global = true,
+        min_values = 0,
+        max_values = 1,
+        require_equals = true
+    )]
+    dry_run: Option<Option<DryRun>>,
+    /// Record every request to the site and its response as a fixture in this directory, with
+    /// session cookies and form tokens scrubbed.
+    #[structopt(long, global = true, parse(from_os_str))]
+    pub record_fixtures: Option<PathBuf>,
+    /// Answer requests with the fixtures recorded in this directory instead of the site, failing
+    /// on any request that wasn't recorded.
+    #[structopt(
+        long,
+        global = true,
+        parse(from_os_str),
+        conflicts_with = "record-fixtures"
+    )]
+    pub replay_fixtures: Option<PathBuf>,
+    /// Print how long each phase of the command took on standard error once it's done: resolving
+    /// the session, sending requests (including DNS, connecting and TLS, which aren't told
+    /// apart), transferring responses, parsing, loading enrichment and rendering. Also added
+    /// under a `timings` key of `--format json` output.
+    #[structopt(long, global = true)]
+    pub timings: bool,
+    /// Leave personal details out of `--format json` output, so that it can be shared publicly:
+    /// your own username is removed, those of others (like friends) are replaced with
+    /// pseudonyms that stay the same from run to run of a profile, and countries and local paths
+    /// are removed.
+    #[structopt(long, global = true)]
+    pub anonymize: bool,
+    /// Connect to the site directly, even if the environment configures a proxy with
+    /// `HTTPS_PROXY` or the like. Otherwise, hosts listed in `NO_PROXY` are connected to directly.
+    #[structopt(long, global = true)]
+    pub no_proxy: bool,
+    /// Use this URL, like `http://localhost:8080`, in place of `https://projecteuler.net` for
+    /// every page, like for a mirror or a test server. Falls back to `base_url` in the user
+    /// configuration.
+    #[structopt(
+        long,
+        global = true,
+        env = "CARGO_EULER_BASE_URL",
+        parse(try_from_str = parse_base_url)
+    )]
+    pub base_url: Option<Url>,
+    /// The username found on the last progress page loaded, if one has been, so that finding it
+    /// doesn't fetch the page again.
+    #[structopt(skip)]
+    loaded_username: Mutex<Option<Option<String>>>,
+}
+
+impl Context {
+    pub fn dry_run(&self) -> Option<DryRun> {
+        self.dry_run.map(|dry_run| dry_run.unwrap_or(DryRun::All))
+    }
+
+    /// Where the progress page of the last successful fetch is kept, for `--offline` use.
+    pub fn progress_cache_path(&self) -> PathBuf {
+        profile_cache_dir(&self.profile).join(PROGRESS_CACHE_FILE_NAME)
+    }
+
+    /// Forgets the cached progress page, like after it's known to be out of date.
+    pub fn invalidate_progress_cache(&self) {
+        let cache_path = self.progress_cache_path();
+        if let Err(e) = remove_file(&cache_path) {
+            if e.kind() != ErrorKind::NotFound {
+                warn!(
+                    "unable to remove cached progress at {}: {}",
+                    cache_path.display(),
+                    e
+                );
+            }
+        }
+    }
+
+    /// Whether progress is read from the cache rather than fetched, which it is when offline or in
+    /// a dry run that sends no requests.
+    fn reads_cached_progress(&self) -> bool {
+        self.offline || self.dry_run() == Some(DryRun::All)
+    }
+
+    /// Fetches the HTML of the progress page and parses it, or reads the cached copy when offline
+    /// or in a dry run that sends no requests. Fetched pages are only cached once they parse, so
+    /// that an error page or one asking to log in doesn't replace the last good copy.
+    pub fn progress_page(&self) -> Result<(String, Progress), Box<dyn Error>> {
+        let cache_path = self.progress_cache_path();
+        if self.reads_cached_progress() {
+            let page = read_to_string(&cache_path).map_err(|e| {
+                format!(
+                    "unable to read cached progress from {} ({}); run without `--offline` or \
+                     `--dry-run` first",
+                    cache_path.display(),
+                    e
+                )
+            })?;
+            let progress = timings::time("parse", || Progress::from_html(&page))?;
+            return Ok((page, progress));
+        }
+
+        let page = fetch_progress_page(self.session_id.as_deref())?;
+        let progress = timings::time("parse", || Progress::from_html(&page))?;
+        if let Err(e) = cache_path
+            .parent()
+            .map_or(Ok(()), create_cache_dir)
+            .and_then(|()| write(&cache_path, &page))
+        {
+            warn!(
+                "unable to cache progress at {}: {}",
+                cache_path.display(),
+                e
+            );
+        }
+        Ok((page, progress))
+    }
+
+    /// Authenticates as the user of the session, for requests that can't be answered from a
+    /// cache.
+    pub fn session(&self) -> Result<Session, Box<dyn Error>> {
+        if self.offline {
+            return Err("this can't be done with `--offline`".into());
+        }
+        Session::new(self.session_id.as_deref())
+    }
+
+    /// Fetches and parses progress, or reads it from the cache when offline. Freshly fetched
+    /// progress is recorded in the history, except in a dry run, which changes nothing.
+    pub fn progress(&self) -> Result<Progress, Box<dyn Error>> {
+        Ok(self.progress_and_username()?.0)
+    }
+
+    /// Like `progress`, but also returns the username the progress page is headed with, if any.
+    pub fn progress_and_username(&self) -> Result<(Progress, Option<String>), Box<dyn Error>> {
+        let (page, progress) = self.progress_page()?;
+        debug!(
+            section = "problems",
+            problem_count = progress.problems.published();
+            "parsed {} problems from the progress page",
+            progress.problems.published()
+        );
+        // Cached progress isn't as of now, so recording it would date it wrong.
+        if !self.reads_cached_progress() && self.dry_run().is_none() && !self.no_history {
+            if let Err(e) = append_snapshot(&self.profile, &Snapshot::of(&progress, Utc::now())) {
+                warn!("unable to record progress in the history: {}", e);
+            }
+        }
+        let username = progress::username(&page);
+        *self.loaded_username.lock().unwrap() = Some(username.clone());
+        Ok((progress, username))
+    }
+
+    /// The username the progress page is headed with, if it can be found. Progress is only
+    /// fetched for it if it hasn't been already.
+    pub fn username(&self) -> Option<String> {
+        if let Some(username) = &*self.loaded_username.lock().unwrap() {
+            return username.clone();
+        }
+        match self.progress_and_username() {
+            Ok((_, username)) => username,
+            Err(e) => {
+                warn!("unable to find your username: {}", e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            client::set_dry_run,
+            endpoints::set_base_url,
+            history::read_history,
+            test_support::{lock_globals, MockServer, Reply},
+        },
+    };
+
+    const PROGRESS: &str = include_str!("../tests/fixtures/pages/progress.html");
+
+    /// A context parsed from `args`, with its dry run set up like at startup.
+    fn context(args: &[&str]) -> Context {
+        let context = Context::from_iter(
+            ["cargo-euler", "--session-id", "s3ss10n"]
+                .iter()
+                .chain(args),
+        );
+        set_dry_run(context.dry_run());
+        context
+    }
+
+    fn cache_progress(context: &Context, page: &str) {
+        let cache_path = context.progress_cache_path();
+        create_cache_dir(cache_path.parent().unwrap()).unwrap();
+        write(cache_path, page).unwrap();
+    }
+
+    #[test]
+    fn sends_nothing_in_a_full_dry_run() {
+        let _globals = lock_globals();
+        let server = MockServer::start();
+        set_base_url(Some(server.url().parse().unwrap()));
+        let context = context(&["--dry-run"]);
+        cache_progress(&context, PROGRESS);
+
+        let (progress, username) = context.progress_and_username().unwrap();
+        assert_eq!(progress.problems.solved_count(), 26);
+        assert_eq!(username.as_deref(), Some("euler_fan"));
+        assert!(server.received().is_empty());
+        assert!(read_history(&context.profile).unwrap().is_empty());
+    }
+
+    #[test]
+    fn records_no_history_in_a_dry_run_that_reads() {
+        let _globals = lock_globals();
+        let server = MockServer::start();
+        server.reply("GET", "/progress", Reply::ok(PROGRESS));
+        set_base_url(Some(server.url().parse().unwrap()));
+        let context = context(&["--dry-run=reads-ok"]);
+
+        assert_eq!(context.progress().unwrap().problems.solved_count(), 26);
+        assert_eq!(server.received().len(), 1);
+        assert!(read_history(&context.profile).unwrap().is_empty());
+    }
+
+    #[test]
+    fn records_no_history_of_cached_progress() {
+        let _globals = lock_globals();
+        let context = context(&["--offline"]);
+        cache_progress(&context, PROGRESS);
+
+        context.progress().unwrap();
+        assert!(read_history(&context.profile).unwrap().is_empty());
+    }
+
+    #[test]
+    fn records_fetched_progress() {
+        let _globals = lock_globals();
+        let server = MockServer::start();
+        server.reply("GET", "/progress", Reply::ok(PROGRESS));
+        set_base_url(Some(server.url().parse().unwrap()));
+        let context = context(&[]);
+
+        context.progress().unwrap();
+        let history = read_history(&context.profile).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].solved_count, 26);
+        assert_eq!(
+            read_to_string(context.progress_cache_path()).unwrap(),
+            PROGRESS
+        );
+    }
+
+    #[test]
+    fn fetches_progress_once_for_the_username() {
+        let _globals = lock_globals();
+        let server = MockServer::start();
+        server.reply("GET", "/progress", Reply::ok(PROGRESS));
+        set_base_url(Some(server.url().parse().unwrap()));
+        let context = context(&[]);
+
+        context.progress().unwrap();
+        assert_eq!(context.username().as_deref(), Some("euler_fan"));
+        assert_eq!(server.received().len(), 1);
+        assert_eq!(read_history(&context.profile).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,18 @@
+//! Native desktop notifications.
+
+use notify_rust::Notification;
+
+/// Shows `message` in a desktop notification from "Project Euler", falling back to printing it
+/// to standard error when there's no notification service to show it.
+pub fn notify(message: &str) {
+    if let Err(e) = Notification::new()
+        .summary("Project Euler")
+        .body(message)
+        .show()
+    {
+        eprintln!(
+            "Project Euler: {} (unable to show a desktop notification: {})",
+            message, e
+        );
+    }
+}
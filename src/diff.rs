@@ -0,0 +1,413 @@
+use {
+    crate::{
+        context::Context,
+        enrichment::Enrichment,
+        history::{read_history, Snapshot},
+        output::{print_json, Format},
+        paths::{profile_data_dir, write_atomically},
+        solved_set::SolvedSet,
+        statement::Statement,
+        ExitStatus,
+    },
+    chrono::{DateTime, NaiveDate, Utc},
+    serde::Serialize,
+    std::{
+        error::Error,
+        fs::{create_dir_all, metadata, read_to_string},
+        io::{stdout, ErrorKind, Write},
+        path::PathBuf,
+    },
+    structopt::StructOpt,
+};
+
+/// Compare current progress against an earlier snapshot from the history. Compares against the
+/// most recent snapshot by default.
+#[derive(Debug, StructOpt)]
+pub struct Diff {
+    /// Compare against the snapshot in effect at this date (`YYYY-MM-DD`) or time (RFC 3339).
+    #[structopt(long, parse(try_from_str = parse_since), conflicts_with = "against")]
+    since: Option<DateTime<Utc>>,
+    /// Compare against the snapshot with this index, as shown by `cargo euler history list`.
+    #[structopt(long)]
+    against: Option<usize>,
+    /// Compare against a solved set exported with `cargo euler export` instead of the history.
+    #[structopt(
+        long,
+        parse(from_os_str),
+        conflicts_with_all = &["since", "against", "quiet-if-unchanged"]
+    )]
+    baseline: Option<PathBuf>,
+    /// The format to print the comparison in: `human` or `json`.
+    #[structopt(long, default_value = "human")]
+    format: Format,
+    /// Compare against what was seen the last time this flag was passed instead, printing a
+    /// line per newly solved problem and exiting with status 10 if any were, and printing
+    /// nothing otherwise. Meant for shell prompts.
+    #[structopt(long, conflicts_with_all = &["since", "against", "format"])]
+    quiet_if_unchanged: bool,
+    /// Print nothing if nothing changed, or else a single uncolored line like
+    /// `+2 solved (188) level 7`. Meant for status bars.
+    #[structopt(long, conflicts_with_all = &["format", "quiet-if-unchanged"])]
+    one_line: bool,
+}
+
+/// The exit status of `--quiet-if-unchanged` when something changed.
+const CHANGED_EXIT_STATUS: i32 = 10;
+
+const LAST_SEEN_FILE_NAME: &str = "last-seen.json";
+
+/// Reads the progress last seen by a reporter keeping it in `file_name` in the data directory
+/// of `profile`, or the latest recorded snapshot if the reporter hasn't run before. Must be
+/// called before fetching, since fetching records a new snapshot.
+pub fn read_last_seen(profile: &str, file_name: &str) -> Result<Option<Snapshot>, Box<dyn Error>> {
+    let path = profile_data_dir(profile).join(file_name);
+    match read_to_string(&path) {
+        Ok(contents) => Ok(Some(
+            serde_json::from_str::<Snapshot>(&contents)
+                .map_err(|e| format!("unable to parse {}: {}", path.display(), e))?,
+        )),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(read_history(profile)?.pop()),
+        Err(e) => Err(format!("unable to read {}: {}", path.display(), e).into()),
+    }
+}
+
+/// Records `snapshot` as the progress last seen by a reporter keeping it in `file_name`.
+pub fn write_last_seen(
+    profile: &str,
+    file_name: &str,
+    snapshot: &Snapshot,
+) -> Result<(), Box<dyn Error>> {
+    let path = profile_data_dir(profile).join(file_name);
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+    }
+    write_atomically(&path, serde_json::to_string(snapshot)?)?;
+    Ok(())
+}
+
+/// Reports problems solved since the last time this was run. The last seen progress is only
+/// updated after the report is written, so nothing goes unreported if writing it fails.
+fn notify_newly_solved(context: &Context) -> Result<(), Box<dyn Error>> {
+    let last_seen = read_last_seen(&context.profile, LAST_SEEN_FILE_NAME)?;
+    let current = Snapshot::of(&context.progress()?, Utc::now());
+
+    let mut changed = false;
+    if let Some(last_seen) = &last_seen {
+        let diff = ProgressDiff::between(last_seen, &current, &Enrichment::load_cached());
+        let mut stdout = stdout().lock();
+        for SolvedProblem { number, title } in &diff.newly_solved {
+            changed = true;
+            match title {
+                Some(title) => writeln!(stdout, "✔ {} {}", number, title)?,
+                None => writeln!(stdout, "✔ {}", number)?,
+            }
+        }
+        stdout.flush()?;
+    }
+
+    write_last_seen(&context.profile, LAST_SEEN_FILE_NAME, &current)?;
+    if changed {
+        return Err(ExitStatus(CHANGED_EXIT_STATUS).into());
+    }
+    Ok(())
+}
+
+pub fn parse_since(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc());
+    }
+    DateTime::parse_from_rfc3339(s)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|e| {
+            format!(
+                "expected a date like `2024-01-01` or an RFC 3339 time: {}",
+                e
+            )
+        })
+}
+
+#[derive(Debug, Serialize)]
+pub struct SolvedProblem {
+    pub number: usize,
+    pub title: Option<String>,
+}
+
+/// How progress changed between two snapshots.
+#[derive(Debug, Serialize)]
+pub struct ProgressDiff {
+    pub since: DateTime<Utc>,
+    /// Ascending by number.
+    pub newly_solved: Vec<SolvedProblem>,
+    pub solved_change: isize,
+    pub level_before: usize,
+    pub level_after: usize,
+}
+
+impl ProgressDiff {
+    /// Compares `after` against `before`, by problem number so that snapshots taken before
+    /// more problems were published compare correctly.
+    pub fn between(before: &Snapshot, after: &Snapshot, enrichment: &Enrichment) -> Self {
+        let newly_solved = after
+            .solved
+            .0
+            .difference(&before.solved.0)
+            .map(|&number| SolvedProblem {
+                number,
+                title: enrichment
+                    .get(number)
+                    .map(|info| info.title.clone())
+                    .or_else(|| Statement::cached(number).map(|statement| statement.title)),
+            })
+            .collect();
+        Self {
+            since: before.timestamp,
+            newly_solved,
+            solved_change: after.solved_count as isize - before.solved_count as isize,
+            level_before: before.level,
+            level_after: after.level,
+        }
+    }
+
+    /// Summarizes the change in a single line like `+2 solved (188) level 7`, given the solved
+    /// count after it, or `None` if nothing changed.
+    pub fn one_line(&self, solved_count: usize) -> Option<String> {
+        if self.newly_solved.is_empty() && self.level_after == self.level_before {
+            return None;
+        }
+        Some(format!(
+            "+{} solved ({}) level {}",
+            self.newly_solved.len(),
+            solved_count,
+            self.level_after
+        ))
+    }
+}
+
+impl Diff {
+    /// Picks the snapshot of the history to compare against. Must be called before fetching,
+    /// since fetching records a new snapshot.
+    fn baseline_from_history(
+        context: &Context,
+        since: Option<DateTime<Utc>>,
+        against: Option<usize>,
+    ) -> Result<Snapshot, Box<dyn Error>> {
+        let mut history = read_history(&context.profile)?;
+        if history.is_empty() {
+            return Err("no snapshots are recorded to compare against yet".into());
+        }
+        let baseline_idx = match (since, against) {
+            (_, Some(idx)) if idx < history.len() => idx,
+            (_, Some(idx)) => {
+                return Err(format!(
+                    "there's no snapshot {}; only {} are recorded",
+                    idx,
+                    history.len()
+                )
+                .into())
+            }
+            (Some(since), None) => history
+                .iter()
+                .rposition(|snapshot| snapshot.timestamp <= since)
+                .unwrap_or(0),
+            // Offline, the latest snapshot is usually of the cached page itself, so compare
+            // against the last one recorded before the page was cached instead.
+            (None, None) if context.offline => {
+                match metadata(context.progress_cache_path()).and_then(|m| m.modified()) {
+                    Ok(cached_at) => {
+                        let cached_at = DateTime::<Utc>::from(cached_at);
+                        history
+                            .iter()
+                            .rposition(|snapshot| snapshot.timestamp < cached_at)
+                            .unwrap_or(0)
+                    }
+                    Err(_) => history.len() - 1,
+                }
+            }
+            (None, None) => history.len() - 1,
+        };
+        Ok(history.swap_remove(baseline_idx))
+    }
+
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self {
+            since,
+            against,
+            baseline,
+            format,
+            quiet_if_unchanged,
+            one_line,
+        } = self;
+        if quiet_if_unchanged {
+            return notify_newly_solved(context);
+        }
+
+        let baseline = match baseline {
+            Some(path) => SolvedSet::read(&path)?.to_snapshot(),
+            None => Self::baseline_from_history(context, since, against)?,
+        };
+        let current = Snapshot::of(&context.progress()?, Utc::now());
+        let diff = ProgressDiff::between(&baseline, &current, &Enrichment::load_cached());
+
+        if one_line {
+            if let Some(line) = diff.one_line(current.solved_count) {
+                println!("{}", line);
+            }
+            return Ok(());
+        }
+        match format {
+            Format::Json => print_json(&diff)?,
+            Format::Human => {
+                println!(
+                    "Since {}: {:+} solved",
+                    diff.since.format("%Y-%m-%d %H:%M"),
+                    diff.solved_change
+                );
+                for SolvedProblem { number, title } in &diff.newly_solved {
+                    match title {
+                        Some(title) => println!("  Solved {}: {}", number, title),
+                        None => println!("  Solved {}", number),
+                    }
+                }
+                if diff.level_after > diff.level_before {
+                    println!(
+                        "Leveled up from level {} to {}",
+                        diff.level_before, diff.level_after
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            history::append_snapshot,
+            test_support::{lock_globals, noon, snapshot, with_each_backend},
+        },
+        chrono::{Duration, TimeZone},
+    };
+
+    /// Records snapshots on 1, 2 and 3 March, with 24, 25 and 26 problems solved.
+    fn record_history() -> Vec<Snapshot> {
+        let history = vec![snapshot(3, 1, 24), snapshot(3, 2, 25), snapshot(3, 3, 26)];
+        for snapshot in &history {
+            append_snapshot("default", snapshot).unwrap();
+        }
+        history
+    }
+
+    #[test]
+    fn compares_against_the_latest_snapshot_by_default() {
+        with_each_backend(|| {
+            let history = record_history();
+            let context = Context::from_iter(["cargo-euler"]);
+            let baseline = Diff::baseline_from_history(&context, None, None).unwrap();
+            assert_eq!(baseline.timestamp, history[2].timestamp);
+        });
+    }
+
+    #[test]
+    fn compares_against_the_snapshot_in_effect_since() {
+        with_each_backend(|| {
+            let history = record_history();
+            let context = Context::from_iter(["cargo-euler"]);
+            let baseline = |since| Diff::baseline_from_history(&context, Some(since), None);
+            assert_eq!(
+                baseline(noon(3, 2)).unwrap().timestamp,
+                history[1].timestamp
+            );
+            assert_eq!(
+                baseline(noon(3, 2) + Duration::hours(1)).unwrap().timestamp,
+                history[1].timestamp
+            );
+            assert_eq!(
+                baseline(noon(2, 1)).unwrap().timestamp,
+                history[0].timestamp
+            );
+            assert_eq!(
+                baseline(noon(4, 1)).unwrap().timestamp,
+                history[2].timestamp
+            );
+        });
+    }
+
+    #[test]
+    fn compares_against_a_snapshot_by_index() {
+        with_each_backend(|| {
+            let history = record_history();
+            let context = Context::from_iter(["cargo-euler"]);
+            let baseline = Diff::baseline_from_history(&context, None, Some(0)).unwrap();
+            assert_eq!(baseline.timestamp, history[0].timestamp);
+            assert_eq!(
+                Diff::baseline_from_history(&context, None, Some(3))
+                    .unwrap_err()
+                    .to_string(),
+                "there's no snapshot 3; only 3 are recorded"
+            );
+        });
+    }
+
+    #[test]
+    fn refuses_to_compare_against_an_empty_history() {
+        with_each_backend(|| {
+            let context = Context::from_iter(["cargo-euler"]);
+            assert_eq!(
+                Diff::baseline_from_history(&context, None, None)
+                    .unwrap_err()
+                    .to_string(),
+                "no snapshots are recorded to compare against yet"
+            );
+        });
+    }
+
+    #[test]
+    fn reads_the_latest_snapshot_as_last_seen_until_something_is_seen() {
+        with_each_backend(|| {
+            assert!(read_last_seen("default", LAST_SEEN_FILE_NAME)
+                .unwrap()
+                .is_none());
+            let history = record_history();
+            assert_eq!(
+                read_last_seen("default", LAST_SEEN_FILE_NAME)
+                    .unwrap()
+                    .map(|snapshot| snapshot.timestamp),
+                Some(history[2].timestamp)
+            );
+            write_last_seen("default", LAST_SEEN_FILE_NAME, &history[0]).unwrap();
+            assert_eq!(
+                read_last_seen("default", LAST_SEEN_FILE_NAME)
+                    .unwrap()
+                    .map(|snapshot| snapshot.timestamp),
+                Some(history[0].timestamp)
+            );
+        });
+    }
+
+    #[test]
+    fn summarizes_changes_in_one_line() {
+        let _globals = lock_globals();
+        let enrichment = Enrichment::default();
+        let diff = ProgressDiff::between(&snapshot(3, 1, 24), &snapshot(3, 2, 26), &enrichment);
+        assert_eq!(diff.one_line(26).as_deref(), Some("+2 solved (26) level 1"));
+        let unchanged =
+            ProgressDiff::between(&snapshot(3, 1, 24), &snapshot(3, 2, 24), &enrichment);
+        assert_eq!(unchanged.one_line(24), None);
+    }
+
+    #[test]
+    fn parses_since() {
+        assert_eq!(
+            parse_since("2024-03-01").unwrap(),
+            Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap()
+        );
+        assert_eq!(
+            parse_since("2024-03-01T14:30:00+02:00").unwrap(),
+            Utc.with_ymd_and_hms(2024, 3, 1, 12, 30, 0).unwrap()
+        );
+        assert!(parse_since("last week").is_err());
+    }
+}
@@ -0,0 +1,97 @@
+//! The URLs of the site's pages. They're all built here, on a base URL that can be pointed at a
+//! mirror or a test server in place of the site.
+
+use {reqwest::Url, std::sync::Mutex};
+
+pub const PROJECT_EULER_HOSTNAME: &str = "projecteuler.net";
+const PROGRESS_ENDPOINT: &str = "progress";
+
+/// The base URL configured in place of the site's, if any. Set once at startup.
+static BASE_URL: Mutex<Option<Url>> = Mutex::new(None);
+
+/// Parses a base URL to use in place of the site's, which must be only a scheme, host and maybe
+/// port, since the paths of pages are appended to it, like `http://localhost:8080`.
+pub fn parse_base_url(s: &str) -> Result<Url, String> {
+    let url = Url::parse(s).map_err(|e| format!("invalid base URL {:?}: {}", s, e))?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return Err(format!(
+            "invalid base URL {:?}; expected an `http` or `https` URL with a host",
+            s
+        ));
+    }
+    if url.path() != "/" || url.query().is_some() || url.fragment().is_some() {
+        return Err(format!(
+            "invalid base URL {:?}; it can't have a path, query or fragment, since those of pages \
+             are appended to it",
+            s
+        ));
+    }
+    Ok(url)
+}
+
+pub fn set_base_url(url: Option<Url>) {
+    *BASE_URL.lock().unwrap() = url;
+}
+
+/// The URL every page's is built on, like `https://projecteuler.net/`.
+pub fn base_url() -> Url {
+    BASE_URL
+        .lock()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| Url::parse(&format!("https://{}/", PROJECT_EULER_HOSTNAME)).unwrap())
+}
+
+/// Builds the URL of the page at `endpoint`, like an index of rankings.
+pub fn page_url(endpoint: &str) -> String {
+    format!("{}{}", base_url(), endpoint)
+}
+
+/// The URL of the progress page of the user of the session.
+pub fn progress_url() -> String {
+    page_url(PROGRESS_ENDPOINT)
+}
+
+/// Builds the URL of the discussion thread of problem `number`.
+pub fn thread_url(number: usize) -> String {
+    page_url(&format!("thread={}", number))
+}
+
+/// The URL of the friends page, which lists friends and manages friendships.
+pub fn friends_url() -> String {
+    page_url("friends")
+}
+
+/// Builds the URL of page `page` of the ranking named `name` at `endpoint`, like the ranking of
+/// a country at `location`.
+pub fn ranking_url(endpoint: &str, name: &str, page: usize) -> String {
+    let url = page_url(&format!("{}={}", endpoint, name.replace(' ', "_")));
+    match page {
+        1 => url,
+        page => format!("{};page={}", url, page),
+    }
+}
+
+/// The URL of the page to sign in on.
+pub fn sign_in_url() -> String {
+    page_url("sign_in")
+}
+
+/// The URL of the news page, which announces when upcoming problems are published.
+pub fn news_url() -> String {
+    page_url("news")
+}
+
+/// Builds the URL of page `page` of the archives, which list published problems with their
+/// titles and how many have solved them.
+pub fn archives_url(page: usize) -> String {
+    match page {
+        1 => page_url("archives"),
+        page => page_url(&format!("archives;page={}", page)),
+    }
+}
+
+/// Builds the URL of the page for problem `number`.
+pub fn problem_url(number: usize) -> String {
+    page_url(&format!("problem={}", number))
+}
@@ -0,0 +1,82 @@
+//! Metadata about problems that isn't part of the progress page, like titles and difficulty
+//! ratings. This data is optional: everything that uses it needs to degrade gracefully when it
+//! hasn't been cached. It's cached from the archives of the site with `cargo euler prefetch`.
+
+use {
+    crate::{
+        paths::{cache_dir, create_cache_dir, write_atomically},
+        timings::PhaseTimer,
+    },
+    log::warn,
+    serde::{Deserialize, Serialize},
+    std::{collections::BTreeMap, error::Error, fs::File, io::ErrorKind, path::PathBuf},
+};
+
+pub const PROBLEMS_CACHE_FILE_NAME: &str = "problems.json";
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ProblemInfo {
+    pub number: usize,
+    pub title: String,
+    /// The difficulty rating of the problem as a percentage, if one has been published.
+    pub difficulty: Option<u8>,
+    pub solved_by: Option<u64>,
+}
+
+/// Cached problem metadata, keyed by problem number.
+#[derive(Debug, Default)]
+pub struct Enrichment(BTreeMap<usize, ProblemInfo>);
+
+impl Enrichment {
+    pub fn cache_path() -> PathBuf {
+        cache_dir().join(PROBLEMS_CACHE_FILE_NAME)
+    }
+
+    /// Loads cached problem metadata, yielding an empty set if none has been cached yet. A
+    /// corrupt cache is reported and otherwise treated the same as a missing one.
+    pub fn load_cached() -> Self {
+        let _timer = PhaseTimer::start("enrichment");
+        let path = Self::cache_path();
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) => {
+                if e.kind() != ErrorKind::NotFound {
+                    warn!("unable to open {}: {}", path.display(), e);
+                }
+                return Self::default();
+            }
+        };
+        match serde_json::from_reader::<_, Vec<ProblemInfo>>(file) {
+            Ok(infos) => Self(infos.into_iter().map(|info| (info.number, info)).collect()),
+            Err(e) => {
+                warn!("ignoring corrupt problem cache {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Replaces the cache with this metadata.
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = Self::cache_path();
+        create_cache_dir(&cache_dir())?;
+        let json = serde_json::to_string_pretty(&self.0.values().collect::<Vec<_>>())?;
+        write_atomically(&path, json)
+            .map_err(|e| format!("unable to write {}: {}", path.display(), e).into())
+    }
+
+    pub fn insert(&mut self, info: ProblemInfo) {
+        self.0.insert(info.number, info);
+    }
+
+    pub fn get(&self, number: usize) -> Option<&ProblemInfo> {
+        self.0.get(&number)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ProblemInfo> {
+        self.0.values()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
@@ -0,0 +1,285 @@
+//! The Eulerians page, ranking members by their performance on recently published problems.
+
+use {
+    crate::{
+        context::Context,
+        endpoints::page_url,
+        friends::{cell_text, parse_count},
+        output::{bold, print_json, Format},
+    },
+    serde::Serialize,
+    std::error::Error,
+    structopt::StructOpt,
+    unhtml::scraper::{ElementRef, Html, Selector},
+};
+
+const EULERIANS_ENDPOINT: &str = "eulerians";
+
+/// A row of the Eulerians ranking.
+#[derive(Debug, Serialize)]
+pub struct Eulerian {
+    pub place: usize,
+    pub username: String,
+    pub score: f64,
+}
+
+/// Parses a score like "1,234.5", ignoring anything around it.
+fn parse_score(text: &str) -> Option<f64> {
+    let number = text
+        .chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(|c| c.is_ascii_digit() || *c == ',' || *c == '.')
+        .filter(|c| *c != ',')
+        .collect::<String>();
+    number.parse().ok()
+}
+
+/// The username in `cell`. The top entries decorate it, so the text of its link is preferred.
+fn username(cell: ElementRef<'_>) -> String {
+    cell.select(&Selector::parse("a").unwrap())
+        .map(cell_text)
+        .find(|text| !text.is_empty())
+        .unwrap_or_else(|| cell_text(cell))
+}
+
+/// Parses the ranking of the Eulerians page. Columns are found by their headings. The top
+/// entries are formatted specially, with medals in place of their places and cells that may be
+/// headings, so a missing place follows on from the row before it.
+pub fn parse_eulerians(page: &str) -> Result<Vec<Eulerian>, Box<dyn Error>> {
+    let html = Html::parse_document(page);
+    let row_selector = Selector::parse("tr").unwrap();
+    let heading_selector = Selector::parse("th").unwrap();
+    let cell_selector = Selector::parse("td, th").unwrap();
+
+    for table in html.select(&Selector::parse("table").unwrap()) {
+        let headings = table
+            .select(&row_selector)
+            .find(|row| row.select(&Selector::parse("td").unwrap()).next().is_none())
+            .map(|row| {
+                row.select(&heading_selector)
+                    .map(|heading| cell_text(heading).to_lowercase())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let column = |names: &[&str]| {
+            headings
+                .iter()
+                .position(|heading| names.iter().any(|name| heading.contains(name)))
+        };
+        let (username_column, score_column) = match (
+            column(&["username", "alias", "name"]),
+            column(&["score", "points", "rating"]),
+        ) {
+            (Some(username), Some(score)) => (username, score),
+            _ => continue,
+        };
+        let place_column = headings.iter().position(|heading| {
+            heading == "#" || heading.contains("place") || heading.contains("rank")
+        });
+
+        let mut eulerians = Vec::<Eulerian>::new();
+        for row in table.select(&row_selector) {
+            let cells = row.select(&cell_selector).collect::<Vec<_>>();
+            if row.select(&heading_selector).count() == cells.len() {
+                continue;
+            }
+            let (username_cell, score) = match (
+                cells.get(username_column),
+                cells
+                    .get(score_column)
+                    .and_then(|cell| parse_score(&cell_text(*cell))),
+            ) {
+                (Some(username_cell), Some(score)) => (*username_cell, score),
+                _ => continue,
+            };
+            let place = place_column
+                .and_then(|column| cells.get(column))
+                .and_then(|cell| parse_count(&cell_text(*cell)))
+                .unwrap_or_else(|| eulerians.last().map_or(1, |previous| previous.place + 1));
+            eulerians.push(Eulerian {
+                place,
+                username: username(username_cell),
+                score,
+            });
+        }
+        return Ok(eulerians);
+    }
+    Err("unable to find the ranking on the Eulerians page".into())
+}
+
+#[derive(Debug, Serialize)]
+struct Entry<'a> {
+    #[serde(flatten)]
+    eulerian: &'a Eulerian,
+    me: bool,
+}
+
+/// Show the Eulerians ranking, of performance on recently published problems.
+#[derive(Debug, StructOpt)]
+pub struct Eulerians {
+    /// Only show members with at least this score.
+    #[structopt(long)]
+    min_score: Option<f64>,
+    /// Your username, to highlight your row by. Read from your progress page if omitted.
+    #[structopt(long)]
+    me: Option<String>,
+    /// The format to print the ranking in: `human` or `json`.
+    #[structopt(long, default_value = "human")]
+    format: Format,
+}
+
+/// The Eulerians on `page` scoring at least `min_score`, by place.
+fn ranking(page: &str, min_score: Option<f64>) -> Result<Vec<Eulerian>, Box<dyn Error>> {
+    let mut eulerians = parse_eulerians(page)?;
+    // Places are read from the page, which should list them in order already.
+    eulerians.sort_by_key(|eulerian| eulerian.place);
+    if let Some(min_score) = min_score {
+        eulerians.retain(|eulerian| eulerian.score >= min_score);
+    }
+    Ok(eulerians)
+}
+
+impl Eulerians {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self {
+            min_score,
+            me,
+            format,
+        } = self;
+
+        let page = context
+            .session()?
+            .fetch_page(&page_url(EULERIANS_ENDPOINT))?;
+        let eulerians = ranking(&page, min_score)?;
+        let me = me.or_else(|| context.username());
+        let is_me = |eulerian: &Eulerian| {
+            me.as_ref()
+                .is_some_and(|me| eulerian.username.eq_ignore_ascii_case(me))
+        };
+
+        match format {
+            Format::Human => {
+                let width = eulerians
+                    .iter()
+                    .map(|eulerian| eulerian.username.chars().count())
+                    .max()
+                    .unwrap_or(0);
+                for eulerian in &eulerians {
+                    let line = format!(
+                        "{} {:>5}. {:<width$}  {:>8.2}",
+                        if is_me(eulerian) { ">" } else { " " },
+                        eulerian.place,
+                        eulerian.username,
+                        eulerian.score,
+                        width = width,
+                    );
+                    if is_me(eulerian) {
+                        println!("{}", bold(&line));
+                    } else {
+                        println!("{}", line);
+                    }
+                }
+            }
+            Format::Json => {
+                let entries = eulerians
+                    .iter()
+                    .map(|eulerian| Entry {
+                        eulerian,
+                        me: is_me(eulerian),
+                    })
+                    .collect::<Vec<_>>();
+                print_json(&entries)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            endpoints::set_base_url,
+            history::read_history,
+            test_support::{lock_globals, MockServer, Reply},
+        },
+    };
+
+    const EULERIANS: &str = r#"
+        <table class="grid">
+          <tr><th>#</th><th>Username</th><th>Country</th><th>Score</th></tr>
+          <tr><th><img src="images/medal_gold.png"></th><th><a href="profile=ace">ace</a></th><td>Sweden</td><td>1,234.5</td></tr>
+          <tr><td>2</td><td><a href="profile=euler_fan">euler_fan</a></td><td>Canada</td><td>987.25</td></tr>
+          <tr><td>4</td><td>quiet</td><td></td><td>12</td></tr>
+        </table>
+    "#;
+
+    #[test]
+    fn parses_eulerians() {
+        let eulerians = parse_eulerians(EULERIANS).unwrap();
+        let summary = eulerians
+            .iter()
+            .map(|eulerian| (eulerian.place, &*eulerian.username, eulerian.score))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                (1, "ace", 1234.5),
+                (2, "euler_fan", 987.25),
+                (4, "quiet", 12.0)
+            ]
+        );
+        assert!(parse_eulerians("<table><tr><th>Nothing</th></tr></table>").is_err());
+    }
+
+    #[test]
+    fn ranks_eulerians_by_place() {
+        let page = r#"
+            <table>
+              <tr><th>#</th><th>Username</th><th>Score</th></tr>
+              <tr><td>3</td><td>third</td><td>50</td></tr>
+              <tr><td>1</td><td>first</td><td>90</td></tr>
+              <tr><td>2</td><td>second</td><td>70</td></tr>
+            </table>
+        "#;
+        let usernames = |eulerians: Vec<Eulerian>| {
+            eulerians
+                .into_iter()
+                .map(|eulerian| eulerian.username)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            usernames(ranking(page, None).unwrap()),
+            ["first", "second", "third"]
+        );
+        assert_eq!(
+            usernames(ranking(page, Some(60.0)).unwrap()),
+            ["first", "second"]
+        );
+    }
+
+    #[test]
+    fn fetches_progress_once_to_find_the_user() {
+        let _globals = lock_globals();
+        let server = MockServer::start();
+        server
+            .reply("GET", "/eulerians", Reply::ok(EULERIANS))
+            .reply(
+                "GET",
+                "/progress",
+                Reply::ok(include_str!("../tests/fixtures/pages/progress.html")),
+            );
+        set_base_url(Some(server.url().parse().unwrap()));
+        let context = Context::from_iter(&["cargo-euler", "--session-id", "s3ss10n"]);
+
+        Eulerians::from_iter(&["eulerians"]).run(&context).unwrap();
+        let paths = server
+            .received()
+            .into_iter()
+            .map(|received| received.path)
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["/eulerians", "/progress"]);
+        assert_eq!(read_history(&context.profile).unwrap().len(), 1);
+    }
+}
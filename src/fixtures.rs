@@ -0,0 +1,403 @@
+//! Recording requests to the site and their responses as fixtures, and replaying them in place of
+//! the site, so that whole flows of commands can be run again without it.
+//!
+//! Each exchange is recorded as a JSON file, numbered in the order it happened. They'd be YAML
+//! like other tools' cassettes, but no YAML crate is available to this build, and JSON keeps the
+//! pages readable enough to edit by hand. The flows recorded for the tests are in
+//! `tests/fixtures/flows`.
+
+use {
+    crate::client::SESSION_COOKIE_NAME,
+    base64::{engine::general_purpose::STANDARD, Engine},
+    once_cell::sync::Lazy,
+    reqwest::{
+        header::{HeaderMap, HeaderName, HeaderValue, COOKIE, SET_COOKIE},
+        Method, Request, Response, Url,
+    },
+    serde::{Deserialize, Serialize},
+    std::{
+        cmp::Reverse,
+        error::Error,
+        ffi::OsStr,
+        fmt::{self, Display, Formatter},
+        fs::{create_dir_all, read_dir, read_to_string, write},
+        path::{Path, PathBuf},
+        sync::Mutex,
+    },
+    unhtml::scraper::{Html, Selector},
+};
+
+/// What takes the place of secrets, like session IDs and form tokens, in recorded responses.
+const SCRUBBED: &str = "scrubbed";
+
+/// A request and the response it got.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Exchange {
+    pub method: String,
+    /// The path and query of the request's URL, like `/progress` or `/archives;page=2`.
+    pub path: String,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    /// The body of the response, if it's text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// The body of the response encoded as base 64, if it isn't text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_base64: Option<String>,
+}
+
+fn path_of(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_owned(),
+    }
+}
+
+/// The values of the session cookie in `Cookie` or `Set-Cookie` headers.
+fn session_ids<'a>(headers: &'a HeaderMap, name: &'a HeaderName) -> impl Iterator<Item = &'a str> {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| {
+            pair.trim()
+                .strip_prefix(SESSION_COOKIE_NAME)?
+                .strip_prefix('=')
+        })
+}
+
+/// The values of form fields in `page` that look like tokens guarding against forgery.
+fn form_tokens(page: &str) -> Vec<String> {
+    static TOKENS: Lazy<Selector> =
+        Lazy::new(|| Selector::parse("input[type=hidden][name][value]").unwrap());
+
+    Html::parse_document(page)
+        .select(&TOKENS)
+        .filter(|input| {
+            let name = input
+                .value()
+                .attr("name")
+                .unwrap_or_default()
+                .to_lowercase();
+            name.contains("token") || name.contains("csrf")
+        })
+        .filter_map(|input| input.value().attr("value"))
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Replaces every occurrence of `secrets` in `text`.
+fn scrub(text: &str, secrets: &[String]) -> String {
+    secrets.iter().fold(text.to_owned(), |text, secret| {
+        text.replace(&**secret, SCRUBBED)
+    })
+}
+
+impl Exchange {
+    fn into_response(self) -> Result<Response, Box<dyn Error>> {
+        let body = match (self.body, self.body_base64) {
+            (_, Some(encoded)) => STANDARD.decode(encoded)?,
+            (Some(body), None) => body.into_bytes(),
+            (None, None) => Vec::new(),
+        };
+        let mut response = http::Response::builder();
+        response.status(self.status);
+        for (name, value) in &self.headers {
+            response.header(
+                HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_str(value)?,
+            );
+        }
+        Ok(response.body(body)?.into())
+    }
+}
+
+/// A request that doesn't match any recorded fixture.
+#[derive(Debug)]
+pub struct UnmatchedRequest {
+    pub method: Method,
+    pub path: String,
+    pub dir: PathBuf,
+}
+
+impl Display for UnmatchedRequest {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "no fixture in {} is left to answer {} {}; record the flow again with \
+             `--record-fixtures`",
+            self.dir.display(),
+            self.method,
+            self.path
+        )
+    }
+}
+
+impl Error for UnmatchedRequest {}
+
+#[derive(Debug)]
+enum Fixtures {
+    Recording {
+        dir: PathBuf,
+        recorded: usize,
+    },
+    /// Recorded exchanges not yet replayed, in the order they were recorded.
+    Replaying {
+        dir: PathBuf,
+        left: Vec<Exchange>,
+    },
+}
+
+static FIXTURES: Mutex<Option<Fixtures>> = Mutex::new(None);
+
+/// Stops recording or replaying fixtures, so that tests start doing neither.
+#[cfg(test)]
+pub fn stop() {
+    *FIXTURES.lock().unwrap() = None;
+}
+
+/// Records every exchange with the site to a file in `dir` from now on.
+pub fn record_to(dir: &Path) -> Result<(), Box<dyn Error>> {
+    create_dir_all(dir)
+        .map_err(|e| format!("unable to create fixtures in {}: {}", dir.display(), e))?;
+    *FIXTURES.lock().unwrap() = Some(Fixtures::Recording {
+        dir: dir.to_owned(),
+        recorded: 0,
+    });
+    Ok(())
+}
+
+/// Answers every request with the fixtures recorded in `dir` from now on, instead of the site.
+pub fn replay_from(dir: &Path) -> Result<(), Box<dyn Error>> {
+    let mut paths = read_dir(dir)
+        .map_err(|e| format!("unable to read fixtures from {}: {}", dir.display(), e))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.retain(|path| path.extension() == Some(OsStr::new("json")));
+    // By number rather than by name, so that fixture 1000 comes after fixture 999.
+    paths.sort_by_key(|path| (sequence_number(path).unwrap_or(usize::MAX), path.clone()));
+    let left = paths
+        .iter()
+        .map(|path| -> Result<Exchange, Box<dyn Error>> {
+            serde_json::from_str(&read_to_string(path)?)
+                .map_err(|e| format!("invalid fixture {}: {}", path.display(), e).into())
+        })
+        .collect::<Result<_, _>>()?;
+    *FIXTURES.lock().unwrap() = Some(Fixtures::Replaying {
+        dir: dir.to_owned(),
+        left,
+    });
+    Ok(())
+}
+
+/// When replaying, answers the request with the first fixture left with the same method, path
+/// and query, failing if there's none. Returns `None` otherwise.
+pub fn replay(method: &Method, url: &Url) -> Result<Option<Response>, Box<dyn Error>> {
+    let mut fixtures = FIXTURES.lock().unwrap();
+    let (dir, left) = match &mut *fixtures {
+        Some(Fixtures::Replaying { dir, left }) => (dir, left),
+        _ => return Ok(None),
+    };
+    let path = path_of(url);
+    match left
+        .iter()
+        .position(|exchange| exchange.method == method.as_str() && exchange.path == path)
+    {
+        Some(idx) => Ok(Some(left.remove(idx).into_response()?)),
+        None => Err(UnmatchedRequest {
+            method: method.clone(),
+            path,
+            dir: dir.clone(),
+        }
+        .into()),
+    }
+}
+
+/// The number a fixture's file name starts with, which orders fixtures by when they were
+/// recorded.
+fn sequence_number(path: &Path) -> Option<usize> {
+    let name = path.file_name()?.to_str()?;
+    name.split('-').next()?.parse().ok()
+}
+
+/// When recording, saves the exchange of `request` with `response`, with secrets scrubbed from
+/// its headers and body: the session cookie sent and any assigned, and form tokens. Returns a
+/// response equivalent to the one that was read.
+pub fn record(request: &Request, response: Response) -> Result<Response, Box<dyn Error>> {
+    let mut fixtures = FIXTURES.lock().unwrap();
+    let (dir, recorded) = match &mut *fixtures {
+        Some(Fixtures::Recording { dir, recorded }) => (dir, recorded),
+        _ => return Ok(response),
+    };
+    let mut response = response;
+    let mut bytes = Vec::new();
+    response.copy_to(&mut bytes)?;
+    let headers = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            let value = value.to_str().ok()?;
+            Some((name.as_str().to_owned(), value.to_owned()))
+        })
+        .collect::<Vec<_>>();
+    let (body, body_base64) = match String::from_utf8(bytes) {
+        Ok(body) => (Some(body), None),
+        Err(e) => (None, Some(STANDARD.encode(e.into_bytes()))),
+    };
+    let exchange = Exchange {
+        method: request.method().as_str().to_owned(),
+        path: path_of(request.url()),
+        status: response.status().as_u16(),
+        headers,
+        body,
+        body_base64,
+    };
+
+    let mut secrets = session_ids(request.headers(), &COOKIE)
+        .chain(session_ids(response.headers(), &SET_COOKIE))
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+    secrets.extend(
+        exchange
+            .body
+            .as_deref()
+            .map(form_tokens)
+            .unwrap_or_default(),
+    );
+    secrets.retain(|secret| !secret.is_empty());
+    // Scrub longer secrets first, so that none is left partly scrubbed by one it contains.
+    secrets.sort_by_key(|secret| Reverse(secret.len()));
+    let scrubbed = Exchange {
+        headers: exchange
+            .headers
+            .iter()
+            .map(|(name, value)| (name.clone(), scrub(value, &secrets)))
+            .collect(),
+        body: exchange.body.as_deref().map(|body| scrub(body, &secrets)),
+        ..exchange.clone()
+    };
+
+    *recorded += 1;
+    let slug = exchange
+        .path
+        .trim_start_matches('/')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .take(40)
+        .collect::<String>();
+    let path = dir.join(format!(
+        "{:06}-{}-{}.json",
+        recorded,
+        exchange.method.to_lowercase(),
+        slug
+    ));
+    write(&path, serde_json::to_string_pretty(&scrubbed)? + "\n")
+        .map_err(|e| format!("unable to record a fixture to {}: {}", path.display(), e))?;
+    // The response was read to record it, so answer with the recording instead.
+    exchange.into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            client::{fetch_public_page, Session},
+            context::Context,
+            endpoints::{progress_url, set_base_url},
+            submit::{submit_answer, SubmissionOutcome},
+            test_support::{flow, lock_globals, MockServer, Reply, ScriptedPrompt, TempDir},
+        },
+        structopt::StructOpt,
+    };
+
+    fn exchange(path: &str, body: &str) -> String {
+        serde_json::to_string(&Exchange {
+            method: "GET".to_owned(),
+            path: path.to_owned(),
+            status: 200,
+            headers: Vec::new(),
+            body: Some(body.to_owned()),
+            body_base64: None,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn scrubs_secrets_from_recordings() {
+        let _globals = lock_globals();
+        let server = MockServer::start();
+        set_base_url(Some(server.url().parse().unwrap()));
+        let page = r#"<p>Signed in as s3ss10n</p>
+            <form><input type="hidden" name="csrf_token" value="7d0c4e9b"></form>"#;
+        server.reply(
+            "GET",
+            "/progress",
+            Reply::ok(page).header("Set-Cookie", "PHPSESSID=n3w1d; path=/; HttpOnly"),
+        );
+        let dir = TempDir::new();
+        record_to(dir.path()).unwrap();
+
+        let mut session = Session::new(Some("s3ss10n")).unwrap();
+        // What's read is left as it is; only the recording is scrubbed.
+        assert_eq!(session.fetch_page(&progress_url()).unwrap(), page);
+        assert_eq!(session.id(), Some("n3w1d"));
+
+        let recording = read_to_string(dir.path().join("000001-get-progress.json")).unwrap();
+        for secret in ["s3ss10n", "n3w1d", "7d0c4e9b"] {
+            assert!(!recording.contains(secret), "{} in {}", secret, recording);
+        }
+        let exchange = serde_json::from_str::<Exchange>(&recording).unwrap();
+        assert!(exchange.headers.contains(&(
+            "set-cookie".to_owned(),
+            "PHPSESSID=scrubbed; path=/; HttpOnly".to_owned()
+        )));
+        assert!(exchange
+            .body
+            .unwrap()
+            .contains(r#"name="csrf_token" value="scrubbed""#));
+    }
+
+    #[test]
+    fn replays_fixtures_in_the_order_they_were_recorded() {
+        let _globals = lock_globals();
+        let dir = TempDir::new();
+        for (name, body) in [
+            ("1000-get-problem_1.json", "second"),
+            ("999-get-problem_1.json", "first"),
+        ] {
+            write(dir.path().join(name), exchange("/problem=1", body)).unwrap();
+        }
+        write(dir.path().join("README.md"), "not a fixture").unwrap();
+        replay_from(dir.path()).unwrap();
+
+        let url = "https://projecteuler.net/problem=1";
+        assert_eq!(fetch_public_page(url).unwrap(), "first");
+        assert_eq!(fetch_public_page(url).unwrap(), "second");
+        assert!(fetch_public_page(url).unwrap_err().is::<UnmatchedRequest>());
+    }
+
+    #[test]
+    fn replays_a_recorded_progress_flow() {
+        let _globals = lock_globals();
+        replay_from(&flow("progress")).unwrap();
+        let context = Context::from_iter(["cargo-euler", "--session-id", "s3ss10n"]);
+
+        let progress = context.progress().unwrap();
+        assert_eq!(progress.problems.solved_count(), 26);
+        assert_eq!(context.username().unwrap(), "euler_fan");
+    }
+
+    #[test]
+    fn replays_a_recorded_submission_flow() {
+        let _globals = lock_globals();
+        replay_from(&flow("submit")).unwrap();
+        let mut session = Session::new(Some("s3ss10n")).unwrap();
+        let mut prompt = ScriptedPrompt::new(&["4821"]);
+
+        let outcome = submit_answer(&mut session, 42, "162", &mut prompt).unwrap();
+        assert_eq!(outcome, SubmissionOutcome::Correct);
+        assert!(prompt.shown[0].starts_with(b"\x89PNG"));
+    }
+}
@@ -0,0 +1,55 @@
+//! Helpers for filling in the HTML forms of the site.
+
+use {
+    reqwest::Url,
+    std::error::Error,
+    unhtml::scraper::{ElementRef, Html, Selector},
+};
+
+/// The names of the fields of `form`.
+pub fn field_names(form: ElementRef<'_>) -> Vec<&str> {
+    form.select(&Selector::parse("input[name]").unwrap())
+        .filter_map(|input| input.value().attr("name"))
+        .collect()
+}
+
+/// Finds the first form of `html` with a field whose name matches `is_field`.
+pub fn find(html: &Html, is_field: impl Fn(&str) -> bool) -> Option<ElementRef<'_>> {
+    html.select(&Selector::parse("form").unwrap())
+        .find(|form| field_names(*form).into_iter().any(&is_field))
+}
+
+/// The hidden fields of `form`, like CSRF tokens, which must be submitted as-is.
+pub fn hidden_fields(form: ElementRef<'_>) -> Vec<(String, String)> {
+    form.select(&Selector::parse("input[type=hidden][name]").unwrap())
+        .map(|input| {
+            let input = input.value();
+            (
+                input.attr("name").unwrap().to_owned(),
+                input.attr("value").unwrap_or("").to_owned(),
+            )
+        })
+        .collect()
+}
+
+/// The name and value of the first named submit button of `form`, which some forms need to tell
+/// apart what was asked of them.
+pub fn submit_button(form: ElementRef<'_>) -> Option<(String, String)> {
+    form.select(&Selector::parse("input[type=submit][name], button[name]").unwrap())
+        .next()
+        .map(|button| {
+            let button = button.value();
+            (
+                button.attr("name").unwrap().to_owned(),
+                button.attr("value").unwrap_or("").to_owned(),
+            )
+        })
+}
+
+/// Where `form` on the page at `page_url` is submitted to.
+pub fn action(form: ElementRef<'_>, page_url: &Url) -> Result<Url, Box<dyn Error>> {
+    Ok(match form.value().attr("action") {
+        Some(action) if !action.is_empty() => page_url.join(action)?,
+        _ => page_url.clone(),
+    })
+}
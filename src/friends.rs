@@ -0,0 +1,728 @@
+//! The friends page, listing the accounts whose progress is shared with the user.
+
+use {
+    crate::{
+        client::Session,
+        context::Context,
+        endpoints::{base_url, friends_url},
+        enrichment::Enrichment,
+        form,
+        output::{bold, print_json, Format},
+        progress::{ProblemStatus, Progress},
+        statement::{collapse_whitespace, Statement},
+        Failure,
+    },
+    log::warn,
+    reqwest::Url,
+    serde::Serialize,
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        error::Error,
+        thread::sleep,
+        time::Duration,
+    },
+    structopt::StructOpt,
+    unhtml::{
+        scraper::{ElementRef, Html, Selector},
+        FromHtml,
+    },
+};
+
+/// A row of the friends list.
+#[derive(Debug, Serialize)]
+pub struct Friend {
+    pub username: String,
+    pub solved: usize,
+    pub level: Option<usize>,
+    /// Where the friend's own progress page can be seen, if the site links to it.
+    #[serde(skip)]
+    pub progress_url: Option<String>,
+    /// Whether the row is the user's own, which the site highlights.
+    #[serde(rename = "me")]
+    pub is_me: bool,
+}
+
+pub fn cell_text(cell: ElementRef<'_>) -> String {
+    collapse_whitespace(&cell.text().collect::<String>())
+}
+
+/// Parses the first number in `text`, ignoring separators like in "1,234".
+pub fn parse_count(text: &str) -> Option<usize> {
+    let digits = text
+        .chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(|c| c.is_ascii_digit() || *c == ',')
+        .filter(char::is_ascii_digit)
+        .collect::<String>();
+    digits.parse().ok()
+}
+
+/// Finds the rows of the friends listing of the friends page, or `None` if there isn't one.
+/// Columns are found by their headings, so that extra or reordered columns don't matter.
+fn friend_rows(html: &Html) -> Option<Vec<(ElementRef<'_>, Friend)>> {
+    let row_selector = Selector::parse("tr").unwrap();
+    let heading_selector = Selector::parse("th").unwrap();
+    let cell_selector = Selector::parse("td").unwrap();
+    let link_selector = Selector::parse("a[href]").unwrap();
+
+    for table in html.select(&Selector::parse("table").unwrap()) {
+        let headings = table
+            .select(&heading_selector)
+            .map(|heading| cell_text(heading).to_lowercase())
+            .collect::<Vec<_>>();
+        let column = |names: &[&str]| {
+            headings
+                .iter()
+                .position(|heading| names.iter().any(|name| heading.contains(name)))
+        };
+        let (username_column, solved_column) =
+            match (column(&["username", "alias", "name"]), column(&["solved"])) {
+                (Some(username), Some(solved)) => (username, solved),
+                _ => continue,
+            };
+        let level_column = column(&["level"]);
+
+        let mut friends = Vec::new();
+        for row in table.select(&row_selector) {
+            let cells = row.select(&cell_selector).collect::<Vec<_>>();
+            let (username_cell, solved) = match (
+                cells.get(username_column),
+                cells
+                    .get(solved_column)
+                    .and_then(|cell| parse_count(&cell_text(*cell))),
+            ) {
+                (Some(username_cell), Some(solved)) => (*username_cell, solved),
+                _ => continue,
+            };
+            let progress_url = username_cell
+                .select(&link_selector)
+                .next()
+                .and_then(|link| base_url().join(link.value().attr("href")?).ok())
+                .map(|url| url.to_string());
+            let is_me = row.value().classes().any(|class| {
+                ["me", "self", "own", "current", "highlight"]
+                    .iter()
+                    .any(|name| class.eq_ignore_ascii_case(name) || class.starts_with(name))
+            });
+            friends.push((
+                row,
+                Friend {
+                    username: cell_text(username_cell),
+                    solved,
+                    level: level_column
+                        .and_then(|column| cells.get(column))
+                        .and_then(|cell| parse_count(&cell_text(*cell))),
+                    progress_url,
+                    is_me,
+                },
+            ));
+        }
+        return Some(friends);
+    }
+    None
+}
+
+const NO_FRIENDS_LIST: &str =
+    "unable to find the list of friends; make sure the session is signed in";
+
+/// Parses the friends listing of the friends page.
+pub fn parse_friends(page: &str) -> Result<Vec<Friend>, Box<dyn Error>> {
+    let html = Html::parse_document(page);
+    let rows = friend_rows(&html).ok_or(NO_FRIENDS_LIST)?;
+    Ok(rows.into_iter().map(|(_, friend)| friend).collect())
+}
+
+/// The text of the main content of `page`, where the site reports what became of a form.
+fn content_text(page: &str) -> String {
+    let html = Html::parse_document(page);
+    let content = html
+        .select(&Selector::parse("#content").unwrap())
+        .next()
+        .unwrap_or_else(|| html.root_element());
+    collapse_whitespace(&content.text().collect::<String>())
+}
+
+/// Finds the user's own friend key on the friends page, which follows the words "friend key".
+pub fn parse_friend_key(page: &str) -> Option<String> {
+    let text = content_text(page);
+    let after = &text[text.to_lowercase().find("friend key")? + "friend key".len()..];
+    after
+        .split(|c: char| c.is_whitespace() || c == ':' || c == '.' || c == ',')
+        .find(|word| {
+            word.len() >= 6
+                && word.chars().any(|c| c.is_ascii_digit())
+                && word
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+        .map(str::to_owned)
+}
+
+/// What the site made of a friend key submitted to be added.
+#[derive(Debug, PartialEq)]
+pub enum AddOutcome {
+    Added,
+    InvalidKey,
+    AlreadyFriends,
+}
+
+impl AddOutcome {
+    /// Classifies the page responded with after submitting a friend key.
+    pub fn from_response(response: &str) -> Self {
+        let text = content_text(response).to_lowercase();
+        if text.contains("already") {
+            Self::AlreadyFriends
+        } else if [
+            "invalid",
+            "not valid",
+            "does not exist",
+            "not found",
+            "unknown",
+        ]
+        .iter()
+        .any(|complaint| text.contains(complaint))
+        {
+            Self::InvalidKey
+        } else {
+            Self::Added
+        }
+    }
+}
+
+/// Fetches the problems `friend` has solved from their progress page, if the site shares it.
+fn fetch_solved(session: &mut Session, friend: &Friend) -> Option<BTreeSet<usize>> {
+    let url = friend.progress_url.as_ref()?;
+    let page = match session.fetch_page(url) {
+        Ok(page) => page,
+        Err(e) => {
+            warn!("unable to fetch the progress of {}: {}", friend.username, e);
+            return None;
+        }
+    };
+    let Progress { problems, .. } = Progress::from_html(&page).ok()?;
+    Some(problems.with_status(ProblemStatus::Solved).collect())
+}
+
+fn describe_gap(theirs: usize, mine: usize) -> String {
+    match theirs as isize - mine as isize {
+        0 => "level with you".to_owned(),
+        ahead if ahead > 0 => format!("{} ahead of you", ahead),
+        behind => format!("{} behind you", -behind),
+    }
+}
+
+/// Sorts `friends` into a leaderboard: by solved count, then level, then username.
+pub fn leaderboard(friends: &mut [Friend]) {
+    friends.sort_by(|a, b| {
+        b.solved
+            .cmp(&a.solved)
+            .then(b.level.cmp(&a.level))
+            .then_with(|| a.username.to_lowercase().cmp(&b.username.to_lowercase()))
+    });
+}
+
+/// Who is directly above and below the user on a leaderboard, and by how many problems.
+#[derive(Debug)]
+pub struct NeighbourGaps<'a> {
+    pub above: Option<(&'a Friend, usize)>,
+    pub below: Option<(&'a Friend, usize)>,
+}
+
+/// How many problems the user at `me` on `board` is behind the person directly above, and ahead
+/// of the person directly below.
+pub fn neighbour_gaps(board: &[Friend], me: usize) -> NeighbourGaps<'_> {
+    let solved = board[me].solved;
+    NeighbourGaps {
+        above: me
+            .checked_sub(1)
+            .map(|idx| (&board[idx], board[idx].solved - solved)),
+        below: board
+            .get(me + 1)
+            .map(|friend| (friend, solved - friend.solved)),
+    }
+}
+
+/// Describes `gaps` like "3 problems behind alice, 7 ahead of bob".
+fn describe_neighbour_gaps(gaps: &NeighbourGaps<'_>) -> String {
+    let problems = |count: usize| match count {
+        1 => "1 problem".to_owned(),
+        count => format!("{} problems", count),
+    };
+    let above = gaps.above.map(|(friend, gap)| match gap {
+        0 => format!("level with {}", friend.username),
+        gap => format!("{} behind {}", problems(gap), friend.username),
+    });
+    let below = gaps.below.map(|(friend, gap)| match gap {
+        0 => format!("level with {}", friend.username),
+        gap => format!("{} ahead of {}", problems(gap), friend.username),
+    });
+    match (above, below) {
+        (Some(above), Some(below)) => format!("{}, {}", above, below),
+        (None, Some(below)) => format!("First among your friends, {}", below),
+        (Some(above), None) => format!("Last among your friends, {}", above),
+        (None, None) => "No friends to compare with".to_owned(),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Neighbour<'a> {
+    username: &'a str,
+    gap: usize,
+}
+
+impl<'a> Neighbour<'a> {
+    fn of((friend, gap): (&'a Friend, usize)) -> Self {
+        Self {
+            username: &friend.username,
+            gap,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Leaderboard<'a> {
+    friends: &'a [Friend],
+    above: Option<Neighbour<'a>>,
+    below: Option<Neighbour<'a>>,
+}
+
+/// Fetches the friends list as a leaderboard, returning it with the index of the user's row.
+/// Without a row marked as the user's own, the user's row is the one named `me`, or else the
+/// username on their progress page. Failing that, one made from the user's progress is added.
+fn fetch_leaderboard(
+    context: &Context,
+    me: Option<&str>,
+) -> Result<(Vec<Friend>, usize), Box<dyn Error>> {
+    let mut board = parse_friends(&context.session()?.fetch_page(&friends_url())?)?;
+    if !board.iter().any(|friend| friend.is_me) {
+        let (progress, username) = context.progress_and_username()?;
+        let username = me.map(str::to_owned).or(username);
+        if let Some(username) = &username {
+            for friend in &mut board {
+                friend.is_me = friend.username.eq_ignore_ascii_case(username);
+            }
+        }
+        if !board.iter().any(|friend| friend.is_me) {
+            board.push(Friend {
+                username: username.unwrap_or_else(|| "you".to_owned()),
+                solved: progress.problems.solved_count(),
+                level: Some(progress.levels.current()),
+                progress_url: None,
+                is_me: true,
+            });
+        }
+    }
+    leaderboard(&mut board);
+    let me = board.iter().position(|friend| friend.is_me).unwrap();
+    Ok((board, me))
+}
+
+fn print_leaderboard(board: &[Friend], me: usize, format: Format) -> Result<(), Box<dyn Error>> {
+    let gaps = neighbour_gaps(board, me);
+    match format {
+        Format::Human => {
+            let width = board
+                .iter()
+                .map(|friend| friend.username.chars().count())
+                .max()
+                .unwrap_or(0);
+            for (idx, friend) in board.iter().enumerate() {
+                let line = format!(
+                    "{} {:>3}. {:<width$}  {:>4} solved{}",
+                    if friend.is_me { ">" } else { " " },
+                    idx + 1,
+                    friend.username,
+                    friend.solved,
+                    friend
+                        .level
+                        .map_or_else(String::new, |level| format!("  level {}", level)),
+                    width = width,
+                );
+                if friend.is_me {
+                    println!("{}", bold(&line));
+                } else {
+                    println!("{}", line);
+                }
+            }
+            println!();
+            println!("{}", describe_neighbour_gaps(&gaps));
+        }
+        Format::Json => {
+            print_json(&Leaderboard {
+                friends: board,
+                above: gaps.above.map(Neighbour::of),
+                below: gaps.below.map(Neighbour::of),
+            })?;
+        }
+    }
+    Ok(())
+}
+
+fn usernames(friends: &[Friend]) -> BTreeSet<String> {
+    friends
+        .iter()
+        .map(|friend| friend.username.to_lowercase())
+        .collect()
+}
+
+/// Adds the friend whose friend key is `key`, returning their username as listed afterwards.
+fn add_friend(session: &mut Session, key: &str) -> Result<String, Box<dyn Error>> {
+    let page_url = Url::parse(&friends_url())?;
+    let page = session.fetch_page(page_url.as_str())?;
+    let before = usernames(&parse_friends(&page)?);
+    let html = Html::parse_document(&page);
+    let form = form::find(&html, |name| name.contains("key"))
+        .ok_or("unable to find the form to add friends with")?;
+    let key_field = form::field_names(form)
+        .into_iter()
+        .find(|name| name.contains("key"))
+        .unwrap()
+        .to_owned();
+
+    let mut fields = form::hidden_fields(form);
+    fields.push((key_field, key.to_owned()));
+    fields.extend(form::submit_button(form));
+    let response = session.post_form(form::action(form, &page_url)?.as_str(), &fields)?;
+    match AddOutcome::from_response(&response) {
+        AddOutcome::Added => (),
+        AddOutcome::InvalidKey => {
+            return Err(Failure(format!("{} isn't a valid friend key", key)).into())
+        }
+        AddOutcome::AlreadyFriends => {
+            return Err(Failure(format!(
+                "the account with friend key {} is already your friend",
+                key
+            ))
+            .into())
+        }
+    }
+
+    parse_friends(&session.fetch_page(page_url.as_str())?)?
+        .into_iter()
+        .find(|friend| !before.contains(&friend.username.to_lowercase()))
+        .map(|friend| friend.username)
+        .ok_or_else(|| "the friend key was submitted, but no new friend is listed".into())
+}
+
+/// Removes `username` from the friends, with the form or link of their row of the listing.
+fn remove_friend(session: &mut Session, username: &str) -> Result<String, Box<dyn Error>> {
+    let page_url = Url::parse(&friends_url())?;
+    let page = session.fetch_page(page_url.as_str())?;
+    let html = Html::parse_document(&page);
+    let (row, friend) = friend_rows(&html)
+        .ok_or(NO_FRIENDS_LIST)?
+        .into_iter()
+        .find(|(_, friend)| friend.username.eq_ignore_ascii_case(username))
+        .ok_or_else(|| Failure(format!("{} isn't one of your friends", username)))?;
+
+    let removal_link = row
+        .select(&Selector::parse("a[href]").unwrap())
+        .filter_map(|link| link.value().attr("href"))
+        .find(|href| {
+            let href = href.to_lowercase();
+            href.contains("remove") || href.contains("delete")
+        });
+    if let Some(form) = row.select(&Selector::parse("form").unwrap()).next() {
+        let mut fields = form::hidden_fields(form);
+        fields.extend(form::submit_button(form));
+        session.post_form(form::action(form, &page_url)?.as_str(), &fields)?;
+    } else if let Some(href) = removal_link {
+        session.fetch_page(page_url.join(href)?.as_str())?;
+    } else {
+        return Err(format!(
+            "unable to find how to remove {} from your friends",
+            username
+        )
+        .into());
+    }
+
+    let still_listed = parse_friends(&session.fetch_page(page_url.as_str())?)?
+        .iter()
+        .any(|listed| listed.username.eq_ignore_ascii_case(username));
+    if still_listed {
+        return Err(format!("{} is still listed among your friends", friend.username).into());
+    }
+    Ok(friend.username)
+}
+
+/// Compare progress with friends, and manage them.
+#[derive(Debug, StructOpt)]
+pub enum FriendsCommand {
+    /// Show how far ahead or behind each friend is, and which problems they solved that you
+    /// haven't, most commonly solved first.
+    Gaps {
+        /// Only compare with this friend.
+        #[structopt(long)]
+        friend: Option<String>,
+    },
+    /// Add a friend by their friend key.
+    Add {
+        /// The friend key they shared.
+        key: String,
+    },
+    /// Remove a friend.
+    Remove {
+        /// The username of the friend, as listed by the friends page.
+        username: String,
+    },
+    /// Show the friends list as a leaderboard, with how far you are from those around you.
+    Rank {
+        /// Your username, to find your own row by if the site doesn't mark it.
+        #[structopt(long)]
+        me: Option<String>,
+        /// The format to print the leaderboard in: `human` or `json`.
+        #[structopt(long, default_value = "human")]
+        format: Format,
+        /// Keep fetching and printing the leaderboard until interrupted.
+        #[structopt(long)]
+        watch: bool,
+        /// With `--watch`, how many minutes to wait between fetches.
+        #[structopt(long, default_value = "10")]
+        interval: u64,
+    },
+    /// Print your own friend key, for sharing with others to add you.
+    Key,
+}
+
+impl FriendsCommand {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        match self {
+            Self::Gaps { friend } => {
+                let mut session = context.session()?;
+                let mut friends = parse_friends(&session.fetch_page(&friends_url())?)?;
+                if let Some(name) = &friend {
+                    friends.retain(|friend| friend.username.eq_ignore_ascii_case(name));
+                    if friends.is_empty() {
+                        return Err(format!("{} isn't one of your friends", name).into());
+                    }
+                }
+                let progress = context.progress()?;
+                let mine = progress.problems.solved_count();
+                let my_solved = progress
+                    .problems
+                    .with_status(ProblemStatus::Solved)
+                    .collect::<BTreeSet<_>>();
+
+                // How many friends solved each problem that hasn't been solved here.
+                let mut gaps = BTreeMap::<usize, usize>::new();
+                for friend in &friends {
+                    print!(
+                        "{}: {} solved{}, {}",
+                        friend.username,
+                        friend.solved,
+                        friend
+                            .level
+                            .map_or_else(String::new, |level| format!(" (level {})", level)),
+                        describe_gap(friend.solved, mine)
+                    );
+                    match fetch_solved(&mut session, friend) {
+                        Some(solved) => {
+                            let missing = solved.difference(&my_solved).collect::<Vec<_>>();
+                            println!(", {} solved that you haven't", missing.len());
+                            for &number in missing {
+                                *gaps.entry(number).or_default() += 1;
+                            }
+                        }
+                        None => println!(" (only counts can be compared for this account)"),
+                    }
+                }
+
+                if gaps.is_empty() {
+                    return Ok(());
+                }
+                let mut gaps = gaps.into_iter().collect::<Vec<_>>();
+                gaps.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+                let enrichment = Enrichment::load_cached();
+                println!();
+                println!("Problems your friends solved that you haven't:");
+                for (number, count) in gaps {
+                    let title = enrichment
+                        .get(number)
+                        .map(|info| info.title.clone())
+                        .or_else(|| Statement::cached(number).map(|statement| statement.title));
+                    println!(
+                        "  {:>4}  {}{}",
+                        number,
+                        match count {
+                            1 => "1 friend".to_owned(),
+                            count => format!("{} friends", count),
+                        },
+                        title.map_or_else(String::new, |title| format!("  {}", title))
+                    );
+                }
+                Ok(())
+            }
+            Self::Add { key } => {
+                let username = add_friend(&mut context.session()?, key.trim())?;
+                println!("Added {} to your friends", username);
+                Ok(())
+            }
+            Self::Remove { username } => {
+                let username = remove_friend(&mut context.session()?, &username)?;
+                println!("Removed {} from your friends", username);
+                Ok(())
+            }
+            Self::Rank {
+                me,
+                format,
+                watch,
+                interval,
+            } => loop {
+                match fetch_leaderboard(context, me.as_deref()) {
+                    Ok((board, me)) => print_leaderboard(&board, me, format)?,
+                    Err(e) if watch => warn!("unable to fetch the leaderboard: {}", e),
+                    Err(e) => return Err(e),
+                }
+                if !watch {
+                    return Ok(());
+                }
+                sleep(Duration::from_secs(interval.max(1) * 60));
+                println!();
+            },
+            Self::Key => {
+                let page = context.session()?.fetch_page(&friends_url())?;
+                let key = parse_friend_key(&page).ok_or(
+                    "unable to find your friend key on the friends page; make sure the session is \
+                     signed in",
+                )?;
+                println!("{}", key);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            fixtures::replay_from,
+            test_support::{flow, lock_globals, recorded_page},
+        },
+        structopt::StructOpt,
+    };
+
+    fn friend(username: &str, solved: usize, level: Option<usize>) -> Friend {
+        Friend {
+            username: username.to_owned(),
+            solved,
+            level,
+            progress_url: None,
+            is_me: false,
+        }
+    }
+
+    #[test]
+    fn parses_friends() {
+        let _globals = lock_globals();
+        let friends = parse_friends(&recorded_page("friends", "/friends")).unwrap();
+        let summary = friends
+            .iter()
+            .map(|friend| (&*friend.username, friend.solved, friend.level, friend.is_me))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                ("alice", 1204, Some(48), false),
+                ("euler_fan", 26, Some(1), true),
+                ("Bob", 26, Some(1), false),
+                ("carol", 3, None, false),
+            ]
+        );
+        assert_eq!(
+            friends[0].progress_url.as_deref(),
+            Some("https://projecteuler.net/progress=alice")
+        );
+        assert!(parse_friends("<p>Sign in</p>").is_err());
+    }
+
+    #[test]
+    fn parses_friend_keys() {
+        assert_eq!(
+            parse_friend_key(&recorded_page("friends", "/friends")).as_deref(),
+            Some("1234567_AbCdEfGh")
+        );
+        assert_eq!(parse_friend_key("<p>No friend key here.</p>"), None);
+    }
+
+    #[test]
+    fn replays_a_recorded_friends_flow() {
+        let _globals = lock_globals();
+        replay_from(&flow("friends")).unwrap();
+        let context = Context::from_iter(["cargo-euler", "--session-id", "s3ss10n"]);
+
+        let (board, me) = fetch_leaderboard(&context, None).unwrap();
+        assert_eq!(board[me].username, "euler_fan");
+        assert_eq!(board[0].username, "alice");
+        let mine = context
+            .progress()
+            .unwrap()
+            .problems
+            .with_status(ProblemStatus::Solved)
+            .collect::<BTreeSet<_>>();
+        let theirs = fetch_solved(&mut context.session().unwrap(), &board[0]).unwrap();
+        assert_eq!(
+            theirs.difference(&mine).copied().collect::<Vec<_>>(),
+            [26, 29, 30]
+        );
+    }
+
+    #[test]
+    fn classifies_adding_friends() {
+        let page = |message| format!(r#"<div id="content"><p>{}</p></div>"#, message);
+        assert_eq!(
+            AddOutcome::from_response(&page("Friend added.")),
+            AddOutcome::Added
+        );
+        assert_eq!(
+            AddOutcome::from_response(&page("That friend key is invalid.")),
+            AddOutcome::InvalidKey
+        );
+        assert_eq!(
+            AddOutcome::from_response(&page("You are already friends.")),
+            AddOutcome::AlreadyFriends
+        );
+    }
+
+    #[test]
+    fn ranks_friends() {
+        let mut board = vec![
+            friend("carol", 3, None),
+            friend("bob", 26, Some(1)),
+            friend("Alice", 26, Some(1)),
+            friend("dave", 26, Some(2)),
+        ];
+        leaderboard(&mut board);
+        let order = board
+            .iter()
+            .map(|friend| &*friend.username)
+            .collect::<Vec<_>>();
+        assert_eq!(order, ["dave", "Alice", "bob", "carol"]);
+
+        assert_eq!(
+            describe_neighbour_gaps(&neighbour_gaps(&board, 3)),
+            "Last among your friends, 23 problems behind bob"
+        );
+        assert_eq!(
+            describe_neighbour_gaps(&neighbour_gaps(&board, 1)),
+            "level with dave, level with bob"
+        );
+        assert_eq!(
+            describe_neighbour_gaps(&neighbour_gaps(&board, 0)),
+            "First among your friends, level with Alice"
+        );
+        assert_eq!(
+            describe_neighbour_gaps(&neighbour_gaps(&board[3..], 0)),
+            "No friends to compare with"
+        );
+    }
+
+    #[test]
+    fn parses_counts() {
+        assert_eq!(parse_count("Solved 1,234 problems"), Some(1234));
+        assert_eq!(parse_count("none"), None);
+        assert_eq!(describe_gap(30, 26), "4 ahead of you");
+        assert_eq!(describe_gap(20, 26), "6 behind you");
+    }
+}
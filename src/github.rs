@@ -0,0 +1,204 @@
+//! Integration with GitHub Actions: job summaries and workflow command annotations.
+
+use {
+    crate::{
+        new::escape_inline_markdown,
+        progress::{NextLevel, Progress},
+        readme_table::render_table,
+    },
+    log::warn,
+    std::{
+        env::{var, var_os},
+        fs::OpenOptions,
+        io::{self, Write},
+        path::PathBuf,
+    },
+};
+
+/// The variable GitHub Actions sets to the file a step appends its job summary to.
+const STEP_SUMMARY_VAR: &str = "GITHUB_STEP_SUMMARY";
+/// The variable GitHub Actions sets to `true` when running a workflow.
+const ACTIONS_VAR: &str = "GITHUB_ACTIONS";
+
+/// The file to append a job summary to, if running in GitHub Actions.
+fn step_summary_path() -> Option<PathBuf> {
+    var_os(STEP_SUMMARY_VAR)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Appends `markdown` to the job summary, if running in GitHub Actions. Failing to is only
+/// warned about, since the summary is in addition to the normal output.
+pub fn append_step_summary(markdown: &str) {
+    let path = match step_summary_path() {
+        Some(path) => path,
+        None => return,
+    };
+    let appended = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{}", markdown));
+    if let Err(e) = appended {
+        warn!(
+            "unable to append to the job summary at {}: {}",
+            path.display(),
+            e
+        );
+    }
+}
+
+/// How severe an annotation is.
+#[derive(Clone, Copy, Debug)]
+pub enum Annotation {
+    Error,
+    Warning,
+}
+
+/// Escapes `message` for the data of a workflow command, which ends at a line break.
+fn escape_data(message: &str) -> String {
+    message
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Prints `message` as a workflow command, so it shows up as an annotation of the run, if
+/// running in GitHub Actions.
+pub fn annotate(annotation: Annotation, message: &str) {
+    if var(ACTIONS_VAR).as_deref() != Ok("true") {
+        return;
+    }
+    let mut stdout = io::stdout();
+    let _ = writeln!(stdout, "{}", workflow_command(annotation, message));
+}
+
+/// The workflow command annotating the run with `message`.
+fn workflow_command(annotation: Annotation, message: &str) -> String {
+    let command = match annotation {
+        Annotation::Error => "error",
+        Annotation::Warning => "warning",
+    };
+    format!("::{}::{}", command, escape_data(message))
+}
+
+/// Renders the progress summary as Markdown: a line with the solved count and level, and a
+/// table of the levels listed on the progress page.
+pub fn progress_summary(progress: &Progress) -> String {
+    let Progress { levels, problems } = progress;
+    let solved = problems.solved_count();
+    let total = problems.available();
+    let percent = if total == 0 {
+        0.0
+    } else {
+        solved as f64 * 100.0 / total as f64
+    };
+    let mut line = format!(
+        "Project Euler: {} of {} solved ({:.1}%), Level {}",
+        solved,
+        total,
+        percent,
+        levels.current()
+    );
+    if let Some(NextLevel {
+        level, remaining, ..
+    }) = progress.next_level_info()
+    {
+        line.push_str(&format!(", {} more to Level {}", remaining, level));
+    }
+    let mut markdown = format!("**{}**", line);
+    markdown.push_str("\n\n");
+    if !levels.0.is_empty() {
+        let rows = levels
+            .0
+            .iter()
+            .enumerate()
+            .map(|(idx, level)| {
+                vec![
+                    (idx + 1).to_string(),
+                    escape_inline_markdown(&level.description),
+                    if level.completed { "✔" } else { "" }.to_owned(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        markdown.push_str(&render_table(&["Level", "Requirement", "Completed"], &rows));
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::test_support::{account_progress, lock_globals, new_account, TempDir},
+        std::{
+            env::{remove_var, set_var},
+            fs::read_to_string,
+        },
+    };
+
+    #[test]
+    fn appends_to_the_step_summary() {
+        let _globals = lock_globals();
+        let previous = var_os(STEP_SUMMARY_VAR);
+        let dir = TempDir::new();
+        let path = dir.path().join("summary.md");
+
+        remove_var(STEP_SUMMARY_VAR);
+        append_step_summary("### Ignored");
+        set_var(STEP_SUMMARY_VAR, "");
+        append_step_summary("### Ignored");
+        set_var(STEP_SUMMARY_VAR, &path);
+        append_step_summary("### Solution tests\n\nAll passed.");
+        append_step_summary("### Consistency checks");
+        let summary = read_to_string(&path);
+        // Failing to write the summary is only warned about.
+        set_var(STEP_SUMMARY_VAR, dir.path());
+        append_step_summary("### Lost");
+        match previous {
+            Some(previous) => set_var(STEP_SUMMARY_VAR, previous),
+            None => remove_var(STEP_SUMMARY_VAR),
+        }
+
+        assert_eq!(
+            summary.unwrap(),
+            "### Solution tests\n\nAll passed.\n### Consistency checks\n"
+        );
+    }
+
+    #[test]
+    fn escapes_annotations() {
+        assert_eq!(
+            workflow_command(Annotation::Error, "problem 1: FAIL 100% wrong\r\nsee above"),
+            "::error::problem 1: FAIL 100%25 wrong%0D%0Asee above"
+        );
+        assert_eq!(
+            workflow_command(Annotation::Warning, "no stored answer"),
+            "::warning::no stored answer"
+        );
+    }
+
+    #[test]
+    fn summarizes_accounts_without_progress() {
+        assert_eq!(
+            progress_summary(&new_account()),
+            "**Project Euler: 0 of 29 solved (0.0%), Level 0, 25 more to Level 1**\n\n"
+        );
+    }
+
+    #[test]
+    fn summarizes_progress_with_a_table_of_levels() {
+        let progress = account_progress();
+        assert_eq!(
+            progress_summary(&progress),
+            "**Project Euler: 26 of 29 solved (89.7%), Level 1, 24 more to Level 2**
+
+| Level | Requirement       | Completed |
+|-------|-------------------|-----------|
+| 1     | Solve 25 problems | ✔         |
+| 2     | Solve 50 problems |           |
+| 3     | Solve 75 problems |           |
+"
+        );
+    }
+}
@@ -0,0 +1,320 @@
+//! Goals to work toward, like a run of consecutive problems for an award, and how far along they
+//! are.
+
+use {
+    crate::{
+        awards::{closest_run, list, longest_run},
+        context::Context,
+        paths::{profile_data_dir, write_atomically},
+        progress::Progress,
+    },
+    serde::{Deserialize, Serialize},
+    std::{
+        convert::TryFrom,
+        error::Error,
+        fmt::{self, Display, Formatter},
+        fs::{create_dir_all, read_to_string},
+        io::ErrorKind,
+        path::PathBuf,
+        str::FromStr,
+    },
+    structopt::StructOpt,
+};
+
+const GOALS_FILE_NAME: &str = "goals.json";
+
+/// A target to reach, written like `consecutive:100`.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Goal {
+    /// A run of this many consecutive solved problems.
+    Consecutive(usize),
+    /// This many solved problems.
+    Solved(usize),
+    /// This level.
+    Level(usize),
+}
+
+impl Goal {
+    fn kind(self) -> &'static str {
+        match self {
+            Self::Consecutive(_) => "consecutive",
+            Self::Solved(_) => "solved",
+            Self::Level(_) => "level",
+        }
+    }
+
+    fn target(self) -> usize {
+        match self {
+            Self::Consecutive(target) | Self::Solved(target) | Self::Level(target) => target,
+        }
+    }
+}
+
+impl Display for Goal {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.kind(), self.target())
+    }
+}
+
+impl FromStr for Goal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid goal {:?}; expected like `consecutive:100`, `solved:200` or `level:10`",
+                s
+            )
+        };
+        let (kind, target) = s.split_once(':').ok_or_else(invalid)?;
+        let target = target.trim().parse().map_err(|_| invalid())?;
+        if target == 0 {
+            return Err(invalid());
+        }
+        match kind.trim() {
+            "consecutive" => Ok(Self::Consecutive(target)),
+            "solved" => Ok(Self::Solved(target)),
+            "level" => Ok(Self::Level(target)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl From<Goal> for String {
+    fn from(goal: Goal) -> Self {
+        goal.to_string()
+    }
+}
+
+impl TryFrom<String> for Goal {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+fn goals_path(profile: &str) -> PathBuf {
+    profile_data_dir(profile).join(GOALS_FILE_NAME)
+}
+
+/// The goals of `profile`, in the order they were set.
+pub fn load(profile: &str) -> Result<Vec<Goal>, Box<dyn Error>> {
+    let path = goals_path(profile);
+    match read_to_string(&path) {
+        Ok(contents) => Ok(serde_json::from_str(&contents)
+            .map_err(|e| format!("unable to parse {}: {}", path.display(), e))?),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("unable to read {}: {}", path.display(), e).into()),
+    }
+}
+
+fn save(profile: &str, goals: &[Goal]) -> Result<(), Box<dyn Error>> {
+    let path = goals_path(profile);
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+    }
+    write_atomically(&path, serde_json::to_string_pretty(goals)?)?;
+    Ok(())
+}
+
+/// Describes how far along `goal` is, like "consecutive run: 61/100, blocked by problems 63,
+/// 77".
+pub fn describe(goal: Goal, progress: &Progress) -> String {
+    let problems = &progress.problems;
+    match goal {
+        Goal::Consecutive(target) => {
+            let longest = longest_run(problems).map_or(0, |run| run.count());
+            let mut description = format!("consecutive run: {}/{}", longest.min(target), target);
+            if longest >= target {
+                description.push_str(", reached");
+                return description;
+            }
+            match closest_run(problems, target) {
+                Some((run, gaps)) => description.push_str(&format!(
+                    ", blocked by problem{} {} in {} to {}",
+                    if gaps.len() == 1 { "" } else { "s" },
+                    list(&gaps),
+                    run.start(),
+                    run.end()
+                )),
+                None if target > problems.published() => description.push_str(&format!(
+                    ", but only {} problems are published",
+                    problems.published()
+                )),
+                None => description.push_str(&format!(
+                    ", but every run of {} problems has a retired one",
+                    target
+                )),
+            }
+            description
+        }
+        Goal::Solved(target) => {
+            let solved = problems.solved_count();
+            match target
+                .checked_sub(solved)
+                .filter(|remaining| *remaining > 0)
+            {
+                Some(remaining) => format!("solved: {}/{}, {} to go", solved, target, remaining),
+                None => format!("solved: {}/{}, reached", solved.min(target), target),
+            }
+        }
+        Goal::Level(target) => {
+            let current = progress.levels.current();
+            if current >= target {
+                return format!("level: {}/{}, reached", target, target);
+            }
+            let remaining = progress
+                .level_threshold(target)
+                .saturating_sub(problems.solved_count());
+            format!("level: {}/{}, {} more to solve", current, target, remaining)
+        }
+    }
+}
+
+/// Prints how far along each goal of `profile` is, if it has any.
+pub fn print_goals(profile: &str, progress: &Progress) -> Result<(), Box<dyn Error>> {
+    let goals = load(profile)?;
+    if goals.is_empty() {
+        return Ok(());
+    }
+    println!();
+    println!("Goals:");
+    for goal in goals {
+        println!("  {}", describe(goal, progress));
+    }
+    Ok(())
+}
+
+/// Track progress toward goals, shown with the progress summary.
+#[derive(Debug, StructOpt)]
+pub enum GoalCommand {
+    /// Set a goal like `consecutive:100`, `solved:200` or `level:10`, replacing any goal of the
+    /// same kind.
+    Set { goal: Goal },
+    /// List the goals and how far along they are.
+    List,
+    /// Remove every goal.
+    Clear,
+}
+
+impl GoalCommand {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        match self {
+            Self::Set { goal } => {
+                let mut goals = load(&context.profile)?;
+                goals.retain(|existing| existing.kind() != goal.kind());
+                goals.push(goal);
+                save(&context.profile, &goals)?;
+                println!("Set the goal {}", goal);
+            }
+            Self::List => {
+                let goals = load(&context.profile)?;
+                if goals.is_empty() {
+                    println!("No goals are set; set one with `cargo euler goal set`");
+                    return Ok(());
+                }
+                let progress = context.progress()?;
+                for goal in goals {
+                    println!("{:<16} {}", goal.to_string(), describe(goal, &progress));
+                }
+            }
+            Self::Clear => {
+                save(&context.profile, &[])?;
+                println!("Cleared every goal");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::test_support::{account_progress, lock_globals},
+        std::fs::write,
+    };
+
+    #[test]
+    fn parses_goals() {
+        for (goal, parsed) in [
+            ("consecutive:100", Goal::Consecutive(100)),
+            ("solved: 200", Goal::Solved(200)),
+            (" level :10", Goal::Level(10)),
+        ] {
+            assert_eq!(goal.parse(), Ok(parsed), "{:?}", goal);
+        }
+        for goal in [
+            "",
+            "solved",
+            "solved:",
+            "solved:0",
+            "solved:-1",
+            "awards:3",
+            "level:1:2",
+        ] {
+            assert!(goal.parse::<Goal>().is_err(), "{:?}", goal);
+        }
+        assert_eq!(Goal::Consecutive(100).to_string(), "consecutive:100");
+    }
+
+    #[test]
+    fn describes_runs_of_consecutive_problems() {
+        let progress = account_progress();
+        assert_eq!(
+            describe(Goal::Consecutive(20), &progress),
+            "consecutive run: 20/20, reached"
+        );
+        assert_eq!(
+            describe(Goal::Consecutive(27), &progress),
+            "consecutive run: 25/27, blocked by problem 26 in 1 to 27"
+        );
+        // Problem 28 is retired, so every run of 29 of the 30 problems is out of reach.
+        assert_eq!(
+            describe(Goal::Consecutive(29), &progress),
+            "consecutive run: 25/29, but every run of 29 problems has a retired one"
+        );
+        assert_eq!(
+            describe(Goal::Consecutive(100), &progress),
+            "consecutive run: 25/100, but only 30 problems are published"
+        );
+    }
+
+    #[test]
+    fn describes_solved_counts_and_levels() {
+        let progress = account_progress();
+        assert_eq!(
+            describe(Goal::Solved(20), &progress),
+            "solved: 20/20, reached"
+        );
+        assert_eq!(
+            describe(Goal::Solved(26), &progress),
+            "solved: 26/26, reached"
+        );
+        assert_eq!(
+            describe(Goal::Solved(30), &progress),
+            "solved: 26/30, 4 to go"
+        );
+        assert_eq!(describe(Goal::Level(1), &progress), "level: 1/1, reached");
+        assert_eq!(
+            describe(Goal::Level(3), &progress),
+            "level: 1/3, 49 more to solve"
+        );
+    }
+
+    #[test]
+    fn saves_goals() {
+        let _globals = lock_globals();
+        assert!(load("default").unwrap().is_empty());
+        let goals = [Goal::Level(2), Goal::Consecutive(50)];
+        save("default", &goals).unwrap();
+        assert_eq!(load("default").unwrap(), goals);
+        assert!(load("work").unwrap().is_empty());
+
+        write(goals_path("default"), r#"["level:2", "awards:3"]"#).unwrap();
+        let error = load("default").unwrap_err().to_string();
+        assert!(error.contains("invalid goal \"awards:3\""), "{}", error);
+    }
+}
@@ -0,0 +1,378 @@
+//! Snapshots of progress recorded over time. With file storage, they're kept in an
+//! append-only `history.jsonl` per profile with one snapshot per line.
+
+use {
+    crate::{
+        chart::Chart,
+        context::Context,
+        progress::{ProblemStatus, Progress},
+        retention::{Compact, Prune},
+        storage,
+    },
+    chrono::{DateTime, Utc},
+    itertools::Itertools,
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        convert::TryFrom,
+        error::Error,
+        fmt::{self, Display, Formatter},
+        str::FromStr,
+    },
+    structopt::StructOpt,
+};
+
+/// A set of problem numbers, serialized compactly as ranges like `1-10,12,15-16`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ProblemSet(pub BTreeSet<usize>);
+
+impl Display for ProblemSet {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut numbers = self.0.iter().copied().peekable();
+        let mut first = true;
+        while let Some(start) = numbers.next() {
+            let mut end = start;
+            while numbers.peek() == Some(&(end + 1)) {
+                end = numbers.next().unwrap();
+            }
+            if !first {
+                f.write_str(",")?;
+            }
+            first = false;
+            if start == end {
+                write!(f, "{}", start)?;
+            } else {
+                write!(f, "{}-{}", start, end)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ProblemSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut set = BTreeSet::new();
+        for range in s.split(',').filter(|range| !range.is_empty()) {
+            let parse = |number: &str| {
+                number
+                    .parse::<usize>()
+                    .map_err(|e| format!("invalid problem number {:?}: {}", number, e))
+            };
+            match range.split_once('-') {
+                Some((start, end)) => {
+                    let (start, end) = (parse(start)?, parse(end)?);
+                    if start > end {
+                        return Err(format!("invalid range of problems {:?}", range));
+                    }
+                    set.extend(start..=end);
+                }
+                None => {
+                    set.insert(parse(range)?);
+                }
+            }
+        }
+        Ok(Self(set))
+    }
+}
+
+impl ProblemSet {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl TryFrom<String> for ProblemSet {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<ProblemSet> for String {
+    fn from(set: ProblemSet) -> Self {
+        set.to_string()
+    }
+}
+
+/// The progress of an account at some point in time.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Snapshot {
+    pub timestamp: DateTime<Utc>,
+    pub solved_count: usize,
+    /// How many problems were published, counting retired ones.
+    pub total: usize,
+    pub level: usize,
+    pub solved: ProblemSet,
+    /// Published problems that were retired, which older snapshots don't record.
+    #[serde(default, skip_serializing_if = "ProblemSet::is_empty")]
+    pub retired: ProblemSet,
+}
+
+impl Snapshot {
+    pub fn of(progress: &Progress, timestamp: DateTime<Utc>) -> Self {
+        let Progress { levels, problems } = progress;
+        Self {
+            timestamp,
+            solved_count: problems.solved_count(),
+            total: problems.published(),
+            level: levels.current(),
+            solved: ProblemSet(problems.with_status(ProblemStatus::Solved).collect()),
+            retired: ProblemSet(problems.with_status(ProblemStatus::Retired).collect()),
+        }
+    }
+
+    /// How many published problems could still be solved.
+    pub fn available(&self) -> usize {
+        self.total.saturating_sub(self.retired.0.len())
+    }
+}
+
+/// Reads every snapshot recorded for `profile`, oldest first.
+pub fn read_history(profile: &str) -> Result<Vec<Snapshot>, Box<dyn Error>> {
+    storage::open(profile)?.snapshots()
+}
+
+/// Durably records `snapshot` in the history of `profile`, along with when any problem it
+/// shows newly solved was first seen solved. That's kept separately so that it outlives the
+/// snapshots themselves.
+pub fn append_snapshot(profile: &str, snapshot: &Snapshot) -> Result<(), Box<dyn Error>> {
+    let store = storage::open(profile)?;
+    if let Some(previous) = store.snapshots()?.last() {
+        let newly_solved = snapshot
+            .solved
+            .0
+            .difference(&previous.solved.0)
+            .copied()
+            .collect::<Vec<_>>();
+        if !newly_solved.is_empty() {
+            store.record_first_seen_solved(&newly_solved, snapshot.timestamp)?;
+        }
+    }
+    store.append_snapshot(snapshot)
+}
+
+/// When each problem was first seen solved, from the snapshots where it flipped from unsolved
+/// to solved.
+pub fn derive_first_seen_solved(snapshots: &[Snapshot]) -> BTreeMap<usize, DateTime<Utc>> {
+    let mut first_seen = BTreeMap::new();
+    for (previous, snapshot) in snapshots.iter().tuple_windows() {
+        for &number in snapshot.solved.0.difference(&previous.solved.0) {
+            first_seen.entry(number).or_insert(snapshot.timestamp);
+        }
+    }
+    first_seen
+}
+
+/// When each problem of `profile` was first seen solved. Problems solved before the history
+/// began have no entry, since when they were solved is unknown.
+pub fn first_seen_solved(profile: &str) -> Result<BTreeMap<usize, DateTime<Utc>>, Box<dyn Error>> {
+    let store = storage::open(profile)?;
+    // Snapshots recorded before first-seen times were tracked still show when problems flipped.
+    let mut first_seen = derive_first_seen_solved(&store.snapshots()?);
+    first_seen.extend(store.first_seen_solved()?);
+    Ok(first_seen)
+}
+
+/// Renders how long ago `timestamp` was in the largest whole unit, like "3 days ago".
+pub fn format_age(timestamp: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let seconds = (now - timestamp).num_seconds();
+    if seconds < 0 {
+        return "in the future".to_owned();
+    }
+    let (amount, unit) = match seconds {
+        0..=59 => return "just now".to_owned(),
+        60..=3599 => (seconds / 60, "minute"),
+        3600..=86399 => (seconds / 3600, "hour"),
+        _ => (seconds / 86400, "day"),
+    };
+    format!(
+        "{} {}{} ago",
+        amount,
+        unit,
+        if amount == 1 { "" } else { "s" }
+    )
+}
+
+/// Inspect the progress snapshots recorded on every fetch.
+#[derive(Debug, StructOpt)]
+pub enum HistoryCommand {
+    /// List the recorded snapshots, oldest first.
+    List,
+    /// Show when a problem was first seen solved, and every snapshot where its status changed.
+    Problem {
+        number: usize,
+    },
+    Chart(Chart),
+    Compact(Compact),
+    Prune(Prune),
+}
+
+impl HistoryCommand {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        match self {
+            Self::List => {
+                let snapshots = read_history(&context.profile)?;
+                if snapshots.is_empty() {
+                    println!("No snapshots have been recorded yet.");
+                }
+                let now = Utc::now();
+                for (idx, snapshot) in snapshots.iter().enumerate() {
+                    println!(
+                        "{:>4}  {}  ({})  {} of {} solved, level {}",
+                        idx,
+                        snapshot.timestamp.format("%Y-%m-%d %H:%M"),
+                        format_age(snapshot.timestamp, now),
+                        snapshot.solved_count,
+                        snapshot.available(),
+                        snapshot.level
+                    );
+                }
+                Ok(())
+            }
+            Self::Chart(chart) => chart.run(&context.profile),
+            Self::Compact(compact) => compact.run(&context.profile),
+            Self::Prune(prune) => prune.run(&context.profile),
+            Self::Problem { number } => {
+                let snapshots = read_history(&context.profile)?;
+                let latest = match snapshots.last() {
+                    Some(latest) => latest,
+                    None => return Err("no snapshots have been recorded yet".into()),
+                };
+                let now = Utc::now();
+                match first_seen_solved(&context.profile)?.get(&number) {
+                    Some(&timestamp) => println!(
+                        "Problem {} was first seen solved {} ({})",
+                        number,
+                        timestamp.format("%Y-%m-%d %H:%M"),
+                        format_age(timestamp, now)
+                    ),
+                    None if latest.solved.0.contains(&number) => println!(
+                        "Problem {} was solved before the history began on {}",
+                        number,
+                        snapshots[0].timestamp.format("%Y-%m-%d %H:%M")
+                    ),
+                    None => println!("Problem {} hasn't been seen solved", number),
+                }
+
+                let mut previous = None;
+                for snapshot in &snapshots {
+                    let solved = snapshot.solved.0.contains(&number);
+                    if previous != Some(solved) {
+                        println!(
+                            "  {}  {}",
+                            snapshot.timestamp.format("%Y-%m-%d %H:%M"),
+                            if solved { "solved" } else { "unsolved" }
+                        );
+                    }
+                    previous = Some(solved);
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{diff::ProgressDiff, enrichment::Enrichment, test_support::with_each_backend},
+        chrono::TimeZone,
+    };
+
+    fn snapshot(hour: u32, solved: &[usize]) -> Snapshot {
+        Snapshot {
+            timestamp: Utc.with_ymd_and_hms(2024, 3, 1, hour, 0, 0).unwrap(),
+            solved_count: solved.len(),
+            total: 10,
+            level: 0,
+            solved: ProblemSet(solved.iter().copied().collect()),
+            retired: ProblemSet::default(),
+        }
+    }
+
+    #[test]
+    fn keeps_snapshots_in_the_order_they_were_recorded() {
+        with_each_backend(|| {
+            // A clock set back between fetches still leaves the latest snapshot last.
+            for snapshot in &[
+                snapshot(2, &[1]),
+                snapshot(1, &[1, 2]),
+                snapshot(3, &[1, 2, 3]),
+            ] {
+                append_snapshot("default", snapshot).unwrap();
+            }
+            let history = read_history("default").unwrap();
+            let hours = history
+                .iter()
+                .map(|snapshot| snapshot.timestamp.format("%H").to_string())
+                .collect::<Vec<_>>();
+            assert_eq!(hours, ["02", "01", "03"]);
+            assert_eq!(history[2].solved.to_string(), "1-3");
+        });
+    }
+
+    #[test]
+    fn records_when_problems_were_first_seen_solved() {
+        with_each_backend(|| {
+            for snapshot in &[
+                snapshot(1, &[1]),
+                snapshot(2, &[1, 2]),
+                snapshot(3, &[2, 3]),
+            ] {
+                append_snapshot("default", snapshot).unwrap();
+            }
+            let first_seen = first_seen_solved("default").unwrap();
+            assert_eq!(
+                first_seen.into_iter().collect::<Vec<_>>(),
+                [
+                    (2, snapshot(2, &[]).timestamp),
+                    (3, snapshot(3, &[]).timestamp)
+                ]
+            );
+            assert!(read_history("other").unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn diffs_recorded_snapshots() {
+        with_each_backend(|| {
+            for snapshot in &[snapshot(1, &[1]), snapshot(2, &[1, 4, 5])] {
+                append_snapshot("default", snapshot).unwrap();
+            }
+            let history = read_history("default").unwrap();
+            let diff = ProgressDiff::between(&history[0], &history[1], &Enrichment::default());
+            let newly_solved = diff
+                .newly_solved
+                .iter()
+                .map(|solved| solved.number)
+                .collect::<Vec<_>>();
+            assert_eq!(newly_solved, [4, 5]);
+            assert_eq!(diff.solved_change, 2);
+            assert_eq!(diff.since, history[0].timestamp);
+        });
+    }
+
+    #[test]
+    fn round_trips_problem_sets() {
+        for s in &["", "7", "1-10,12,15-16", "1,3,5", "2-3,100-102"] {
+            let set = s.parse::<ProblemSet>().unwrap();
+            assert_eq!(set.to_string(), *s);
+        }
+        assert_eq!(
+            "3,1-2,2".parse::<ProblemSet>().unwrap(),
+            ProblemSet((1..=3).collect())
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_problem_sets() {
+        for s in &["10-5", "1-", "x", "1,,-2", "1-2-3"] {
+            assert!(s.parse::<ProblemSet>().is_err(), "{:?} parsed", s);
+        }
+    }
+}
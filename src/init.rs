@@ -0,0 +1,151 @@
+use {
+    crate::{
+        client::SESSION_COOKIE_NAME,
+        runner::cargo,
+        workspace::{Config, Layout, LayoutKind, CONFIG_FILE_NAME, MANIFEST_FILE_NAME},
+    },
+    std::{
+        error::Error,
+        fs::{create_dir_all, read_to_string, write, OpenOptions},
+        io::{ErrorKind, Write},
+        path::{Path, PathBuf},
+    },
+    structopt::StructOpt,
+};
+
+const GITIGNORE_FILE_NAME: &str = ".gitignore";
+pub const HASHED_ANSWERS_FILE_NAME: &str = "answers.sha256";
+
+const VIRTUAL_MANIFEST: &str = "[workspace]\nmembers = []\n";
+const HASHED_ANSWERS_PLACEHOLDER: &str = "\
+# Expected answers to problems, one `<problem> <SHA-256 of the answer>` pair per line.
+";
+
+/// Set up a workspace for solutions.
+#[derive(Debug, StructOpt)]
+pub struct Init {
+    /// The directory to set up. Created if it doesn't exist.
+    #[structopt(default_value = ".", parse(from_os_str))]
+    dir: PathBuf,
+    /// Make every solution its own crate in a virtual workspace, rather than a binary of a
+    /// single package.
+    #[structopt(long)]
+    crate_per_problem: bool,
+    /// The minimum number of digits problem numbers are zero-padded to.
+    #[structopt(long, default_value = "3")]
+    padding: usize,
+    /// What comes before the padded problem number in the names of solutions.
+    #[structopt(long, default_value = "p")]
+    prefix: String,
+}
+
+/// Writes `contents` to `path` unless something already exists there, returning whether the
+/// file was created.
+fn create_new(path: &Path, contents: &str) -> Result<bool, Box<dyn Error>> {
+    match OpenOptions::new().write(true).create_new(true).open(path) {
+        Ok(mut file) => {
+            file.write_all(contents.as_bytes())?;
+            Ok(true)
+        }
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(false),
+        Err(e) => Err(format!("unable to create {}: {}", path.display(), e).into()),
+    }
+}
+
+/// Adds `entry` to the `.gitignore` in `dir` if it isn't already there, returning whether it
+/// was added.
+fn ensure_ignored(dir: &Path, entry: &str) -> Result<bool, Box<dyn Error>> {
+    let path = dir.join(GITIGNORE_FILE_NAME);
+    let existing = match read_to_string(&path) {
+        Ok(existing) => existing,
+        Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    if existing
+        .lines()
+        .any(|line| line.trim().trim_start_matches('/') == entry)
+    {
+        return Ok(false);
+    }
+    let mut updated = existing;
+    if !updated.is_empty() && !updated.ends_with('\n') {
+        updated.push('\n');
+    }
+    updated.push('/');
+    updated.push_str(entry);
+    updated.push('\n');
+    write(&path, updated)?;
+    Ok(true)
+}
+
+impl Init {
+    pub fn run(self) -> Result<(), Box<dyn Error>> {
+        let Self {
+            dir,
+            crate_per_problem,
+            padding,
+            prefix,
+        } = self;
+        let kind = if crate_per_problem {
+            LayoutKind::CratePerProblem
+        } else {
+            LayoutKind::BinPerProblem
+        };
+
+        create_dir_all(&dir)?;
+        let report = |created: bool, what: &dyn std::fmt::Display| {
+            if created {
+                println!("Created {}", what);
+            } else {
+                println!("Kept existing {}", what);
+            }
+        };
+
+        let manifest_path = dir.join(MANIFEST_FILE_NAME);
+        let manifest_created = if manifest_path.exists() {
+            false
+        } else {
+            match kind {
+                LayoutKind::BinPerProblem => {
+                    let status = cargo().arg("init").arg("--lib").arg(&dir).status()?;
+                    if !status.success() {
+                        return Err(format!("`cargo init` failed with {}", status).into());
+                    }
+                    true
+                }
+                LayoutKind::CratePerProblem => create_new(&manifest_path, VIRTUAL_MANIFEST)?,
+            }
+        };
+        report(manifest_created, &manifest_path.display());
+
+        let config_path = dir.join(CONFIG_FILE_NAME);
+        let config = Config {
+            layout: Layout {
+                kind,
+                padding,
+                prefix,
+                ..Layout::default()
+            },
+            ..Config::default()
+        };
+        report(
+            create_new(&config_path, &toml::to_string(&config)?)?,
+            &config_path.display(),
+        );
+
+        let answers_path = dir.join(HASHED_ANSWERS_FILE_NAME);
+        report(
+            create_new(&answers_path, HASHED_ANSWERS_PLACEHOLDER)?,
+            &answers_path.display(),
+        );
+
+        if ensure_ignored(&dir, SESSION_COOKIE_NAME)? {
+            println!(
+                "Added {} to {}",
+                SESSION_COOKIE_NAME,
+                dir.join(GITIGNORE_FILE_NAME).display()
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,209 @@
+//! Levels, and how solved problems are spread over the bands of problems they're counted in.
+
+use {
+    crate::{
+        context::Context,
+        output::bar,
+        progress::{ProblemStatus, Problems, Progress},
+        stats::PROBLEMS_PER_LEVEL,
+    },
+    std::{error::Error, ops::RangeInclusive},
+    structopt::StructOpt,
+};
+
+/// A band is cheap to complete with at most this many problems left in it.
+const CHEAP_UNSOLVED: usize = 3;
+
+const BAR_WIDTH: usize = 25;
+
+/// A band of consecutive problems, `PROBLEMS_PER_LEVEL` long except for perhaps the last.
+#[derive(Debug, PartialEq)]
+pub struct Band {
+    pub problems: RangeInclusive<usize>,
+    pub solved: usize,
+    pub unsolved: Vec<usize>,
+}
+
+impl Band {
+    /// How many problems of the band can be solved, which leaves out retired ones.
+    pub fn available(&self) -> usize {
+        self.solved + self.unsolved.len()
+    }
+}
+
+impl Band {
+    /// Whether only a few problems are left to complete the band as a consecutive run.
+    pub fn is_cheap(&self) -> bool {
+        (1..=CHEAP_UNSOLVED).contains(&self.unsolved.len())
+    }
+}
+
+/// Splits the published problems into bands of `PROBLEMS_PER_LEVEL` by number, so that retired
+/// problems leave their bands short rather than shifting the problems after them.
+pub fn bands(problems: &Problems) -> Vec<Band> {
+    problems
+        .0
+        .chunks(PROBLEMS_PER_LEVEL)
+        .enumerate()
+        .map(|(idx, chunk)| {
+            let first = idx * PROBLEMS_PER_LEVEL + 1;
+            let with_status = |status| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .filter(move |(_, problem)| **problem == status)
+                    .map(move |(offset, _)| first + offset)
+            };
+            Band {
+                problems: first..=first + chunk.len() - 1,
+                solved: with_status(ProblemStatus::Solved).count(),
+                unsolved: with_status(ProblemStatus::Unsolved).collect(),
+            }
+        })
+        .collect()
+}
+
+/// Renders a line of the distribution of solved problems over bands, with the numbers of
+/// problems padded to `width`.
+fn band_line(band: &Band, width: usize) -> String {
+    let count = band.available();
+    let mut line = format!(
+        "{:>width$}-{:<width$}  {}  {:>2}/{}",
+        band.problems.start(),
+        band.problems.end(),
+        bar(band.solved as f64 / count.max(1) as f64, BAR_WIDTH),
+        band.solved,
+        count,
+        width = width,
+    );
+    if band.is_cheap() {
+        line.push_str(&format!(
+            "  cheap: {} left ({})",
+            band.unsolved.len(),
+            band.unsolved
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    line
+}
+
+/// List levels and their thresholds.
+#[derive(Debug, StructOpt)]
+pub struct Levels {
+    /// Instead, show how many problems are solved in each band of problems a level's worth
+    /// long, flagging bands that are cheap to complete.
+    #[structopt(long)]
+    distribution: bool,
+}
+
+impl Levels {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self { distribution } = self;
+
+        let progress = context.progress()?;
+        let Progress { levels, problems } = &progress;
+        if !distribution {
+            // New accounts' progress pages list no levels.
+            if levels.0.is_empty() {
+                if let Some(next) = progress.next_level_info() {
+                    println!(
+                        "No levels are listed yet; Level {} is reached at {} solved",
+                        next.level, next.threshold
+                    );
+                }
+                return Ok(());
+            }
+            for (idx, level) in levels.0.iter().enumerate() {
+                println!(
+                    "{} Level {}: {}",
+                    if level.completed { "✔" } else { " " },
+                    idx + 1,
+                    level.description
+                );
+            }
+            return Ok(());
+        }
+
+        let bands = bands(problems);
+        let width = bands
+            .last()
+            .map_or(0, |band| format!("{}", band.problems.end()).len());
+        for band in &bands {
+            println!("{}", band_line(band, width));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::test_support::{new_account, problems},
+    };
+
+    #[test]
+    fn splits_problems_into_bands() {
+        let statuses = format!("{}U{}SRUSS", "S".repeat(20), "S".repeat(4));
+        let bands = bands(&problems(&statuses));
+        assert_eq!(
+            bands,
+            [
+                Band {
+                    problems: 1..=25,
+                    solved: 24,
+                    unsolved: vec![21],
+                },
+                Band {
+                    problems: 26..=30,
+                    solved: 3,
+                    unsolved: vec![28],
+                },
+            ]
+        );
+        // The retired problem leaves the last band short of its five problems.
+        assert_eq!(bands[1].available(), 4);
+        assert!(bands[0].is_cheap());
+    }
+
+    #[test]
+    fn flags_bands_cheap_to_complete() {
+        let band = |unsolved: &[usize]| Band {
+            problems: 1..=25,
+            solved: 25 - unsolved.len(),
+            unsolved: unsolved.to_vec(),
+        };
+        assert!(!band(&[]).is_cheap());
+        assert!(band(&[1, 2, 3]).is_cheap());
+        assert!(!band(&[1, 2, 3, 4]).is_cheap());
+        assert!(bands(&problems("")).is_empty());
+    }
+
+    #[test]
+    fn draws_bands_of_accounts_without_progress() {
+        let lines = bands(&new_account().problems)
+            .iter()
+            .map(|band| band_line(band, 2))
+            .collect::<Vec<_>>();
+        let empty = " ".repeat(BAR_WIDTH);
+        assert_eq!(
+            lines,
+            [
+                format!(" 1-25  {}   0/25", empty),
+                format!("26-30  {}   0/4", empty),
+            ]
+        );
+    }
+
+    #[test]
+    fn draws_bands_cheap_to_complete() {
+        let line = band_line(&bands(&problems("SSUSS"))[0], 1);
+        assert_eq!(
+            line,
+            format!("1-5  {:<25}   4/5  cheap: 1 left (3)", "█".repeat(20))
+        );
+    }
+}
@@ -0,0 +1,581 @@
+//! Checks for inconsistencies between a workspace and the account it's solving for, reported
+//! like compiler lints.
+
+use {
+    crate::{
+        answers::Answers,
+        context::Context,
+        status::{categorize, Category, Entry, StatusReport},
+        workspace::Workspace,
+        Failure,
+    },
+    std::{
+        collections::BTreeMap,
+        error::Error,
+        fmt::{self, Display, Formatter},
+        fs::read_to_string,
+        path::{Path, PathBuf},
+        str::FromStr,
+    },
+    structopt::StructOpt,
+};
+
+/// How a lint affects the outcome of linting.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Level {
+    /// Not reported at all.
+    Allow,
+    /// Reported, without failing.
+    Warn,
+    /// Reported, failing linting.
+    Deny,
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Warn => "warning",
+            Self::Deny => "error",
+        }
+    }
+}
+
+/// A consistency check.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rule {
+    /// A problem is solved on the site, but has no local solution.
+    SolvedWithoutLocal,
+    /// A problem has a local solution, but isn't solved on the site.
+    LocalWithoutSolved,
+    /// A local solution has no stored answer to test it against.
+    SolutionWithoutAnswer,
+    /// An answer is stored for a problem without a local solution.
+    AnswerWithoutSolution,
+    /// A solution includes a data file with `include_str!` or `include_bytes!` that doesn't
+    /// exist.
+    MissingDataFile,
+    /// A solution still has a `todo!()` from being scaffolded.
+    TodoStub,
+}
+
+impl Rule {
+    const ALL: &'static [Self] = &[
+        Self::SolvedWithoutLocal,
+        Self::LocalWithoutSolved,
+        Self::SolutionWithoutAnswer,
+        Self::AnswerWithoutSolution,
+        Self::MissingDataFile,
+        Self::TodoStub,
+    ];
+
+    fn id(self) -> &'static str {
+        match self {
+            Self::SolvedWithoutLocal => "solved-without-local",
+            Self::LocalWithoutSolved => "local-without-solved",
+            Self::SolutionWithoutAnswer => "solution-without-answer",
+            Self::AnswerWithoutSolution => "answer-without-solution",
+            Self::MissingDataFile => "missing-data-file",
+            Self::TodoStub => "todo-stub",
+        }
+    }
+
+    /// The level of the rule unless `--deny` or `--allow` says otherwise. Only what's likely a
+    /// mistake, rather than work in progress, is denied.
+    fn default_level(self) -> Level {
+        match self {
+            Self::LocalWithoutSolved | Self::MissingDataFile => Level::Deny,
+            Self::SolvedWithoutLocal
+            | Self::SolutionWithoutAnswer
+            | Self::AnswerWithoutSolution
+            | Self::TodoStub => Level::Warn,
+        }
+    }
+
+    /// Whether the rule needs the problems solved on the site.
+    fn is_remote(self) -> bool {
+        matches!(self, Self::SolvedWithoutLocal | Self::LocalWithoutSolved)
+    }
+
+    fn check(self, model: &Model) -> Result<Vec<String>, Box<dyn Error>> {
+        match self {
+            Self::SolvedWithoutLocal => Ok(solved_without_local(model)),
+            Self::LocalWithoutSolved => Ok(local_without_solved(model)),
+            Self::SolutionWithoutAnswer => Ok(solution_without_answer(model)),
+            Self::AnswerWithoutSolution => Ok(answer_without_solution(model)),
+            Self::MissingDataFile => missing_data_file(model),
+            Self::TodoStub => todo_stub(model),
+        }
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|rule| rule.id() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown lint {:?}; expected one of {}",
+                    s,
+                    Self::ALL
+                        .iter()
+                        .map(|rule| rule.id())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+    }
+}
+
+/// Everything lints check.
+pub struct Model<'w> {
+    pub workspace: &'w Workspace,
+    /// The local solutions of the workspace, grouped by problem number.
+    pub local: BTreeMap<usize, Vec<PathBuf>>,
+    pub answers: Answers,
+    /// How local solutions relate to the problems solved on the site, if any rule needs it.
+    pub report: Option<StatusReport>,
+}
+
+impl Model<'_> {
+    fn entries(&self, category: Category) -> &[Entry] {
+        self.report
+            .as_ref()
+            .and_then(|report| report.get(&category))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    fn display_paths(&self, paths: &[PathBuf]) -> String {
+        paths
+            .iter()
+            .map(|path| self.workspace.display_path(path).to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Every local solution file, with the problem it's for.
+    fn solution_files(&self) -> impl Iterator<Item = (usize, &PathBuf)> {
+        self.local
+            .iter()
+            .flat_map(|(number, paths)| paths.iter().map(move |path| (*number, path)))
+    }
+}
+
+fn solved_without_local(model: &Model) -> Vec<String> {
+    model
+        .entries(Category::SolvedWithoutLocal)
+        .iter()
+        .map(|Entry { number, .. }| {
+            format!(
+                "problem {} is solved on the site, but has no local solution",
+                number
+            )
+        })
+        .collect()
+}
+
+fn local_without_solved(model: &Model) -> Vec<String> {
+    model
+        .entries(Category::LocalUnsolved)
+        .iter()
+        .map(|Entry { number, paths, .. }| {
+            format!(
+                "problem {} has a local solution ({}), but isn't solved on the site",
+                number,
+                model.display_paths(paths)
+            )
+        })
+        .collect()
+}
+
+fn solution_without_answer(model: &Model) -> Vec<String> {
+    model
+        .local
+        .iter()
+        .filter(|(number, _)| model.answers.get(**number).is_none())
+        .map(|(number, paths)| {
+            format!(
+                "problem {} has a local solution ({}), but no stored answer",
+                number,
+                model.display_paths(paths)
+            )
+        })
+        .collect()
+}
+
+fn answer_without_solution(model: &Model) -> Vec<String> {
+    model
+        .answers
+        .numbers()
+        .filter(|number| !model.local.contains_key(number))
+        .map(|number| {
+            format!(
+                "problem {} has a stored answer, but no local solution",
+                number
+            )
+        })
+        .collect()
+}
+
+/// The code of `line`, without any `//` comment.
+fn code(line: &str) -> &str {
+    line.find("//").map_or(line, |start| &line[..start])
+}
+
+/// Parses the string literal at the start of `s`, without handling escapes other than of `"`
+/// and `\`.
+fn string_literal(s: &str) -> Option<String> {
+    let mut chars = s.trim_start().strip_prefix('"')?.chars();
+    let mut literal = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(literal),
+            '\\' => literal.push(chars.next()?),
+            c => literal.push(c),
+        }
+    }
+}
+
+/// Finds the paths `source` includes with `include_str!` or `include_bytes!`, either relative
+/// to the file, or to the package with `concat!(env!("CARGO_MANIFEST_DIR"), ...)`, as
+/// `cargo euler new` writes them. The latter are returned with `true`.
+fn included_paths(source: &str) -> Vec<(String, bool)> {
+    const MANIFEST_DIR: &str = "concat!(env!(\"CARGO_MANIFEST_DIR\"),";
+
+    let mut included = Vec::new();
+    for line in source.lines().map(code) {
+        for (start, _) in line.match_indices("include_") {
+            let args = ["include_str!(", "include_bytes!("]
+                .iter()
+                .find_map(|macro_call| line[start..].strip_prefix(macro_call));
+            let args = match args {
+                Some(args) => args.trim_start(),
+                None => continue,
+            };
+            let found = match args.strip_prefix(MANIFEST_DIR) {
+                Some(rest) => string_literal(rest).map(|path| (path, true)),
+                None => string_literal(args).map(|path| (path, false)),
+            };
+            included.extend(found);
+        }
+    }
+    included
+}
+
+fn missing_data_file(model: &Model) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut findings = Vec::new();
+    for (number, path) in model.solution_files() {
+        let source = read_to_string(path)
+            .map_err(|e| format!("unable to read {}: {}", path.display(), e))?;
+        for (included, from_manifest_dir) in included_paths(&source) {
+            let base = if from_manifest_dir {
+                model.workspace.package_dir(number)
+            } else {
+                path.parent().map_or_else(PathBuf::new, Path::to_owned)
+            };
+            let resolved = base.join(included.trim_start_matches('/'));
+            if !resolved.is_file() {
+                findings.push(format!(
+                    "{} includes {}, which doesn't exist",
+                    model.workspace.display_path(path),
+                    model.workspace.display_path(&resolved)
+                ));
+            }
+        }
+    }
+    Ok(findings)
+}
+
+fn todo_stub(model: &Model) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut findings = Vec::new();
+    for (_, path) in model.solution_files() {
+        let source = read_to_string(path)
+            .map_err(|e| format!("unable to read {}: {}", path.display(), e))?;
+        if let Some(idx) = source
+            .lines()
+            .position(|line| code(line).contains("todo!("))
+        {
+            findings.push(format!(
+                "{}:{} still has a `todo!()`",
+                model.workspace.display_path(path),
+                idx + 1
+            ));
+        }
+    }
+    Ok(findings)
+}
+
+/// The level of `rule`, given the lints passed to `--deny` and `--allow`.
+fn level(rule: Rule, deny: &[Rule], allow: &[Rule]) -> Level {
+    if deny.contains(&rule) {
+        Level::Deny
+    } else if allow.contains(&rule) {
+        Level::Allow
+    } else {
+        rule.default_level()
+    }
+}
+
+/// Check the workspace and the account for inconsistencies, printing each finding prefixed with
+/// the lint it's from. Exits with 1 if any denied lint has findings.
+///
+/// Lints: `solved-without-local`, `local-without-solved` (denied by default),
+/// `solution-without-answer`, `answer-without-solution`, `missing-data-file` (denied by default)
+/// and `todo-stub`.
+#[derive(Debug, StructOpt)]
+pub struct Lint {
+    /// Fail if this lint has any findings.
+    #[structopt(long)]
+    deny: Vec<Rule>,
+    /// Skip this lint entirely.
+    #[structopt(long)]
+    allow: Vec<Rule>,
+}
+
+impl Lint {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self { deny, allow } = self;
+        if let Some(rule) = deny.iter().find(|rule| allow.contains(rule)) {
+            return Err(format!("the lint {} can't be both denied and allowed", rule).into());
+        }
+        let level = |rule| level(rule, &deny, &allow);
+        let rules = Rule::ALL
+            .iter()
+            .copied()
+            .filter(|rule| level(*rule) != Level::Allow)
+            .collect::<Vec<_>>();
+
+        let workspace = Workspace::discover()?;
+        let local = workspace.scan_solutions()?;
+        let report = if rules.iter().any(|rule| rule.is_remote()) {
+            Some(categorize(&context.progress()?.problems, local.clone()))
+        } else {
+            None
+        };
+        let model = Model {
+            workspace: &workspace,
+            local,
+            answers: Answers::load(&workspace, false)?,
+            report,
+        };
+
+        let (mut warnings, mut errors) = (0, 0);
+        for rule in rules {
+            let level = level(rule);
+            for finding in rule.check(&model)? {
+                println!("{}[{}]: {}", level.name(), rule, finding);
+                match level {
+                    Level::Deny => errors += 1,
+                    _ => warnings += 1,
+                }
+            }
+        }
+        if errors > 0 {
+            return Err(Failure(format!(
+                "{} error(s) and {} warning(s) from lints",
+                errors, warnings
+            ))
+            .into());
+        }
+        if warnings > 0 {
+            println!("{} warning(s) from lints", warnings);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            answers::PLAINTEXT_ANSWERS_FILE_NAME,
+            test_support::{problems, TempDir},
+            workspace::{Config, LayoutKind},
+        },
+        std::fs::{create_dir_all, write},
+    };
+
+    /// A bin-per-problem workspace in `dir` with the given files and their contents.
+    fn workspace(dir: &TempDir, files: &[(&str, &str)]) -> Workspace {
+        for (file, contents) in files {
+            let path = dir.path().join(file);
+            create_dir_all(path.parent().unwrap()).unwrap();
+            write(path, contents).unwrap();
+        }
+        Workspace::new(dir.path().to_owned(), Config::default())
+    }
+
+    /// The findings of `rule` in `workspace`, with `statuses` solved on the site as spelled out
+    /// for `test_support::problems`.
+    fn findings(rule: Rule, workspace: &Workspace, statuses: &str) -> Vec<String> {
+        let local = workspace.scan_solutions().unwrap();
+        let model = Model {
+            workspace,
+            report: Some(categorize(&problems(statuses), local.clone())),
+            local,
+            answers: Answers::load(workspace, false).unwrap(),
+        };
+        rule.check(&model).unwrap()
+    }
+
+    const SOLUTION: &str = "fn main() {}\n";
+
+    #[test]
+    fn finds_problems_solved_without_local_solutions() {
+        let dir = TempDir::new();
+        let workspace = workspace(&dir, &[("src/bin/p001.rs", SOLUTION)]);
+        assert_eq!(
+            findings(Rule::SolvedWithoutLocal, &workspace, "SSU"),
+            ["problem 2 is solved on the site, but has no local solution"]
+        );
+    }
+
+    #[test]
+    fn finds_local_solutions_to_unsolved_problems() {
+        let dir = TempDir::new();
+        let workspace = workspace(
+            &dir,
+            &[("src/bin/p001.rs", SOLUTION), ("src/bin/p003.rs", SOLUTION)],
+        );
+        assert_eq!(
+            findings(Rule::LocalWithoutSolved, &workspace, "SSU"),
+            ["problem 3 has a local solution (src/bin/p003.rs), but isn't solved on the site"]
+        );
+    }
+
+    #[test]
+    fn finds_solutions_and_answers_without_each_other() {
+        let dir = TempDir::new();
+        let workspace = workspace(
+            &dir,
+            &[
+                ("src/bin/p001.rs", SOLUTION),
+                ("src/bin/p002.rs", SOLUTION),
+                (PLAINTEXT_ANSWERS_FILE_NAME, "1 = 233168\n3 = 4613732\n"),
+            ],
+        );
+        assert_eq!(
+            findings(Rule::SolutionWithoutAnswer, &workspace, "SSS"),
+            ["problem 2 has a local solution (src/bin/p002.rs), but no stored answer"]
+        );
+        assert_eq!(
+            findings(Rule::AnswerWithoutSolution, &workspace, "SSS"),
+            ["problem 3 has a stored answer, but no local solution"]
+        );
+    }
+
+    #[test]
+    fn finds_included_paths() {
+        let source = r#"
+            const NAMES: &str = include_str!("../../data/names.txt");
+            const MATRIX: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/data/matrix.txt"));
+            // const OLD: &str = include_str!("old.txt");
+            const ESCAPED: &str = include_str!("a \"quoted\" name.txt"); // include_str!("no.txt")
+        "#;
+        assert_eq!(
+            included_paths(source),
+            [
+                ("../../data/names.txt".to_owned(), false),
+                ("/data/matrix.txt".to_owned(), true),
+                ("a \"quoted\" name.txt".to_owned(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_missing_data_files() {
+        let dir = TempDir::new();
+        let workspace = workspace(
+            &dir,
+            &[
+                (
+                    "src/bin/p022.rs",
+                    concat!(
+                        "const NAMES: &str = include_str!(\"../../data/p022/names.txt\");\n",
+                        "const KEYS: &str = include_str!(concat!(env!(\"CARGO_MANIFEST_DIR\"), ",
+                        "\"/data/p022/keys.txt\"));\n",
+                    ),
+                ),
+                ("data/p022/names.txt", ""),
+            ],
+        );
+        assert_eq!(
+            findings(Rule::MissingDataFile, &workspace, ""),
+            ["src/bin/p022.rs includes data/p022/keys.txt, which doesn't exist"]
+        );
+    }
+
+    #[test]
+    fn resolves_data_files_from_the_package_of_each_solution() {
+        let dir = TempDir::new();
+        for (file, contents) in &[
+            (
+                "problems/p022/src/main.rs",
+                "const NAMES: &str = include_str!(concat!(env!(\"CARGO_MANIFEST_DIR\"), \
+                 \"/data/names.txt\"));\n",
+            ),
+            ("problems/p022/data/names.txt", ""),
+        ] {
+            let path = dir.path().join(file);
+            create_dir_all(path.parent().unwrap()).unwrap();
+            write(path, contents).unwrap();
+        }
+        let mut config = Config::default();
+        config.layout.kind = LayoutKind::CratePerProblem;
+        let workspace = Workspace::new(dir.path().to_owned(), config);
+        assert!(findings(Rule::MissingDataFile, &workspace, "").is_empty());
+    }
+
+    #[test]
+    fn finds_todo_stubs() {
+        let dir = TempDir::new();
+        let workspace = workspace(
+            &dir,
+            &[
+                (
+                    "src/bin/p001.rs",
+                    "fn main() {\n    todo!(\"solve it\")\n}\n",
+                ),
+                (
+                    "src/bin/p002.rs",
+                    "fn main() {\n    // todo!() once it's fast\n}\n",
+                ),
+            ],
+        );
+        assert_eq!(
+            findings(Rule::TodoStub, &workspace, ""),
+            ["src/bin/p001.rs:2 still has a `todo!()`"]
+        );
+    }
+
+    #[test]
+    fn denies_and_allows_lints() {
+        assert_eq!(level(Rule::TodoStub, &[], &[]), Level::Warn);
+        assert_eq!(level(Rule::MissingDataFile, &[], &[]), Level::Deny);
+        assert_eq!(level(Rule::TodoStub, &[Rule::TodoStub], &[]), Level::Deny);
+        assert_eq!(
+            level(Rule::MissingDataFile, &[], &[Rule::MissingDataFile]),
+            Level::Allow
+        );
+    }
+
+    #[test]
+    fn parses_lint_ids() {
+        for rule in Rule::ALL {
+            assert_eq!(rule.id().parse::<Rule>(), Ok(*rule));
+        }
+        let e = "todo".parse::<Rule>().unwrap_err();
+        assert!(e.contains("todo-stub"), "{}", e);
+    }
+}
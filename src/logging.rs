@@ -0,0 +1,215 @@
+//! Setting up log output, either for people to read or as JSON for log aggregation.
+
+use {
+    crate::output::Format,
+    chrono::{SecondsFormat, Utc},
+    env_logger::{filter::Filter, Env},
+    log::{
+        kv::{self, Key, Value, VisitSource},
+        Log, Metadata, Record,
+    },
+    serde_json::{Map, Number},
+    std::{
+        env,
+        io::{stderr, Write},
+        sync::Mutex,
+    },
+};
+
+/// Collects the structured fields of a record as JSON values.
+struct Fields(Map<String, serde_json::Value>);
+
+impl<'kvs> VisitSource<'kvs> for Fields {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        let value = if let Some(number) = value.to_u64() {
+            serde_json::Value::Number(number.into())
+        } else if let Some(number) = value.to_i64() {
+            serde_json::Value::Number(number.into())
+        } else if let Some(number) = value.to_f64().and_then(Number::from_f64) {
+            serde_json::Value::Number(number)
+        } else if let Some(boolean) = value.to_bool() {
+            serde_json::Value::Bool(boolean)
+        } else {
+            serde_json::Value::String(value.to_string())
+        };
+        self.0.insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
+/// Renders `record` as a JSON object, with its structured fields alongside its message.
+fn json_record(record: &Record) -> serde_json::Value {
+    let mut fields = Fields(Map::new());
+    let _ = record.key_values().visit(&mut fields);
+    let mut object = fields.0;
+    object.insert(
+        "timestamp".to_owned(),
+        Utc::now()
+            .to_rfc3339_opts(SecondsFormat::Millis, true)
+            .into(),
+    );
+    object.insert("level".to_owned(), record.level().as_str().into());
+    object.insert("target".to_owned(), record.target().into());
+    object.insert("message".to_owned(), record.args().to_string().into());
+    serde_json::Value::Object(object)
+}
+
+/// Writes the records `filter` lets through to `writer`, each as a JSON object on its own line.
+struct JsonLogger<W> {
+    filter: Filter,
+    writer: Mutex<W>,
+}
+
+impl<W: Write + Send> Log for JsonLogger<W> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.filter.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.filter.matches(record) {
+            let mut writer = self.writer.lock().unwrap();
+            let _ = writeln!(writer, "{}", json_record(record));
+        }
+    }
+
+    fn flush(&self) {
+        let _ = self.writer.lock().unwrap().flush();
+    }
+}
+
+/// Sends JSON log output to `writer`, filtered by `filter`.
+fn init_json(filter: Filter, writer: impl Write + Send + 'static) {
+    log::set_max_level(filter.filter());
+    log::set_boxed_logger(Box::new(JsonLogger {
+        filter,
+        writer: Mutex::new(writer),
+    }))
+    .expect("a logger was already set up");
+}
+
+/// Sends log output to stderr, filtered by `RUST_LOG` like usual. In the JSON format, every
+/// record is printed as an object on its own line.
+pub fn init(format: Format) {
+    match format {
+        Format::Human => {
+            env_logger::Builder::from_env(Env::default().default_filter_or("warn")).init()
+        }
+        Format::Json => {
+            let filters = env::var("RUST_LOG").unwrap_or_else(|_| "warn".to_owned());
+            init_json(
+                env_logger::filter::Builder::new().parse(&filters).build(),
+                stderr(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            client::fetch_progress_page,
+            endpoints::set_base_url,
+            test_support::{lock_globals, MockServer, Reply},
+        },
+        log::{Level, LevelFilter},
+        std::sync::Arc,
+    };
+
+    /// Log output kept in memory, for reading back.
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn keeps_session_ids_out_of_trace_logs() {
+        let _globals = lock_globals();
+        let server = MockServer::start();
+        set_base_url(Some(server.url().parse().unwrap()));
+        server.reply(
+            "GET",
+            "/progress",
+            Reply::ok("progress").header("Set-Cookie", "PHPSESSID=f00dfeedrenewed; path=/"),
+        );
+
+        // A logger can only be set up once per process, so this is the one test that does.
+        let captured = Captured::default();
+        init_json(
+            env_logger::filter::Builder::new().parse("trace").build(),
+            captured.clone(),
+        );
+        let fetched = fetch_progress_page(Some("0ddba11c0ffee"));
+        log::set_max_level(LevelFilter::Off);
+        fetched.unwrap();
+
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        let records = output
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert!(records
+            .iter()
+            .any(|record| record["target"] == "cargo_euler::client"));
+        assert!(!output.contains("0ddba11c0ffee"), "{}", output);
+        assert!(!output.contains("f00dfeedrenewed"), "{}", output);
+    }
+
+    #[test]
+    fn renders_records_as_json_with_their_fields() {
+        let fields: &[(&str, Value)] = &[
+            ("url", Value::from("https://projecteuler.net/progress")),
+            ("status", Value::from(200u16)),
+            ("offset", Value::from(-3i64)),
+            ("ratio", Value::from(0.5f64)),
+            ("cached", Value::from(false)),
+        ];
+        let record = json_record(
+            &Record::builder()
+                .args(format_args!("responded with {}", 200))
+                .level(Level::Debug)
+                .target("cargo_euler::client")
+                .key_values(&fields)
+                .build(),
+        );
+
+        let object = record.as_object().unwrap();
+        assert_eq!(object["message"], "responded with 200");
+        assert_eq!(object["level"], "DEBUG");
+        assert_eq!(object["target"], "cargo_euler::client");
+        assert_eq!(object["url"], "https://projecteuler.net/progress");
+        assert_eq!(object["status"], 200);
+        assert_eq!(object["offset"], -3);
+        assert_eq!(object["ratio"], 0.5);
+        assert_eq!(object["cached"], false);
+        let timestamp = object["timestamp"].as_str().unwrap();
+        assert!(chrono::DateTime::parse_from_rfc3339(timestamp).is_ok());
+        assert!(timestamp.ends_with('Z'));
+        // Each record has to stay on a line of its own.
+        assert!(!record.to_string().contains('\n'));
+    }
+
+    #[test]
+    fn keeps_messages_over_fields_of_the_same_name() {
+        let fields: &[(&str, &str)] = &[("message", "shadowed"), ("level", "shadowed")];
+        let record = json_record(
+            &Record::builder()
+                .args(format_args!("multi\nline"))
+                .level(Level::Warn)
+                .key_values(&fields)
+                .build(),
+        );
+        assert_eq!(record["message"], "multi\nline");
+        assert_eq!(record["level"], "WARN");
+        assert!(!record.to_string().contains('\n'));
+    }
+}
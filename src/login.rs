@@ -0,0 +1,89 @@
+use {
+    crate::{
+        captcha::{self, CaptchaPrompt, TerminalPrompt},
+        client::Session,
+        context::Context,
+        endpoints::sign_in_url,
+        form,
+        paths::{create_private_dir, session_id_path},
+    },
+    reqwest::Url,
+    std::{
+        error::Error,
+        fs::write,
+        io::{stdin, stdout, Write},
+    },
+    structopt::StructOpt,
+    unhtml::scraper::Html,
+};
+
+/// Sign in, saving the session to the `PHPSESSID` file for other commands to use.
+#[derive(Debug, StructOpt)]
+pub struct Login {
+    /// The username to sign in with. Prompted for if omitted.
+    #[structopt(long)]
+    username: Option<String>,
+}
+
+/// Signs in to a new session, returning its ID.
+pub fn sign_in(
+    username: &str,
+    password: &str,
+    prompt: &mut dyn CaptchaPrompt,
+) -> Result<String, Box<dyn Error>> {
+    let mut session = Session::anonymous()?;
+    let page_url = Url::parse(&sign_in_url())?;
+    let page = session.fetch_page(page_url.as_str())?;
+    let html = Html::parse_document(&page);
+    let form =
+        form::find(&html, |name| name == "password").ok_or("unable to find the sign-in form")?;
+
+    let mut fields = form::hidden_fields(form);
+    fields.push(("username".to_owned(), username.to_owned()));
+    fields.push(("password".to_owned(), password.to_owned()));
+    fields.push(("remember_me".to_owned(), "1".to_owned()));
+    if let Some((field, image_url)) = captcha::find_in_form(form, &page_url)? {
+        let solution = captcha::solve(&mut session, image_url.as_str(), prompt)?;
+        fields.push((field, solution));
+    }
+    let action = form::action(form, &page_url)?;
+
+    let response = session.post_form(action.as_str(), &fields)?;
+    if !response.contains("sign_out") {
+        return Err("unable to sign in; check your username, password and captcha".into());
+    }
+    Ok(session
+        .id()
+        .ok_or("the site didn't assign a session")?
+        .to_owned())
+}
+
+impl Login {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self { username } = self;
+        if context.offline {
+            return Err("unable to sign in with `--offline`".into());
+        }
+
+        let username = match username {
+            Some(username) => username,
+            None => {
+                print!("Username: ");
+                stdout().flush()?;
+                let mut username = String::new();
+                stdin().read_line(&mut username)?;
+                username.trim().to_owned()
+            }
+        };
+        let password = rpassword::prompt_password("Password: ")?;
+
+        let session_id = sign_in(&username, &password, &mut TerminalPrompt)?;
+        let path = session_id_path();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            create_private_dir(dir)?;
+        }
+        write(&path, session_id)?;
+        println!("Signed in; the session is saved in {}", path.display());
+        Ok(())
+    }
+}
@@ -1,192 +1,354 @@
+mod account;
+mod anonymize;
+mod answers;
+mod awards;
+mod bench;
+mod cache;
+mod captcha;
+mod chart;
+mod check;
+mod client;
+mod clipboard;
+mod commit_msg;
+mod context;
+mod desktop;
+mod diff;
+mod endpoints;
+mod enrichment;
+mod eulerians;
+mod fixtures;
+mod form;
+mod friends;
+mod github;
+mod goals;
+mod history;
+mod init;
+mod levels;
+mod lint;
+mod logging;
+mod login;
+mod new;
+mod notify;
+mod output;
+mod paths;
+mod pattern;
+mod percentile;
+mod pick;
+mod plan;
+mod prefetch;
+mod progress;
+mod random;
+mod rankings;
+mod readme_table;
+mod retention;
+mod run;
+mod runner;
+mod schedule;
+mod serve;
+mod solved_set;
+mod statement;
+mod stats;
+mod status;
+mod storage;
+mod submissions;
+mod submit;
+mod suggest;
+mod template;
+mod test;
+#[cfg(test)]
+mod test_support;
+mod tex;
+mod thread;
+mod timings;
+mod user_config;
+mod workspace;
+
 use {
-    itertools::Itertools,
+    crate::{
+        answers::AnswersCommand,
+        awards::Awards,
+        bench::Bench,
+        cache::CacheCommand,
+        check::Check,
+        client::{set_direct, set_dry_run, DryRunError},
+        commit_msg::CommitMsg,
+        context::Context,
+        diff::Diff,
+        endpoints::{parse_base_url, set_base_url},
+        eulerians::Eulerians,
+        friends::FriendsCommand,
+        goals::GoalCommand,
+        history::HistoryCommand,
+        init::Init,
+        levels::Levels,
+        lint::Lint,
+        login::Login,
+        new::New,
+        notify::Notify,
+        paths::Paths,
+        pattern::DetectLayout,
+        percentile::HardestSolved,
+        pick::Pick,
+        plan::Plan,
+        prefetch::Prefetch,
+        progress::{NextLevel, Progress},
+        random::Random,
+        rankings::Rankings,
+        readme_table::ReadmeTable,
+        run::Run,
+        schedule::Schedule,
+        serve::Serve,
+        solved_set::{Export, Import},
+        stats::Stats,
+        status::Status,
+        storage::StorageCommand,
+        submissions::SubmissionsCommand,
+        submit::Submit,
+        suggest::Suggest,
+        test::Test,
+        user_config::UserConfig,
+    },
     log::warn,
-    reqwest::{
-        header::{HeaderValue, COOKIE},
-        Client,
+    std::{
+        env::args_os,
+        error::Error,
+        fmt::{self, Display, Formatter},
+        process::exit,
     },
-    std::{error::Error, ffi::OsStr, fs::read_to_string, num::ParseIntError},
     structopt::StructOpt,
-    unhtml::{
-        Error as UnhtmlError,
-        scraper::{Html, Node, Selector},
-        ElemIter,
-        FromHtml,
-    },
-    unhtml_derive::FromHtml,
 };
 
-const PROJECT_EULER_HOSTNAME: &str = "projecteuler.net";
-const PROGRESS_ENDPOINT: &str = "progress";
-const SESSION_COOKIE_NAME: &str = "PHPSESSID";
-
-fn default_session_id_path() -> &'static OsStr {
-    SESSION_COOKIE_NAME.as_ref()
-}
-
 #[derive(Debug, StructOpt)]
 #[structopt(about, author)]
 struct Cli {
-    session_id: Option<String>,
+    #[structopt(flatten)]
+    context: Context,
+    #[structopt(subcommand)]
+    command: Option<Command>,
 }
 
-#[derive(Debug)]
-struct Level {
-    description: String,
-    completed: bool,
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Show the progress of your account. This is the default when no command is given.
+    Progress,
+    Answers(AnswersCommand),
+    Awards(Awards),
+    Bench(Bench),
+    Cache(CacheCommand),
+    Check(Check),
+    CommitMsg(CommitMsg),
+    DetectLayout(DetectLayout),
+    Diff(Diff),
+    Eulerians(Eulerians),
+    Export(Export),
+    Friends(FriendsCommand),
+    Goal(GoalCommand),
+    HardestSolved(HardestSolved),
+    History(HistoryCommand),
+    Import(Import),
+    Init(Init),
+    Levels(Levels),
+    Lint(Lint),
+    Login(Login),
+    New(New),
+    Notify(Notify),
+    Paths(Paths),
+    Pick(Pick),
+    Plan(Plan),
+    Prefetch(Prefetch),
+    Random(Random),
+    Rankings(Rankings),
+    ReadmeTable(ReadmeTable),
+    Run(Run),
+    Schedule(Schedule),
+    Serve(Serve),
+    Stats(Stats),
+    Status(Status),
+    Storage(StorageCommand),
+    Submissions(SubmissionsCommand),
+    Submit(Submit),
+    Suggest(Suggest),
+    Test(Test),
 }
 
-#[derive(Debug)]
-struct Levels(Vec<Level>);
-
-#[derive(Debug)]
-enum LevelLinkParseError<'a> {
-    SplitFailed(&'a str),
-    ParseFailed(ParseIntError),
+/// Summarizes `progress` in a few lines, for the default command.
+fn summary(progress: &Progress) -> String {
+    let Progress { levels, problems } = progress;
+    let solved = problems.solved_count();
+    let total = problems.available();
+    let percent = if total == 0 {
+        0.0
+    } else {
+        solved as f64 * 100.0 / total as f64
+    };
+    let mut summary = format!(
+        "Solved {} of {} problems ({:.1}%)\n",
+        solved, total, percent
+    );
+    match levels.current() {
+        0 => summary.push_str("No levels completed yet\n"),
+        level => summary.push_str(&format!("Level {}\n", level)),
+    }
+    match progress.next_level_info() {
+        Some(NextLevel {
+            level, remaining, ..
+        }) => summary.push_str(&format!("{} more to Level {}\n", remaining, level)),
+        None => summary.push_str("Reached the last published level\n"),
+    }
+    summary
 }
 
-fn parse_from_relative_link<'h>(
-    thing: &str,
-    href: &'h str,
-) -> Result<usize, LevelLinkParseError<'h>> {
-    use self::LevelLinkParseError::*;
+fn run() -> Result<(), Box<dyn Error>> {
+    let Cli { context, command } = {
+        // When invoked as `cargo euler`, cargo passes `euler` as the first argument.
+        let mut args = args_os().collect::<Vec<_>>();
+        if args.get(1).is_some_and(|arg| arg == "euler") {
+            args.remove(1);
+        }
+        Cli::from_iter(args)
+    };
+    logging::init(context.log_format);
+    if context.timings {
+        timings::enable();
+    }
+    if context.anonymize {
+        anonymize::enable(&context.profile)?;
+    }
+    set_dry_run(context.dry_run());
+    set_direct(context.no_proxy);
+    set_base_url(match &context.base_url {
+        Some(base_url) => Some(base_url.clone()),
+        None => match UserConfig::load().ok().and_then(|config| config.base_url) {
+            Some(base_url) => Some(
+                parse_base_url(&base_url)
+                    .map_err(|e| format!("{} in {}", e, UserConfig::path().display()))?,
+            ),
+            None => None,
+        },
+    });
+    if let Some(dir) = &context.record_fixtures {
+        fixtures::record_to(dir)?;
+    }
+    if let Some(dir) = &context.replay_fixtures {
+        fixtures::replay_from(dir)?;
+    }
 
-    match href.split('=').collect_tuple() {
-        Some((thing, level)) => Ok(level.parse().map_err(ParseFailed)?),
-        _ => Err(SplitFailed(href)),
+    match command.unwrap_or(Command::Progress) {
+        Command::Progress => {
+            let progress = context.progress()?;
+            timings::time("render", || print!("{}", summary(&progress)));
+            github::append_step_summary(&github::progress_summary(&progress));
+            if let Err(e) = goals::print_goals(&context.profile, &progress) {
+                warn!("unable to show progress toward goals: {}", e);
+            }
+        }
+        Command::Answers(answers) => answers.run()?,
+        Command::Awards(awards) => awards.run(&context)?,
+        Command::Bench(bench) => bench.run(&context.profile)?,
+        Command::Cache(cache) => cache.run(&context)?,
+        Command::Check(check) => check.run(&context)?,
+        Command::CommitMsg(commit_msg) => commit_msg.run(&context)?,
+        Command::DetectLayout(detect_layout) => detect_layout.run()?,
+        Command::Diff(diff) => diff.run(&context)?,
+        Command::Eulerians(eulerians) => eulerians.run(&context)?,
+        Command::Export(export) => export.run(&context)?,
+        Command::Friends(friends) => friends.run(&context)?,
+        Command::Goal(goal) => goal.run(&context)?,
+        Command::HardestSolved(hardest_solved) => hardest_solved.run(&context)?,
+        Command::History(history) => history.run(&context)?,
+        Command::Import(import) => import.run(&context)?,
+        Command::Init(init) => init.run()?,
+        Command::Levels(levels) => levels.run(&context)?,
+        Command::Lint(lint) => lint.run(&context)?,
+        Command::Login(login) => login.run(&context)?,
+        Command::New(new) => new.run(context.offline)?,
+        Command::Notify(notify) => notify.run(&context)?,
+        Command::Paths(paths) => paths.run(&context)?,
+        Command::Pick(pick) => pick.run(&context)?,
+        Command::Plan(plan) => plan.run(&context)?,
+        Command::Prefetch(prefetch) => prefetch.run(&context)?,
+        Command::Random(random) => random.run(&context)?,
+        Command::Rankings(rankings) => rankings.run(&context)?,
+        Command::ReadmeTable(readme_table) => readme_table.run(&context)?,
+        Command::Run(run) => run.run()?,
+        Command::Schedule(schedule) => schedule.run(&context)?,
+        Command::Serve(serve) => serve.run(&context)?,
+        Command::Stats(stats) => stats.run(&context)?,
+        Command::Status(status) => status.run(&context)?,
+        Command::Storage(storage) => storage.run()?,
+        Command::Submissions(submissions) => submissions.run(&context)?,
+        Command::Submit(submit) => submit.run(&context)?,
+        Command::Suggest(suggest) => suggest.run(&context)?,
+        Command::Test(test) => test.run()?,
     }
+    Ok(())
 }
 
-impl FromHtml for Levels {
-    fn from_elements(iter: ElemIter) -> Result<Self, UnhtmlError> {
-        let mut levels = Vec::new();
+/// An error signifying that a command ran to completion, but found problems it's meant to find
+/// (like failing tests), as opposed to being unable to do its job. Failures exit with status 1,
+/// and all other errors with status 2.
+#[derive(Debug)]
+pub struct Failure(pub String);
 
-        let selector = Selector::parse("div.info a").unwrap();
-        for anchor_el in iter {
-            use self::Node::*;
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
 
-            let level =
-                parse_from_relative_link("level", anchor_el.value().attr("href").unwrap()).unwrap();
-            let expected_idx = levels.len().checked_add(1).unwrap();
-            if level != expected_idx {
-                panic!("Missing expected level {}", expected_idx);
-            }
+impl Error for Failure {}
 
-            match anchor_el
-                .children()
-                .collect_tuple()
-                .map(|(rt, ds)| (rt.value(), ds))
-            {
-                Some((Element(resolution_tag), description_span)) => levels.push(Level {
-                    description: match description_span
-                        .children()
-                        .map(|nr| nr.value())
-                        .collect_tuple()
-                    {
-                        Some((Element(title), Text(description)))
-                            if &*title.name.local == "div" =>
-                        {
-                            format!("{}", description.text)
-                        }
-                        _ => panic!(
-                            "unexpected description format in level {}: {:#?}",
-                            level, description_span
-                        ),
-                    },
-                    completed: match &*resolution_tag.name.local {
-                        "div" => false,
-                        "img" => true,
-                        _ => panic!(
-                            "unrecognized completion tag in level {}: {:#?}",
-                            level, resolution_tag
-                        ),
-                    },
-                }),
-                _ => panic!(
-                    "unrecognized format underneath anchor in level {}: {:#?}",
-                    level, anchor_el
-                ),
-            }
-        }
+/// Ends the program with a particular exit status and nothing else, for commands whose exit
+/// status means more than whether they succeeded.
+#[derive(Debug)]
+pub struct ExitStatus(pub i32);
 
-        Ok(Levels(levels))
+impl Display for ExitStatus {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "exiting with status {}", self.0)
     }
 }
 
-#[derive(Debug)]
-struct Problems(Vec<bool>);
-
-impl FromHtml for Problems {
-    fn from_elements(iter: ElemIter) -> Result<Self, UnhtmlError> {
-        use self::Node::*;
-
-        let mut problems = Vec::new();
-
-        let selector = Selector::parse("td.problem_solved,td.problem_unsolved").unwrap();
-        for problem_el in iter {
-            let mut solved = None;
-            for class in problem_el.value().classes.iter() {
-                let class: &str = &*class;
-                let solved_value = match class {
-                    "problem_solved" => true,
-                    "problem_unsolved" => false,
-                    _ => {
-                        warn!(
-                            "unable to determine solution status from class \"{}\"",
-                            class
-                        );
-                        continue;
-                    }
-                };
-                assert!(solved.is_none());
-                solved = Some(solved_value);
-            }
-            let solved = solved.expect("unable to find solution status");
-            match problem_el.children().map(|nr| nr.value()).collect_tuple() {
-                Some((Element(anchor),)) if &*anchor.name.local == "a" => {
-                    let link = anchor.attr("href").unwrap();
-                    let level = parse_from_relative_link("problem", link).unwrap();
-                    let expected_idx = problems.len().checked_add(1).unwrap();
-                    if level != expected_idx {
-                        panic!("Missing expected problem {}", expected_idx);
-                    }
-                    problems.push(solved);
-                }
-                _ => panic!(
-                    "unrecognized set of child elements in problem listing: {:#?}",
-                    problem_el.value()
-                ),
-            }
-        }
+impl Error for ExitStatus {}
 
-        Ok(Problems(problems))
+fn main() {
+    let result = run();
+    timings::print_report();
+    if let Err(e) = result {
+        if let Some(ExitStatus(status)) = e.downcast_ref() {
+            exit(*status);
+        }
+        // Stopping at a request a dry run won't send is as far as it can go, not a failure.
+        if e.is::<DryRunError>() {
+            eprintln!("dry run: {}", e);
+            return;
+        }
+        eprintln!("error: {}", e);
+        exit(if e.is::<Failure>() { 1 } else { 2 });
     }
 }
 
-#[derive(Debug, FromHtml)]
-struct Progress {
-    #[html(selector = "#levels_completed_section")]
-    levels: Levels,
-    #[html(selector = "#problems_solved_section")]
-    problems: Problems,
-}
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::test_support::{account_progress, new_account},
+    };
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let Cli { session_id } = Cli::from_args();
+    #[test]
+    fn summarizes_accounts_without_progress() {
+        assert_eq!(
+            summary(&new_account()),
+            "Solved 0 of 29 problems (0.0%)\nNo levels completed yet\n25 more to Level 1\n"
+        );
+    }
 
-    let request_url = format!("https://{}/{}", PROJECT_EULER_HOSTNAME, PROGRESS_ENDPOINT);
-    let session_cookie_value = match session_id {
-        Some(value) => value,
-        None => read_to_string(default_session_id_path())?,
-    };
-    let cookie_header = format!("{}={}", SESSION_COOKIE_NAME, session_cookie_value.trim());
-
-    let mut progress_response = Client::new()
-        .get(&request_url)
-        .header(COOKIE, HeaderValue::from_str(&cookie_header)?)
-        .send()?;
-    let progress_page = progress_response.text()?;
-    let progress = Progress::from_html(&progress_page)?;
-    println!("progress: {:#?}", progress);
-    Ok(())
+    #[test]
+    fn summarizes_progress() {
+        let progress = account_progress();
+        assert_eq!(
+            summary(&progress),
+            "Solved 26 of 29 problems (89.7%)\nLevel 1\n24 more to Level 2\n"
+        );
+    }
 }
@@ -0,0 +1,607 @@
+use {
+    crate::{
+        client::fetch_bytes,
+        endpoints::problem_url,
+        enrichment::Enrichment,
+        paths::config_dir,
+        statement::{wrap, Statement},
+        template::Template,
+        tex::convert_math,
+        workspace::{LayoutKind, Workspace, MANIFEST_FILE_NAME},
+    },
+    log::warn,
+    std::{
+        error::Error,
+        fs::{create_dir_all, read, write},
+        path::Path,
+    },
+    structopt::StructOpt,
+};
+
+const DOC_COMMENT_WIDTH: usize = 80;
+const DOC_COMMENT_PREFIX: &str = "//! ";
+
+const USER_TEMPLATE_FILE_NAME: &str = "solution.rs.hbs";
+const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    ("minimal", include_str!("templates/minimal.rs.hbs")),
+    ("with-test", include_str!("templates/with-test.rs.hbs")),
+];
+/// The variables solution templates are rendered with.
+const TEMPLATE_VARIABLES: [&str; 8] = [
+    "number",
+    "padded_number",
+    "title",
+    "heading",
+    "url",
+    "statement",
+    "data",
+    "example",
+];
+
+/// Scaffold a solution for a problem.
+#[derive(Debug, StructOpt)]
+pub struct New {
+    /// The number of the problem to scaffold a solution for.
+    number: usize,
+    /// Overwrite the solution file if it already exists.
+    #[structopt(long)]
+    force: bool,
+    /// The template to scaffold from: either the name of a built-in template (`minimal` or
+    /// `with-test`) or the path to a template file. Defaults to the template configured in
+    /// `euler.toml`, then `solution.rs.hbs` in the user's configuration directory, then
+    /// `minimal`.
+    #[structopt(long)]
+    template: Option<String>,
+    /// Scaffold from the `with-test` template, with a test of the solution against the example
+    /// given in the statement, if one can be found.
+    #[structopt(long, conflicts_with = "template")]
+    with_test: bool,
+    /// Download the data files attached to the problem into the workspace and include them in
+    /// the solution.
+    #[structopt(long)]
+    download_data: bool,
+    /// If the root manifest of a crate-per-problem workspace isn't a workspace yet, convert it
+    /// into one to register the new crate.
+    #[structopt(long)]
+    yes: bool,
+}
+
+fn builtin_template(name: &str) -> Option<Template> {
+    BUILTIN_TEMPLATES
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+        .map(|(name, text)| Template::new(format!("built-in template `{}`", name), *text))
+}
+
+fn resolve_template(
+    requested: Option<&str>,
+    workspace: &Workspace,
+) -> Result<Template, Box<dyn Error>> {
+    if let Some(requested) = requested {
+        return match builtin_template(requested) {
+            Some(template) => Ok(template),
+            None => Template::read(Path::new(requested)),
+        };
+    }
+    if let Some(path) = &workspace.config.templates.solution {
+        return Template::read(&workspace.root.join(path));
+    }
+    let user_template = config_dir().join("templates").join(USER_TEMPLATE_FILE_NAME);
+    if user_template.is_file() {
+        return Template::read(&user_template);
+    }
+    Ok(builtin_template("minimal").unwrap())
+}
+
+const MISSING_STATEMENT_NOTE: &str = "\
+//!
+//! *The statement of this problem wasn't available when this file was scaffolded.*";
+
+/// Escapes characters that Markdown would otherwise interpret within a line of text.
+pub fn escape_inline_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if let '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' = c {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Escapes the start of `line` if Markdown would otherwise parse it as the start of a block,
+/// like a heading, list item or quote. Inline escapes are assumed to already be applied.
+fn escape_line_start(line: &str) -> String {
+    let starts_block = line.starts_with(['#', '-', '+', '=', '~'])
+        || line
+            .find(|c: char| !c.is_ascii_digit())
+            .is_some_and(|idx| idx > 0 && line[idx..].starts_with(['.', ')']));
+    if starts_block {
+        format!("\\{}", line)
+    } else {
+        line.to_owned()
+    }
+}
+
+fn render_statement(statement: Option<&Statement>) -> String {
+    let statement = match statement {
+        Some(statement) => statement,
+        None => return MISSING_STATEMENT_NOTE.to_owned(),
+    };
+    let mut doc = Vec::new();
+    for paragraph in &statement.paragraphs {
+        doc.push("//!".to_owned());
+        let paragraph = escape_inline_markdown(&convert_math(paragraph));
+        for line in wrap(&paragraph, DOC_COMMENT_WIDTH - DOC_COMMENT_PREFIX.len()) {
+            doc.push(format!(
+                "{}{}",
+                DOC_COMMENT_PREFIX,
+                escape_line_start(&line)
+            ));
+        }
+    }
+    doc.join("\n")
+}
+
+/// Renders the sentences of `statement` that look like examples as a comment, indented to sit
+/// in the `tests` module of a solution.
+fn render_example(statement: Option<&Statement>) -> String {
+    const INDENT: &str = "    ";
+    const PREFIX: &str = "// ";
+
+    let examples = statement.map_or_else(Vec::new, |statement| statement.examples());
+    if examples.is_empty() {
+        return String::new();
+    }
+    let mut lines = vec!["// EXAMPLE:".to_owned()];
+    for example in examples {
+        for line in wrap(
+            &convert_math(example),
+            DOC_COMMENT_WIDTH - INDENT.len() - PREFIX.len(),
+        ) {
+            lines.push(format!("{}{}", PREFIX, line));
+        }
+    }
+    lines.join(&format!("\n{}", INDENT))
+}
+
+/// Makes sure the data files attached to `statement` are in the workspace, downloading them if
+/// needed, and renders declarations including them in the solution.
+fn wire_data_files(
+    workspace: &Workspace,
+    statement: &Statement,
+    offline: bool,
+) -> Result<String, Box<dyn Error>> {
+    let number = statement.number;
+    let data_dir = workspace.data_dir(number);
+    let mut declarations = String::new();
+    for attachment in &statement.attachments {
+        let path = data_dir.join(&attachment.name);
+        let bytes = if path.is_file() {
+            read(&path)?
+        } else if offline {
+            return Err(format!(
+                "unable to download {} while offline",
+                workspace.display_path(&path)
+            )
+            .into());
+        } else {
+            let bytes = fetch_bytes(&attachment.url)?;
+            create_dir_all(&data_dir)?;
+            write(&path, &bytes)?;
+            println!("Downloaded {}", workspace.display_path(&path));
+            bytes
+        };
+
+        let name = if statement.attachments.len() == 1 {
+            "DATA".to_owned()
+        } else {
+            let stem = Path::new(&attachment.name)
+                .file_stem()
+                .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+            let ident = stem
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() {
+                        c.to_ascii_uppercase()
+                    } else {
+                        '_'
+                    }
+                })
+                .collect::<String>();
+            format!("{}_DATA", ident)
+        };
+        let (ty, include) = if std::str::from_utf8(&bytes).is_ok() {
+            ("&str", "include_str")
+        } else {
+            ("&[u8]", "include_bytes")
+        };
+        declarations.push_str(&format!(
+            "\nconst {}: {} = {}!(concat!(env!(\"CARGO_MANIFEST_DIR\"), {:?}));",
+            name,
+            ty,
+            include,
+            workspace.data_include_path(number, &attachment.name)
+        ));
+    }
+    Ok(declarations)
+}
+
+fn render_package_manifest(name: &str) -> String {
+    format!(
+        "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2018\"\n\n[dependencies]\n",
+        name
+    )
+}
+
+impl New {
+    /// Scaffolds a solution for `number` like `cargo euler new <number>` would, with every
+    /// option left at its default.
+    pub fn for_number(number: usize) -> Self {
+        Self {
+            number,
+            force: false,
+            template: None,
+            with_test: false,
+            download_data: false,
+            yes: false,
+        }
+    }
+
+    pub fn run(self, offline: bool) -> Result<(), Box<dyn Error>> {
+        let workspace = Workspace::discover()?;
+        let number = self.number;
+        let path = workspace.solution_path(number);
+        if path.exists() && !self.force {
+            return Err(format!(
+                "{} already exists; pass `--force` to overwrite it",
+                workspace.display_path(&path)
+            )
+            .into());
+        }
+
+        let statement = if offline {
+            Statement::cached(number)
+        } else {
+            Statement::load(number)
+                .map_err(|e| warn!("unable to load the statement of problem {}: {}", number, e))
+                .ok()
+        };
+        self.scaffold(
+            &workspace,
+            statement.as_ref(),
+            &Enrichment::load_cached(),
+            offline,
+        )
+    }
+
+    /// Scaffolds the solution in `workspace`, from `statement` if it could be loaded, without
+    /// checking whether it already exists.
+    fn scaffold(
+        self,
+        workspace: &Workspace,
+        statement: Option<&Statement>,
+        enrichment: &Enrichment,
+        offline: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let Self {
+            number,
+            force: _,
+            template,
+            with_test,
+            download_data,
+            yes,
+        } = self;
+
+        let template = if with_test {
+            builtin_template("with-test").unwrap()
+        } else {
+            resolve_template(template.as_deref(), workspace)?
+        };
+        let title = statement
+            .map(|s| &*s.title)
+            .or_else(|| enrichment.get(number).map(|info| &*info.title));
+        // Only now that the title is known can the file be named after it.
+        let path = workspace.new_solution_path(number, title);
+
+        let data = match (statement, download_data) {
+            (Some(statement), true) if statement.attachments.is_empty() => {
+                warn!("problem {} has no data files", number);
+                String::new()
+            }
+            (Some(statement), true) => wire_data_files(workspace, statement, offline)?,
+            (None, true) => {
+                return Err(format!(
+                    "unable to find the data files of problem {} without its statement",
+                    number
+                )
+                .into())
+            }
+            (_, false) => String::new(),
+        };
+
+        let padded_number = workspace.config.layout.padded_number(number);
+        let heading = match title {
+            Some(title) => format!(
+                "Problem {}: {}",
+                number,
+                escape_inline_markdown(&convert_math(title))
+            ),
+            None => format!("Problem {}", number),
+        };
+        let values = [
+            number.to_string(),
+            padded_number,
+            title.unwrap_or_default().to_owned(),
+            heading,
+            problem_url(number),
+            render_statement(statement),
+            data,
+            render_example(statement),
+        ];
+        let solution = template.render(
+            &TEMPLATE_VARIABLES
+                .iter()
+                .copied()
+                .zip(values.iter().map(String::as_str))
+                .collect::<Vec<_>>(),
+        )?;
+
+        let crate_per_problem = workspace.config.layout.kind == LayoutKind::CratePerProblem;
+        let package_dir = workspace.package_dir(number);
+        // Register the crate first, so nothing gets scaffolded if the workspace can't hold it.
+        if crate_per_problem && workspace.register_member(&package_dir, yes)? {
+            println!(
+                "Added {} to the workspace members in {}",
+                workspace.display_path(&package_dir),
+                MANIFEST_FILE_NAME
+            );
+        }
+
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+        write(&path, solution)?;
+        println!("Created {}", workspace.display_path(&path));
+        if workspace.config.layout.pattern.is_some()
+            && !path.starts_with(workspace.root.join("src").join("bin"))
+        {
+            warn!(
+                "{} isn't in `src/bin`, so cargo needs a `[[bin]]` entry named {:?} for it in {}",
+                workspace.display_path(&path),
+                workspace.bin_name(number),
+                MANIFEST_FILE_NAME
+            );
+        }
+
+        if crate_per_problem {
+            let manifest_path = package_dir.join(MANIFEST_FILE_NAME);
+            if !manifest_path.exists() {
+                write(
+                    &manifest_path,
+                    render_package_manifest(&workspace.config.layout.bin_name(number)),
+                )?;
+                println!("Created {}", workspace.display_path(&manifest_path));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            statement::Attachment,
+            test_support::{assert_builds, lock_globals, TempDir},
+            workspace::Config,
+        },
+        std::fs::read_to_string,
+    };
+
+    fn statement(number: usize) -> Statement {
+        Statement {
+            number,
+            title: "Sums of `Squares` & *Stars*".to_owned(),
+            paragraphs: vec![
+                "Find the sum of $n^2$ for all <var>n</var> below 10, which is 285.".to_owned(),
+                "# Not a heading, and 1. not a list".to_owned(),
+            ],
+            attachments: Vec::new(),
+        }
+    }
+
+    fn bin_workspace(dir: &TempDir) -> Workspace {
+        write(
+            dir.path().join(MANIFEST_FILE_NAME),
+            render_package_manifest("solutions"),
+        )
+        .unwrap();
+        Workspace::new(dir.path().to_owned(), Config::default())
+    }
+
+    #[test]
+    fn renders_builtin_templates_with_every_variable() {
+        let vars = TEMPLATE_VARIABLES
+            .iter()
+            .map(|&name| (name, format!("<{}>", name)))
+            .collect::<Vec<_>>();
+        let vars = vars
+            .iter()
+            .map(|(name, value)| (*name, &**value))
+            .collect::<Vec<_>>();
+        for (name, _) in BUILTIN_TEMPLATES {
+            let rendered = builtin_template(name).unwrap().render(&vars).unwrap();
+            assert!(!rendered.contains("{{"), "{}", name);
+            assert!(rendered.starts_with("//! # <heading>\n//!\n//! <<url>>\n<statement>\n"));
+        }
+    }
+
+    #[test]
+    fn scaffolds_a_solution_that_compiles() {
+        let _globals = lock_globals();
+        let dir = TempDir::new();
+        let workspace = bin_workspace(&dir);
+        for (number, with_test) in &[(1, false), (42, true)] {
+            let mut new = New::for_number(*number);
+            new.with_test = *with_test;
+            new.scaffold(
+                &workspace,
+                Some(&statement(*number)),
+                &Enrichment::default(),
+                true,
+            )
+            .unwrap();
+        }
+
+        let solution = read_to_string(dir.path().join("src/bin/p042.rs")).unwrap();
+        assert!(solution.starts_with(
+            "//! # Problem 42: Sums of \\`Squares\\` & \\*Stars\\*\n//!\n//! <https://projecteuler.net/problem=42>\n"
+        ));
+        assert!(solution.contains("//! \\# Not a heading, and 1. not a list\n"));
+        assert!(solution.contains("mod tests"));
+        assert_builds(dir.path());
+    }
+
+    #[test]
+    fn scaffolds_a_test_skeleton_without_examples() {
+        let _globals = lock_globals();
+        let dir = TempDir::new();
+        let workspace = bin_workspace(&dir);
+        let statement = Statement {
+            paragraphs: vec!["Find the sum of all the primes below two million.".to_owned()],
+            ..statement(10)
+        };
+        assert!(statement.examples().is_empty());
+        let mut new = New::for_number(10);
+        new.with_test = true;
+        new.scaffold(&workspace, Some(&statement), &Enrichment::default(), true)
+            .unwrap();
+
+        let solution = read_to_string(dir.path().join("src/bin/p010.rs")).unwrap();
+        assert!(!solution.contains("EXAMPLE"));
+        assert!(solution.contains(
+            "mod tests {\n    #[test]\n    fn example() {\n        \
+             assert_eq!(super::solve_for(todo!()), todo!());\n    }\n"
+        ));
+    }
+
+    #[test]
+    fn scaffolds_without_a_statement() {
+        let _globals = lock_globals();
+        let dir = TempDir::new();
+        let workspace = bin_workspace(&dir);
+        New::for_number(7)
+            .scaffold(&workspace, None, &Enrichment::default(), true)
+            .unwrap();
+
+        let solution = read_to_string(dir.path().join("src/bin/p007.rs")).unwrap();
+        assert!(solution.starts_with("//! # Problem 7\n"));
+        assert!(solution.contains(MISSING_STATEMENT_NOTE));
+        assert_builds(dir.path());
+    }
+
+    /// Puts data files for problem 22 in the workspace, like a download would, and scaffolds its
+    /// solution with them.
+    fn scaffold_with_data(workspace: &Workspace) -> String {
+        let data_dir = workspace.data_dir(22);
+        create_dir_all(&data_dir).unwrap();
+        write(data_dir.join("names.txt"), "\"MARY\",\"PATRICIA\"").unwrap();
+        write(data_dir.join("cipher.bin"), [0xff, 0xfe, 0x00]).unwrap();
+        let mut statement = statement(22);
+        statement.attachments = ["names.txt", "cipher.bin"]
+            .iter()
+            .map(|name| Attachment {
+                name: (*name).to_owned(),
+                url: format!("https://projecteuler.net/resources/documents/{}", name),
+            })
+            .collect();
+
+        let mut new = New::for_number(22);
+        new.download_data = true;
+        new.scaffold(workspace, Some(&statement), &Enrichment::default(), true)
+            .unwrap();
+        read_to_string(workspace.solution_path(22)).unwrap()
+    }
+
+    #[test]
+    fn includes_data_files_in_bin_per_problem_layouts() {
+        let _globals = lock_globals();
+        let dir = TempDir::new();
+        let workspace = bin_workspace(&dir);
+
+        let solution = scaffold_with_data(&workspace);
+        assert!(solution.contains(
+            "const NAMES_DATA: &str = include_str!(concat!(env!(\"CARGO_MANIFEST_DIR\"), \
+             \"/data/p022/names.txt\"));"
+        ));
+        assert!(solution.contains(
+            "const CIPHER_DATA: &[u8] = include_bytes!(concat!(env!(\"CARGO_MANIFEST_DIR\"), \
+             \"/data/p022/cipher.bin\"));"
+        ));
+        assert_builds(dir.path());
+    }
+
+    #[test]
+    fn includes_data_files_in_crate_per_problem_layouts() {
+        let _globals = lock_globals();
+        let dir = TempDir::new();
+        write(
+            dir.path().join(MANIFEST_FILE_NAME),
+            "[workspace]\nmembers = []\n",
+        )
+        .unwrap();
+        let mut config = Config::default();
+        config.layout.kind = LayoutKind::CratePerProblem;
+        let workspace = Workspace::new(dir.path().to_owned(), config);
+
+        let solution = scaffold_with_data(&workspace);
+        assert!(solution.contains("\"/../../data/p022/names.txt\""));
+        assert!(dir.path().join("problems/p022/Cargo.toml").is_file());
+        assert_builds(dir.path());
+    }
+
+    #[test]
+    fn names_a_single_data_file_data() {
+        let _globals = lock_globals();
+        let dir = TempDir::new();
+        let workspace = bin_workspace(&dir);
+        create_dir_all(workspace.data_dir(59)).unwrap();
+        write(workspace.data_dir(59).join("p059_cipher.txt"), "36,22,80").unwrap();
+        let mut statement = statement(59);
+        statement.attachments.push(Attachment {
+            name: "p059_cipher.txt".to_owned(),
+            url: "https://projecteuler.net/resources/documents/0059_cipher.txt".to_owned(),
+        });
+        let mut new = New::for_number(59);
+        new.download_data = true;
+        new.scaffold(&workspace, Some(&statement), &Enrichment::default(), true)
+            .unwrap();
+
+        let solution = read_to_string(workspace.solution_path(59)).unwrap();
+        assert!(solution.contains("const DATA: &str = include_str!"));
+    }
+
+    #[test]
+    fn refuses_to_download_data_offline() {
+        let _globals = lock_globals();
+        let dir = TempDir::new();
+        let workspace = bin_workspace(&dir);
+        let mut statement = statement(22);
+        statement.attachments.push(Attachment {
+            name: "names.txt".to_owned(),
+            url: "https://projecteuler.net/resources/documents/names.txt".to_owned(),
+        });
+        let mut new = New::for_number(22);
+        new.download_data = true;
+        let e = new
+            .scaffold(&workspace, Some(&statement), &Enrichment::default(), true)
+            .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "unable to download data/p022/names.txt while offline"
+        );
+        assert!(!workspace.solution_path(22).exists());
+    }
+}
@@ -0,0 +1,417 @@
+//! Notifications of newly solved problems and level-ups, delivered to webhooks and the desktop.
+
+use {
+    crate::{
+        client::post_json,
+        context::Context,
+        desktop,
+        diff::{read_last_seen, write_last_seen, ProgressDiff, SolvedProblem},
+        enrichment::Enrichment,
+        history::Snapshot,
+        user_config::UserConfig,
+    },
+    chrono::Utc,
+    log::warn,
+    reqwest::Url,
+    serde::Deserialize,
+    serde_json::{json, Value},
+    std::{error::Error, str::FromStr},
+    structopt::StructOpt,
+};
+
+const LAST_NOTIFIED_FILE_NAME: &str = "last-notified.json";
+
+/// How the payload POSTed to a webhook is formatted.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum WebhookFormat {
+    /// A Discord message with an embed.
+    Discord,
+    /// A Slack message with blocks.
+    Slack,
+    /// The diff as JSON, like `cargo euler diff --format json` prints.
+    GenericJson,
+}
+
+impl FromStr for WebhookFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "discord" => Ok(Self::Discord),
+            "slack" => Ok(Self::Slack),
+            "generic-json" => Ok(Self::GenericJson),
+            _ => Err(format!(
+                "unknown webhook format {:?}; expected `discord`, `slack` or `generic-json`",
+                s
+            )),
+        }
+    }
+}
+
+impl WebhookFormat {
+    /// Guesses the format from the host of `url`, falling back to generic JSON.
+    fn guess(url: &str) -> Self {
+        if url.contains("discord.com/") || url.contains("discordapp.com/") {
+            Self::Discord
+        } else if url.contains("hooks.slack.com/") {
+            Self::Slack
+        } else {
+            Self::GenericJson
+        }
+    }
+}
+
+/// A webhook configured in the `[webhooks]` table of the user configuration, named by its key.
+#[derive(Debug, Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    /// Guessed from the URL if omitted.
+    pub format: Option<WebhookFormat>,
+}
+
+/// Summarizes what changed in a line, like "Solved problem 187 (188 total)".
+fn headline(diff: &ProgressDiff, current: &Snapshot) -> String {
+    let solved = match diff.newly_solved.as_slice() {
+        [] => None,
+        [SolvedProblem { number, .. }] => Some(format!("Solved problem {}", number)),
+        problems => Some(format!("Solved {} problems", problems.len())),
+    };
+    let leveled_up = diff.level_after > diff.level_before;
+    match (solved, leveled_up) {
+        (Some(solved), true) => format!(
+            "{} ({} total) and reached level {}",
+            solved, current.solved_count, diff.level_after
+        ),
+        (Some(solved), false) => format!("{} ({} total)", solved, current.solved_count),
+        (None, true) => format!("Reached level {}", diff.level_after),
+        (None, false) => "No new progress".to_owned(),
+    }
+}
+
+fn problem_lines(diff: &ProgressDiff, bullet: &str) -> Vec<String> {
+    diff.newly_solved
+        .iter()
+        .map(|SolvedProblem { number, title }| match title {
+            Some(title) => format!("{}**{}** {}", bullet, number, title),
+            None => format!("{}**{}**", bullet, number),
+        })
+        .collect()
+}
+
+fn payload(format: WebhookFormat, diff: &ProgressDiff, current: &Snapshot) -> Value {
+    let headline = headline(diff, current);
+    let footer = format!(
+        "{} of {} solved, level {}",
+        current.solved_count,
+        current.available(),
+        current.level
+    );
+    match format {
+        WebhookFormat::Discord => json!({
+            "embeds": [{
+                "title": format!("Project Euler: {}", headline),
+                "description": problem_lines(diff, "").join("\n"),
+                "footer": { "text": footer },
+            }],
+        }),
+        WebhookFormat::Slack => {
+            // Slack's flavor of Markdown bolds with single asterisks.
+            let lines = problem_lines(diff, "• ").join("\n").replace("**", "*");
+            json!({
+                "text": format!("Project Euler: {}", headline),
+                "blocks": [
+                    {
+                        "type": "section",
+                        "text": {
+                            "type": "mrkdwn",
+                            "text": format!("*Project Euler: {}*\n{}", headline, lines),
+                        },
+                    },
+                    {
+                        "type": "context",
+                        "elements": [{ "type": "mrkdwn", "text": footer }],
+                    },
+                ],
+            })
+        }
+        WebhookFormat::GenericJson => json!(diff),
+    }
+}
+
+/// Shows `url` by its host alone, since the rest of a webhook's URL is usually its secret token.
+fn redact(url: &str) -> String {
+    match Url::parse(url).ok().as_ref().and_then(Url::host_str) {
+        Some(host) => format!("{}/…", host),
+        None => "…".to_owned(),
+    }
+}
+
+/// POSTs `payload` to `url`, retrying once on failure. Errors show `url` redacted.
+fn deliver(url: &str, payload: &Value) -> Result<(), Box<dyn Error>> {
+    let redacted = |e: Box<dyn Error>| e.to_string().replace(url, &redact(url));
+    post_json(url, payload).or_else(|e| {
+        warn!(
+            "delivering to {} failed, retrying: {}",
+            redact(url),
+            redacted(e)
+        );
+        post_json(url, payload).map_err(|e| redacted(e).into())
+    })
+}
+
+/// Notify webhooks of problems solved and levels reached since the last notification.
+#[derive(Debug, StructOpt)]
+pub struct Notify {
+    /// Notify this webhook instead of those in the user configuration. May be given more than
+    /// once.
+    #[structopt(long = "webhook")]
+    webhooks: Vec<String>,
+    /// The format of the payload for `--webhook`s: `discord`, `slack` or `generic-json`.
+    /// Guessed from each URL if omitted.
+    #[structopt(long, requires = "webhooks")]
+    format: Option<WebhookFormat>,
+    /// Also show what changed in a desktop notification. Webhooks are optional with this.
+    #[structopt(long)]
+    desktop_notify: bool,
+}
+
+impl Notify {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self {
+            webhooks,
+            format,
+            desktop_notify,
+        } = self;
+        // With `--dry-run`, payloads are printed instead of sent.
+        let dry_run = context.dry_run().is_some();
+
+        let webhooks = if webhooks.is_empty() {
+            UserConfig::load()?
+                .webhooks
+                .into_iter()
+                .map(|(name, Webhook { url, format })| {
+                    let format = format.unwrap_or_else(|| WebhookFormat::guess(&url));
+                    (name, url, format)
+                })
+                .collect::<Vec<_>>()
+        } else {
+            webhooks
+                .into_iter()
+                .map(|url| {
+                    let format = format.unwrap_or_else(|| WebhookFormat::guess(&url));
+                    (redact(&url), url, format)
+                })
+                .collect()
+        };
+        if webhooks.is_empty() && !desktop_notify {
+            return Err(format!(
+                "no webhooks to notify; pass `--webhook` or `--desktop-notify`, or configure `[webhooks]` in {}",
+                UserConfig::path().display()
+            )
+            .into());
+        }
+
+        // Read the last notified progress before fetching, since fetching records a snapshot.
+        let last_notified = read_last_seen(&context.profile, LAST_NOTIFIED_FILE_NAME)?;
+        let current = Snapshot::of(&context.progress()?, Utc::now());
+        let last_notified = match last_notified {
+            Some(last_notified) => last_notified,
+            None => {
+                println!("Nothing to compare against yet; notifications start from now.");
+                if !dry_run {
+                    write_last_seen(&context.profile, LAST_NOTIFIED_FILE_NAME, &current)?;
+                }
+                return Ok(());
+            }
+        };
+
+        let diff = ProgressDiff::between(&last_notified, &current, &Enrichment::load_cached());
+        if diff.newly_solved.is_empty() && diff.level_after <= diff.level_before {
+            println!(
+                "No new progress since {}.",
+                diff.since.format("%Y-%m-%d %H:%M")
+            );
+            return Ok(());
+        }
+
+        let headline = headline(&diff, &current);
+        println!("{}", headline);
+        if desktop_notify {
+            if dry_run {
+                println!("desktop:\n{}", headline);
+            } else {
+                desktop::notify(&headline);
+            }
+        }
+
+        let mut failures = 0;
+        for (name, url, format) in &webhooks {
+            let payload = payload(*format, &diff, &current);
+            if dry_run {
+                println!("{}:\n{}", name, serde_json::to_string_pretty(&payload)?);
+                continue;
+            }
+            match deliver(url, &payload) {
+                Ok(()) => println!("Notified {}", name),
+                Err(e) => {
+                    eprintln!("Unable to notify {}: {}", name, e);
+                    failures += 1;
+                }
+            }
+        }
+
+        if dry_run {
+            return Ok(());
+        }
+        // Keep the last notified progress if any delivery failed, so the next run tries again.
+        if failures > 0 {
+            return Err(format!(
+                "unable to notify {} of {} webhook(s)",
+                failures,
+                webhooks.len()
+            )
+            .into());
+        }
+        write_last_seen(&context.profile, LAST_NOTIFIED_FILE_NAME, &current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::test_support::{lock_globals, noon, snapshot, MockServer, Reply},
+    };
+
+    /// Problems 25 and 26 solved since 1 March, reaching level 1.
+    fn diff() -> ProgressDiff {
+        ProgressDiff {
+            since: noon(3, 1),
+            newly_solved: vec![
+                SolvedProblem {
+                    number: 25,
+                    title: Some("1000-digit Fibonacci Number".to_owned()),
+                },
+                SolvedProblem {
+                    number: 26,
+                    title: None,
+                },
+            ],
+            solved_change: 2,
+            level_before: 0,
+            level_after: 1,
+        }
+    }
+
+    #[test]
+    fn formats_discord_embeds() {
+        assert_eq!(
+            payload(WebhookFormat::Discord, &diff(), &snapshot(3, 2, 26)),
+            json!({
+                "embeds": [{
+                    "title": "Project Euler: Solved 2 problems (26 total) and reached level 1",
+                    "description": "**25** 1000-digit Fibonacci Number\n**26**",
+                    "footer": { "text": "26 of 100 solved, level 1" },
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn formats_slack_blocks() {
+        assert_eq!(
+            payload(WebhookFormat::Slack, &diff(), &snapshot(3, 2, 26)),
+            json!({
+                "text": "Project Euler: Solved 2 problems (26 total) and reached level 1",
+                "blocks": [
+                    {
+                        "type": "section",
+                        "text": {
+                            "type": "mrkdwn",
+                            "text": "*Project Euler: Solved 2 problems (26 total) and reached \
+                                level 1*\n• *25* 1000-digit Fibonacci Number\n• *26*",
+                        },
+                    },
+                    {
+                        "type": "context",
+                        "elements": [{ "type": "mrkdwn", "text": "26 of 100 solved, level 1" }],
+                    },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn formats_generic_json_like_diff() {
+        assert_eq!(
+            payload(WebhookFormat::GenericJson, &diff(), &snapshot(3, 2, 26)),
+            json!({
+                "since": "2024-03-01T12:00:00Z",
+                "newly_solved": [
+                    { "number": 25, "title": "1000-digit Fibonacci Number" },
+                    { "number": 26, "title": null },
+                ],
+                "solved_change": 2,
+                "level_before": 0,
+                "level_after": 1,
+            })
+        );
+    }
+
+    #[test]
+    fn redacts_webhook_urls() {
+        assert_eq!(
+            redact("https://discord.com/api/webhooks/1234/s3cr3tt0k3n"),
+            "discord.com/…"
+        );
+        assert_eq!(redact("not a URL with s3cr3tt0k3n"), "…");
+    }
+
+    #[test]
+    fn requires_webhooks_for_a_format() {
+        assert!(Notify::from_iter_safe(["notify", "--format", "slack"]).is_err());
+        assert!(Notify::from_iter_safe([
+            "notify",
+            "--webhook",
+            "https://example.com/hook",
+            "--format",
+            "slack"
+        ])
+        .is_ok());
+    }
+
+    #[test]
+    fn retries_a_failed_delivery_once() {
+        let _globals = lock_globals();
+        let server = MockServer::start();
+        server
+            .reply("POST", "/hooks/s3cr3tt0k3n", Reply::status(500))
+            .reply("POST", "/hooks/s3cr3tt0k3n", Reply::ok(""));
+        let url = format!("{}hooks/s3cr3tt0k3n", server.url());
+        let payload = json!({ "text": "Solved problem 1" });
+
+        deliver(&url, &payload).unwrap();
+        let received = server.received();
+        assert_eq!(received.len(), 2);
+        for request in &received {
+            assert_eq!(
+                serde_json::from_str::<Value>(&request.body).unwrap(),
+                payload
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_webhook_tokens_out_of_delivery_errors() {
+        let _globals = lock_globals();
+        let server = MockServer::start();
+        server.reply("POST", "/hooks/s3cr3tt0k3n", Reply::status(500));
+        let url = format!("{}hooks/s3cr3tt0k3n", server.url());
+
+        let error = deliver(&url, &json!({})).unwrap_err().to_string();
+        assert_eq!(server.received().len(), 2);
+        assert!(error.contains("127.0.0.1/…"), "{}", error);
+        assert!(!error.contains("s3cr3tt0k3n"), "{}", error);
+    }
+}
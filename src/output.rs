@@ -0,0 +1,107 @@
+//! Helpers for rendering output.
+
+use {
+    crate::{
+        anonymize,
+        timings::{self, PhaseTimer},
+    },
+    serde::Serialize,
+    std::{
+        error::Error,
+        io::{stdin, stdout, IsTerminal, Write},
+    },
+};
+
+/// Colors `text` red if standard output is a terminal.
+pub fn red(text: &str) -> String {
+    if stdout().is_terminal() {
+        format!("\x1b[31m{}\x1b[0m", text)
+    } else {
+        text.to_owned()
+    }
+}
+
+/// Makes `text` bold if standard output is a terminal.
+pub fn bold(text: &str) -> String {
+    if stdout().is_terminal() {
+        format!("\x1b[1m{}\x1b[0m", text)
+    } else {
+        text.to_owned()
+    }
+}
+
+/// Asks a yes-or-no question on the terminal, defaulting to no.
+pub fn confirm(question: &str) -> Result<bool, Box<dyn Error>> {
+    if !stdin().is_terminal() {
+        return Err("unable to ask for confirmation without a terminal; pass `--yes`".into());
+    }
+    print!("{} [y/N] ", question);
+    stdout().flush()?;
+    let mut reply = String::new();
+    stdin().read_line(&mut reply)?;
+    Ok(matches!(reply.trim(), "y" | "Y" | "yes"))
+}
+
+/// Prints `value` as pretty JSON, anonymized with `--anonymize`. With `--timings`, the phases
+/// measured so far are added under a `timings` key, after the others so their order is kept, or
+/// alongside the value under an `output` key if it isn't an object.
+pub fn print_json(value: &impl Serialize) -> Result<(), Box<dyn Error>> {
+    let _timer = PhaseTimer::start("render");
+    let json = anonymize::to_string_pretty(value)?;
+    if !timings::is_enabled() {
+        println!("{}", json);
+        return Ok(());
+    }
+    let report = serde_json::to_string_pretty(&timings::report())?.replace('\n', "\n  ");
+    match json.strip_suffix("\n}") {
+        Some(fields) if json.starts_with('{') => {
+            println!("{},\n  \"timings\": {}\n}}", fields, report)
+        }
+        _ => println!(
+            "{{\n  \"output\": {},\n  \"timings\": {}\n}}",
+            json.replace('\n', "\n  "),
+            report
+        ),
+    }
+    Ok(())
+}
+
+/// Draws a horizontal bar `width` characters wide, filled to `fraction` (from 0 to 1) in
+/// eighths of a character.
+pub fn bar(fraction: f64, width: usize) -> String {
+    const PARTIAL_CHARS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+    let eighths = (fraction.clamp(0.0, 1.0) * (width * 8) as f64).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    if let Some(partial) = (eighths % 8).checked_sub(1) {
+        bar.push(PARTIAL_CHARS[partial]);
+    }
+    let drawn = bar.chars().count();
+    bar + &" ".repeat(width - drawn)
+}
+
+/// The format machine-readable output can be rendered in. Output is the same for the same
+/// input, so it can be diffed between runs: problems are listed ascending by number, levels
+/// ascending, rankings by place and leaderboards by their documented keys. JSON objects have
+/// their keys in a fixed order, that of the fields of the structs they're rendered from, or
+/// sorted for maps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Human,
+    Json,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "unknown format {:?}; expected `human` or `json`",
+                s
+            )),
+        }
+    }
+}
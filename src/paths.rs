@@ -0,0 +1,275 @@
+//! Where the tool keeps its files, following the conventions of each platform: the XDG base
+//! directories on Linux and other Unixes, `~/Library` on macOS and `%APPDATA%` on Windows. The
+//! XDG environment variables take precedence everywhere when they're set.
+
+use {
+    crate::{
+        client::SESSION_COOKIE_NAME, context::Context, enrichment::Enrichment,
+        user_config::UserConfig,
+    },
+    log::debug,
+    std::{
+        env::var_os,
+        error::Error,
+        fs::{create_dir_all, rename, write},
+        io,
+        path::{Path, PathBuf},
+    },
+    structopt::StructOpt,
+};
+
+const APP_DIR_NAME: &str = "cargo-euler";
+
+/// Marks the cache directory as the tool's, following the Cache Directory Tagging
+/// Specification, so that `cargo euler cache` only ever deletes from a directory it created.
+pub const CACHE_MARKER_FILE_NAME: &str = "CACHEDIR.TAG";
+const CACHE_MARKER_CONTENTS: &str = "Signature: 8a477f597d28d172789f06886806bc55\n\
+    # This file is a cache directory tag created by cargo-euler.\n";
+
+/// The kinds of directories the tool keeps files in.
+#[derive(Clone, Copy, Debug)]
+enum DirKind {
+    Config,
+    Cache,
+    Data,
+}
+
+impl DirKind {
+    fn xdg_var(self) -> &'static str {
+        match self {
+            Self::Config => "XDG_CONFIG_HOME",
+            Self::Cache => "XDG_CACHE_HOME",
+            Self::Data => "XDG_DATA_HOME",
+        }
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    var_os("HOME").map(PathBuf::from)
+}
+
+/// The directory the platform keeps directories of `kind` for every application in.
+#[cfg(windows)]
+fn platform_dir(kind: DirKind) -> Option<PathBuf> {
+    let var = match kind {
+        DirKind::Config | DirKind::Data => "APPDATA",
+        DirKind::Cache => "LOCALAPPDATA",
+    };
+    var_os(var)
+        .map(PathBuf::from)
+        .or_else(|| var_os("USERPROFILE").map(|home| PathBuf::from(home).join("AppData")))
+}
+
+#[cfg(target_os = "macos")]
+fn platform_dir(kind: DirKind) -> Option<PathBuf> {
+    let home_relative = match kind {
+        DirKind::Config | DirKind::Data => "Library/Application Support",
+        DirKind::Cache => "Library/Caches",
+    };
+    home_dir().map(|home| home.join(home_relative))
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn platform_dir(kind: DirKind) -> Option<PathBuf> {
+    let home_relative = match kind {
+        DirKind::Config => ".config",
+        DirKind::Cache => ".cache",
+        DirKind::Data => ".local/share",
+    };
+    home_dir().map(|home| home.join(home_relative))
+}
+
+fn app_dir(kind: DirKind) -> PathBuf {
+    // Like the XDG specification says, an empty variable counts as unset.
+    var_os(kind.xdg_var())
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| platform_dir(kind))
+        .unwrap_or_default()
+        .join(APP_DIR_NAME)
+}
+
+/// The directory that user configuration (like templates) is stored in.
+pub fn config_dir() -> PathBuf {
+    app_dir(DirKind::Config)
+}
+
+/// The directory that cached data (like problem metadata) is stored in.
+pub fn cache_dir() -> PathBuf {
+    app_dir(DirKind::Cache)
+}
+
+/// Creates `dir` within the cache directory, tagging the cache directory as the tool's.
+pub fn create_cache_dir(dir: &Path) -> io::Result<()> {
+    create_dir_all(dir)?;
+    let marker = cache_dir().join(CACHE_MARKER_FILE_NAME);
+    if !marker.exists() {
+        write(marker, CACHE_MARKER_CONTENTS)?;
+    }
+    Ok(())
+}
+
+/// The directory that cached data specific to `profile` (like its progress) is stored in.
+pub fn profile_cache_dir(profile: &str) -> PathBuf {
+    cache_dir().join(profile)
+}
+
+/// The directory that persistent data is stored in.
+pub fn data_dir() -> PathBuf {
+    app_dir(DirKind::Data)
+}
+
+/// The file the session ID is read from when it isn't given. A `PHPSESSID` file in the current
+/// directory, where it used to be kept, is still preferred if there is one.
+pub fn session_id_path() -> PathBuf {
+    let legacy = PathBuf::from(SESSION_COOKIE_NAME);
+    if legacy.is_file() {
+        debug!("using the session file in the current directory");
+        return legacy;
+    }
+    config_dir().join(SESSION_COOKIE_NAME)
+}
+
+/// Creates `dir` such that only the user can access it with the permissions of new directories,
+/// like for keeping the session in.
+pub fn create_private_dir(dir: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::{fs::DirBuilder, os::unix::fs::DirBuilderExt};
+        DirBuilder::new().recursive(true).mode(0o700).create(dir)
+    }
+    #[cfg(not(unix))]
+    create_dir_all(dir)
+}
+
+/// The directory that persistent data (like benchmark history) for `profile` is stored in.
+pub fn profile_data_dir(profile: &str) -> PathBuf {
+    data_dir().join(profile)
+}
+
+/// Replaces the contents of the file at `path` such that readers see either the old or the new
+/// contents, never a partial write.
+pub fn write_atomically(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_owned();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+    write(&temp_path, contents)?;
+    rename(&temp_path, path)
+}
+
+/// Print where every file the tool uses is, or would be, kept.
+#[derive(Debug, StructOpt)]
+pub struct Paths {}
+
+impl Paths {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let paths = [
+            ("config dir", config_dir()),
+            ("user config", UserConfig::path()),
+            ("session file", session_id_path()),
+            ("cache dir", cache_dir()),
+            ("profile cache dir", profile_cache_dir(&context.profile)),
+            ("problem metadata", Enrichment::cache_path()),
+            ("data dir", data_dir()),
+            ("profile data dir", profile_data_dir(&context.profile)),
+        ];
+        for (name, path) in &paths {
+            println!("{:<18} {}", format!("{}:", name), path.display());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::test_support::{lock_globals, TempDir},
+        std::{
+            env::{remove_var, set_var},
+            fs::read_to_string,
+        },
+    };
+
+    #[test]
+    fn prefers_xdg_directories() {
+        let _globals = lock_globals();
+        let dir = TempDir::new();
+        set_var("XDG_CONFIG_HOME", dir.path().join("config"));
+        set_var("XDG_CACHE_HOME", dir.path().join("cache"));
+        set_var("XDG_DATA_HOME", dir.path().join("data"));
+
+        assert_eq!(config_dir(), dir.path().join("config/cargo-euler"));
+        assert_eq!(cache_dir(), dir.path().join("cache/cargo-euler"));
+        assert_eq!(
+            profile_cache_dir("work"),
+            dir.path().join("cache/cargo-euler/work")
+        );
+        assert_eq!(data_dir(), dir.path().join("data/cargo-euler"));
+        assert_eq!(
+            profile_data_dir("work"),
+            dir.path().join("data/cargo-euler/work")
+        );
+        assert_eq!(
+            session_id_path(),
+            dir.path().join("config/cargo-euler/PHPSESSID")
+        );
+    }
+
+    #[cfg(not(any(windows, target_os = "macos")))]
+    #[test]
+    fn falls_back_to_directories_in_the_home_directory() {
+        let _globals = lock_globals();
+        let home = TempDir::new();
+        let previous_home = var_os("HOME");
+        set_var("HOME", home.path());
+        remove_var("XDG_CONFIG_HOME");
+        set_var("XDG_CACHE_HOME", "");
+        remove_var("XDG_DATA_HOME");
+
+        let dirs = (config_dir(), cache_dir(), data_dir());
+        if let Some(previous_home) = previous_home {
+            set_var("HOME", previous_home);
+        }
+        assert_eq!(
+            dirs,
+            (
+                home.path().join(".config/cargo-euler"),
+                home.path().join(".cache/cargo-euler"),
+                home.path().join(".local/share/cargo-euler"),
+            )
+        );
+    }
+
+    #[test]
+    fn tags_the_cache_directory() {
+        let _globals = lock_globals();
+        let dir = profile_cache_dir("default");
+        create_cache_dir(&dir).unwrap();
+        assert!(dir.is_dir());
+        let marker = read_to_string(cache_dir().join(CACHE_MARKER_FILE_NAME)).unwrap();
+        assert!(marker.starts_with("Signature: 8a477f597d28d172789f06886806bc55"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn creates_private_directories() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new();
+        let private = dir.path().join("config/cargo-euler");
+        create_private_dir(&private).unwrap();
+        let mode = private.metadata().unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+
+    #[test]
+    fn writes_atomically() {
+        let dir = TempDir::new();
+        let path = dir.path().join("progress.html");
+        write_atomically(&path, "old").unwrap();
+        write_atomically(&path, "new").unwrap();
+        assert_eq!(read_to_string(&path).unwrap(), "new");
+        assert!(!dir.path().join("progress.html.tmp").exists());
+    }
+}
@@ -0,0 +1,584 @@
+//! Patterns for the paths of solution files, like `src/bin/euler{n:03}.rs` or
+//! `solutions/{n:03}_{slug}.rs`, and detecting which one an existing workspace follows.
+
+use {
+    crate::{
+        output::confirm,
+        paths::write_atomically,
+        workspace::{LayoutKind, Workspace, CONFIG_FILE_NAME, MANIFEST_FILE_NAME},
+        Failure,
+    },
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::BTreeMap,
+        convert::TryFrom,
+        error::Error,
+        fmt::{self, Display, Formatter},
+        fs::read_to_string,
+        io::ErrorKind,
+        str::FromStr,
+    },
+    structopt::StructOpt,
+    toml_edit::{table, value, DocumentMut},
+};
+
+/// What takes the place of `{slug}` when there's no title to make one from.
+const DEFAULT_SLUG: &str = "solution";
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Literal(String),
+    /// The problem number, zero-padded to at least `width` digits.
+    Number {
+        width: usize,
+    },
+    /// Words describing the problem, like `coded_triangle_numbers`.
+    Slug,
+}
+
+/// The path of solution files relative to the workspace root, with `/` separating directories,
+/// `{n}` or `{n:03}` in place of the (zero-padded) problem number, and optionally `{slug}` in
+/// place of words describing the problem.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Pattern {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| format!("invalid solution pattern {:?}: {}", s, reason);
+        let mut segments = Vec::new();
+        let mut rest = s;
+        while !rest.is_empty() {
+            let (literal, placeholder) = match rest.find('{') {
+                Some(start) => {
+                    let end = rest[start..]
+                        .find('}')
+                        .ok_or_else(|| invalid("a `{` isn't closed"))?;
+                    (&rest[..start], Some(&rest[start + 1..start + end]))
+                }
+                None => (rest, None),
+            };
+            if literal.contains('}') {
+                return Err(invalid("a `}` isn't opened"));
+            }
+            if !literal.is_empty() {
+                segments.push(Segment::Literal(literal.to_owned()));
+            }
+            rest = &rest[literal.len()..];
+            let placeholder = match placeholder {
+                Some(placeholder) => placeholder,
+                None => break,
+            };
+            rest = &rest[placeholder.len() + 2..];
+            if let Some(Segment::Number { .. } | Segment::Slug) = segments.last() {
+                return Err(invalid("placeholders must be separated by something"));
+            }
+            segments.push(match placeholder {
+                "n" => Segment::Number { width: 0 },
+                "slug" => Segment::Slug,
+                _ => match placeholder.strip_prefix("n:") {
+                    Some(width) if width.starts_with('0') => Segment::Number {
+                        width: width
+                            .parse()
+                            .map_err(|_| invalid("expected a width like `{n:03}`"))?,
+                    },
+                    _ => {
+                        return Err(invalid(&format!(
+                            "unknown placeholder `{{{}}}`; expected `{{n}}`, `{{n:03}}` or \
+                             `{{slug}}`",
+                            placeholder
+                        )))
+                    }
+                },
+            });
+        }
+        match segments
+            .iter()
+            .filter(|segment| matches!(segment, Segment::Number { .. }))
+            .count()
+        {
+            1 => (),
+            0 => return Err(invalid("it needs a `{n}` for the problem number")),
+            _ => return Err(invalid("it can only have one `{n}`")),
+        }
+        if !s.ends_with(".rs") {
+            return Err(invalid("solution files must end in `.rs`"));
+        }
+        if s.starts_with('/') || s.split('/').any(|component| component == "..") {
+            return Err(invalid(
+                "it must be relative to the workspace root, and inside it",
+            ));
+        }
+        Ok(Self {
+            source: s.to_owned(),
+            segments,
+        })
+    }
+}
+
+impl From<Pattern> for String {
+    fn from(pattern: Pattern) -> Self {
+        pattern.source
+    }
+}
+
+impl TryFrom<String> for Pattern {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Makes a slug like `coded_triangle_numbers` out of a title like "Coded triangle numbers".
+pub fn slugify(title: &str) -> String {
+    title
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+fn is_slug_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+impl Pattern {
+    /// Whether the pattern has a `{slug}`, so that the path of a solution can't be known
+    /// without finding it.
+    pub fn has_slug(&self) -> bool {
+        self.segments.contains(&Segment::Slug)
+    }
+
+    /// The directory to look for solution files in: everything before the first placeholder,
+    /// up to the last `/`.
+    pub fn base_dir(&self) -> &str {
+        let literal = match self.segments.first() {
+            Some(Segment::Literal(literal)) => literal,
+            _ => return "",
+        };
+        literal.rfind('/').map_or("", |end| &literal[..end])
+    }
+
+    /// The path of the solution to problem `number`, described by `title` if it has a slug.
+    pub fn render(&self, number: usize, title: Option<&str>) -> String {
+        let slug = title.map(slugify).filter(|slug| !slug.is_empty());
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(literal) => literal.clone(),
+                Segment::Number { width } => format!("{:0width$}", number, width = width),
+                Segment::Slug => slug.clone().unwrap_or_else(|| DEFAULT_SLUG.to_owned()),
+            })
+            .collect()
+    }
+
+    /// Matches `path`, relative to the workspace root with `/` separating directories,
+    /// returning the problem number it's for.
+    pub fn matches(&self, path: &str) -> Option<usize> {
+        fn match_from(segments: &[Segment], path: &str) -> Option<Option<usize>> {
+            let (segment, rest) = match segments.split_first() {
+                Some(split) => split,
+                None => return path.is_empty().then_some(None),
+            };
+            match segment {
+                Segment::Literal(literal) => match_from(rest, path.strip_prefix(&**literal)?),
+                Segment::Number { width } => {
+                    let digits = path
+                        .find(|c: char| !c.is_ascii_digit())
+                        .unwrap_or(path.len());
+                    let number = path[..digits].parse::<usize>().ok().filter(|n| *n > 0)?;
+                    // Only numbers written exactly as the pattern would write them match.
+                    if format!("{:0width$}", number, width = width) != path[..digits] {
+                        return None;
+                    }
+                    match_from(rest, &path[digits..])?;
+                    Some(Some(number))
+                }
+                Segment::Slug => {
+                    let longest = path.find(|c| !is_slug_char(c)).unwrap_or(path.len());
+                    (1..=longest).find_map(|len| match_from(rest, &path[len..]))
+                }
+            }
+        }
+
+        // Patterns always have a number, so a match always has one.
+        match_from(&self.segments, path).flatten()
+    }
+}
+
+/// Matches `path`, relative to the workspace root with `/` separating directories, against
+/// `glob`, where `*` stands for anything but a `/`, `**/` for any number of directories and `?`
+/// for any one character but a `/`.
+pub fn glob_matches(glob: &str, path: &str) -> bool {
+    if let Some(rest) = glob.strip_prefix("**/") {
+        return glob_matches(rest, path)
+            || path
+                .find('/')
+                .is_some_and(|end| glob_matches(glob, &path[end + 1..]));
+    }
+    let mut glob_chars = glob.chars();
+    match glob_chars.next() {
+        None => path.is_empty(),
+        Some('*') => {
+            let rest = glob_chars.as_str();
+            let longest = path.find('/').unwrap_or(path.len());
+            (0..=longest)
+                .filter(|len| path.is_char_boundary(*len))
+                .any(|len| glob_matches(rest, &path[len..]))
+        }
+        Some(expected) => {
+            let mut path_chars = path.chars();
+            match path_chars.next() {
+                Some(c) if c == expected || (expected == '?' && c != '/') => {
+                    glob_matches(glob_chars.as_str(), path_chars.as_str())
+                }
+                _ => false,
+            }
+        }
+    }
+}
+
+/// How well a candidate pattern fits the files of a workspace.
+#[derive(Debug, PartialEq)]
+pub struct Detected {
+    pub pattern: Pattern,
+    /// How many of the files considered it matches.
+    pub matched: usize,
+    /// How many files were considered, being Rust files with a standalone number in their name.
+    pub considered: usize,
+}
+
+/// Guesses the pattern a solution file at `path` follows, if its file name has a standalone
+/// number in it, like the `042` of `euler042.rs` or `042_coded_triangle_numbers.rs`, but not
+/// the `2` of `vec2d.rs`.
+fn candidate(path: &str) -> Option<Pattern> {
+    let stem = path.strip_suffix(".rs")?;
+    let name_start = stem.rfind('/').map_or(0, |end| end + 1);
+    let name = &stem[name_start..];
+    let start = name.find(|c: char| c.is_ascii_digit())?;
+    let end = name[start..]
+        .find(|c: char| !c.is_ascii_digit())
+        .map_or(name.len(), |len| start + len);
+    let (prefix, digits, suffix) = (&name[..start], &name[start..end], &name[end..]);
+    // Leading zeroes give away the padding, but otherwise there's no telling it apart from
+    // none, and the files of other numbers settle it.
+    let number = if digits.len() > 1 && digits.starts_with('0') {
+        format!("{{n:0{}}}", digits.len())
+    } else {
+        "{n}".to_owned()
+    };
+    let suffix = match suffix.chars().next() {
+        None => String::new(),
+        Some(separator @ ('_' | '-')) if suffix[1..].chars().all(is_slug_char) => {
+            format!("{}{{slug}}", separator)
+        }
+        Some(_) => return None,
+    };
+    format!("{}{}{}{}.rs", &stem[..name_start], prefix, number, suffix)
+        .parse()
+        .ok()
+}
+
+/// Scores the patterns `paths` suggest by how many of `paths` each matches, returning the one
+/// matching the most, if it matches at least two. Ties go to the pattern that's first in
+/// order, so detection is deterministic.
+pub fn detect<'a>(paths: impl IntoIterator<Item = &'a str>) -> Option<Detected> {
+    let considered = paths
+        .into_iter()
+        .filter(|path| candidate(path).is_some())
+        .collect::<Vec<_>>();
+    let candidates = considered
+        .iter()
+        .filter_map(|path| candidate(path))
+        .map(|pattern| (pattern.source.clone(), pattern))
+        .collect::<BTreeMap<_, _>>();
+    candidates
+        .into_values()
+        .map(|pattern| {
+            let matched = considered
+                .iter()
+                .filter(|path| pattern.matches(path).is_some())
+                .count();
+            (matched, pattern)
+        })
+        .filter(|(matched, _)| *matched >= 2)
+        .fold(
+            None,
+            |best: Option<(usize, Pattern)>, (matched, pattern)| match best {
+                Some((best_matched, _)) if best_matched >= matched => best,
+                _ => Some((matched, pattern)),
+            },
+        )
+        .map(|(matched, pattern)| Detected {
+            pattern,
+            matched,
+            considered: considered.len(),
+        })
+}
+
+/// Detect the pattern existing solution files are named with, and save it as the
+/// `layout.pattern` of `euler.toml` once confirmed.
+#[derive(Debug, StructOpt)]
+pub struct DetectLayout {
+    /// Save the detected pattern without asking for confirmation.
+    #[structopt(short, long)]
+    yes: bool,
+}
+
+impl DetectLayout {
+    pub fn run(self) -> Result<(), Box<dyn Error>> {
+        let Self { yes } = self;
+
+        let workspace = Workspace::discover()?;
+        if workspace.config.layout.kind != LayoutKind::BinPerProblem {
+            return Err("only bin-per-problem layouts can have a pattern".into());
+        }
+        let Detected {
+            pattern,
+            matched,
+            considered,
+        } = workspace.detect_pattern()?.ok_or_else(|| {
+            Failure("no Rust files with numbers in their names follow a common pattern".to_owned())
+        })?;
+        println!(
+            "Detected `{}`, matching {} of {} Rust files with a number in their name",
+            pattern, matched, considered
+        );
+        if workspace.config.layout.pattern.as_ref() == Some(&pattern) {
+            println!("It's already the pattern in {}", CONFIG_FILE_NAME);
+            return Ok(());
+        }
+
+        let config_path = workspace.root.join(CONFIG_FILE_NAME);
+        if !yes
+            && !confirm(&format!(
+                "Save it to {}?",
+                workspace.display_path(&config_path)
+            ))?
+        {
+            println!("Left {} unchanged", CONFIG_FILE_NAME);
+            return Ok(());
+        }
+        let mut document = match read_to_string(&config_path) {
+            Ok(config) => config
+                .parse::<DocumentMut>()
+                .map_err(|e| format!("unable to parse {}: {}", config_path.display(), e))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => DocumentMut::new(),
+            Err(e) => return Err(format!("unable to read {}: {}", config_path.display(), e).into()),
+        };
+        let layout = document
+            .entry("layout")
+            .or_insert_with(table)
+            .as_table_mut()
+            .ok_or_else(|| format!("`layout` in {} isn't a table", config_path.display()))?;
+        layout["pattern"] = value(pattern.to_string());
+        write_atomically(&config_path, document.to_string())?;
+        println!(
+            "Saved the pattern to {}; solutions outside of `src/bin` need `[[bin]]` entries named \
+             after their file stems in {}",
+            workspace.display_path(&config_path),
+            MANIFEST_FILE_NAME
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(source: &str) -> Pattern {
+        source.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_patterns() {
+        for (source, segments) in &[
+            (
+                "src/bin/p{n:03}.rs",
+                vec![
+                    Segment::Literal("src/bin/p".to_owned()),
+                    Segment::Number { width: 3 },
+                    Segment::Literal(".rs".to_owned()),
+                ],
+            ),
+            (
+                "{n}_{slug}.rs",
+                vec![
+                    Segment::Number { width: 0 },
+                    Segment::Literal("_".to_owned()),
+                    Segment::Slug,
+                    Segment::Literal(".rs".to_owned()),
+                ],
+            ),
+        ] {
+            assert_eq!(pattern(source).segments, *segments, "{}", source);
+            assert_eq!(pattern(source).to_string(), *source);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        for (source, reason) in &[
+            ("src/bin/p{n.rs", "isn't closed"),
+            ("src/bin/p}{n}.rs", "isn't opened"),
+            ("src/bin/{n}{slug}.rs", "separated"),
+            ("src/bin/{number}.rs", "unknown placeholder"),
+            ("src/bin/{n:3}.rs", "unknown placeholder"),
+            ("src/bin/{n:0x}.rs", "expected a width"),
+            ("src/bin/main.rs", "needs a `{n}`"),
+            ("{n}/{n}.rs", "only have one"),
+            ("src/bin/{n}.txt", "end in `.rs`"),
+            ("/src/bin/{n}.rs", "relative"),
+            ("../{n}.rs", "relative"),
+        ] {
+            let e = source.parse::<Pattern>().unwrap_err();
+            assert!(e.contains(reason), "{}: {}", source, e);
+        }
+    }
+
+    #[test]
+    fn renders_paths() {
+        assert_eq!(
+            pattern("src/bin/euler{n:03}.rs").render(7, None),
+            "src/bin/euler007.rs"
+        );
+        assert_eq!(pattern("{n}.rs").render(1234, None), "1234.rs");
+        assert_eq!(
+            pattern("solutions/{n:03}_{slug}.rs").render(42, Some("Coded triangle numbers")),
+            "solutions/042_coded_triangle_numbers.rs"
+        );
+        // Titles without anything to make a slug out of get the default one.
+        assert_eq!(
+            pattern("solutions/{n:03}_{slug}.rs").render(42, Some("∑")),
+            "solutions/042_solution.rs"
+        );
+    }
+
+    #[test]
+    fn matches_paths() {
+        for (source, path, number) in &[
+            ("src/bin/euler{n:03}.rs", "src/bin/euler042.rs", Some(42)),
+            ("src/bin/euler{n:03}.rs", "src/bin/euler1234.rs", Some(1234)),
+            // Numbers must be padded exactly as the pattern pads them.
+            ("src/bin/euler{n:03}.rs", "src/bin/euler42.rs", None),
+            ("src/bin/euler{n}.rs", "src/bin/euler042.rs", None),
+            ("src/bin/euler{n:03}.rs", "src/bin/euler000.rs", None),
+            ("src/bin/euler{n:03}.rs", "src/bin/p042.rs", None),
+            (
+                "{n:03}_{slug}.rs",
+                "042_coded-triangle_numbers.rs",
+                Some(42),
+            ),
+            ("{n:03}_{slug}.rs", "042_.rs", None),
+            ("{n:03}_{slug}.rs", "042_a/b.rs", None),
+            ("{slug}_{n}.rs", "coded_triangle_42.rs", Some(42)),
+        ] {
+            assert_eq!(
+                pattern(source).matches(path),
+                *number,
+                "{} {}",
+                source,
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn finds_where_to_look_for_solutions() {
+        assert_eq!(pattern("src/bin/euler{n:03}.rs").base_dir(), "src/bin");
+        assert_eq!(pattern("solutions{n}/main.rs").base_dir(), "");
+        assert_eq!(pattern("{n}/main.rs").base_dir(), "");
+    }
+
+    #[test]
+    fn matches_globs() {
+        for (glob, path, matches) in &[
+            ("src/bin/p*.rs", "src/bin/p042.rs", true),
+            ("src/bin/p*.rs", "src/bin/p.rs", true),
+            ("src/bin/p*.rs", "src/bin/problem42/main.rs", false),
+            ("src/bin/*/main.rs", "src/bin/problem42/main.rs", true),
+            ("src/bin/p0??.rs", "src/bin/p042.rs", true),
+            ("src/bin/p0??.rs", "src/bin/p0421.rs", false),
+            ("src/bin/p?.rs", "src/bin/p/.rs", false),
+            ("**/main.rs", "main.rs", true),
+            ("**/main.rs", "problems/p042/src/main.rs", true),
+            ("problems/**/lib.rs", "problems/p042/src/main.rs", false),
+            ("src/bin/*é.rs", "src/bin/pé.rs", true),
+        ] {
+            assert_eq!(glob_matches(glob, path), *matches, "{} {}", glob, path);
+        }
+    }
+
+    #[test]
+    fn suggests_patterns_for_paths() {
+        for (path, suggested) in &[
+            ("src/bin/euler042.rs", Some("src/bin/euler{n:03}.rs")),
+            ("src/bin/euler42.rs", Some("src/bin/euler{n}.rs")),
+            ("src/bin/7.rs", Some("src/bin/{n}.rs")),
+            (
+                "solutions/042_coded_triangle_numbers.rs",
+                Some("solutions/{n:03}_{slug}.rs"),
+            ),
+            ("src/bin/vec2d.rs", None),
+            ("src/bin/utils.rs", None),
+            ("src/bin/p042.txt", None),
+        ] {
+            assert_eq!(
+                candidate(path).map(|pattern| pattern.to_string()),
+                suggested.map(str::to_owned),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn detects_the_pattern_matching_the_most_files() {
+        let detected = detect(vec![
+            "src/bin/euler001.rs",
+            "src/bin/euler002.rs",
+            "src/bin/euler010.rs",
+            "src/bin/euler100.rs",
+            "src/bin/p2.rs",
+            "src/bin/p3.rs",
+            "src/main.rs",
+            "src/geometry/vec2d.rs",
+        ])
+        .unwrap();
+        assert_eq!(
+            detected,
+            Detected {
+                pattern: pattern("src/bin/euler{n:03}.rs"),
+                matched: 4,
+                considered: 6,
+            }
+        );
+    }
+
+    #[test]
+    fn breaks_ties_between_patterns_by_their_order() {
+        let detected = detect(vec!["b/p1.rs", "b/p2.rs", "a/p1.rs", "a/p2.rs"]).unwrap();
+        assert_eq!(detected.pattern, pattern("a/p{n}.rs"));
+        assert_eq!(detected.matched, 2);
+    }
+
+    #[test]
+    fn detects_nothing_from_lone_files() {
+        assert_eq!(detect(vec!["src/bin/euler001.rs", "src/bin/p2.rs"]), None);
+        assert_eq!(detect(Vec::new()), None);
+    }
+}
@@ -0,0 +1,130 @@
+//! Context from cached solved-by counts: how mainstream problems are, and which solved problems
+//! few others have.
+
+use {
+    crate::{
+        context::Context,
+        enrichment::{Enrichment, ProblemInfo},
+        progress::{Levels, Problems},
+        stats::PROBLEMS_PER_LEVEL,
+    },
+    std::error::Error,
+    structopt::StructOpt,
+};
+
+/// The largest cached solved-by count, which the others are relative to.
+pub fn max_solved_by(enrichment: &Enrichment) -> Option<u64> {
+    enrichment.iter().filter_map(|info| info.solved_by).max()
+}
+
+/// How mainstream problem `number` is: its solved-by count as a percentage of `max_solved_by`.
+pub fn mainstream(enrichment: &Enrichment, number: usize, max_solved_by: u64) -> Option<f64> {
+    let solved_by = enrichment.get(number)?.solved_by?;
+    Some(solved_by as f64 * 100.0 / max_solved_by.max(1) as f64)
+}
+
+/// The solved counts of the published levels that `solved` has reached: their thresholds from
+/// their descriptions, falling back to a level every `PROBLEMS_PER_LEVEL` problems. Without any
+/// levels listed, like for new accounts, levels are assumed every `PROBLEMS_PER_LEVEL` problems.
+pub fn passed_milestones(levels: &Levels, solved: usize) -> (Vec<usize>, Option<usize>) {
+    if levels.0.is_empty() {
+        let passed = (1..=solved / PROBLEMS_PER_LEVEL)
+            .map(|level| level * PROBLEMS_PER_LEVEL)
+            .collect();
+        return (
+            passed,
+            Some((solved / PROBLEMS_PER_LEVEL + 1) * PROBLEMS_PER_LEVEL),
+        );
+    }
+    let thresholds = levels
+        .0
+        .iter()
+        .enumerate()
+        .map(|(idx, level)| level.threshold().unwrap_or((idx + 1) * PROBLEMS_PER_LEVEL));
+    let (passed, ahead) = thresholds.partition::<Vec<_>, _>(|threshold| *threshold <= solved);
+    (passed, ahead.into_iter().min())
+}
+
+/// Describes `passed_milestones` like "Milestones passed: 25, 50 (next: 75)".
+pub fn describe_milestones(levels: &Levels, solved: usize) -> String {
+    let (passed, next) = passed_milestones(levels, solved);
+    let mut description = match passed.is_empty() {
+        true => "Milestones passed: none".to_owned(),
+        false => format!(
+            "Milestones passed: {}",
+            passed
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    match next {
+        Some(next) => description.push_str(&format!(" (next: {})", next)),
+        None => description.push_str(" (every published level)"),
+    }
+    description
+}
+
+/// The `count` solved problems with the lowest cached solved-by counts, lowest first.
+pub fn hardest_solved<'e>(
+    problems: &Problems,
+    enrichment: &'e Enrichment,
+    count: usize,
+) -> Vec<&'e ProblemInfo> {
+    let mut solved = enrichment
+        .iter()
+        .filter(|info| problems.is_solved(info.number) && info.solved_by.is_some())
+        .collect::<Vec<_>>();
+    solved.sort_by_key(|info| (info.solved_by, info.number));
+    solved.truncate(count);
+    solved
+}
+
+/// The message to show when solved-by counts are needed but none are cached.
+pub fn missing_solved_by() -> String {
+    format!(
+        "no solved-by counts are cached in {}; cache them with `cargo euler prefetch`",
+        Enrichment::cache_path().display()
+    )
+}
+
+/// List the solved problems that the fewest others have solved.
+#[derive(Debug, StructOpt)]
+pub struct HardestSolved {
+    /// How many problems to list.
+    #[structopt(long, default_value = "10")]
+    count: usize,
+}
+
+impl HardestSolved {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self { count } = self;
+
+        let enrichment = Enrichment::load_cached();
+        if max_solved_by(&enrichment).is_none() {
+            return Err(missing_solved_by().into());
+        }
+        let problems = context.progress()?.problems;
+        let hardest = hardest_solved(&problems, &enrichment, count);
+        if hardest.is_empty() {
+            println!("None of your solved problems have cached solved-by counts");
+            return Ok(());
+        }
+        let number_width = hardest
+            .iter()
+            .map(|info| info.number.to_string().len())
+            .max()
+            .unwrap_or(1);
+        for info in hardest {
+            println!(
+                "{:>nw$}  {:>9}  {}",
+                info.number,
+                info.solved_by.unwrap(),
+                info.title,
+                nw = number_width
+            );
+        }
+        Ok(())
+    }
+}
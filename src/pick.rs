@@ -0,0 +1,520 @@
+//! Picking an unsolved problem interactively, by fuzzy search over numbers and titles.
+//!
+//! The picker is written by hand against termios, since no picker crate like skim is available
+//! to this build. It only has to narrow a list of a thousand or so short lines, which a simple
+//! subsequence scorer does well enough.
+
+use {
+    crate::{context::Context, endpoints::problem_url, enrichment::Enrichment, new::New, Failure},
+    log::warn,
+    std::{
+        env::var,
+        error::Error,
+        io::{stderr, stdin, IsTerminal, Write},
+    },
+    structopt::StructOpt,
+};
+
+/// How many matches the interactive picker shows at once.
+const PICKER_HEIGHT: usize = 10;
+/// How many matches the numbered menu lists at once.
+const MENU_LEN: usize = 20;
+/// The width to fit lines to when the terminal's can't be found.
+const DEFAULT_WIDTH: usize = 80;
+
+/// An unsolved problem to pick from.
+#[derive(Debug)]
+struct Candidate {
+    number: usize,
+    title: Option<String>,
+    difficulty: Option<u8>,
+    /// What's searched, in lowercase.
+    haystack: String,
+}
+
+impl Candidate {
+    fn new(number: usize, enrichment: &Enrichment) -> Self {
+        let info = enrichment.get(number);
+        let title = info.map(|info| info.title.clone());
+        Self {
+            number,
+            haystack: match &title {
+                Some(title) => format!("{} {}", number, title.to_lowercase()),
+                None => number.to_string(),
+            },
+            title,
+            difficulty: info.and_then(|info| info.difficulty),
+        }
+    }
+
+    /// Describes the problem on one line, cut to `width` characters.
+    fn line(&self, number_width: usize, width: usize) -> String {
+        let mut line = format!("{:>width$}", self.number, width = number_width);
+        if let Some(title) = &self.title {
+            line.push_str(&format!("  {}", title));
+        }
+        if let Some(difficulty) = self.difficulty {
+            line.push_str(&format!(" ({}%)", difficulty));
+        }
+        line.chars().take(width).collect()
+    }
+}
+
+/// How well `word` matches `haystack` as a subsequence of its characters, favoring runs of
+/// consecutive characters and the starts of words. `None` if it doesn't match at all.
+fn word_score(word: &str, haystack: &str) -> Option<usize> {
+    let mut haystack = haystack.chars().enumerate().scan(None, |before, (idx, c)| {
+        let at_word_start = before.is_none_or(|before: char| !before.is_alphanumeric());
+        *before = Some(c);
+        Some((idx, c, at_word_start))
+    });
+    let mut score = 0;
+    let mut last_match = None;
+    for wanted in word.chars() {
+        let (idx, _, at_word_start) = haystack.find(|(_, c, _)| *c == wanted)?;
+        score += 1;
+        if last_match.is_some_and(|last| last + 1 == idx) {
+            score += 2;
+        }
+        if at_word_start {
+            score += 3;
+        }
+        last_match = Some(idx);
+    }
+    Some(score)
+}
+
+/// How well `query` matches `candidate`, where every word of the query must match.
+fn score(query: &str, candidate: &Candidate) -> Option<usize> {
+    query
+        .split_whitespace()
+        .map(|word| word_score(word, &candidate.haystack))
+        .sum()
+}
+
+/// The candidates matching `query`, best first. A query of just a number always puts that
+/// problem first, however well others match it.
+fn rank<'c>(query: &str, candidates: &'c [Candidate]) -> Vec<&'c Candidate> {
+    let query = query.trim().to_lowercase();
+    let mut scored = candidates
+        .iter()
+        .filter_map(|candidate| score(&query, candidate).map(|score| (score, candidate)))
+        .collect::<Vec<_>>();
+    let exact = query.parse::<usize>().ok();
+    scored.sort_by_key(|(score, candidate)| {
+        (
+            Some(candidate.number) != exact,
+            usize::MAX - score,
+            candidate.number,
+        )
+    });
+    scored.into_iter().map(|(_, candidate)| candidate).collect()
+}
+
+fn number_width(candidates: &[Candidate]) -> usize {
+    candidates
+        .iter()
+        .map(|candidate| candidate.number.to_string().len())
+        .max()
+        .unwrap_or(1)
+}
+
+/// Whether the terminal can show the interactive picker, rather than the numbered menu.
+fn supports_picker() -> bool {
+    let term = var("TERM").unwrap_or_default();
+    cfg!(unix)
+        && stdin().is_terminal()
+        && stderr().is_terminal()
+        && !term.is_empty()
+        && term != "dumb"
+}
+
+/// Standard input switched to reading key by key without echoing, until dropped.
+#[cfg(unix)]
+struct RawMode {
+    original: libc::termios,
+}
+
+#[cfg(unix)]
+impl RawMode {
+    fn enable() -> Option<Self> {
+        let mut original = std::mem::MaybeUninit::uninit();
+        // SAFETY: `tcgetattr` initializes `original` when it succeeds.
+        let original = unsafe {
+            if libc::tcgetattr(libc::STDIN_FILENO, original.as_mut_ptr()) != 0 {
+                return None;
+            }
+            original.assume_init()
+        };
+        let mut raw = original;
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG);
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+        // SAFETY: `raw` is a valid `termios`, from `tcgetattr`.
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+            return None;
+        }
+        Some(Self { original })
+    }
+
+    /// How many columns wide the terminal is, if that can be found.
+    fn width(&self) -> Option<usize> {
+        let mut size = std::mem::MaybeUninit::<libc::winsize>::uninit();
+        // SAFETY: `TIOCGWINSZ` initializes `size` when it succeeds.
+        let size = unsafe {
+            if libc::ioctl(libc::STDERR_FILENO, libc::TIOCGWINSZ, size.as_mut_ptr()) != 0 {
+                return None;
+            }
+            size.assume_init()
+        };
+        Some(usize::from(size.ws_col)).filter(|width| *width > 0)
+    }
+
+    /// Reads a byte straight from the terminal, bypassing the buffering of `stdin()`, which
+    /// would hide from `input_pending` what it has already read. `None` at the end of input.
+    fn read_byte(&self) -> Result<Option<u8>, Box<dyn Error>> {
+        let mut byte = 0u8;
+        // SAFETY: `byte` is valid for a write of one byte.
+        match unsafe { libc::read(libc::STDIN_FILENO, (&mut byte as *mut u8).cast(), 1) } {
+            1 => Ok(Some(byte)),
+            0 => Ok(None),
+            _ => Err(std::io::Error::last_os_error().into()),
+        }
+    }
+
+    /// Whether more input arrives within a moment, like the rest of an escape sequence.
+    fn input_pending(&self) -> bool {
+        let mut poll = libc::pollfd {
+            fd: libc::STDIN_FILENO,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: `poll` is a single valid `pollfd`.
+        unsafe { libc::poll(&mut poll, 1, 50) > 0 }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        // SAFETY: `original` is the valid `termios` from `tcgetattr`.
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original) };
+    }
+}
+
+#[cfg(not(unix))]
+struct RawMode;
+
+#[cfg(not(unix))]
+impl RawMode {
+    fn enable() -> Option<Self> {
+        None
+    }
+
+    fn width(&self) -> Option<usize> {
+        None
+    }
+
+    fn read_byte(&self) -> Result<Option<u8>, Box<dyn Error>> {
+        Ok(None)
+    }
+
+    fn input_pending(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug)]
+enum Key {
+    Char(char),
+    Backspace,
+    Up,
+    Down,
+    Enter,
+    Cancel,
+    Other,
+}
+
+fn read_key(raw: &RawMode) -> Result<Key, Box<dyn Error>> {
+    let byte = match raw.read_byte()? {
+        Some(byte) => byte,
+        None => return Ok(Key::Cancel),
+    };
+    Ok(match byte {
+        b'\r' | b'\n' => Key::Enter,
+        0x7f | 0x08 => Key::Backspace,
+        // Ctrl-C, Ctrl-D and Ctrl-G.
+        0x03 | 0x04 | 0x07 => Key::Cancel,
+        // Ctrl-P and Ctrl-N.
+        0x10 => Key::Up,
+        0x0e => Key::Down,
+        0x1b if !raw.input_pending() => Key::Cancel,
+        0x1b => match (raw.read_byte()?, raw.read_byte()?) {
+            (Some(b'[' | b'O'), Some(b'A')) => Key::Up,
+            (Some(b'[' | b'O'), Some(b'B')) => Key::Down,
+            _ => Key::Other,
+        },
+        byte if byte < 0x20 => Key::Other,
+        byte => {
+            // Collect the rest of a multi-byte character.
+            let len = match byte.leading_ones() {
+                0 => 1,
+                len => len as usize,
+            };
+            let mut bytes = vec![byte];
+            for _ in 1..len {
+                bytes.extend(raw.read_byte()?);
+            }
+            match std::str::from_utf8(&bytes)
+                .ok()
+                .and_then(|s| s.chars().next())
+            {
+                Some(c) => Key::Char(c),
+                None => Key::Other,
+            }
+        }
+    })
+}
+
+/// Pick with a prompt that narrows the matches as the query is typed, drawn on standard error.
+fn pick_interactively(
+    raw: &RawMode,
+    candidates: &[Candidate],
+) -> Result<Option<usize>, Box<dyn Error>> {
+    let width = raw
+        .width()
+        .unwrap_or(DEFAULT_WIDTH)
+        .saturating_sub(1)
+        .max(1);
+    let number_width = number_width(candidates);
+    let mut query = String::new();
+    let mut selected = 0;
+    let mut out = stderr();
+    loop {
+        let ranked = rank(&query, candidates);
+        let shown = ranked.len().min(PICKER_HEIGHT);
+        selected = selected.min(shown.saturating_sub(1));
+
+        let mut frame = format!("\r\x1b[J> {}", query);
+        frame.push_str(&format!("  ({}/{})", ranked.len(), candidates.len()));
+        for (idx, candidate) in ranked.iter().take(shown).enumerate() {
+            let line = candidate.line(number_width, width.saturating_sub(2));
+            if idx == selected {
+                frame.push_str(&format!("\n\x1b[7m> {}\x1b[0m", line));
+            } else {
+                frame.push_str(&format!("\n  {}", line));
+            }
+        }
+        if shown > 0 {
+            frame.push_str(&format!("\x1b[{}A", shown));
+        }
+        frame.push_str(&format!("\r\x1b[{}C", 2 + query.chars().count()));
+        write!(out, "{}", frame)?;
+        out.flush()?;
+
+        match read_key(raw)? {
+            Key::Char(c) => {
+                query.push(c);
+                selected = 0;
+            }
+            Key::Backspace => {
+                query.pop();
+                selected = 0;
+            }
+            Key::Up => selected = selected.saturating_sub(1),
+            Key::Down => selected += 1,
+            Key::Enter if shown > 0 => {
+                write!(out, "\r\x1b[J")?;
+                return Ok(Some(ranked[selected].number));
+            }
+            Key::Cancel => {
+                write!(out, "\r\x1b[J")?;
+                return Ok(None);
+            }
+            Key::Enter | Key::Other => (),
+        }
+    }
+}
+
+/// Pick from a numbered menu, for terminals the interactive picker doesn't work on. Entering a
+/// number picks that problem, and anything else searches for it.
+fn pick_from_menu(candidates: &[Candidate]) -> Result<Option<usize>, Box<dyn Error>> {
+    let number_width = number_width(candidates);
+    let mut ranked = rank("", candidates);
+    loop {
+        let mut out = stderr();
+        for candidate in ranked.iter().take(MENU_LEN) {
+            writeln!(out, "  {}", candidate.line(number_width, DEFAULT_WIDTH - 2))?;
+        }
+        if ranked.len() > MENU_LEN {
+            writeln!(out, "  ...and {} more", ranked.len() - MENU_LEN)?;
+        }
+        write!(out, "Problem number, or text to search for: ")?;
+        out.flush()?;
+
+        let mut reply = String::new();
+        if stdin().read_line(&mut reply)? == 0 {
+            return Ok(None);
+        }
+        let reply = reply.trim();
+        if reply.is_empty() {
+            return Ok(None);
+        }
+        if let Ok(number) = reply.parse::<usize>() {
+            if candidates
+                .iter()
+                .any(|candidate| candidate.number == number)
+            {
+                return Ok(Some(number));
+            }
+            writeln!(
+                out,
+                "Problem {} isn't one of your unsolved problems.",
+                number
+            )?;
+            continue;
+        }
+        ranked = rank(reply, candidates);
+        match ranked.as_slice() {
+            [] => {
+                writeln!(out, "Nothing matches {:?}.", reply)?;
+                ranked = rank("", candidates);
+            }
+            [only] => return Ok(Some(only.number)),
+            _ => (),
+        }
+    }
+}
+
+/// Pick an unsolved problem by searching its number and title, printing its number. For
+/// example, scaffold the picked problem with `cargo euler new $(cargo euler pick)`.
+#[derive(Debug, StructOpt)]
+pub struct Pick {
+    /// Open the picked problem in a web browser instead of printing its number.
+    #[structopt(long, conflicts_with = "new")]
+    open: bool,
+    /// Scaffold a solution for the picked problem, like `cargo euler new`, instead of printing
+    /// its number.
+    #[structopt(long)]
+    new: bool,
+}
+
+impl Pick {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self { open, new } = self;
+
+        let progress = context.progress()?;
+        let enrichment = Enrichment::load_cached();
+        if enrichment.is_empty() {
+            warn!(
+                "no problem titles are cached in {}, so only numbers can be searched; cache them \
+                 with `cargo euler prefetch`",
+                Enrichment::cache_path().display()
+            );
+        }
+        let candidates = progress
+            .problems
+            .unsolved()
+            .map(|number| Candidate::new(number, &enrichment))
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return Err("there are no unsolved problems to pick from".into());
+        }
+
+        let raw = if supports_picker() {
+            RawMode::enable()
+        } else {
+            None
+        };
+        let picked = match &raw {
+            Some(raw) => pick_interactively(raw, &candidates)?,
+            None => pick_from_menu(&candidates)?,
+        };
+        drop(raw);
+        let number = picked.ok_or_else(|| Failure("no problem was picked".to_owned()))?;
+
+        if open {
+            webbrowser::open(&problem_url(number))?;
+        } else if new {
+            New::for_number(number).run(context.offline)?;
+        } else {
+            println!("{}", number);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(number: usize, title: &str) -> Candidate {
+        Candidate {
+            number,
+            title: Some(title.to_owned()),
+            difficulty: None,
+            haystack: format!("{} {}", number, title.to_lowercase()),
+        }
+    }
+
+    fn numbers(ranked: Vec<&Candidate>) -> Vec<usize> {
+        ranked.iter().map(|candidate| candidate.number).collect()
+    }
+
+    #[test]
+    fn scores_words_as_subsequences() {
+        // Each character scores 1, with 2 more for following the last and 3 for starting a
+        // word.
+        assert_eq!(word_score("abc", "abc"), Some(10));
+        assert_eq!(word_score("tri", "coded triangle numbers"), Some(10));
+        // Matching is greedy, so this `n` is that of `triangle` rather than of `numbers`.
+        assert_eq!(word_score("ctn", "coded triangle numbers"), Some(9));
+        assert_eq!(word_score("cdd", "coded triangle numbers"), Some(6));
+        assert_eq!(word_score("", "anything"), Some(0));
+        // Characters have to appear in order.
+        assert_eq!(word_score("ba", "ab"), None);
+        assert_eq!(word_score("z", "coded triangle numbers"), None);
+    }
+
+    #[test]
+    fn requires_every_word_of_a_query_to_match() {
+        let candidate = candidate(42, "Coded triangle numbers");
+        assert_eq!(score("tri num", &candidate), Some(10 + 5));
+        assert_eq!(score("tri fib", &candidate), None);
+        assert_eq!(score("", &candidate), Some(0));
+    }
+
+    #[test]
+    fn ranks_the_best_matches_first() {
+        let candidates = [
+            candidate(12, "Highly divisible triangular number"),
+            candidate(18, "Maximum path sum I"),
+            candidate(42, "Coded triangle numbers"),
+            candidate(67, "Maximum path sum II"),
+        ];
+        assert_eq!(numbers(rank("triangle", &candidates)), [42, 12]);
+        assert_eq!(numbers(rank("coded tri", &candidates)), [42]);
+        // Ties go to the lower number.
+        assert_eq!(numbers(rank("max path", &candidates)), [18, 67]);
+        assert_eq!(numbers(rank("", &candidates)), [12, 18, 42, 67]);
+    }
+
+    #[test]
+    fn ranks_the_problem_with_a_number_first() {
+        let candidates = [
+            candidate(1, "Multiples of 3 or 5"),
+            candidate(3, "Largest prime factor"),
+            candidate(13, "Large sum"),
+        ];
+        assert_eq!(numbers(rank(" 3 ", &candidates)), [3, 1, 13]);
+        assert_eq!(numbers(rank("13", &candidates)), [13, 1]);
+    }
+
+    #[test]
+    fn fits_lines_to_the_width() {
+        let mut candidate = candidate(7, "10001st prime");
+        candidate.difficulty = Some(5);
+        assert_eq!(candidate.line(3, 80), "  7  10001st prime (5%)");
+        assert_eq!(candidate.line(3, 8), "  7  100");
+    }
+}
@@ -0,0 +1,194 @@
+//! Planning which problems to solve next to complete bands of problems and reach levels.
+
+use {
+    crate::{
+        context::Context,
+        enrichment::Enrichment,
+        levels::{bands, Band},
+        progress::{Problems, Progress},
+    },
+    log::warn,
+    std::{error::Error, ops::RangeInclusive},
+    structopt::StructOpt,
+};
+
+/// The bands of `problems` with any unsolved problems left, those closest to complete first.
+pub fn incomplete_bands(problems: &Problems) -> Vec<Band> {
+    let mut bands = bands(problems)
+        .into_iter()
+        .filter(|band| !band.unsolved.is_empty())
+        .collect::<Vec<_>>();
+    bands.sort_by_key(|band| (band.unsolved.len(), *band.problems.start()));
+    bands
+}
+
+/// The `count` unsolved problems with the lowest cached difficulty ratings, easiest first. Unrated
+/// problems come after rated ones, since they can't be told to be easy. Ties go to the lowest
+/// number.
+pub fn cheapest_unsolved(problems: &Problems, enrichment: &Enrichment, count: usize) -> Vec<usize> {
+    let mut unsolved = problems
+        .unsolved()
+        .map(|number| {
+            let difficulty = enrichment.get(number).and_then(|info| info.difficulty);
+            (difficulty.is_none(), difficulty, number)
+        })
+        .collect::<Vec<_>>();
+    unsolved.sort();
+    unsolved
+        .into_iter()
+        .take(count)
+        .map(|(_, _, number)| number)
+        .collect()
+}
+
+fn range(problems: &RangeInclusive<usize>) -> String {
+    format!("{}-{}", problems.start(), problems.end())
+}
+
+/// Describes `number` with its cached difficulty rating, like `27 (15%)`.
+fn rated(number: usize, enrichment: &Enrichment) -> String {
+    match enrichment.get(number).and_then(|info| info.difficulty) {
+        Some(difficulty) => format!("{} ({}%)", number, difficulty),
+        None => format!("{} (unrated)", number),
+    }
+}
+
+/// Show the unsolved problems in each band a level's worth long, those closest to complete
+/// first, or the easiest problems to solve to reach a level.
+#[derive(Debug, StructOpt)]
+pub struct Plan {
+    /// Instead, list the easiest unsolved problems by cached difficulty ratings that would reach
+    /// this level once solved.
+    #[structopt(long)]
+    target_level: Option<usize>,
+}
+
+impl Plan {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self { target_level } = self;
+
+        let progress = context.progress()?;
+        let Progress { levels, problems } = &progress;
+        let enrichment = Enrichment::load_cached();
+        if enrichment.is_empty() {
+            warn!(
+                "no difficulty ratings are cached in {}; cache them with `cargo euler prefetch \
+                 --difficulty`",
+                Enrichment::cache_path().display()
+            );
+        }
+
+        match target_level {
+            None => {
+                let bands = incomplete_bands(problems);
+                if bands.is_empty() {
+                    println!("Every published problem is solved");
+                }
+                for band in &bands {
+                    println!(
+                        "{:<9}  {:>2} to complete: {}",
+                        range(&band.problems),
+                        band.unsolved.len(),
+                        band.unsolved
+                            .iter()
+                            .map(|number| rated(*number, &enrichment))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                }
+            }
+            Some(0) => return Err("the target level must be at least 1".into()),
+            Some(level) => {
+                if levels.current() >= level {
+                    println!("Level {} is already reached", level);
+                    return Ok(());
+                }
+                let threshold = progress.level_threshold(level);
+                let needed = threshold.saturating_sub(problems.solved_count());
+                let cheapest = cheapest_unsolved(problems, &enrichment, needed);
+                if cheapest.len() < needed {
+                    return Err(format!(
+                        "Level {} needs {} solved, but only {} problems are left unsolved",
+                        level,
+                        threshold,
+                        cheapest.len()
+                    )
+                    .into());
+                }
+                println!(
+                    "Solve {} more to reach Level {} at {} solved:",
+                    needed, level, threshold
+                );
+                for number in cheapest {
+                    println!("  {}", rated(number, &enrichment));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{enrichment::ProblemInfo, test_support::problems},
+    };
+
+    fn rated(difficulties: &[(usize, u8)]) -> Enrichment {
+        let mut enrichment = Enrichment::default();
+        for &(number, difficulty) in difficulties {
+            enrichment.insert(ProblemInfo {
+                number,
+                title: format!("Problem {}", number),
+                difficulty: Some(difficulty),
+                solved_by: None,
+            });
+        }
+        enrichment
+    }
+
+    #[test]
+    fn orders_bands_closest_to_complete_first() {
+        let split = problems(&format!(
+            "{}UU {}U {} SURU",
+            "S".repeat(23),
+            "S".repeat(24),
+            "S".repeat(25)
+        ));
+        let bands = incomplete_bands(&split);
+        assert_eq!(
+            bands
+                .iter()
+                .map(|band| (range(&band.problems), band.unsolved.clone()))
+                .collect::<Vec<_>>(),
+            [
+                ("26-50".to_owned(), vec![50]),
+                ("1-25".to_owned(), vec![24, 25]),
+                ("76-79".to_owned(), vec![77, 79]),
+            ]
+        );
+        assert!(incomplete_bands(&problems("SSRS")).is_empty());
+    }
+
+    #[test]
+    fn picks_the_easiest_unsolved_problems() {
+        let problems = problems("SUUUUR");
+        let enrichment = rated(&[(2, 40), (3, 5), (5, 5), (6, 1)]);
+        assert_eq!(cheapest_unsolved(&problems, &enrichment, 2), [3, 5]);
+        // Unrated problems come last, since they can't be told to be easy.
+        assert_eq!(cheapest_unsolved(&problems, &enrichment, 10), [3, 5, 2, 4]);
+        assert_eq!(
+            cheapest_unsolved(&problems, &Enrichment::default(), 2),
+            [2, 3]
+        );
+        assert!(cheapest_unsolved(&problems, &enrichment, 0).is_empty());
+    }
+
+    #[test]
+    fn describes_problems_with_their_ratings() {
+        let enrichment = rated(&[(27, 15)]);
+        assert_eq!(super::rated(27, &enrichment), "27 (15%)");
+        assert_eq!(super::rated(28, &enrichment), "28 (unrated)");
+    }
+}
@@ -0,0 +1,347 @@
+//! Caching problem metadata from the archives of the site, for everything that uses titles,
+//! difficulty ratings or solved-by counts.
+
+use {
+    crate::{
+        client::fetch_public_page,
+        context::Context,
+        endpoints::{archives_url, problem_url},
+        enrichment::{Enrichment, ProblemInfo},
+        friends::{cell_text, parse_count},
+        rankings::last_page,
+        Failure,
+    },
+    log::warn,
+    std::{
+        error::Error,
+        io::{stderr, Write},
+    },
+    structopt::StructOpt,
+    unhtml::scraper::{Html, Selector},
+};
+
+const DIFFICULTY_LABEL: &str = "difficulty rating:";
+
+/// Parses the problems listed on a page of the archives: rows of a table whose cells are the
+/// number, the title (linking to the problem) and how many have solved it. Returns `None` if the
+/// page has no such table.
+pub fn parse_archives(page: &str) -> Option<Vec<ProblemInfo>> {
+    let html = Html::parse_document(page);
+    let row_selector = Selector::parse("tr").unwrap();
+    let cell_selector = Selector::parse("td").unwrap();
+    let link_selector = Selector::parse("a[href*=\"problem=\"]").unwrap();
+
+    for table in html.select(&Selector::parse("table").unwrap()) {
+        let mut infos = Vec::new();
+        for row in table.select(&row_selector) {
+            let cells = row.select(&cell_selector).collect::<Vec<_>>();
+            let (number, title) = match (cells.first(), cells.get(1)) {
+                (Some(number), Some(title)) if title.select(&link_selector).next().is_some() => {
+                    match parse_count(&cell_text(*number)) {
+                        Some(number) => (number, cell_text(*title)),
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+            infos.push(ProblemInfo {
+                number,
+                title,
+                difficulty: None,
+                solved_by: cells
+                    .get(2)
+                    .and_then(|cell| parse_count(&cell_text(*cell)))
+                    .map(|solved_by| solved_by as u64),
+            });
+        }
+        if !infos.is_empty() {
+            return Some(infos);
+        }
+    }
+    None
+}
+
+/// Finds the difficulty rating on the page of a problem, like "Difficulty rating: 5%". Problems
+/// that are too recent to be rated don't have one.
+pub fn parse_difficulty(page: &str) -> Option<u8> {
+    let text = Html::parse_document(page)
+        .root_element()
+        .text()
+        .collect::<String>()
+        .to_lowercase();
+    let rest = &text[text.find(DIFFICULTY_LABEL)? + DIFFICULTY_LABEL.len()..];
+    let rest = rest.trim_start();
+    let digits = &rest[..rest.find(|c: char| !c.is_ascii_digit())?];
+    if !rest[digits.len()..].starts_with('%') {
+        return None;
+    }
+    digits.parse().ok().filter(|difficulty| *difficulty <= 100)
+}
+
+/// Cache the titles and solved-by counts of every published problem from the archives, for
+/// commands like `suggest`, `pick` and `hardest-solved`.
+#[derive(Debug, StructOpt)]
+pub struct Prefetch {
+    /// Also cache difficulty ratings, which are only on the page of each problem, so this makes
+    /// a request per problem that doesn't have one cached yet.
+    #[structopt(long)]
+    difficulty: bool,
+    /// With `--difficulty`, stop at the first problem whose page fails to be fetched, instead of
+    /// fetching the rest and reporting every failure at the end.
+    #[structopt(long)]
+    fail_fast: bool,
+}
+
+impl Prefetch {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self {
+            difficulty,
+            fail_fast,
+        } = self;
+
+        if context.offline {
+            return Err("problem metadata can't be fetched with `--offline`".into());
+        }
+        let cached = Enrichment::load_cached();
+        let mut enrichment = Enrichment::default();
+        let mut page_number = 1;
+        loop {
+            let page = fetch_public_page(&archives_url(page_number))?;
+            let infos = match parse_archives(&page) {
+                Some(infos) => infos,
+                None if page_number == 1 => {
+                    return Err("unable to find the problems listed in the archives".into())
+                }
+                None => break,
+            };
+            for mut info in infos {
+                // Ratings don't change once published, so they're kept from earlier prefetches.
+                info.difficulty = cached.get(info.number).and_then(|cached| cached.difficulty);
+                enrichment.insert(info);
+            }
+            if page_number >= last_page(&page).unwrap_or(page_number) {
+                break;
+            }
+            page_number += 1;
+        }
+
+        let unrated = if difficulty {
+            enrichment
+                .iter()
+                .filter(|info| info.difficulty.is_none())
+                .map(|info| info.number)
+                .collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+        let mut failed = Vec::new();
+        let mut stderr = stderr();
+        for (idx, number) in unrated.iter().enumerate() {
+            let _ = write!(
+                stderr,
+                "\rFetching difficulty ratings: {}/{}",
+                idx + 1,
+                unrated.len()
+            );
+            let _ = stderr.flush();
+            let page = match fetch_public_page(&problem_url(*number)) {
+                Ok(page) => page,
+                Err(e) => {
+                    let _ = writeln!(stderr);
+                    if fail_fast {
+                        // Keep what's been fetched so far, so that it isn't fetched again.
+                        enrichment.save()?;
+                        return Err(Failure(format!(
+                            "stopped at the first failure, fetching the difficulty rating \
+                             of problem {}: {}",
+                            number, e
+                        ))
+                        .into());
+                    }
+                    warn!(
+                        "unable to fetch the difficulty rating of problem {}: {}",
+                        number, e
+                    );
+                    failed.push(number.to_string());
+                    continue;
+                }
+            };
+            if let Some(rating) = parse_difficulty(&page) {
+                let mut info = enrichment.get(*number).unwrap().clone();
+                info.difficulty = Some(rating);
+                enrichment.insert(info);
+            }
+        }
+        if !unrated.is_empty() {
+            let _ = writeln!(stderr);
+        }
+
+        enrichment.save()?;
+        println!(
+            "Cached metadata of {} problems in {}",
+            enrichment.iter().count(),
+            Enrichment::cache_path().display()
+        );
+        if !failed.is_empty() {
+            return Err(Failure(format!(
+                "the difficulty ratings of {} of {} problems failed to be fetched: {}",
+                failed.len(),
+                unrated.len(),
+                failed.join(", ")
+            ))
+            .into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            endpoints::set_base_url,
+            fixtures::{replay_from, UnmatchedRequest},
+            test_support::{flow, lock_globals, recorded_page, MockServer, Reply},
+            Failure,
+        },
+    };
+
+    /// Serves archives listing problems 1 to 3, where the page of problem 2 can't be fetched,
+    /// returning how prefetching difficulty ratings from it went.
+    fn prefetch_with_a_failure(fail_fast: bool) -> (Result<(), Box<dyn Error>>, MockServer) {
+        let server = MockServer::start();
+        set_base_url(Some(server.url().parse().unwrap()));
+        let rated = |rating| Reply::ok(format!("<p>Difficulty rating: {}%</p>", rating));
+        server
+            .reply(
+                "GET",
+                "/archives",
+                Reply::ok(recorded_page("archives", "/archives").replace("archives;page=2", "")),
+            )
+            .reply("GET", "/problem=1", rated(5))
+            .reply(
+                "GET",
+                "/problem=2",
+                Reply::ok(include_str!("../tests/fixtures/pages/maintenance.html")),
+            )
+            .reply("GET", "/problem=3", rated(10));
+        let context = Context::from_iter(["cargo-euler"]);
+        let prefetched = Prefetch {
+            difficulty: true,
+            fail_fast,
+        }
+        .run(&context);
+        (prefetched, server)
+    }
+
+    fn ratings() -> Vec<(usize, Option<u8>)> {
+        Enrichment::load_cached()
+            .iter()
+            .map(|info| (info.number, info.difficulty))
+            .collect()
+    }
+
+    fn summary(infos: &[ProblemInfo]) -> Vec<(usize, &str, Option<u64>)> {
+        infos
+            .iter()
+            .map(|info| (info.number, &*info.title, info.solved_by))
+            .collect()
+    }
+
+    #[test]
+    fn parses_archives() {
+        let first = recorded_page("archives", "/archives");
+        assert_eq!(
+            summary(&parse_archives(&first).unwrap()),
+            [
+                (1, "Multiples of 3 or 5", Some(1_031_113)),
+                (2, "Even Fibonacci Numbers", Some(823_456)),
+                (3, "Largest Prime Factor", Some(592_270)),
+            ]
+        );
+        assert_eq!(last_page(&first), Some(2));
+        let last = recorded_page("archives", "/archives;page=2");
+        assert_eq!(parse_archives(&last).unwrap().len(), 2);
+        assert_eq!(last_page(&last), Some(1));
+    }
+
+    #[test]
+    fn crawls_every_page_of_the_archives() {
+        let _globals = lock_globals();
+        replay_from(&flow("archives")).unwrap();
+        let context = Context::from_iter(["cargo-euler"]);
+
+        Prefetch {
+            difficulty: false,
+            fail_fast: false,
+        }
+        .run(&context)
+        .unwrap();
+        let enrichment = Enrichment::load_cached();
+        assert_eq!(
+            summary(&enrichment.iter().cloned().collect::<Vec<_>>()),
+            [
+                (1, "Multiples of 3 or 5", Some(1_031_113)),
+                (2, "Even Fibonacci Numbers", Some(823_456)),
+                (3, "Largest Prime Factor", Some(592_270)),
+                (4, "Largest Palindrome Product", Some(527_211)),
+                (5, "Smallest Multiple", Some(533_043)),
+            ]
+        );
+        // Both pages were fetched, and nothing else.
+        assert!(fetch_public_page(&archives_url(2))
+            .unwrap_err()
+            .is::<UnmatchedRequest>());
+    }
+
+    #[test]
+    fn keeps_fetching_difficulty_ratings_after_a_failure() {
+        let _globals = lock_globals();
+        let (prefetched, server) = prefetch_with_a_failure(false);
+
+        let error = prefetched.unwrap_err();
+        assert!(error.is::<Failure>());
+        assert_eq!(
+            error.to_string(),
+            "the difficulty ratings of 1 of 3 problems failed to be fetched: 2"
+        );
+        assert_eq!(ratings(), [(1, Some(5)), (2, None), (3, Some(10))]);
+        assert_eq!(server.received().len(), 4);
+    }
+
+    #[test]
+    fn stops_fetching_difficulty_ratings_at_a_failure_with_fail_fast() {
+        let _globals = lock_globals();
+        let (prefetched, server) = prefetch_with_a_failure(true);
+
+        let error = prefetched.unwrap_err();
+        assert!(error.is::<Failure>());
+        assert!(error.to_string().starts_with(
+            "stopped at the first failure, fetching the difficulty rating of problem 2"
+        ));
+        // What was fetched before the failure is kept.
+        assert_eq!(ratings(), [(1, Some(5)), (2, None), (3, None)]);
+        assert_eq!(server.received().len(), 3);
+    }
+
+    #[test]
+    fn finds_no_archives_without_problem_links() {
+        assert!(
+            parse_archives("<table><tr><td>1</td><td>Not a problem</td></tr></table>").is_none()
+        );
+    }
+
+    #[test]
+    fn parses_difficulty() {
+        let page = r#"<div id="problem_info"><span class="tooltiptext_right">Published on
+            Friday, 5th October 2001; Solved by 1031113;<br>Difficulty rating: 5%</span></div>"#;
+        assert_eq!(parse_difficulty(page), Some(5));
+        assert_eq!(
+            parse_difficulty("<p>Difficulty rating: 100%</p>"),
+            Some(100)
+        );
+        assert_eq!(parse_difficulty("<p>Not yet rated</p>"), None);
+        assert_eq!(parse_difficulty("<p>Difficulty rating: 150%</p>"), None);
+    }
+}
@@ -0,0 +1,817 @@
+use {
+    crate::stats::PROBLEMS_PER_LEVEL,
+    itertools::Itertools,
+    log::warn,
+    once_cell::sync::Lazy,
+    std::{
+        borrow::Cow,
+        fmt::{self, Display, Formatter},
+        num::ParseIntError,
+    },
+    unhtml::{
+        scraper::{ElementRef, Html, Node, Selector},
+        ElemIter, Error as UnhtmlError, FromHtml,
+    },
+};
+
+/// Compiled once, since every parse of a progress page needs them.
+static LEVEL_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("div.info a").unwrap());
+static PROBLEM_SELECTOR: Lazy<Selector> = Lazy::new(|| {
+    Selector::parse(
+        "td.problem_solved,td.problem_unsolved,td.problem_retired,td.problem_unavailable",
+    )
+    .unwrap()
+});
+
+#[derive(Debug)]
+pub struct Level {
+    pub description: String,
+    pub completed: bool,
+}
+
+impl Level {
+    /// How many problems must be solved for the level, from a description like "Solve 25
+    /// problems".
+    pub fn threshold(&self) -> Option<usize> {
+        self.description
+            .split_whitespace()
+            .find_map(|word| word.replace(',', "").parse().ok())
+    }
+}
+
+#[derive(Debug)]
+pub struct Levels(pub Vec<Level>);
+
+impl Levels {
+    /// The highest level reached, where levels must be completed in order. `0` means no level
+    /// has been completed yet.
+    pub fn current(&self) -> usize {
+        self.0.iter().take_while(|level| level.completed).count()
+    }
+}
+
+#[derive(Debug)]
+enum LevelLinkParseError<'a> {
+    SplitFailed(&'a str),
+    ParseFailed(ParseIntError),
+}
+
+impl Display for LevelLinkParseError<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            LevelLinkParseError::SplitFailed(href) => {
+                write!(f, "unable to split link {:?} into a page and number", href)
+            }
+            LevelLinkParseError::ParseFailed(e) => write!(f, "unable to parse number: {}", e),
+        }
+    }
+}
+
+fn parse_from_relative_link<'h>(
+    thing: &str,
+    href: &'h str,
+) -> Result<usize, LevelLinkParseError<'h>> {
+    use self::LevelLinkParseError::*;
+
+    match href.split('=').collect_tuple() {
+        Some((prefix, level)) if prefix == thing => Ok(level.parse().map_err(ParseFailed)?),
+        _ => Err(SplitFailed(href)),
+    }
+}
+
+/// An error for `text` that can't be parsed as `what` because of `problem`, so that a page
+/// that isn't laid out as expected fails to parse rather than panicking.
+fn parse_error(text: impl Into<String>, what: &str, problem: impl Display) -> UnhtmlError {
+    UnhtmlError::TextParseError {
+        text: text.into(),
+        type_name: what.to_owned(),
+        err: problem.to_string(),
+    }
+}
+
+impl FromHtml for Levels {
+    fn from_elements(iter: ElemIter) -> Result<Self, UnhtmlError> {
+        let mut levels = Vec::new();
+
+        for anchor_el in iter.flat_map(|section| section.select(&LEVEL_SELECTOR)) {
+            use self::Node::*;
+
+            let href = match anchor_el.value().attr("href") {
+                Some(href) => href,
+                None => {
+                    warn!("skipping a level without a link");
+                    continue;
+                }
+            };
+            let level = parse_from_relative_link("level", href)
+                .map_err(|e| parse_error(href, "a level link", e))?;
+            let expected_idx = levels.len() + 1;
+            if level != expected_idx {
+                return Err(parse_error(
+                    href,
+                    "a level link",
+                    format!("expected level {}", expected_idx),
+                ));
+            }
+
+            let (resolution_tag, description_span) = match anchor_el
+                .children()
+                .collect_tuple()
+                .map(|(rt, ds)| (rt.value(), ds))
+            {
+                Some((Element(resolution_tag), description_span)) => {
+                    (resolution_tag, description_span)
+                }
+                _ => {
+                    return Err(parse_error(
+                        anchor_el.html(),
+                        "a level",
+                        format!(
+                            "unrecognized format underneath the anchor of level {}",
+                            level
+                        ),
+                    ))
+                }
+            };
+            let description = match description_span
+                .children()
+                .map(|nr| nr.value())
+                .collect_tuple()
+            {
+                Some((Element(title), Text(description))) if &*title.name.local == "div" => {
+                    String::from(&*description.text)
+                }
+                _ => {
+                    return Err(parse_error(
+                        anchor_el.html(),
+                        "a level",
+                        format!("unexpected description format in level {}", level),
+                    ))
+                }
+            };
+            let completed = match &*resolution_tag.name.local {
+                "div" => false,
+                "img" => true,
+                tag => {
+                    return Err(parse_error(
+                        anchor_el.html(),
+                        "a level",
+                        format!("unrecognized completion tag `{}` in level {}", tag, level),
+                    ))
+                }
+            };
+            levels.push(Level {
+                description,
+                completed,
+            });
+        }
+
+        Ok(Levels(levels))
+    }
+}
+
+/// The status of a published problem.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProblemStatus {
+    Solved,
+    Unsolved,
+    /// No longer available, like problems the site has hidden, so neither solvable nor counted.
+    Retired,
+}
+
+impl ProblemStatus {
+    /// The status shown by a class of a problem's cell in the listing, if any.
+    fn from_class(class: &str) -> Option<Self> {
+        match class {
+            "problem_solved" => Some(Self::Solved),
+            "problem_unsolved" => Some(Self::Unsolved),
+            "problem_retired" | "problem_unavailable" => Some(Self::Retired),
+            _ => None,
+        }
+    }
+}
+
+/// The status of every published problem, indexed by problem number minus one, so that retired
+/// problems keep their place and everything after them keeps its number.
+#[derive(Debug)]
+pub struct Problems(pub Vec<ProblemStatus>);
+
+impl Problems {
+    /// The highest problem number published, counting retired problems.
+    pub fn published(&self) -> usize {
+        self.0.len()
+    }
+
+    /// How many published problems can still be solved, which is what's left after retired
+    /// ones.
+    pub fn available(&self) -> usize {
+        self.0
+            .iter()
+            .filter(|status| **status != ProblemStatus::Retired)
+            .count()
+    }
+
+    pub fn status(&self, number: usize) -> Option<ProblemStatus> {
+        number
+            .checked_sub(1)
+            .and_then(|idx| self.0.get(idx))
+            .copied()
+    }
+
+    pub fn is_solved(&self, number: usize) -> bool {
+        self.status(number) == Some(ProblemStatus::Solved)
+    }
+
+    pub fn solved_count(&self) -> usize {
+        self.with_status(ProblemStatus::Solved).count()
+    }
+
+    /// Iterates over the numbers of all problems with `status`, in ascending order.
+    pub fn with_status(&self, status: ProblemStatus) -> impl Iterator<Item = usize> + '_ {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(move |(_, problem)| **problem == status)
+            .map(|(idx, _)| idx + 1)
+    }
+
+    /// Iterates over the numbers of all problems that haven't been solved yet, and can still be,
+    /// in ascending order.
+    pub fn unsolved(&self) -> impl Iterator<Item = usize> + '_ {
+        self.with_status(ProblemStatus::Unsolved)
+    }
+}
+
+impl FromHtml for Problems {
+    fn from_elements(iter: ElemIter) -> Result<Self, UnhtmlError> {
+        use self::Node::*;
+
+        let mut problems = Vec::new();
+
+        for problem_el in iter.flat_map(|section| section.select(&PROBLEM_SELECTOR)) {
+            let mut status = None;
+            for class in problem_el.value().classes.iter() {
+                let class: &str = class;
+                match (ProblemStatus::from_class(class), status) {
+                    (Some(_), Some(_)) => {
+                        return Err(parse_error(
+                            problem_el.html(),
+                            "a problem",
+                            "it has more than one solution status",
+                        ))
+                    }
+                    (Some(class_status), None) => status = Some(class_status),
+                    (None, _) => warn!(
+                        "unable to determine solution status from class \"{}\"",
+                        class
+                    ),
+                }
+            }
+            let status = status.ok_or_else(|| {
+                parse_error(
+                    problem_el.html(),
+                    "a problem",
+                    "unable to find its solution status",
+                )
+            })?;
+            let number = match problem_el.children().map(|nr| nr.value()).collect_tuple() {
+                Some((Element(anchor),)) if &*anchor.name.local == "a" => {
+                    let link = anchor.attr("href").ok_or_else(|| {
+                        parse_error(problem_el.html(), "a problem", "its link has no target")
+                    })?;
+                    parse_from_relative_link("problem", link)
+                        .map_err(|e| parse_error(link, "a problem link", e))?
+                }
+                // Retired problems aren't necessarily linked to anymore.
+                Some((Text(number),)) if status == ProblemStatus::Retired => number
+                    .trim()
+                    .parse()
+                    .map_err(|e| parse_error(&*number.text, "a retired problem number", e))?,
+                _ => {
+                    return Err(parse_error(
+                        problem_el.html(),
+                        "a problem",
+                        "unrecognized set of child elements in the problem listing",
+                    ))
+                }
+            };
+            let expected_number = problems.len() + 1;
+            if number < expected_number {
+                return Err(parse_error(
+                    problem_el.html(),
+                    "a problem",
+                    format!("problem {} is listed out of order", number),
+                ));
+            }
+            // A problem left out of the listing can't be solved anymore, and taking it as retired
+            // keeps the problems after it at their own numbers.
+            for missing in expected_number..number {
+                warn!(
+                    "problem {} isn't listed, so it's taken to have been retired",
+                    missing
+                );
+                problems.push(ProblemStatus::Retired);
+            }
+            problems.push(status);
+        }
+
+        Ok(Problems(problems))
+    }
+}
+
+/// Selectors for the element of the progress page's header naming the user, tried in order.
+static USERNAME_SELECTORS: Lazy<Vec<Selector>> = Lazy::new(|| {
+    ["#profile_name_text", "#profile_name", ".profile_name"]
+        .iter()
+        .map(|selector| Selector::parse(selector).unwrap())
+        .collect()
+});
+
+/// The username of the user whose progress page `page` is, if its header names them.
+pub fn username(page: &str) -> Option<String> {
+    let html = Html::parse_document(page);
+    USERNAME_SELECTORS
+        .iter()
+        .find_map(|selector| html.select(selector).next())
+        .map(|header| header.text().collect::<String>().trim().to_owned())
+        .filter(|username| !username.is_empty())
+}
+
+/// The next level to reach, and how far away it is.
+#[derive(Debug, PartialEq)]
+pub struct NextLevel {
+    pub level: usize,
+    /// How many problems must be solved for the level.
+    pub threshold: usize,
+    pub remaining: usize,
+}
+
+/// How to find a section of the progress page.
+#[derive(Debug)]
+struct SectionSelector {
+    /// The selectors the section is found with, for warnings.
+    description: Cow<'static, str>,
+    /// Matches the section itself, or, with a `container`, something in it.
+    content: Selector,
+    /// Matches the section among the ancestors of the first element matching `content`, for
+    /// when nothing names the section anymore.
+    container: Option<Selector>,
+}
+
+impl SectionSelector {
+    /// The first element matching `selector`.
+    fn css(selector: &'static str) -> Self {
+        Self {
+            description: Cow::Borrowed(selector),
+            content: Selector::parse(selector).unwrap(),
+            container: None,
+        }
+    }
+
+    /// The nearest ancestor matching `container` of the first element matching `content`.
+    fn containing(container: &'static str, content: &'static str) -> Self {
+        Self {
+            description: Cow::Owned(format!("{} containing {}", container, content)),
+            content: Selector::parse(content).unwrap(),
+            container: Some(Selector::parse(container).unwrap()),
+        }
+    }
+
+    fn find<'a>(&self, root: ElementRef<'a>) -> Option<ElementRef<'a>> {
+        let found = root.select(&self.content).next()?;
+        match &self.container {
+            None => Some(found),
+            Some(container) => found
+                .ancestors()
+                .filter_map(ElementRef::wrap)
+                .find(|ancestor| container.matches(ancestor)),
+        }
+    }
+}
+
+impl Display for SectionSelector {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "`{}`", self.description)
+    }
+}
+
+/// Candidates for each section of the progress page, tried in order. The first is what the site
+/// uses now, and the rest are fallbacks in case it renames things, as it has before. Compiled
+/// once, since pages that have drifted try every one on every parse.
+static LEVELS_SECTION_SELECTORS: Lazy<[SectionSelector; 3]> = Lazy::new(|| {
+    [
+        SectionSelector::css("#levels_completed_section"),
+        SectionSelector::css(".levels_completed_section"),
+        SectionSelector::containing("div:not(.info)", "div.info a[href^=\"level=\"]"),
+    ]
+});
+static PROBLEMS_SECTION_SELECTORS: Lazy<[SectionSelector; 3]> = Lazy::new(|| {
+    [
+        SectionSelector::css("#problems_solved_section"),
+        SectionSelector::css(".problems_solved_section"),
+        SectionSelector::containing("table", "td.problem_solved, td.problem_unsolved"),
+    ]
+});
+
+/// Finds the section of the progress page holding `what` with the first of `candidates` to
+/// match, warning if that's a fallback so that drift in the site is noticed before the
+/// fallbacks run out too.
+fn find_section<'a>(
+    root: ElementRef<'a>,
+    what: &str,
+    candidates: &[SectionSelector],
+) -> Option<ElementRef<'a>> {
+    let (idx, section) = candidates
+        .iter()
+        .enumerate()
+        .find_map(|(idx, candidate)| candidate.find(root).map(|section| (idx, section)))?;
+    if idx > 0 {
+        warn!(
+            "unable to find the {} of the progress page with {}, so the fallback {} was used; the \
+             site may have changed",
+            what, candidates[0], candidates[idx]
+        );
+    }
+    Some(section)
+}
+
+#[derive(Debug)]
+pub struct Progress {
+    /// Empty when the page has no levels section, like for accounts that haven't solved anything
+    /// yet.
+    pub levels: Levels,
+    pub problems: Problems,
+}
+
+impl FromHtml for Progress {
+    fn from_elements(iter: ElemIter) -> Result<Self, UnhtmlError> {
+        let root = iter.next();
+        let section = |what, candidates| root.and_then(|root| find_section(root, what, candidates));
+        // Without a listing of problems, the page isn't a progress page at all, like one asking
+        // to sign in.
+        let problems = section("problems", &*PROBLEMS_SECTION_SELECTORS).ok_or_else(|| {
+            parse_error(
+                "the page",
+                "a progress page",
+                "it has no listing of problems; make sure the session is signed in",
+            )
+        })?;
+        Ok(Self {
+            levels: Levels::from_elements(
+                &mut section("levels", &*LEVELS_SECTION_SELECTORS).into_iter(),
+            )?,
+            problems: Problems::from_elements(&mut Some(problems).into_iter())?,
+        })
+    }
+}
+
+impl Progress {
+    /// How many problems must be solved for `level`, by the thresholds in the level descriptions
+    /// where possible, falling back to a level every `PROBLEMS_PER_LEVEL` problems.
+    pub fn level_threshold(&self, level: usize) -> usize {
+        self.levels
+            .0
+            .get(level - 1)
+            .and_then(|level| level.threshold())
+            .unwrap_or(level * PROBLEMS_PER_LEVEL)
+    }
+
+    /// The next level to reach, or `None` if the last published one has been. Thresholds are
+    /// parsed from the level descriptions where possible, falling back to a level every
+    /// `PROBLEMS_PER_LEVEL` problems. A level whose threshold is already met, but that the site
+    /// doesn't show completed yet, is skipped.
+    pub fn next_level_info(&self) -> Option<NextLevel> {
+        let solved = self.problems.solved_count();
+        let (level, threshold) = if self.levels.0.is_empty() {
+            let level = solved / PROBLEMS_PER_LEVEL + 1;
+            (level, level * PROBLEMS_PER_LEVEL)
+        } else {
+            self.levels
+                .0
+                .iter()
+                .enumerate()
+                .skip(self.levels.current())
+                .map(|(idx, level)| {
+                    let number = idx + 1;
+                    (
+                        number,
+                        level.threshold().unwrap_or(number * PROBLEMS_PER_LEVEL),
+                    )
+                })
+                .find(|(_, threshold)| *threshold > solved)?
+        };
+        Some(NextLevel {
+            level,
+            threshold,
+            remaining: threshold - solved,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::test_support::{count_allocations, gap_progress, problems},
+    };
+
+    fn progress(solved: usize, levels: &[(&str, bool)]) -> Progress {
+        Progress {
+            levels: Levels(
+                levels
+                    .iter()
+                    .map(|&(description, completed)| Level {
+                        description: description.to_owned(),
+                        completed,
+                    })
+                    .collect(),
+            ),
+            problems: problems(&format!(
+                "{}{}",
+                "S".repeat(solved),
+                "U".repeat(80 - solved)
+            )),
+        }
+    }
+
+    /// A progress page with `levels` and `problems` as the contents of their sections.
+    fn page(levels: &str, problems: &str) -> String {
+        format!(
+            r#"<div id="levels_completed_section">{}</div>
+            <div id="problems_solved_section"><table><tr>{}</tr></table></div>"#,
+            levels, problems
+        )
+    }
+
+    fn level(number: usize, completed: bool) -> String {
+        format!(
+            r#"<div class="info"><a href="level={0}">{1}<span><div>Level {0}</div>Solve {2} problems</span></a></div>"#,
+            number,
+            if completed { "<img>" } else { "<div></div>" },
+            number * 25
+        )
+    }
+
+    #[test]
+    fn parses_progress_pages() {
+        let progress =
+            Progress::from_html(include_str!("../tests/fixtures/pages/progress.html")).unwrap();
+        assert_eq!(progress.problems.published(), 30);
+        assert_eq!(progress.problems.solved_count(), 26);
+        assert_eq!(progress.problems.available(), 29);
+        assert_eq!(progress.problems.status(28), Some(ProblemStatus::Retired));
+        assert_eq!(progress.levels.current(), 1);
+        assert_eq!(progress.levels.0[1].description, "Solve 50 problems");
+    }
+
+    #[test]
+    fn takes_problems_missing_from_the_listing_as_retired() {
+        let progress = gap_progress();
+        assert_eq!(progress.problems.published(), 30);
+        assert_eq!(progress.problems.status(11), Some(ProblemStatus::Solved));
+        assert_eq!(progress.problems.status(12), Some(ProblemStatus::Retired));
+        assert_eq!(progress.problems.status(13), Some(ProblemStatus::Solved));
+        assert_eq!(progress.problems.status(28), Some(ProblemStatus::Retired));
+        assert_eq!(progress.problems.solved_count(), 25);
+        assert_eq!(progress.problems.available(), 28);
+    }
+
+    #[test]
+    fn finds_sections_with_fallbacks() {
+        let levels = level(1, true) + &level(2, false);
+        let problems = r#"<td class="problem_solved"><a href="problem=1">1</a></td>
+            <td class="problem_unsolved"><a href="problem=2">2</a></td>"#;
+        for page in [
+            format!(
+                r#"<div class="levels_completed_section">{}</div>
+                <div class="problems_solved_section"><table><tr>{}</tr></table></div>"#,
+                levels, problems
+            ),
+            format!(
+                r#"<div>{}</div><div><table><tr>{}</tr></table></div>"#,
+                levels, problems
+            ),
+        ] {
+            let progress = Progress::from_html(&page).unwrap();
+            assert_eq!(progress.levels.0.len(), 2, "{}", page);
+            assert_eq!(progress.levels.current(), 1, "{}", page);
+            assert_eq!(progress.problems.published(), 2, "{}", page);
+            assert_eq!(progress.problems.solved_count(), 1, "{}", page);
+        }
+    }
+
+    #[test]
+    fn finds_sections_of_progress_pages_that_have_drifted() {
+        for page in [
+            include_str!("../tests/fixtures/pages/progress-renamed-sections.html"),
+            include_str!("../tests/fixtures/pages/progress-unnamed-sections.html"),
+        ] {
+            let progress = Progress::from_html(page).unwrap();
+            assert_eq!(progress.problems.published(), 30);
+            assert_eq!(progress.problems.solved_count(), 26);
+            assert_eq!(progress.problems.status(28), Some(ProblemStatus::Retired));
+            assert_eq!(progress.levels.0.len(), 3);
+            assert_eq!(progress.levels.current(), 1);
+        }
+    }
+
+    #[test]
+    fn describes_section_selectors() {
+        assert_eq!(
+            PROBLEMS_SECTION_SELECTORS[0].to_string(),
+            "`#problems_solved_section`"
+        );
+        assert_eq!(
+            PROBLEMS_SECTION_SELECTORS[2].to_string(),
+            "`table containing td.problem_solved, td.problem_unsolved`"
+        );
+    }
+
+    /// A progress page with more levels and problems than the site has yet.
+    fn large_page() -> String {
+        let levels = (1..=40)
+            .map(|number| level(number, number <= 20))
+            .collect::<String>();
+        let problems = (1..=1000)
+            .map(|number| {
+                format!(
+                    r#"<td class="problem_{}"><a href="problem={1}">{1}</a></td>"#,
+                    if number % 2 == 0 {
+                        "solved"
+                    } else {
+                        "unsolved"
+                    },
+                    number
+                )
+            })
+            .collect::<String>();
+        page(&levels, &problems)
+    }
+
+    /// How many allocations parsing `page` takes, first to build its tree of elements, then to
+    /// find the progress in it.
+    fn allocations_parsing(page: &str) -> (usize, usize) {
+        // Compiling the selectors is left out, since it's done once rather than per parse.
+        let html = Html::parse_document(page);
+        Progress::from_elements(&mut Some(html.root_element()).into_iter()).unwrap();
+        let (html, building) = count_allocations(|| Html::parse_document(page));
+        let (progress, finding) = count_allocations(|| {
+            Progress::from_elements(&mut Some(html.root_element()).into_iter())
+        });
+        progress.unwrap();
+        (building, finding)
+    }
+
+    #[test]
+    fn allocates_little_beyond_the_tree_of_the_page() {
+        for (page, levels) in [
+            (
+                include_str!("../tests/fixtures/pages/progress.html").to_owned(),
+                3,
+            ),
+            (large_page(), 40),
+        ] {
+            let (building, finding) = allocations_parsing(&page);
+            println!(
+                "{} levels: {} allocations building the tree, {} finding the progress",
+                levels, building, finding
+            );
+            // A description per level, and the growth of the lists of levels and problems, but
+            // nothing per problem.
+            assert!(finding <= levels + 16, "{} allocations", finding);
+        }
+    }
+
+    /// Times parsing a page with more problems than the site has yet. Criterion isn't available
+    /// to this build, so this stands in for a benchmark; run it with `cargo test --release --
+    /// --ignored --nocapture times_parsing`.
+    #[test]
+    #[ignore]
+    fn times_parsing_a_large_progress_page() {
+        const RUNS: u32 = 50;
+
+        let page = large_page();
+        let start = std::time::Instant::now();
+        for _ in 0..RUNS {
+            let progress = Progress::from_html(&page).unwrap();
+            assert_eq!(progress.problems.published(), 1000);
+            assert_eq!(progress.levels.current(), 20);
+        }
+        println!("{:?} per parse", start.elapsed() / RUNS);
+    }
+
+    #[test]
+    fn fails_to_parse_malformed_levels() {
+        let problems = r#"<td class="problem_solved"><a href="problem=1">1</a></td>"#;
+        for levels in [
+            r#"<div class="info"><a href="level=one"><img><span><div>L</div>Solve</span></a></div>"#
+                .to_owned(),
+            level(2, true),
+            level(1, true).replace("<img>", "<p></p>"),
+            level(1, true).replace("<div>Level 1</div>", ""),
+            level(1, true).replace("<img>", ""),
+        ] {
+            let error = Progress::from_html(&page(&levels, problems)).unwrap_err();
+            assert!(
+                matches!(error, UnhtmlError::TextParseError { .. }),
+                "{:?}",
+                error
+            );
+        }
+        assert_eq!(
+            Progress::from_html(&page(&(level(1, true) + &level(2, false)), problems))
+                .unwrap()
+                .levels
+                .current(),
+            1
+        );
+    }
+
+    #[test]
+    fn fails_to_parse_malformed_problems() {
+        for problems in [
+            r#"<td class="problem_solved problem_unsolved"><a href="problem=1">1</a></td>"#,
+            r#"<td class="problem_solved"><a>1</a></td>"#,
+            r#"<td class="problem_solved"><a href="problem=x">1</a></td>"#,
+            r#"<td class="problem_retired">one</td>"#,
+            r#"<td class="problem_solved">1</td>"#,
+            r#"<td class="problem_solved"><a href="problem=2">2</a></td><td class="problem_solved"><a href="problem=1">1</a></td>"#,
+        ] {
+            let error = Progress::from_html(&page("", problems)).unwrap_err();
+            assert!(
+                matches!(error, UnhtmlError::TextParseError { .. }),
+                "{:?}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn fails_to_parse_pages_without_problems() {
+        let error = Progress::from_html("<p>Please sign in</p>").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("make sure the session is signed in"));
+        for page in [
+            include_str!("../tests/fixtures/pages/challenge.html"),
+            include_str!("../tests/fixtures/pages/maintenance.html"),
+        ] {
+            assert!(Progress::from_html(page).is_err());
+        }
+    }
+
+    fn next_level(level: usize, threshold: usize, remaining: usize) -> Option<NextLevel> {
+        Some(NextLevel {
+            level,
+            threshold,
+            remaining,
+        })
+    }
+
+    #[test]
+    fn counts_down_to_the_next_level() {
+        let levels = [("Solve 25 problems", true), ("Solve 50 problems", false)];
+        assert_eq!(
+            progress(26, &levels).next_level_info(),
+            next_level(2, 50, 24)
+        );
+        // Exactly on a boundary, the level reached is done with.
+        assert_eq!(
+            progress(25, &levels).next_level_info(),
+            next_level(2, 50, 25)
+        );
+    }
+
+    #[test]
+    fn skips_levels_reached_but_not_shown_completed_yet() {
+        let levels = [
+            ("Solve 25 problems", true),
+            ("Solve 50 problems", false),
+            ("Solve 75 problems", false),
+        ];
+        assert_eq!(
+            progress(50, &levels).next_level_info(),
+            next_level(3, 75, 25)
+        );
+    }
+
+    #[test]
+    fn falls_back_to_a_level_every_25_problems() {
+        assert_eq!(progress(0, &[]).next_level_info(), next_level(1, 25, 25));
+        assert_eq!(progress(25, &[]).next_level_info(), next_level(2, 50, 25));
+        let levels = [
+            ("Solve some problems", true),
+            ("Solve more problems", false),
+        ];
+        assert_eq!(
+            progress(30, &levels).next_level_info(),
+            next_level(2, 50, 20)
+        );
+    }
+
+    #[test]
+    fn has_no_next_level_after_the_last_published() {
+        let levels = [("Solve 25 problems", true), ("Solve 50 problems", true)];
+        assert_eq!(progress(60, &levels).next_level_info(), None);
+    }
+}
@@ -0,0 +1,112 @@
+use {
+    crate::{context::Context, endpoints::problem_url, enrichment::Enrichment},
+    rand::{rngs::StdRng, seq::SliceRandom, SeedableRng},
+    std::{error::Error, str::FromStr},
+    structopt::StructOpt,
+};
+
+/// An inclusive range of problem numbers, written as `<start>-<end>`.
+#[derive(Clone, Copy, Debug)]
+pub struct ProblemRange {
+    start: usize,
+    end: usize,
+}
+
+impl ProblemRange {
+    fn contains(&self, number: usize) -> bool {
+        self.start <= number && number <= self.end
+    }
+}
+
+impl FromStr for ProblemRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = match s.find('-') {
+            Some(idx) => (&s[..idx], &s[idx + 1..]),
+            None => return Err(format!("expected a range like `1-100`, got {:?}", s)),
+        };
+        let parse = |n: &str| {
+            n.trim()
+                .parse()
+                .map_err(|e| format!("invalid problem number {:?}: {}", n, e))
+        };
+        let (start, end) = (parse(start)?, parse(end)?);
+        if start > end {
+            return Err(format!("range start {} is after its end {}", start, end));
+        }
+        Ok(Self { start, end })
+    }
+}
+
+/// Pick a random unsolved problem.
+#[derive(Debug, StructOpt)]
+pub struct Random {
+    /// Only consider problems with a cached difficulty rating of at most this percentage.
+    #[structopt(long)]
+    max_difficulty: Option<u8>,
+    /// Only consider problems in this inclusive range of numbers, e.g., `1-100`.
+    #[structopt(long)]
+    range: Option<ProblemRange>,
+    /// Only consider problems numbered below this one.
+    #[structopt(long)]
+    below_number: Option<usize>,
+    /// Seed the random number generator for a reproducible pick.
+    #[structopt(long)]
+    seed: Option<u64>,
+    /// Open the picked problem in a web browser.
+    #[structopt(long)]
+    open: bool,
+}
+
+impl Random {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self {
+            max_difficulty,
+            range,
+            below_number,
+            seed,
+            open,
+        } = self;
+
+        let progress = context.progress()?;
+        let enrichment = Enrichment::load_cached();
+
+        let candidates = progress
+            .problems
+            .unsolved()
+            .filter(|&number| range.is_none_or(|r| r.contains(number)))
+            .filter(|&number| below_number.is_none_or(|below| number < below))
+            .filter(|&number| {
+                max_difficulty.is_none_or(|max| {
+                    enrichment
+                        .get(number)
+                        .and_then(|info| info.difficulty)
+                        .is_some_and(|difficulty| difficulty <= max)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let number = match candidates.choose(&mut rng) {
+            Some(&number) => number,
+            None => return Err("no unsolved problems match the given filters".into()),
+        };
+
+        match enrichment.get(number) {
+            Some(info) => match info.difficulty {
+                Some(difficulty) => println!("{}: {} ({}%)", number, info.title, difficulty),
+                None => println!("{}: {}", number, info.title),
+            },
+            None => println!("{}", number),
+        }
+
+        if open {
+            webbrowser::open(&problem_url(number))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,555 @@
+//! The rankings of members by country and by programming language.
+
+use {
+    crate::{
+        client::Session,
+        context::Context,
+        endpoints::{page_url, ranking_url},
+        friends::{cell_text, parse_count},
+        output::bold,
+    },
+    log::warn,
+    std::error::Error,
+    structopt::StructOpt,
+    unhtml::scraper::{Html, Selector},
+};
+
+/// Which kind of ranking to look at.
+#[derive(Clone, Copy, Debug)]
+enum RankingKind {
+    Country,
+    Language,
+}
+
+impl RankingKind {
+    /// Where rankings of this kind are, like `location=United_Kingdom`.
+    fn endpoint(self) -> &'static str {
+        match self {
+            Self::Country => "location",
+            Self::Language => "language",
+        }
+    }
+
+    /// Where rankings of this kind are listed.
+    fn index_endpoint(self) -> &'static str {
+        match self {
+            Self::Country => "locations",
+            Self::Language => "languages",
+        }
+    }
+
+    fn noun(self) -> &'static str {
+        match self {
+            Self::Country => "country",
+            Self::Language => "language",
+        }
+    }
+}
+
+/// A row of a ranking.
+#[derive(Debug)]
+pub struct Ranked {
+    pub place: usize,
+    pub username: String,
+    pub solved: usize,
+    pub level: Option<usize>,
+}
+
+impl Ranked {
+    /// Whether the row is that of the member named `username`.
+    fn is(&self, username: &str) -> bool {
+        self.username.eq_ignore_ascii_case(username)
+    }
+}
+
+/// Parses the ranked table of a page of a ranking, or returns `None` if it has none. Columns are
+/// found by their headings; without a place column, rows are numbered from `first_place`.
+pub fn parse_ranking(page: &str, first_place: usize) -> Option<Vec<Ranked>> {
+    let html = Html::parse_document(page);
+    let row_selector = Selector::parse("tr").unwrap();
+    let heading_selector = Selector::parse("th").unwrap();
+    let cell_selector = Selector::parse("td").unwrap();
+
+    for table in html.select(&Selector::parse("table").unwrap()) {
+        let headings = table
+            .select(&heading_selector)
+            .map(|heading| cell_text(heading).to_lowercase())
+            .collect::<Vec<_>>();
+        let column = |names: &[&str]| {
+            headings
+                .iter()
+                .position(|heading| names.iter().any(|name| heading.contains(name)))
+        };
+        let (username_column, solved_column) =
+            match (column(&["username", "alias", "name"]), column(&["solved"])) {
+                (Some(username), Some(solved)) => (username, solved),
+                _ => continue,
+            };
+        let place_column = headings.iter().position(|heading| {
+            heading == "#" || heading.contains("place") || heading.contains("rank")
+        });
+        let level_column = column(&["level"]);
+
+        let mut ranking = Vec::new();
+        for row in table.select(&row_selector) {
+            let cells = row.select(&cell_selector).collect::<Vec<_>>();
+            let count = |column: Option<usize>| {
+                column
+                    .and_then(|column| cells.get(column))
+                    .and_then(|cell| parse_count(&cell_text(*cell)))
+            };
+            let (username, solved) = match (cells.get(username_column), count(Some(solved_column)))
+            {
+                (Some(username), Some(solved)) => (cell_text(*username), solved),
+                _ => continue,
+            };
+            ranking.push(Ranked {
+                place: count(place_column).unwrap_or(first_place + ranking.len()),
+                username,
+                solved,
+                level: count(level_column),
+            });
+        }
+        return Some(ranking);
+    }
+    None
+}
+
+/// The number of the last page of a paginated listing, like a ranking, from the links of its
+/// pagination.
+pub fn last_page(page: &str) -> Option<usize> {
+    Html::parse_document(page)
+        .select(&Selector::parse("a[href]").unwrap())
+        .filter_map(|link| {
+            let href = link.value().attr("href")?;
+            parse_count(&href[href.find("page=")? + "page=".len()..])
+        })
+        .max()
+}
+
+/// The rankings listed on an index page, as their names and the names their URLs use.
+fn parse_index(page: &str, kind: RankingKind) -> Vec<(String, String)> {
+    let prefix = format!("{}=", kind.endpoint());
+    Html::parse_document(page)
+        .select(&Selector::parse("a[href]").unwrap())
+        .filter_map(|link| {
+            let href = link.value().attr("href")?;
+            let slug = href.trim_start_matches('/').strip_prefix(&prefix)?;
+            let slug = slug.split(';').next().unwrap();
+            Some((cell_text(link), slug.to_owned()))
+        })
+        .filter(|(name, _)| !name.is_empty())
+        .collect()
+}
+
+/// The Levenshtein distance between `a` and `b`, ignoring case.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b) = (
+        a.to_lowercase().chars().collect::<Vec<_>>(),
+        b.to_lowercase().chars().collect::<Vec<_>>(),
+    );
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a_char) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// The names of `index` close to `name`, closest first.
+fn close_matches<'a>(name: &str, index: &'a [(String, String)]) -> Vec<&'a str> {
+    let lowercase = name.to_lowercase();
+    let mut matches = index
+        .iter()
+        .map(|(candidate, _)| (edit_distance(name, candidate), candidate.as_str()))
+        .filter(|(distance, candidate)| {
+            *distance <= (name.chars().count() / 3).max(2)
+                || (!lowercase.is_empty() && candidate.to_lowercase().contains(&lowercase))
+        })
+        .collect::<Vec<_>>();
+    matches.sort();
+    matches
+        .into_iter()
+        .map(|(_, candidate)| candidate)
+        .take(5)
+        .collect()
+}
+
+/// Finds the name the URL of the ranking `name` uses from the index of rankings of `kind`,
+/// falling back to `name` itself if the index can't be read.
+fn resolve(session: &mut Session, kind: RankingKind, name: &str) -> Result<String, Box<dyn Error>> {
+    let index = match session.fetch_page(&page_url(kind.index_endpoint())) {
+        Ok(page) => parse_index(&page, kind),
+        Err(e) => {
+            warn!(
+                "unable to fetch the list of {} rankings: {}",
+                kind.noun(),
+                e
+            );
+            Vec::new()
+        }
+    };
+    if index.is_empty() {
+        return Ok(name.to_owned());
+    }
+    let normalize = |name: &str| name.replace('_', " ").to_lowercase();
+    if let Some((_, slug)) = index.iter().find(|(candidate, slug)| {
+        normalize(candidate) == normalize(name) || normalize(slug) == normalize(name)
+    }) {
+        return Ok(slug.clone());
+    }
+    let mut message = format!("there's no ranking for the {} {:?}", kind.noun(), name);
+    let matches = close_matches(name, &index);
+    if !matches.is_empty() {
+        message.push_str("; did you mean ");
+        message.push_str(&matches.join(", "));
+        message.push('?');
+    }
+    Err(message.into())
+}
+
+/// Show the ranking of a country or programming language.
+#[derive(Debug, StructOpt)]
+pub struct Rankings {
+    /// Show the ranking of this country.
+    #[structopt(long, required_unless = "language", conflicts_with = "language")]
+    country: Option<String>,
+    /// Show the ranking of this programming language.
+    #[structopt(long)]
+    language: Option<String>,
+    /// How many of the top ranked members to show.
+    #[structopt(long, default_value = "25")]
+    top: usize,
+    /// Your username, to find your place by. Read from your progress page if omitted.
+    #[structopt(long)]
+    me: Option<String>,
+    /// The most pages of the ranking to look through for your place. Pages needed to show
+    /// `--top` are always fetched.
+    #[structopt(long, default_value = "10")]
+    max_pages: usize,
+}
+
+/// What's been fetched of a ranking.
+#[derive(Debug)]
+struct Fetched {
+    ranking: Vec<Ranked>,
+    pages: usize,
+    /// Whether every page of the ranking was fetched.
+    complete: bool,
+}
+
+/// Fetches pages of the ranking of `kind` named `slug` until its first `top` places and the
+/// user's, named `me`, are known, or the ranking ends. Without finding the user, stops after
+/// `max_pages`, unless more are needed for the top.
+fn fetch_ranking(
+    session: &mut Session,
+    kind: RankingKind,
+    slug: &str,
+    top: usize,
+    max_pages: usize,
+    me: Option<&str>,
+) -> Result<Fetched, Box<dyn Error>> {
+    let mut ranking = Vec::new();
+    let mut page_number = 1;
+    loop {
+        let page = session.fetch_page(&ranking_url(kind.endpoint(), slug, page_number))?;
+        let rows = match parse_ranking(&page, ranking.len() + 1) {
+            Some(rows) => rows,
+            None if page_number == 1 => {
+                return Err(format!(
+                    "unable to find the ranking of the {} {:?}",
+                    kind.noun(),
+                    slug.replace('_', " ")
+                )
+                .into())
+            }
+            None => {
+                return Ok(Fetched {
+                    ranking,
+                    pages: page_number - 1,
+                    complete: true,
+                })
+            }
+        };
+        let empty = rows.is_empty();
+        ranking.extend(rows);
+        if empty || page_number >= last_page(&page).unwrap_or(page_number) {
+            return Ok(Fetched {
+                ranking,
+                pages: page_number,
+                complete: true,
+            });
+        }
+        let found_me = me.is_none_or(|me| ranking.iter().any(|ranked| ranked.is(me)));
+        if ranking.len() >= top && (found_me || page_number >= max_pages) {
+            return Ok(Fetched {
+                ranking,
+                pages: page_number,
+                complete: false,
+            });
+        }
+        page_number += 1;
+    }
+}
+
+impl Rankings {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self {
+            country,
+            language,
+            top,
+            me,
+            max_pages,
+        } = self;
+
+        let (kind, name) = match (country, language) {
+            (Some(country), _) => (RankingKind::Country, country),
+            (None, Some(language)) => (RankingKind::Language, language),
+            (None, None) => unreachable!(),
+        };
+        let me = me.or_else(|| context.username());
+        let is_me = |ranked: &Ranked| me.as_ref().is_some_and(|me| ranked.is(me));
+
+        let mut session = context.session()?;
+        let slug = resolve(&mut session, kind, &name)?;
+        let Fetched {
+            ranking,
+            pages,
+            complete,
+        } = fetch_ranking(&mut session, kind, &slug, top, max_pages, me.as_deref())?;
+
+        println!("Ranking of {}:", name);
+        let width = ranking
+            .iter()
+            .take(top)
+            .map(|ranked| ranked.username.chars().count())
+            .max()
+            .unwrap_or(0);
+        for ranked in ranking.iter().take(top) {
+            let line = format!(
+                "{} {:>5}. {:<width$}  {:>4} solved{}",
+                if is_me(ranked) { ">" } else { " " },
+                ranked.place,
+                ranked.username,
+                ranked.solved,
+                ranked
+                    .level
+                    .map_or_else(String::new, |level| format!("  level {}", level)),
+                width = width,
+            );
+            if is_me(ranked) {
+                println!("{}", bold(&line));
+            } else {
+                println!("{}", line);
+            }
+        }
+        if let Some(me) = &me {
+            println!();
+            match ranking.iter().find(|ranked| is_me(ranked)) {
+                Some(ranked) => println!(
+                    "You're in place {}, with {} solved",
+                    ranked.place, ranked.solved
+                ),
+                None if complete => println!("{} isn't in this ranking", me),
+                None => println!(
+                    "{} isn't in the first {} pages of this ranking; look through more with \
+                     `--max-pages`",
+                    me, pages
+                ),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            endpoints::set_base_url,
+            test_support::{lock_globals, MockServer, Reply},
+        },
+    };
+
+    const RANKING: &str = include_str!("../tests/fixtures/pages/ranking.html");
+
+    fn summary(ranking: &[Ranked]) -> Vec<(usize, &str, usize, Option<usize>)> {
+        ranking
+            .iter()
+            .map(|ranked| (ranked.place, &*ranked.username, ranked.solved, ranked.level))
+            .collect()
+    }
+
+    #[test]
+    fn parses_rankings() {
+        assert_eq!(
+            summary(&parse_ranking(RANKING, 1).unwrap()),
+            [
+                (1, "alice", 1204, Some(48)),
+                (2, "Bjørn", 875, Some(35)),
+                (3, "kari_n", 3, None),
+            ]
+        );
+        assert_eq!(last_page(RANKING), Some(3));
+        assert!(parse_ranking("<p>No such country</p>", 1).is_none());
+    }
+
+    #[test]
+    fn numbers_rankings_without_places_from_the_first_place() {
+        let page = r#"<table><tr><th>Username</th><th>Solved</th></tr>
+            <tr><td>alice</td><td>12</td></tr><tr><td>bob</td><td>7</td></tr></table>"#;
+        assert_eq!(
+            summary(&parse_ranking(page, 51).unwrap()),
+            [(51, "alice", 12, None), (52, "bob", 7, None)]
+        );
+        assert_eq!(last_page(page), None);
+    }
+
+    #[test]
+    fn parses_indexes_of_rankings() {
+        let page = r#"<a href="location=Norway">Norway</a>
+            <a href="/location=United_Kingdom;page=2">United Kingdom</a>
+            <a href="language=Rust">Rust</a><a href="location=Peru"></a>"#;
+        assert_eq!(
+            parse_index(page, RankingKind::Country),
+            [
+                ("Norway".to_owned(), "Norway".to_owned()),
+                ("United Kingdom".to_owned(), "United_Kingdom".to_owned()),
+            ]
+        );
+        assert_eq!(
+            parse_index(page, RankingKind::Language),
+            [("Rust".to_owned(), "Rust".to_owned())]
+        );
+    }
+
+    #[test]
+    fn measures_edit_distances() {
+        for (a, b, distance) in [
+            ("", "", 0),
+            ("norway", "Norway", 0),
+            ("", "Peru", 4),
+            ("Norwy", "Norway", 1),
+            ("Sweden", "Swedne", 2),
+            ("kitten", "sitting", 3),
+            ("Bjørn", "Bjorn", 1),
+        ] {
+            assert_eq!(edit_distance(a, b), distance, "{:?} to {:?}", a, b);
+            assert_eq!(edit_distance(b, a), distance, "{:?} to {:?}", b, a);
+        }
+    }
+
+    #[test]
+    fn suggests_close_matches() {
+        let index = [
+            "Norway",
+            "United Kingdom",
+            "United States",
+            "Sweden",
+            "Switzerland",
+            "Peru",
+        ]
+        .iter()
+        .map(|name| (name.to_string(), name.replace(' ', "_")))
+        .collect::<Vec<_>>();
+        for (name, matches) in [
+            ("Norwy", &["Norway"][..]),
+            ("sweeden", &["Sweden"]),
+            ("united", &["United States", "United Kingdom"]),
+            ("Pern", &["Peru"]),
+            ("Atlantis", &[]),
+            // Every name contains an empty one, but that doesn't make them close.
+            ("", &[]),
+        ] {
+            assert_eq!(close_matches(name, &index), matches, "{:?}", name);
+        }
+    }
+
+    /// Serves a ranking of Norway over three pages of two members each, named `member1` to
+    /// `member6`.
+    fn serve_ranking() -> MockServer {
+        let server = MockServer::start();
+        set_base_url(Some(server.url().parse().unwrap()));
+        for page in 1..=3 {
+            let rows = (1..=2)
+                .map(|row| {
+                    let place = (page - 1) * 2 + row;
+                    format!(
+                        "<tr><td>{0}</td><td>member{0}</td><td>{1}</td></tr>",
+                        place,
+                        100 - place
+                    )
+                })
+                .collect::<String>();
+            let path = match page {
+                1 => "/location=Norway".to_owned(),
+                page => format!("/location=Norway;page={}", page),
+            };
+            server.reply(
+                "GET",
+                &path,
+                Reply::ok(format!(
+                    r#"<table><tr><th>#</th><th>Username</th><th>Solved</th></tr>{}</table>
+                    <a href="location=Norway;page=3">Last</a>"#,
+                    rows
+                )),
+            );
+        }
+        server
+    }
+
+    #[test]
+    fn fetches_rankings_until_the_top_and_the_users_place_are_known() {
+        let _globals = lock_globals();
+        let server = serve_ranking();
+        let mut session = Session::new(Some("s3ss10n")).unwrap();
+        let fetch = |session: &mut Session, top, max_pages, me| {
+            let fetched =
+                fetch_ranking(session, RankingKind::Country, "Norway", top, max_pages, me).unwrap();
+            (fetched.ranking.len(), fetched.pages, fetched.complete)
+        };
+
+        assert_eq!(fetch(&mut session, 2, 10, None), (2, 1, false));
+        assert_eq!(fetch(&mut session, 3, 10, None), (4, 2, false));
+        assert_eq!(fetch(&mut session, 2, 10, Some("MEMBER2")), (2, 1, false));
+        assert_eq!(fetch(&mut session, 2, 10, Some("member5")), (6, 3, true));
+        // The last page is as far as it goes, however many more are allowed.
+        assert_eq!(fetch(&mut session, 2, 10, Some("nobody")), (6, 3, true));
+        assert_eq!(server.received().len(), 1 + 2 + 1 + 3 + 3);
+    }
+
+    #[test]
+    fn stops_looking_for_the_user_after_the_most_pages() {
+        let _globals = lock_globals();
+        let server = serve_ranking();
+        let mut session = Session::new(Some("s3ss10n")).unwrap();
+
+        let fetched = fetch_ranking(
+            &mut session,
+            RankingKind::Country,
+            "Norway",
+            2,
+            2,
+            Some("nobody"),
+        )
+        .unwrap();
+        assert_eq!((fetched.pages, fetched.complete), (2, false));
+        // The pages needed for the top are fetched regardless.
+        let fetched = fetch_ranking(
+            &mut session,
+            RankingKind::Country,
+            "Norway",
+            5,
+            1,
+            Some("nobody"),
+        )
+        .unwrap();
+        assert_eq!((fetched.pages, fetched.complete), (3, true));
+        assert_eq!(server.received().len(), 2 + 3);
+    }
+}
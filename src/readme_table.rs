@@ -0,0 +1,312 @@
+use {
+    crate::{
+        bench::read_history, context::Context, endpoints::problem_url, enrichment::Enrichment,
+        history::first_seen_solved, new::escape_inline_markdown, paths::write_atomically,
+        progress::Problems, statement::Statement, workspace::Workspace,
+    },
+    std::{
+        collections::BTreeMap,
+        error::Error,
+        fs::read_to_string,
+        path::{Path, PathBuf},
+        time::Duration,
+    },
+    structopt::StructOpt,
+};
+
+/// Print a Markdown table of local solutions, for a README.
+#[derive(Debug, StructOpt)]
+pub struct ReadmeTable {
+    /// Instead of printing the table, replace everything between the first two occurrences of
+    /// the marker in this file with it.
+    #[structopt(long, parse(from_os_str))]
+    write: Option<PathBuf>,
+    /// The comment marking where the table goes in the file given to `--write`.
+    #[structopt(long, default_value = "<!-- euler-table -->")]
+    marker: String,
+    /// Include the median time of the most recent benchmark of each solution.
+    #[structopt(long)]
+    bench: bool,
+    /// Include the date each problem was first seen solved, as recorded in the history.
+    #[structopt(long)]
+    solved_on: bool,
+}
+
+/// Renders `rows` as a Markdown table with every column padded to the same width, so the table
+/// only changes where its contents do.
+pub fn render_table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths = header
+        .iter()
+        .map(|cell| cell.chars().count())
+        .collect::<Vec<_>>();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let render_row = |cells: &mut dyn Iterator<Item = &str>| {
+        let mut line = String::from("|");
+        for (cell, width) in cells.zip(&widths) {
+            line.push(' ');
+            line.push_str(cell);
+            line.extend(std::iter::repeat_n(' ', width - cell.chars().count()));
+            line.push_str(" |");
+        }
+        line.push('\n');
+        line
+    };
+    let mut table = render_row(&mut header.iter().copied());
+    table.push('|');
+    for width in &widths {
+        table.push_str(&"-".repeat(width + 2));
+        table.push('|');
+    }
+    table.push('\n');
+    for row in rows {
+        table.push_str(&render_row(&mut row.iter().map(|cell| &**cell)));
+    }
+    table
+}
+
+/// Replaces what's between the first two occurrences of `marker` in `document` with `table`.
+fn splice(document: &str, marker: &str, table: &str) -> Option<String> {
+    let start = document.find(marker)? + marker.len();
+    let end = start + document[start..].find(marker)?;
+    Some(format!(
+        "{}\n{}{}",
+        &document[..start],
+        table,
+        &document[end..]
+    ))
+}
+
+/// Renders `path` relative to `base`, with forward slashes as Markdown links expect.
+fn link_path(path: &Path, base: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .iter()
+        .map(|component| component.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// The rows of the table for the `local` solutions, in order of problem number: each problem
+/// linked to, its title, its first solution linked to relative to `link_base`, and whether it's
+/// solved, by the status of the problem of that number in `problems`.
+fn rows(
+    local: &BTreeMap<usize, Vec<PathBuf>>,
+    problems: &Problems,
+    link_base: &Path,
+    title: impl Fn(usize) -> String,
+) -> Vec<Vec<String>> {
+    local
+        .iter()
+        .map(|(&number, paths)| {
+            let path = link_path(&paths[0], link_base);
+            vec![
+                format!("[{}]({})", number, problem_url(number)),
+                escape_inline_markdown(&title(number)),
+                format!("[`{}`]({})", path, path),
+                if problems.is_solved(number) {
+                    "✓"
+                } else {
+                    ""
+                }
+                .to_owned(),
+            ]
+        })
+        .collect()
+}
+
+fn format_seconds(duration: Duration) -> String {
+    format!("{:.3} s", duration.as_secs_f64())
+}
+
+impl ReadmeTable {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self {
+            write,
+            marker,
+            bench,
+            solved_on,
+        } = self;
+
+        let workspace = Workspace::discover()?;
+        let local = workspace.scan_solutions()?;
+        let progress = context.progress()?;
+        let enrichment = Enrichment::load_cached();
+        let history = if bench {
+            read_history(&context.profile)?
+        } else {
+            Vec::new()
+        };
+        // Links in the table are relative to the file it goes in.
+        let link_base = write
+            .as_ref()
+            .and_then(|path| path.canonicalize().ok())
+            .and_then(|path| path.parent().map(Path::to_owned))
+            .unwrap_or_else(|| workspace.root.clone());
+
+        let first_seen = if solved_on {
+            first_seen_solved(&context.profile)?
+        } else {
+            BTreeMap::new()
+        };
+
+        let mut header = vec!["Problem", "Title", "Solution", "Solved"];
+        if solved_on {
+            header.push("Solved on");
+        }
+        if bench {
+            header.push("Time");
+        }
+        let mut rows = rows(&local, &progress.problems, &link_base, |number| {
+            enrichment
+                .get(number)
+                .map(|info| info.title.clone())
+                .or_else(|| Statement::cached(number).map(|statement| statement.title))
+                .unwrap_or_default()
+        });
+        for (row, &number) in rows.iter_mut().zip(local.keys()) {
+            if solved_on {
+                row.push(
+                    first_seen
+                        .get(&number)
+                        .map_or_else(String::new, |timestamp| {
+                            timestamp.format("%Y-%m-%d").to_string()
+                        }),
+                );
+            }
+            if bench {
+                row.push(
+                    history
+                        .iter()
+                        .rev()
+                        .find(|record| record.problem == number)
+                        .map_or_else(String::new, |record| format_seconds(record.median)),
+                );
+            }
+        }
+        let table = render_table(&header, &rows);
+
+        match write {
+            None => print!("{}", table),
+            Some(path) => {
+                let document = read_to_string(&path)
+                    .map_err(|e| format!("unable to read {}: {}", path.display(), e))?;
+                let document = splice(&document, &marker, &table).ok_or_else(|| {
+                    format!(
+                        "unable to find two occurrences of {:?} to put the table between in {}",
+                        marker,
+                        path.display()
+                    )
+                })?;
+                write_atomically(&path, document)?;
+                println!("Updated the table in {}", path.display());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::test_support::gap_progress};
+
+    #[test]
+    fn lines_rows_up_with_problem_numbers_past_a_gap() {
+        let local = [11, 12, 13, 28, 29]
+            .iter()
+            .map(|&number| {
+                (
+                    number,
+                    vec![PathBuf::from(format!("src/bin/{}.rs", number))],
+                )
+            })
+            .collect::<BTreeMap<_, _>>();
+        let rows = rows(&local, &gap_progress().problems, Path::new(""), |number| {
+            format!("Problem {}", number)
+        });
+        let summary = rows
+            .iter()
+            .map(|row| (&*row[1], &*row[2], &*row[3]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                ("Problem 11", "[`src/bin/11.rs`](src/bin/11.rs)", "✓"),
+                ("Problem 12", "[`src/bin/12.rs`](src/bin/12.rs)", ""),
+                ("Problem 13", "[`src/bin/13.rs`](src/bin/13.rs)", "✓"),
+                ("Problem 28", "[`src/bin/28.rs`](src/bin/28.rs)", ""),
+                ("Problem 29", "[`src/bin/29.rs`](src/bin/29.rs)", ""),
+            ]
+        );
+        assert_eq!(rows[1][0], format!("[12]({})", problem_url(12)));
+
+        let table = render_table(&["Problem", "Title", "Solution", "Solved"], &rows);
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2 + local.len());
+        assert!(lines[3].starts_with("| [12]("), "{}", table);
+        assert!(lines[4].starts_with("| [13]("), "{}", table);
+        assert!(lines[4].ends_with("| ✓      |"), "{}", table);
+    }
+
+    const MARKER: &str = "<!-- euler-table -->";
+
+    fn table() -> String {
+        render_table(
+            &["Problem", "Solved"],
+            &[
+                vec!["1".to_owned(), "✓".to_owned()],
+                vec!["100".to_owned(), "".to_owned()],
+            ],
+        )
+    }
+
+    #[test]
+    fn pads_every_column_to_the_same_width() {
+        assert_eq!(
+            table(),
+            "| Problem | Solved |\n\
+             |---------|--------|\n\
+             | 1       | ✓      |\n\
+             | 100     |        |\n"
+        );
+        assert_eq!(
+            render_table(&["Problem"], &[]),
+            "| Problem |\n|---------|\n"
+        );
+    }
+
+    #[test]
+    fn splices_the_table_between_the_markers() {
+        let document = format!(
+            "# Solutions\n\nSome text.\n\n{}\nan old table\n{}\n\nMore text.\n",
+            MARKER, MARKER
+        );
+        assert_eq!(
+            splice(&document, MARKER, &table()).unwrap(),
+            format!(
+                "# Solutions\n\nSome text.\n\n{}\n{}{}\n\nMore text.\n",
+                MARKER,
+                table(),
+                MARKER
+            )
+        );
+    }
+
+    #[test]
+    fn splices_the_same_table_into_the_same_document() {
+        let document = format!("Before\n{}\n{}\nAfter\n", MARKER, MARKER);
+        let spliced = splice(&document, MARKER, &table()).unwrap();
+        assert_eq!(splice(&spliced, MARKER, &table()).unwrap(), spliced);
+    }
+
+    #[test]
+    fn refuses_to_splice_without_both_markers() {
+        assert_eq!(splice("No markers here.\n", MARKER, &table()), None);
+        let document = format!("Before\n{}\nAfter\n", MARKER);
+        assert_eq!(splice(&document, MARKER, &table()), None);
+    }
+}
@@ -0,0 +1,265 @@
+//! Thinning out the history, which otherwise grows by a snapshot on every fetch.
+
+use {
+    crate::{
+        chart::Bucket,
+        diff::parse_since,
+        history::{derive_first_seen_solved, Snapshot},
+        storage::{self, Store},
+    },
+    chrono::{DateTime, Duration, NaiveDate, Utc},
+    std::{collections::BTreeSet, convert::TryFrom, error::Error, str::FromStr},
+    structopt::StructOpt,
+};
+
+/// How many periods to keep a snapshot of each of.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Keep {
+    Last(usize),
+    All,
+}
+
+impl FromStr for Keep {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(Self::All),
+            _ => s
+                .parse()
+                .map(Self::Last)
+                .map_err(|_| format!("expected a number or `all`, not {:?}", s)),
+        }
+    }
+}
+
+/// Which snapshots to keep besides those where the solved set changed.
+#[derive(Clone, Copy, Debug)]
+pub struct Retention {
+    /// Keep the last snapshot of each of this many days, counting today.
+    pub daily: Keep,
+    /// Keep the last snapshot of each of this many weeks, counting this one.
+    pub weekly: Keep,
+}
+
+/// The start of the oldest of the last `keep` periods of `length`, counting the current one
+/// starting at `current`, or `None` if none are kept.
+fn oldest_kept(keep: Keep, current: NaiveDate, length: Duration) -> Option<NaiveDate> {
+    match keep {
+        Keep::All => Some(NaiveDate::MIN),
+        Keep::Last(0) => None,
+        Keep::Last(count) => Some(
+            i32::try_from(count - 1)
+                .ok()
+                .and_then(|periods| length.checked_mul(periods))
+                .and_then(|span| current.checked_sub_signed(span))
+                .unwrap_or(NaiveDate::MIN),
+        ),
+    }
+}
+
+/// Decides which of `snapshots` (oldest first) to keep under `retention`. The first snapshot,
+/// the latest, and every one where the solved set differs from the one before it are always
+/// kept. Otherwise, the last snapshot of each day and week within the retention policy is kept.
+/// Days and weeks are in UTC, and weeks start on Monday.
+pub fn retained(snapshots: &[Snapshot], retention: &Retention, now: DateTime<Utc>) -> Vec<bool> {
+    let today = now.date_naive();
+    let oldest_day = oldest_kept(retention.daily, today, Duration::days(1));
+    let oldest_week = oldest_kept(
+        retention.weekly,
+        Bucket::Week.start(today),
+        Duration::weeks(1),
+    );
+
+    let mut keep = vec![false; snapshots.len()];
+    let (mut days, mut weeks) = (BTreeSet::new(), BTreeSet::new());
+    // Walking backwards, the first snapshot seen of each day or week is its last.
+    for (idx, snapshot) in snapshots.iter().enumerate().rev() {
+        let day = snapshot.timestamp.date_naive();
+        let week = Bucket::Week.start(day);
+        let changed = match idx.checked_sub(1) {
+            Some(previous) => snapshots[previous].solved != snapshot.solved,
+            None => true,
+        };
+        let last_of_day = oldest_day.is_some_and(|oldest| day >= oldest) && days.insert(day);
+        let last_of_week = oldest_week.is_some_and(|oldest| week >= oldest) && weeks.insert(week);
+        keep[idx] = changed || idx == snapshots.len() - 1 || last_of_day || last_of_week;
+    }
+    keep
+}
+
+/// Replaces the history with `kept`, first recording when problems were first seen solved from
+/// the full history so that thinning out the snapshots doesn't lose it.
+fn rewrite(
+    store: &dyn Store,
+    snapshots: &[Snapshot],
+    kept: &[&Snapshot],
+) -> Result<(), Box<dyn Error>> {
+    for (number, timestamp) in derive_first_seen_solved(snapshots) {
+        store.record_first_seen_solved(&[number], timestamp)?;
+    }
+    store.replace_snapshots(kept)
+}
+
+/// Thin out old snapshots, keeping every one where the solved set changed.
+#[derive(Debug, StructOpt)]
+pub struct Compact {
+    /// Keep the last snapshot of each of this many days, counting today, or `all`.
+    #[structopt(long, default_value = "90")]
+    keep_daily: Keep,
+    /// Keep the last snapshot of each of this many weeks, counting this one, or `all`.
+    #[structopt(long, default_value = "all")]
+    keep_weekly: Keep,
+}
+
+impl Compact {
+    pub fn run(self, profile: &str) -> Result<(), Box<dyn Error>> {
+        let Self {
+            keep_daily,
+            keep_weekly,
+        } = self;
+
+        let store = storage::open(profile)?;
+        let snapshots = store.snapshots()?;
+        let retention = Retention {
+            daily: keep_daily,
+            weekly: keep_weekly,
+        };
+        let keep = retained(&snapshots, &retention, Utc::now());
+        let kept = snapshots
+            .iter()
+            .zip(&keep)
+            .filter(|(_, keep)| **keep)
+            .map(|(snapshot, _)| snapshot)
+            .collect::<Vec<_>>();
+        let removed = snapshots.len() - kept.len();
+        if removed > 0 {
+            rewrite(&*store, &snapshots, &kept)?;
+        }
+        println!(
+            "Removed {} of {} snapshots, keeping {}",
+            removed,
+            snapshots.len(),
+            kept.len()
+        );
+        Ok(())
+    }
+}
+
+/// Remove every snapshot from before a date.
+#[derive(Debug, StructOpt)]
+pub struct Prune {
+    /// Remove snapshots from before this date (`YYYY-MM-DD`) or time (RFC 3339).
+    #[structopt(long, parse(try_from_str = parse_since))]
+    before: DateTime<Utc>,
+}
+
+impl Prune {
+    pub fn run(self, profile: &str) -> Result<(), Box<dyn Error>> {
+        let Self { before } = self;
+
+        let store = storage::open(profile)?;
+        let snapshots = store.snapshots()?;
+        let kept = snapshots
+            .iter()
+            .filter(|snapshot| snapshot.timestamp >= before)
+            .collect::<Vec<_>>();
+        let removed = snapshots.len() - kept.len();
+        if removed > 0 {
+            rewrite(&*store, &snapshots, &kept)?;
+        }
+        println!(
+            "Removed {} of {} snapshots, keeping {}",
+            removed,
+            snapshots.len(),
+            kept.len()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            history::{first_seen_solved, read_history},
+            test_support::{noon, snapshot, with_each_backend},
+        },
+    };
+
+    /// A snapshot at `hour` UTC on a day of 2024, with the first `solved_count` problems solved.
+    fn at(month: u32, day: u32, hour: i64, solved_count: usize) -> Snapshot {
+        Snapshot {
+            timestamp: noon(month, day) + Duration::hours(hour - 12),
+            ..snapshot(month, day, solved_count)
+        }
+    }
+
+    fn history() -> Vec<Snapshot> {
+        vec![
+            at(3, 1, 8, 10),
+            at(3, 1, 9, 10),
+            at(3, 2, 9, 11),
+            at(3, 19, 8, 11),
+            at(3, 19, 9, 11),
+            at(3, 20, 8, 11),
+            at(3, 20, 9, 11),
+        ]
+    }
+
+    #[test]
+    fn parses_how_many_to_keep() {
+        assert_eq!("all".parse(), Ok(Keep::All));
+        assert_eq!("7".parse(), Ok(Keep::Last(7)));
+        assert!("-1".parse::<Keep>().is_err());
+    }
+
+    #[test]
+    fn keeps_the_last_snapshot_of_recent_days() {
+        let retention = Retention {
+            daily: Keep::Last(2),
+            weekly: Keep::Last(0),
+        };
+        assert_eq!(
+            retained(&history(), &retention, noon(3, 20)),
+            [true, false, true, false, true, false, true]
+        );
+    }
+
+    #[test]
+    fn keeps_the_last_snapshot_of_each_week() {
+        let retention = Retention {
+            daily: Keep::Last(0),
+            weekly: Keep::All,
+        };
+        assert_eq!(
+            retained(&history(), &retention, noon(3, 20)),
+            [true, false, true, false, false, false, true]
+        );
+    }
+
+    #[test]
+    fn keeps_when_problems_were_first_seen_solved_after_pruning() {
+        with_each_backend(|| {
+            let store = storage::open("default").unwrap();
+            for snapshot in &history() {
+                store.append_snapshot(snapshot).unwrap();
+            }
+
+            Prune {
+                before: noon(3, 10),
+            }
+            .run("default")
+            .unwrap();
+            assert_eq!(read_history("default").unwrap().len(), 4);
+            assert_eq!(
+                first_seen_solved("default")
+                    .unwrap()
+                    .into_iter()
+                    .collect::<Vec<_>>(),
+                [(11, at(3, 2, 9, 11).timestamp)]
+            );
+        });
+    }
+}
@@ -0,0 +1,217 @@
+use {
+    crate::{
+        clipboard::{copy, SystemClipboard},
+        desktop,
+        runner::{format_duration, Solution, TimeLimit},
+        workspace::{LayoutKind, Workspace},
+    },
+    log::warn,
+    notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher},
+    std::{
+        error::Error,
+        ffi::OsString,
+        path::Path,
+        sync::mpsc::{channel, RecvTimeoutError},
+        time::Duration,
+    },
+    structopt::StructOpt,
+};
+
+/// How long the solution's files must go unchanged before a rebuild starts, so that editors
+/// saving several files (or one file in several steps) only trigger one.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Build and run the local solution to a problem.
+#[derive(Debug, StructOpt)]
+pub struct Run {
+    /// The number of the problem whose solution to run.
+    number: usize,
+    /// Build the solution with optimizations.
+    #[structopt(long)]
+    release: bool,
+    /// Keep running the solution again whenever its sources change.
+    #[structopt(long)]
+    watch: bool,
+    /// With `--watch`, also show the result of each run in a desktop notification.
+    #[structopt(long, requires = "watch")]
+    desktop_notify: bool,
+    /// Also copy the answer to the clipboard, to paste it into the site by hand.
+    #[structopt(long)]
+    copy: bool,
+    /// Kill the solution if it runs longer than this, like `60s`. Defaults to the time limit in
+    /// `euler.toml`, if any.
+    #[structopt(long)]
+    time_limit: Option<TimeLimit>,
+    /// Use the solution file matching this glob, like `src/bin/p*.rs`, for problems with
+    /// several. Tried before the `layout.prefer` globs in `euler.toml`.
+    #[structopt(long)]
+    prefer: Vec<String>,
+    /// Arguments to pass through to the solution.
+    #[structopt(last = true, parse(from_os_str))]
+    args: Vec<OsString>,
+}
+
+/// Runs the solution once, returning the line summarizing its answer after printing it, and
+/// copying the answer to the clipboard if `copy_answer` is set.
+fn run_once(
+    workspace: &Workspace,
+    solution: &Solution,
+    release: bool,
+    args: &[OsString],
+    time_limit: Option<Duration>,
+    copy_answer: bool,
+) -> Result<String, Box<dyn Error>> {
+    let number = solution.number;
+    let output = solution.run(workspace, release, args, time_limit)?;
+    let (answer, incidental) = output.answer(&workspace.config.run);
+    for line in incidental {
+        println!("{}", line);
+    }
+    match answer {
+        Some(answer) => {
+            let summary = format!(
+                "Problem {}: {} ({})",
+                number,
+                answer,
+                format_duration(output.elapsed)
+            );
+            println!("{}", summary);
+            if copy_answer {
+                copy(&mut SystemClipboard, answer);
+            }
+            Ok(summary)
+        }
+        None => Err(format!("the solution to problem {} didn't print an answer", number).into()),
+    }
+}
+
+/// Whether a change to `path` should trigger a rebuild, ignoring build output and the scratch
+/// files editors write next to the files being edited.
+fn is_relevant_change(path: &Path) -> bool {
+    if path.iter().any(|component| component == "target") {
+        return false;
+    }
+    let name = match path.file_name().and_then(|name| name.to_str()) {
+        Some(name) => name,
+        None => return false,
+    };
+    // Vim probes whether it can write to a directory by creating `4913`.
+    !(name.starts_with('.') || name.ends_with('~') || name.ends_with(".swp") || name == "4913")
+}
+
+fn watch(
+    workspace: &Workspace,
+    solution: &Solution,
+    release: bool,
+    args: &[OsString],
+    time_limit: Option<Duration>,
+    desktop_notify: bool,
+    copy_answer: bool,
+) -> Result<(), Box<dyn Error>> {
+    let (sender, receiver) = channel();
+    let ctrl_c_sender = sender.clone();
+    ctrlc::set_handler(move || {
+        let _ = ctrl_c_sender.send(None);
+    })?;
+    let mut watcher = RecommendedWatcher::new(
+        move |event: notify::Result<Event>| {
+            let _ = sender.send(Some(event));
+        },
+        notify::Config::default(),
+    )?;
+    // Directories are watched rather than the solution file itself, since editors that save by
+    // writing a new file and renaming it over the old one would otherwise end the watch.
+    let package_dir = workspace.package_dir(solution.number);
+    match workspace.config.layout.kind {
+        LayoutKind::BinPerProblem => {
+            watcher.watch(&package_dir, RecursiveMode::NonRecursive)?;
+            watcher.watch(&package_dir.join("src"), RecursiveMode::Recursive)?;
+        }
+        LayoutKind::CratePerProblem => watcher.watch(&package_dir, RecursiveMode::Recursive)?,
+    }
+
+    loop {
+        let summary = match run_once(workspace, solution, release, args, time_limit, copy_answer) {
+            Ok(summary) => summary,
+            Err(e) => {
+                let summary = format!("error: {}", e);
+                eprintln!("{}", summary);
+                summary
+            }
+        };
+        if desktop_notify {
+            desktop::notify(&summary);
+        }
+        println!("--- Waiting for changes to problem {} ---", solution.number);
+
+        // Wait for a relevant change, then for things to settle down.
+        let mut changed = false;
+        loop {
+            let received = if changed {
+                match receiver.recv_timeout(WATCH_DEBOUNCE) {
+                    Ok(received) => received,
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return Ok(()),
+                }
+            } else {
+                match receiver.recv() {
+                    Ok(received) => received,
+                    Err(_) => return Ok(()),
+                }
+            };
+            match received {
+                None => return Ok(()),
+                Some(Ok(event)) => {
+                    changed |= !matches!(event.kind, EventKind::Access(_))
+                        && event.paths.iter().any(|path| is_relevant_change(path));
+                }
+                Some(Err(e)) => warn!("unable to watch for changes: {}", e),
+            }
+        }
+        println!();
+        println!("=== Problem {} changed; rebuilding ===", solution.number);
+    }
+}
+
+impl Run {
+    pub fn run(self) -> Result<(), Box<dyn Error>> {
+        let Self {
+            number,
+            release,
+            watch: watch_changes,
+            desktop_notify,
+            copy: copy_answer,
+            time_limit,
+            prefer,
+            args,
+        } = self;
+
+        let mut workspace = Workspace::discover()?;
+        workspace.prefer(prefer);
+        let time_limit = time_limit
+            .or(workspace.config.run.time_limit)
+            .map(|TimeLimit(limit)| limit);
+        let solution = Solution::resolve(&workspace, number)?;
+        if watch_changes {
+            watch(
+                &workspace,
+                &solution,
+                release,
+                &args,
+                time_limit,
+                desktop_notify,
+                copy_answer,
+            )
+        } else {
+            run_once(
+                &workspace,
+                &solution,
+                release,
+                &args,
+                time_limit,
+                copy_answer,
+            )
+            .map(drop)
+        }
+    }
+}
@@ -0,0 +1,673 @@
+//! Building and running local solutions.
+
+use {
+    crate::workspace::{LayoutKind, RunConfig, Workspace},
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::BTreeMap,
+        convert::TryFrom,
+        env::var_os,
+        error::Error,
+        ffi::OsString,
+        fmt::{self, Display, Formatter},
+        io::{self, BufRead, Read},
+        path::{Path, PathBuf},
+        process::{Child, Command, ExitStatus, Stdio},
+        str::FromStr,
+        sync::atomic::{AtomicBool, Ordering},
+        thread::{self, JoinHandle},
+        time::{Duration, Instant},
+    },
+};
+
+/// How often a solution with a time limit is checked on.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// How long a solution that ran out of time gets to exit after being asked to, before it's
+/// killed outright.
+const KILL_GRACE: Duration = Duration::from_secs(2);
+
+pub fn cargo() -> Command {
+    Command::new(var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+}
+
+/// A limit on how long a solution may run, like `60s`, `1.5m` or `500ms`.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct TimeLimit(pub Duration);
+
+impl Display for TimeLimit {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}s", self.0.as_secs_f64())
+    }
+}
+
+impl FromStr for TimeLimit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid time limit {:?}; expected like `60s`", s);
+        let split = s
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let multiplier = match unit.trim() {
+            "ms" => 0.001,
+            "s" => 1.0,
+            "m" => 60.0,
+            "h" => 60.0 * 60.0,
+            _ => return Err(invalid()),
+        };
+        let number = number.parse::<f64>().map_err(|_| invalid())?;
+        Duration::try_from_secs_f64(number * multiplier)
+            .ok()
+            .filter(|limit| !limit.is_zero())
+            .map(Self)
+            .ok_or_else(invalid)
+    }
+}
+
+impl From<TimeLimit> for String {
+    fn from(limit: TimeLimit) -> Self {
+        limit.to_string()
+    }
+}
+
+impl TryFrom<String> for TimeLimit {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// A solution that ran longer than its time limit, and was killed.
+#[derive(Debug)]
+pub struct TimedOut {
+    pub number: usize,
+    pub limit: Duration,
+}
+
+impl Display for TimedOut {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "the solution to problem {} exceeded the time limit of {} and was killed",
+            self.number,
+            format_duration(self.limit)
+        )
+    }
+}
+
+impl Error for TimedOut {}
+
+/// A solution that was killed before it finished, because its run was cancelled.
+#[derive(Debug)]
+pub struct Cancelled {
+    pub number: usize,
+}
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "the solution to problem {} was cancelled before it finished",
+            self.number
+        )
+    }
+}
+
+impl Error for Cancelled {}
+
+/// Asks the process group led by `child` to exit, then kills whatever of it is left after
+/// `KILL_GRACE`, so that processes the solution started are stopped too.
+#[cfg(unix)]
+fn kill_tree(child: &mut Child) {
+    let group = child.id() as libc::pid_t;
+    // Signalling the group once it's empty fails harmlessly, and its ID isn't reused while any
+    // process is left in it, even after the solution itself is reaped.
+    let signal = |signal| unsafe { libc::killpg(group, signal) };
+    signal(libc::SIGTERM);
+    let deadline = Instant::now() + KILL_GRACE;
+    while Instant::now() < deadline && matches!(child.try_wait(), Ok(None)) {
+        thread::sleep(POLL_INTERVAL);
+    }
+    // Processes the solution started may outlive it, so the group is killed either way.
+    signal(libc::SIGKILL);
+    let _ = child.kill();
+}
+
+/// Kills `child` along with the processes it started, falling back to killing only `child`
+/// if `taskkill` isn't available.
+#[cfg(windows)]
+fn kill_tree(child: &mut Child) {
+    let killed = Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &child.id().to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if !killed {
+        let _ = child.kill();
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn kill_tree(child: &mut Child) {
+    let _ = child.kill();
+}
+
+/// A local solution that has been found in a workspace.
+#[derive(Debug)]
+pub struct Solution {
+    pub number: usize,
+    /// The name of the binary target, which is also the package name in crate-per-problem
+    /// layouts.
+    pub bin_name: String,
+    kind: LayoutKind,
+}
+
+impl Solution {
+    /// Finds the local solution to problem `number`, suggesting how to create one if it
+    /// doesn't exist, and refusing to pick between several that nothing prefers.
+    pub fn resolve(workspace: &Workspace, number: usize) -> Result<Self, Box<dyn Error>> {
+        let path = workspace.resolve_solution(number)?.ok_or_else(|| {
+            format!(
+                "no solution to problem {} exists at {}; create one with `cargo euler new {}`",
+                number,
+                workspace.display_path(&workspace.solution_path(number)),
+                number
+            )
+        })?;
+        Ok(Self {
+            number,
+            bin_name: workspace.target_name(&path),
+            kind: workspace.config.layout.kind,
+        })
+    }
+
+    fn target_args(&self, command: &mut Command) {
+        match self.kind {
+            LayoutKind::BinPerProblem => command.arg("--bin").arg(&self.bin_name),
+            LayoutKind::CratePerProblem => command.arg("-p").arg(&self.bin_name),
+        };
+    }
+
+    /// Builds the solution with cargo, returning the path of its executable. Build output is
+    /// forwarded to stderr.
+    pub fn build(&self, workspace: &Workspace, release: bool) -> Result<PathBuf, Box<dyn Error>> {
+        let (status, mut executables) = build_executables(workspace, &[self], release)?;
+        if !status.success() {
+            return Err(format!(
+                "building the solution to problem {} failed with {}",
+                self.number, status
+            )
+            .into());
+        }
+        executables.remove(&self.number).ok_or_else(|| {
+            format!(
+                "cargo didn't report an executable for the solution to problem {}",
+                self.number
+            )
+            .into()
+        })
+    }
+
+    /// Builds and runs the solution, capturing its standard output. The build doesn't count
+    /// toward `time_limit`.
+    pub fn run(
+        &self,
+        workspace: &Workspace,
+        release: bool,
+        args: &[OsString],
+        time_limit: Option<Duration>,
+    ) -> Result<RunOutput, Box<dyn Error>> {
+        let executable = self.build(workspace, release)?;
+        self.execute(workspace, &executable, args, time_limit)
+    }
+
+    /// Runs an already built `executable` of the solution, capturing its standard output. If
+    /// it runs longer than `time_limit`, it's killed along with any processes it started, and
+    /// a `TimedOut` error is returned.
+    pub fn execute(
+        &self,
+        workspace: &Workspace,
+        executable: &Path,
+        args: &[OsString],
+        time_limit: Option<Duration>,
+    ) -> Result<RunOutput, Box<dyn Error>> {
+        self.execute_with(workspace, executable, args, time_limit, None)
+    }
+
+    /// Like `execute`, but captures standard error too, and gives the solution no standard
+    /// input, so that solutions can run alongside each other without their output interleaving.
+    /// Once `cancel` is set, the solution is killed and a `Cancelled` error is returned.
+    pub fn execute_captured(
+        &self,
+        workspace: &Workspace,
+        executable: &Path,
+        time_limit: Option<Duration>,
+        cancel: &AtomicBool,
+    ) -> Result<RunOutput, Box<dyn Error>> {
+        self.execute_with(workspace, executable, &[], time_limit, Some(cancel))
+    }
+
+    /// Runs `executable`, with its output captured and in a process group of its own if it can
+    /// be cancelled.
+    fn execute_with(
+        &self,
+        workspace: &Workspace,
+        executable: &Path,
+        args: &[OsString],
+        time_limit: Option<Duration>,
+        cancel: Option<&AtomicBool>,
+    ) -> Result<RunOutput, Box<dyn Error>> {
+        let captured = cancel.is_some();
+        let mut command = Command::new(executable);
+        command
+            .current_dir(&workspace.root)
+            .args(args)
+            .stdout(Stdio::piped());
+        if captured {
+            command.stdin(Stdio::null()).stderr(Stdio::piped());
+        } else {
+            command.stdin(Stdio::inherit()).stderr(Stdio::inherit());
+        }
+        // A solution is only put in a process group of its own when it may need killing, since
+        // one outside the terminal's foreground group can't read from it.
+        #[cfg(unix)]
+        if time_limit.is_some() || captured {
+            use std::os::unix::process::CommandExt;
+            command.process_group(0);
+        }
+
+        let start = Instant::now();
+        let mut child = command.spawn()?;
+        // Output is read as it comes, so a solution printing a lot doesn't block on a full pipe.
+        let stdout_reader = read_in_background(child.stdout.take());
+        let stderr_reader = read_in_background(child.stderr.take());
+        let status = if time_limit.is_none() && cancel.is_none() {
+            child.wait()?
+        } else {
+            loop {
+                if let Some(status) = child.try_wait()? {
+                    break status;
+                }
+                let timed_out = time_limit.filter(|limit| start.elapsed() > *limit);
+                let cancelled = cancel.is_some_and(|cancel| cancel.load(Ordering::SeqCst));
+                if timed_out.is_some() || cancelled {
+                    kill_tree(&mut child);
+                    let _ = child.wait();
+                    return Err(match timed_out {
+                        Some(limit) => TimedOut {
+                            number: self.number,
+                            limit,
+                        }
+                        .into(),
+                        None => Cancelled {
+                            number: self.number,
+                        }
+                        .into(),
+                    });
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        };
+        let output = RunOutput {
+            elapsed: start.elapsed(),
+            stdout: stdout_reader.join().unwrap()?,
+            stderr: stderr_reader.join().unwrap()?,
+        };
+        if !status.success() {
+            return Err(SolutionFailed {
+                number: self.number,
+                status,
+                output,
+            }
+            .into());
+        }
+        Ok(output)
+    }
+}
+
+/// Reads all of `pipe`, if any, on a thread of its own.
+fn read_in_background<R>(pipe: Option<R>) -> JoinHandle<io::Result<String>>
+where
+    R: Read + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            pipe.read_to_end(&mut bytes)?;
+        }
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    })
+}
+
+/// Builds `solutions` with a single cargo invocation, returning its exit status and the paths
+/// of the executables of the solutions that were built, by problem number. Build output is
+/// forwarded to stderr. Solutions that fail to build don't stop the rest from being built.
+fn build_executables(
+    workspace: &Workspace,
+    solutions: &[&Solution],
+    release: bool,
+) -> Result<(ExitStatus, BTreeMap<usize, PathBuf>), Box<dyn Error>> {
+    #[derive(Deserialize)]
+    struct Target {
+        kind: Vec<String>,
+        name: String,
+    }
+    #[derive(Deserialize)]
+    struct Message {
+        reason: String,
+        target: Option<Target>,
+        executable: Option<PathBuf>,
+    }
+
+    let mut command = cargo();
+    command
+        .current_dir(&workspace.root)
+        .args([
+            "build",
+            "--keep-going",
+            "--message-format=json-render-diagnostics",
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit());
+    for solution in solutions {
+        solution.target_args(&mut command);
+    }
+    if release {
+        command.arg("--release");
+    }
+    let output = command.output()?;
+
+    let numbers = solutions
+        .iter()
+        .map(|solution| (&*solution.bin_name, solution.number))
+        .collect::<BTreeMap<_, _>>();
+    let mut executables = BTreeMap::new();
+    for line in output.stdout.lines() {
+        let message = match serde_json::from_str::<Message>(&line?) {
+            Ok(message) => message,
+            Err(_) => continue,
+        };
+        if message.reason != "compiler-artifact" {
+            continue;
+        }
+        if let (Some(target), Some(path)) = (message.target, message.executable) {
+            if !target.kind.iter().any(|k| k == "bin") {
+                continue;
+            }
+            if let Some(number) = numbers.get(&*target.name) {
+                executables.insert(*number, path);
+            }
+        }
+    }
+    Ok((output.status, executables))
+}
+
+/// Builds every one of `solutions` with a single cargo invocation, returning the paths of the
+/// executables of those that built, by problem number.
+pub fn build_all(
+    workspace: &Workspace,
+    solutions: &[Solution],
+    release: bool,
+) -> Result<BTreeMap<usize, PathBuf>, Box<dyn Error>> {
+    let solutions = solutions.iter().collect::<Vec<_>>();
+    Ok(build_executables(workspace, &solutions, release)?.1)
+}
+
+/// A solution that exited unsuccessfully.
+#[derive(Debug)]
+pub struct SolutionFailed {
+    pub number: usize,
+    pub status: ExitStatus,
+    /// What the solution printed, where standard error is only known if it was captured.
+    pub output: RunOutput,
+}
+
+impl Display for SolutionFailed {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "the solution to problem {} failed with {}",
+            self.number, self.status
+        )
+    }
+}
+
+impl Error for SolutionFailed {}
+
+#[derive(Debug)]
+pub struct RunOutput {
+    pub stdout: String,
+    /// Empty unless standard error was captured.
+    pub stderr: String,
+    pub elapsed: Duration,
+}
+
+impl RunOutput {
+    /// Splits the output of a solution into its answer and any incidental output. By
+    /// convention, the answer is the last non-empty line; if an answer prefix is configured, it's
+    /// the last line starting with that prefix instead, with the prefix removed.
+    pub fn answer<'o>(&'o self, config: &RunConfig) -> (Option<&'o str>, Vec<&'o str>) {
+        let lines = self.stdout.lines().collect::<Vec<_>>();
+        let answer_idx = match &config.answer_prefix {
+            Some(prefix) => lines.iter().rposition(|line| line.starts_with(&**prefix)),
+            None => lines.iter().rposition(|line| !line.trim().is_empty()),
+        };
+        let answer = answer_idx.map(|idx| {
+            let line = lines[idx];
+            config
+                .answer_prefix
+                .as_deref()
+                .map_or(line, |prefix| &line[prefix.len()..])
+                .trim()
+        });
+        let mut incidental = lines
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| Some(*idx) != answer_idx)
+            .map(|(_, line)| *line)
+            .collect::<Vec<_>>();
+        while incidental.last().is_some_and(|line| line.trim().is_empty()) {
+            incidental.pop();
+        }
+        (answer, incidental)
+    }
+}
+
+/// Renders a duration compactly, like `1.234s` or `56.7ms`.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs_f64();
+    if secs >= 1.0 {
+        format!("{:.3}s", secs)
+    } else {
+        format!("{:.1}ms", secs * 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{test_support::TempDir, workspace::Config},
+        std::fs::read_to_string,
+    };
+
+    fn output(stdout: &str) -> RunOutput {
+        RunOutput {
+            stdout: stdout.to_owned(),
+            stderr: String::new(),
+            elapsed: Duration::default(),
+        }
+    }
+
+    #[test]
+    fn parses_time_limits() {
+        for (limit, duration) in [
+            ("60s", Duration::from_secs(60)),
+            ("1.5m", Duration::from_secs(90)),
+            ("500ms", Duration::from_millis(500)),
+            ("2h", Duration::from_secs(2 * 60 * 60)),
+            ("10 s", Duration::from_secs(10)),
+        ] {
+            assert_eq!(limit.parse(), Ok(TimeLimit(duration)), "{:?}", limit);
+        }
+        for limit in ["", "60", "s", "0s", "0ms", "-1s", "1e3s", "1.2.3s", "5d"] {
+            assert!(limit.parse::<TimeLimit>().is_err(), "{:?}", limit);
+        }
+    }
+
+    #[test]
+    fn writes_time_limits_that_parse_back() {
+        for limit in ["60s", "1.5m", "500ms"] {
+            let limit = limit.parse::<TimeLimit>().unwrap();
+            assert_eq!(limit.to_string().parse(), Ok(limit));
+            let json = serde_json::to_string(&limit).unwrap();
+            assert_eq!(serde_json::from_str::<TimeLimit>(&json).unwrap(), limit);
+        }
+        assert_eq!(TimeLimit(Duration::from_millis(1500)).to_string(), "1.5s");
+    }
+
+    #[test]
+    fn takes_the_last_line_as_the_answer() {
+        let config = RunConfig::default();
+        assert_eq!(
+            output("Searching...\n\n233168\n\n").answer(&config),
+            (Some("233168"), vec!["Searching..."])
+        );
+        assert_eq!(
+            output("\na\n  1 2 \n").answer(&config),
+            (Some("1 2"), vec!["", "a"])
+        );
+        assert_eq!(output("").answer(&config), (None, vec![]));
+        assert_eq!(output(" \n\n").answer(&config), (None, vec![]));
+    }
+
+    #[test]
+    fn takes_the_last_prefixed_line_as_the_answer() {
+        let config = RunConfig {
+            answer_prefix: Some("Answer:".to_owned()),
+            ..RunConfig::default()
+        };
+        let prefixed = output("Answer: 1\nsearching\nAnswer: 233168\ndone in 3ms\n");
+        assert_eq!(
+            prefixed.answer(&config),
+            (
+                Some("233168"),
+                vec!["Answer: 1", "searching", "done in 3ms"]
+            )
+        );
+        assert_eq!(output("233168\n").answer(&config), (None, vec!["233168"]));
+    }
+
+    #[cfg(unix)]
+    mod killing {
+        use super::*;
+
+        fn workspace(dir: &TempDir) -> Workspace {
+            Workspace::new(dir.path().to_owned(), Config::default())
+        }
+
+        fn solution() -> Solution {
+            Solution {
+                number: 1,
+                bin_name: "p001".to_owned(),
+                kind: LayoutKind::BinPerProblem,
+            }
+        }
+
+        /// Runs `script` with `sh` as if it were a solution.
+        fn run_script(
+            workspace: &Workspace,
+            script: &str,
+            time_limit: Duration,
+        ) -> Result<RunOutput, Box<dyn Error>> {
+            solution().execute(
+                workspace,
+                Path::new("/bin/sh"),
+                &["-c".into(), script.into()],
+                Some(time_limit),
+            )
+        }
+
+        /// Whether the process `pid` is still running, as opposed to gone or a zombie.
+        fn is_running(pid: libc::pid_t) -> bool {
+            match read_to_string(format!("/proc/{}/stat", pid)) {
+                // The state follows the parenthesized name of the executable.
+                Ok(stat) => !stat.rsplit(") ").next().unwrap().starts_with('Z'),
+                // Without procfs, fall back to whether the process can be signalled.
+                Err(_) if !Path::new("/proc/self").exists() => unsafe { libc::kill(pid, 0) == 0 },
+                Err(_) => false,
+            }
+        }
+
+        #[test]
+        fn runs_solutions_within_their_time_limit() {
+            let dir = TempDir::new();
+            let output = run_script(&workspace(&dir), "echo 233168", Duration::from_secs(10));
+            assert_eq!(output.unwrap().stdout, "233168\n");
+        }
+
+        #[test]
+        fn kills_sleeping_solutions_and_what_they_started() {
+            let dir = TempDir::new();
+            let workspace = workspace(&dir);
+            let start = Instant::now();
+            let error = run_script(
+                &workspace,
+                "sleep 30 & echo $! > sleeper.pid; wait",
+                Duration::from_millis(300),
+            )
+            .unwrap_err();
+
+            let timed_out = error.downcast_ref::<TimedOut>().unwrap();
+            assert_eq!(timed_out.limit, Duration::from_millis(300));
+            // Asking nicely was enough, so the grace period wasn't waited out.
+            assert!(start.elapsed() < KILL_GRACE, "{:?}", start.elapsed());
+            let sleeper = read_to_string(dir.path().join("sleeper.pid")).unwrap();
+            let sleeper = sleeper.trim().parse().unwrap();
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while is_running(sleeper) && Instant::now() < deadline {
+                thread::sleep(POLL_INTERVAL);
+            }
+            assert!(
+                !is_running(sleeper),
+                "process {} outlived the solution",
+                sleeper
+            );
+        }
+
+        #[test]
+        fn kills_solutions_that_wont_exit_when_asked() {
+            let dir = TempDir::new();
+            let start = Instant::now();
+            let error = run_script(
+                &workspace(&dir),
+                "trap '' TERM; sleep 30",
+                Duration::from_millis(100),
+            )
+            .unwrap_err();
+            assert!(error.is::<TimedOut>());
+            assert!(start.elapsed() >= KILL_GRACE);
+            assert!(start.elapsed() < KILL_GRACE * 5, "{:?}", start.elapsed());
+        }
+
+        #[test]
+        fn kills_cancelled_solutions() {
+            let dir = TempDir::new();
+            let cancel = AtomicBool::new(true);
+            let error = solution()
+                .execute_with(
+                    &workspace(&dir),
+                    Path::new("/bin/sh"),
+                    &["-c".into(), "sleep 30".into()],
+                    None,
+                    Some(&cancel),
+                )
+                .unwrap_err();
+            assert!(error.is::<Cancelled>());
+        }
+    }
+}
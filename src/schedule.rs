@@ -0,0 +1,363 @@
+//! The schedule of upcoming problems announced on the news page, and exporting it as an
+//! iCalendar file.
+
+use {
+    crate::{
+        client::fetch_public_page,
+        context::Context,
+        endpoints::{news_url, problem_url, PROJECT_EULER_HOSTNAME},
+        paths::write_atomically,
+        statement::flatten_paragraphs,
+    },
+    chrono::{DateTime, NaiveDateTime, Utc},
+    std::{error::Error, path::PathBuf},
+    structopt::StructOpt,
+    unhtml::scraper::Html,
+};
+
+/// Formats announcements give publication times in, once ordinal suffixes, commas and filler
+/// words like "at" are stripped. Times are always in UTC.
+const DATE_FORMATS: &[&str] = &[
+    "%A %d %B %Y %I:%M %p",
+    "%d %B %Y %I:%M %p",
+    "%A %d %B %Y %H:%M",
+    "%d %B %Y %H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+/// How long an event for a publication lasts.
+const EVENT_DURATION: &str = "PT1H";
+/// How long before a publication an alarm goes off.
+const ALARM_TRIGGER: &str = "-PT15M";
+/// The longest a line of an iCalendar file may be, in octets, not counting the line break.
+const MAX_LINE_OCTETS: usize = 75;
+
+/// When an upcoming problem is to be published.
+#[derive(Debug, PartialEq)]
+pub struct Announcement {
+    pub number: usize,
+    pub published: DateTime<Utc>,
+}
+
+/// Removes an ordinal suffix from a day of the month, like `12th`.
+fn strip_ordinal(word: &str) -> &str {
+    ["st", "nd", "rd", "th"]
+        .iter()
+        .find_map(|suffix| {
+            word.strip_suffix(suffix)
+                .filter(|day| !day.is_empty() && day.chars().all(|c| c.is_ascii_digit()))
+        })
+        .unwrap_or(word)
+}
+
+/// Finds the first time in `words` in one of the `DATE_FORMATS`, preferring the longest match, so
+/// that a trailing "pm" isn't left off.
+fn find_time(words: &[&str]) -> Option<DateTime<Utc>> {
+    // Between the two words of `%Y-%m-%d %H:%M` and the six of `%A %d %B %Y %I:%M %p`.
+    (0..words.len()).find_map(|start| {
+        (2..=6)
+            .rev()
+            .filter(|len| start + len <= words.len())
+            .find_map(|len| {
+                let candidate = words[start..start + len].join(" ");
+                DATE_FORMATS.iter().find_map(|format| {
+                    NaiveDateTime::parse_from_str(&candidate, format)
+                        .ok()
+                        .map(|time| time.and_utc())
+                })
+            })
+    })
+}
+
+/// Parses an announcement like "Problem 912 will be published on Saturday, 12th October 2024, at
+/// 11:00 pm (UTC)", where `text` is what follows "Problem ".
+fn parse_announcement(text: &str) -> Option<Announcement> {
+    let digits = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let number = text[..digits].parse().ok()?;
+    let words = text[digits..]
+        .split_whitespace()
+        .map(|word| strip_ordinal(word.trim_end_matches([',', '.', ';'])))
+        .filter(|word| !matches!(*word, "at" | "on" | "UTC" | "(UTC)"))
+        .collect::<Vec<_>>();
+    Some(Announcement {
+        number,
+        published: find_time(&words)?,
+    })
+}
+
+/// Parses the announcements of upcoming problems out of the news page, in the order they're
+/// published.
+pub fn parse_news(page: &str) -> Vec<Announcement> {
+    let html = Html::parse_document(page);
+    let mut paragraphs = Vec::new();
+    flatten_paragraphs(html.root_element(), &mut paragraphs);
+    // Paragraphs can run together, so each mention of a problem is parsed up to the next.
+    let mut announcements = paragraphs
+        .iter()
+        .flat_map(|paragraph| paragraph.split("Problem ").skip(1))
+        .filter_map(parse_announcement)
+        .collect::<Vec<_>>();
+    // A problem announced more than once keeps its earliest time.
+    announcements.sort_by_key(|announcement| (announcement.number, announcement.published));
+    announcements.dedup_by_key(|announcement| announcement.number);
+    announcements.sort_by_key(|announcement| announcement.published);
+    announcements
+}
+
+/// Escapes `text` for a text property value of an iCalendar file.
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => (),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Appends `line` to `ics`, folded so that no line is longer than `MAX_LINE_OCTETS`, without
+/// splitting a character. Continuation lines start with a space, which counts toward the limit.
+fn push_line(ics: &mut String, line: &str) {
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            ics.push_str("\r\n ");
+            octets = 1;
+        }
+        ics.push(c);
+        octets += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+fn ics_time(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Renders `announcements` as an iCalendar file with an event for each, and an alarm before
+/// each if `alarm` is set.
+pub fn to_ics(announcements: &[Announcement], alarm: bool, now: DateTime<Utc>) -> String {
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, "PRODID:-//cargo-euler//Problem schedule//EN");
+    push_line(&mut ics, "CALSCALE:GREGORIAN");
+    for Announcement { number, published } in announcements {
+        let summary = escape_text(&format!("Project Euler problem {} published", number));
+        push_line(&mut ics, "BEGIN:VEVENT");
+        push_line(
+            &mut ics,
+            &format!("UID:problem-{}@{}", number, PROJECT_EULER_HOSTNAME),
+        );
+        push_line(&mut ics, &format!("DTSTAMP:{}", ics_time(now)));
+        push_line(&mut ics, &format!("DTSTART:{}", ics_time(*published)));
+        push_line(&mut ics, &format!("DURATION:{}", EVENT_DURATION));
+        push_line(&mut ics, &format!("SUMMARY:{}", summary));
+        push_line(
+            &mut ics,
+            &format!("URL:{}", escape_text(&problem_url(*number))),
+        );
+        if alarm {
+            push_line(&mut ics, "BEGIN:VALARM");
+            push_line(&mut ics, "ACTION:DISPLAY");
+            push_line(&mut ics, &format!("DESCRIPTION:{}", summary));
+            push_line(&mut ics, &format!("TRIGGER:{}", ALARM_TRIGGER));
+            push_line(&mut ics, "END:VALARM");
+        }
+        push_line(&mut ics, "END:VEVENT");
+    }
+    push_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+/// Show when upcoming problems are to be published, as announced on the news page.
+#[derive(Debug, StructOpt)]
+pub struct Schedule {
+    /// Print the schedule as an iCalendar file, with an event for each upcoming problem.
+    #[structopt(long)]
+    ics: bool,
+    /// Add an alarm 15 minutes before each event of the iCalendar file.
+    #[structopt(long, requires = "ics")]
+    alarm: bool,
+    /// Write the schedule to this file instead of standard output.
+    #[structopt(short, long, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+impl Schedule {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self { ics, alarm, output } = self;
+
+        if context.offline {
+            return Err("the schedule can't be fetched with `--offline`".into());
+        }
+        let now = Utc::now();
+        let upcoming = parse_news(&fetch_public_page(&news_url())?)
+            .into_iter()
+            .filter(|announcement| announcement.published > now)
+            .collect::<Vec<_>>();
+
+        let rendered = if ics {
+            to_ics(&upcoming, alarm, now)
+        } else if upcoming.is_empty() {
+            "No upcoming problems are announced\n".to_owned()
+        } else {
+            upcoming
+                .iter()
+                .map(|Announcement { number, published }| {
+                    format!(
+                        "Problem {:<5} {}\n",
+                        number,
+                        published.format("%Y-%m-%d %H:%M UTC")
+                    )
+                })
+                .collect()
+        };
+        match output {
+            Some(path) => write_atomically(&path, rendered)?,
+            None => print!("{}", rendered),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::test_support::lock_globals, chrono::TimeZone, std::collections::BTreeMap,
+    };
+
+    fn at(month: u32, day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, month, day, hour, 0, 0).unwrap()
+    }
+
+    fn unescape_text(text: &str) -> String {
+        let mut unescaped = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                unescaped.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some(escaped) => unescaped.push(escaped),
+                None => panic!("{:?} ends in an escape", text),
+            }
+        }
+        unescaped
+    }
+
+    /// The properties of each component of an iCalendar file named `component`, with folded
+    /// lines joined back together and text values unescaped. The properties of components
+    /// nested in one are left out of it.
+    fn components(ics: &str, component: &str) -> Vec<BTreeMap<String, String>> {
+        let unfolded = ics.replace("\r\n ", "");
+        let mut components = Vec::new();
+        let mut current = None;
+        // How deeply nested in the current component the line is.
+        let mut depth = 0;
+        for line in unfolded.split_terminator("\r\n") {
+            let (name, value) = line.split_once(':').unwrap();
+            match (name, &mut current) {
+                ("BEGIN", None) if value == component => current = Some(BTreeMap::new()),
+                ("BEGIN", Some(_)) => depth += 1,
+                ("END", Some(_)) if depth > 0 => depth -= 1,
+                ("END", Some(_)) if value == component => components.extend(current.take()),
+                (_, Some(properties)) if depth == 0 => {
+                    properties.insert(name.to_owned(), unescape_text(value));
+                }
+                _ => (),
+            }
+        }
+        components
+    }
+
+    #[test]
+    fn parses_announcements_on_the_news_page() {
+        assert_eq!(
+            parse_news(include_str!("../tests/fixtures/pages/news.html")),
+            [
+                Announcement {
+                    number: 912,
+                    published: at(10, 12, 23),
+                },
+                Announcement {
+                    number: 913,
+                    published: at(10, 20, 2),
+                },
+                Announcement {
+                    number: 914,
+                    published: at(10, 27, 14),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn exports_announcements_that_read_back_the_same() {
+        let _globals = lock_globals();
+        let announcements = parse_news(include_str!("../tests/fixtures/pages/news.html"));
+        let ics = to_ics(&announcements, true, at(10, 1, 12));
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        for line in ics.split_terminator("\r\n") {
+            assert!(line.len() <= MAX_LINE_OCTETS, "{:?}", line);
+        }
+
+        let events = components(&ics, "VEVENT");
+        let read_back = events
+            .iter()
+            .map(|event| Announcement {
+                number: event["UID"]
+                    .strip_prefix("problem-")
+                    .and_then(|uid| uid.strip_suffix("@projecteuler.net"))
+                    .unwrap()
+                    .parse()
+                    .unwrap(),
+                published: NaiveDateTime::parse_from_str(&event["DTSTART"], "%Y%m%dT%H%M%SZ")
+                    .unwrap()
+                    .and_utc(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(read_back, announcements);
+        let event = &events[0];
+        assert_eq!(event["DTSTAMP"], "20241001T120000Z");
+        assert_eq!(event["DURATION"], "PT1H");
+        assert_eq!(event["SUMMARY"], "Project Euler problem 912 published");
+        assert_eq!(event["URL"], "https://projecteuler.net/problem=912");
+        assert!(!event.contains_key("TRIGGER"));
+
+        let alarms = components(&ics, "VALARM");
+        assert_eq!(alarms.len(), 3);
+        assert_eq!(alarms[0]["TRIGGER"], "-PT15M");
+        assert!(components(&to_ics(&announcements, false, at(10, 1, 12)), "VALARM").is_empty());
+    }
+
+    #[test]
+    fn folds_long_lines_without_splitting_characters() {
+        let line = format!("SUMMARY:{}", "é".repeat(50));
+        let mut ics = String::new();
+        push_line(&mut ics, &line);
+        let lines = ics.split_terminator("\r\n").collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.len() <= MAX_LINE_OCTETS));
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(ics.replace("\r\n ", ""), format!("{}\r\n", line));
+    }
+
+    #[test]
+    fn escapes_text() {
+        let text = "a, b; c\\d\r\ne";
+        assert_eq!(escape_text(text), r"a\, b\; c\\d\ne");
+        assert_eq!(unescape_text(&escape_text(text)), "a, b; c\\d\ne");
+    }
+}
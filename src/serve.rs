@@ -0,0 +1,565 @@
+//! A tiny HTTP server for badges of progress that are always fresh, without committing them.
+
+use {
+    crate::{
+        context::Context,
+        progress::{NextLevel, Progress},
+    },
+    chrono::{DateTime, Utc},
+    log::{info, warn},
+    serde::Serialize,
+    std::{
+        error::Error,
+        io::{BufRead, BufReader, Read, Write},
+        net::{SocketAddr, TcpListener, TcpStream},
+        str::FromStr,
+        sync::Mutex,
+        thread::{self, sleep},
+        time::{Duration, Instant},
+    },
+    structopt::StructOpt,
+};
+
+const BADGE_LABEL: &str = "Project Euler";
+const BADGE_COLOR: &str = "orange";
+const BADGE_COLOR_HEX: &str = "#fe7d37";
+/// Roughly how wide a character of badge text is, in pixels.
+const BADGE_CHAR_WIDTH: f64 = 6.5;
+const BADGE_PADDING: f64 = 10.0;
+/// The shortest time shields.io accepts caching an endpoint's response for.
+const MIN_SHIELDS_CACHE_SECONDS: u64 = 300;
+/// The shortest time responses are cached for, even right before a refresh.
+const MIN_MAX_AGE: Duration = Duration::from_secs(60);
+/// How long a client gets to send its request before it's dropped, so that a slow client
+/// can't hold up the others.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// The most of a request read, since only its request line matters.
+const MAX_REQUEST_BYTES: u64 = 8 * 1024;
+
+/// What's served, derived from the progress page so that nothing else about the account (like
+/// the session) can leak.
+#[derive(Clone, Debug, Serialize)]
+pub struct Served {
+    pub solved: usize,
+    pub total: usize,
+    pub level: usize,
+    /// The next level to reach and how many more problems that takes, if any is left.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_level: Option<ServedLevel>,
+    pub fetched: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ServedLevel {
+    pub level: usize,
+    pub remaining: usize,
+}
+
+impl Served {
+    pub fn of(progress: &Progress, fetched: DateTime<Utc>) -> Self {
+        Self {
+            solved: progress.problems.solved_count(),
+            total: progress.problems.available(),
+            level: progress.levels.current(),
+            next_level: progress.next_level_info().map(
+                |NextLevel {
+                     level, remaining, ..
+                 }| ServedLevel { level, remaining },
+            ),
+            fetched,
+        }
+    }
+
+    fn badge_message(&self, style: BadgeStyle) -> String {
+        match (style, &self.next_level) {
+            (BadgeStyle::NextLevel, Some(ServedLevel { level, remaining })) => {
+                format!("{} solved · {} to Level {}", self.solved, remaining, level)
+            }
+            _ => format!("{} solved · Level {}", self.solved, self.level),
+        }
+    }
+}
+
+/// What badges say: `solved` for the solved count and level, or `next-level` for how far away
+/// the next level is instead of the current one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BadgeStyle {
+    Solved,
+    NextLevel,
+}
+
+impl FromStr for BadgeStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "solved" => Ok(Self::Solved),
+            "next-level" => Ok(Self::NextLevel),
+            _ => Err(format!(
+                "unknown badge style {:?}; expected `solved` or `next-level`",
+                s
+            )),
+        }
+    }
+}
+
+/// Renders the JSON shields.io's endpoint badges read, caching it for at least `max_age`.
+fn badge_json(
+    served: &Served,
+    style: BadgeStyle,
+    max_age: Duration,
+) -> Result<String, Box<dyn Error>> {
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Endpoint<'a> {
+        schema_version: u8,
+        label: &'a str,
+        message: String,
+        color: &'a str,
+        cache_seconds: u64,
+    }
+
+    Ok(serde_json::to_string(&Endpoint {
+        schema_version: 1,
+        label: BADGE_LABEL,
+        message: served.badge_message(style),
+        color: BADGE_COLOR,
+        cache_seconds: max_age.as_secs().max(MIN_SHIELDS_CACHE_SECONDS),
+    })?)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Renders a flat badge like those of shields.io.
+fn badge_svg(served: &Served, style: BadgeStyle) -> String {
+    let message = served.badge_message(style);
+    let text_width = |text: &str| text.chars().count() as f64 * BADGE_CHAR_WIDTH + BADGE_PADDING;
+    let label_width = text_width(BADGE_LABEL).round();
+    let message_width = text_width(&message).round();
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}">
+  <title>{label}: {message}</title>
+  <rect width="{label_width}" height="20" fill="#555"/>
+  <rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/>
+  <g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+    <text x="{label_x}" y="14">{label}</text>
+    <text x="{message_x}" y="14">{message}</text>
+  </g>
+</svg>
+"##,
+        width = label_width + message_width,
+        label = escape_xml(BADGE_LABEL),
+        message = escape_xml(&message),
+        label_width = label_width,
+        message_width = message_width,
+        color = BADGE_COLOR_HEX,
+        label_x = label_width / 2.0,
+        message_x = label_width + message_width / 2.0,
+    )
+}
+
+/// The last good data, and when it's next refreshed.
+struct State {
+    served: Option<Served>,
+    next_refresh: Instant,
+    badge_style: BadgeStyle,
+}
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    cache_control: String,
+    body: String,
+}
+
+impl Response {
+    fn error(status: &'static str, message: &str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            cache_control: "no-store".to_owned(),
+            body: format!("{}\n", message),
+        }
+    }
+}
+
+/// Responds to a `GET` of `path` with the last good data in `state`.
+fn respond(path: &str, state: &State) -> Result<Response, Box<dyn Error>> {
+    let served = match &state.served {
+        Some(served) => served,
+        None => {
+            return Ok(Response::error(
+                "503 Service Unavailable",
+                "progress hasn't been fetched yet",
+            ))
+        }
+    };
+    // Caching until the next refresh saves clients from asking for data that can't have
+    // changed.
+    let max_age = state
+        .next_refresh
+        .saturating_duration_since(Instant::now())
+        .max(MIN_MAX_AGE);
+    let (content_type, body) = match path {
+        "/badge.json" => (
+            "application/json",
+            badge_json(served, state.badge_style, max_age)?,
+        ),
+        "/badge.svg" => ("image/svg+xml", badge_svg(served, state.badge_style)),
+        "/progress.json" => ("application/json", serde_json::to_string_pretty(served)?),
+        _ => return Ok(Response::error("404 Not Found", "not found")),
+    };
+    Ok(Response {
+        status: "200 OK",
+        content_type,
+        cache_control: format!("public, max-age={}", max_age.as_secs()),
+        body,
+    })
+}
+
+/// Reads a request from `stream` and responds to it from `state`.
+fn handle(stream: TcpStream, state: &Mutex<State>) -> Result<(), Box<dyn Error>> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new((&stream).take(MAX_REQUEST_BYTES));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Drain the headers, so closing the connection doesn't reset it before the response is
+    // read.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next(), parts.next());
+    let path = target.map(|target| target.split(['?', '#']).next().unwrap_or(target));
+    let response = match (method, path) {
+        (Some("GET" | "HEAD"), Some(path)) => respond(path, &state.lock().unwrap())?,
+        (Some(_), Some(_)) => Response::error("405 Method Not Allowed", "only GET is allowed"),
+        _ => Response::error("400 Bad Request", "malformed request"),
+    };
+    let Response {
+        status,
+        content_type,
+        cache_control,
+        body,
+    } = response;
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: {}\r\n\
+         Connection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len(),
+        cache_control
+    )?;
+    if method != Some("HEAD") {
+        stream.write_all(body.as_bytes())?;
+    }
+    stream.flush()?;
+    Ok(())
+}
+
+/// Fetches progress into `state`, keeping the last good data if that fails.
+fn refresh(context: &Context, state: &Mutex<State>, interval: Duration) {
+    let fetched = context
+        .progress()
+        .map(|progress| Served::of(&progress, Utc::now()));
+    let mut state = state.lock().unwrap();
+    state.next_refresh = Instant::now() + interval;
+    match fetched {
+        Ok(served) => {
+            info!("refreshed progress: {} solved", served.solved);
+            state.served = Some(served);
+        }
+        Err(e) => warn!(
+            "unable to refresh progress, so the last good data is served: {}",
+            e
+        ),
+    }
+}
+
+/// Serve badges of progress over HTTP, refreshed from the site periodically: shields.io
+/// endpoint JSON at `/badge.json`, an SVG badge at `/badge.svg`, and a summary at
+/// `/progress.json`.
+#[derive(Debug, StructOpt)]
+pub struct Serve {
+    /// The address to listen on.
+    #[structopt(long, default_value = "127.0.0.1:8080")]
+    bind: SocketAddr,
+    /// How many minutes to wait between refreshes of progress.
+    #[structopt(long, default_value = "60")]
+    interval: u64,
+    /// What badges say: `solved` for the solved count and level, or `next-level` for how many
+    /// more problems the next level takes.
+    #[structopt(long, default_value = "solved")]
+    badge_style: BadgeStyle,
+}
+
+impl Serve {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self {
+            bind,
+            interval,
+            badge_style,
+        } = self;
+        let interval = Duration::from_secs(interval.max(1) * 60);
+
+        let listener =
+            TcpListener::bind(bind).map_err(|e| format!("unable to listen on {}: {}", bind, e))?;
+        println!(
+            "Serving badges on http://{}/badge.svg",
+            listener.local_addr()?
+        );
+        let state = Mutex::new(State {
+            served: None,
+            next_refresh: Instant::now(),
+            badge_style,
+        });
+        let state = &state;
+        thread::scope(|scope| {
+            scope.spawn(move || loop {
+                refresh(context, state, interval);
+                sleep(interval);
+            });
+            for stream in listener.incoming() {
+                let handled = match stream {
+                    Ok(stream) => handle(stream, state),
+                    Err(e) => Err(e.into()),
+                };
+                if let Err(e) = handled {
+                    warn!("unable to respond to a request: {}", e);
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            endpoints::set_base_url,
+            progress::{Level, Levels},
+            test_support::{lock_globals, new_account, noon, problems, MockServer, Reply},
+        },
+        std::net::Shutdown,
+    };
+
+    /// Sends `request` to the server at `addr`, returning the whole response.
+    fn send(addr: SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        stream.shutdown(Shutdown::Write).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    fn served(solved: usize, levels: &[bool]) -> Served {
+        let progress = Progress {
+            levels: Levels(
+                levels
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, &completed)| Level {
+                        description: format!("Solve {} problems", (idx + 1) * 25),
+                        completed,
+                    })
+                    .collect(),
+            ),
+            problems: problems(&format!(
+                "{}{}",
+                "S".repeat(solved),
+                "U".repeat(60 - solved)
+            )),
+        };
+        Served::of(&progress, noon(3, 1))
+    }
+
+    #[test]
+    fn parses_badge_styles() {
+        assert_eq!("solved".parse(), Ok(BadgeStyle::Solved));
+        assert_eq!("next-level".parse(), Ok(BadgeStyle::NextLevel));
+        assert!("level".parse::<BadgeStyle>().is_err());
+    }
+
+    #[test]
+    fn counts_down_to_the_next_level_on_badges() {
+        let served = served(26, &[true, false]);
+        assert_eq!(
+            served.badge_message(BadgeStyle::Solved),
+            "26 solved · Level 1"
+        );
+        assert_eq!(
+            served.badge_message(BadgeStyle::NextLevel),
+            "26 solved · 24 to Level 2"
+        );
+        let json = badge_json(&served, BadgeStyle::NextLevel, Duration::from_secs(0)).unwrap();
+        assert!(
+            json.contains(r#""message":"26 solved · 24 to Level 2""#),
+            "{}",
+            json
+        );
+        assert!(json.contains(r#""cacheSeconds":300"#), "{}", json);
+        assert!(badge_svg(&served, BadgeStyle::NextLevel).contains("24 to Level 2"));
+    }
+
+    #[test]
+    fn shows_the_level_on_badges_once_every_level_is_reached() {
+        let served = served(50, &[true, true]);
+        assert!(served.next_level.is_none());
+        assert_eq!(
+            served.badge_message(BadgeStyle::NextLevel),
+            "50 solved · Level 2"
+        );
+    }
+
+    #[test]
+    fn serves_progress() {
+        let mut state = State {
+            served: None,
+            next_refresh: Instant::now(),
+            badge_style: BadgeStyle::Solved,
+        };
+        assert_eq!(
+            respond("/badge.json", &state).unwrap().status,
+            "503 Service Unavailable"
+        );
+        state.served = Some(served(25, &[true, false]));
+        let progress = respond("/progress.json", &state).unwrap();
+        assert_eq!(progress.status, "200 OK");
+        assert!(progress.cache_control.starts_with("public, max-age="));
+        let progress = serde_json::from_str::<serde_json::Value>(&progress.body).unwrap();
+        assert_eq!(progress["solved"], 25);
+        assert_eq!(progress["next_level"]["level"], 2);
+        assert_eq!(progress["next_level"]["remaining"], 25);
+        assert_eq!(respond("/", &state).unwrap().status, "404 Not Found");
+    }
+
+    #[test]
+    fn serves_accounts_without_progress() {
+        let served = Served::of(&new_account(), noon(3, 1));
+        for (style, message) in [
+            (BadgeStyle::Solved, "0 solved · Level 0"),
+            (BadgeStyle::NextLevel, "0 solved · 25 to Level 1"),
+        ] {
+            let json = serde_json::from_str::<serde_json::Value>(
+                &badge_json(&served, style, Duration::from_secs(600)).unwrap(),
+            )
+            .unwrap();
+            assert_eq!(json["message"], message);
+            // Badges are always the same color, however little is solved.
+            assert_eq!(json["color"], BADGE_COLOR);
+            assert_eq!(json["cacheSeconds"], 600);
+            let svg = badge_svg(&served, style);
+            assert!(svg.contains(&format!("<title>Project Euler: {}</title>", message)));
+            assert!(svg.contains(&format!(r#"fill="{}""#, BADGE_COLOR_HEX)));
+        }
+
+        let state = State {
+            served: Some(served),
+            next_refresh: Instant::now(),
+            badge_style: BadgeStyle::Solved,
+        };
+        let progress = respond("/progress.json", &state).unwrap();
+        let progress = serde_json::from_str::<serde_json::Value>(&progress.body).unwrap();
+        assert_eq!(
+            progress,
+            serde_json::json!({
+                "solved": 0,
+                "total": 29,
+                "level": 0,
+                "next_level": { "level": 1, "remaining": 25 },
+                "fetched": "2024-03-01T12:00:00Z",
+            })
+        );
+    }
+
+    #[test]
+    fn serves_the_last_good_progress_fetched() {
+        let _globals = lock_globals();
+        let upstream = MockServer::start();
+        upstream
+            .reply(
+                "GET",
+                "/progress",
+                Reply::ok(include_str!("../tests/fixtures/pages/progress.html")),
+            )
+            .reply("GET", "/progress", Reply::status(500));
+        set_base_url(Some(upstream.url().parse().unwrap()));
+        let context = Context::from_iter(&["cargo-euler", "--session-id", "s3ss10n"]);
+        let state = Mutex::new(State {
+            served: None,
+            next_refresh: Instant::now(),
+            badge_style: BadgeStyle::Solved,
+        });
+        let interval = Duration::from_secs(600);
+
+        refresh(&context, &state, interval);
+        // The site failing doesn't lose what was fetched before.
+        refresh(&context, &state, interval);
+        assert_eq!(upstream.received().len(), 2);
+        assert_eq!(state.lock().unwrap().served.as_ref().unwrap().solved, 26);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = [
+            "GET /badge.json HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            "GET /badge.svg?cache=bust HTTP/1.1\r\nCookie: PHPSESSID=s3ss10n\r\n\r\n",
+            "HEAD /progress.json HTTP/1.1\r\n\r\n",
+            "POST /badge.json HTTP/1.1\r\n\r\n",
+            "\r\n",
+        ];
+        let responses = thread::scope(|scope| {
+            scope.spawn(|| {
+                for stream in listener.incoming().take(requests.len()) {
+                    handle(stream.unwrap(), &state).unwrap();
+                }
+            });
+            requests
+                .iter()
+                .map(|request| send(addr, request))
+                .collect::<Vec<_>>()
+        });
+
+        let (head, body) = responses[0].split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK\r\n"), "{}", head);
+        assert!(
+            head.contains("Content-Type: application/json\r\n"),
+            "{}",
+            head
+        );
+        let max_age = head
+            .split("max-age=")
+            .nth(1)
+            .and_then(|rest| rest.split("\r\n").next())
+            .and_then(|seconds| seconds.parse::<u64>().ok())
+            .unwrap();
+        assert!((MIN_MAX_AGE.as_secs()..=600).contains(&max_age), "{}", head);
+        let badge = serde_json::from_str::<serde_json::Value>(body).unwrap();
+        assert_eq!(badge["message"], "26 solved · Level 1");
+
+        assert!(responses[1].starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(responses[1].contains("<title>Project Euler: 26 solved · Level 1</title>"));
+        // Nothing sent by clients is echoed back.
+        assert!(!responses[1].contains("s3ss10n"), "{}", responses[1]);
+
+        let (head, body) = responses[2].split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK\r\n"), "{}", head);
+        assert_eq!(body, "");
+
+        assert!(responses[3].starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+        assert!(responses[3].contains("Cache-Control: no-store\r\n"));
+        assert!(responses[4].starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    }
+}
@@ -0,0 +1,497 @@
+//! A compact, portable file of which problems are solved, meant to be committed to a repository
+//! as a baseline to compare against. It holds nothing but problem numbers and a timestamp.
+//!
+//! A file is a single line of (standard, padded) base64 encoding these bytes, with integers in
+//! little-endian order:
+//!
+//! | Bytes     | Contents                                                                        |
+//! |-----------|---------------------------------------------------------------------------------|
+//! | 4         | The magic bytes `PESS`                                                          |
+//! | 1         | The format version, 1 or 2                                                      |
+//! | 8         | When the set was exported, as signed seconds since the Unix epoch               |
+//! | 4         | How many problems were published, `total`, counting retired ones                |
+//! | 4         | How many problems are solved                                                    |
+//! | 4         | Version 2 only: how many problems are retired                                   |
+//! | remainder | A bitset over problem numbers; bit `i % 8` of byte `i / 8` is problem `i + 1`.  |
+//! |           | Version 2 follows it with a bitset of retired problems of the same length       |
+//! | 4         | The first four bytes of the SHA-256 of everything before them                   |
+//!
+//! Bitsets must cover `total` problems, but may be longer, with the extra bits clear, so
+//! future writers can pad them. Problems are always at the bits of their own numbers, so retired
+//! problems leave their bits clear in the bitset of solved problems rather than shifting the
+//! ones after them. Version 1 is still written when no problem is retired, so that sets stay
+//! readable by older versions where they can be. Readers reject versions newer than they know.
+
+use {
+    crate::{
+        context::Context,
+        history::{append_snapshot, read_history, ProblemSet, Snapshot},
+        paths::write_atomically,
+        progress::{ProblemStatus, Problems},
+        stats::PROBLEMS_PER_LEVEL,
+    },
+    base64::{engine::general_purpose::STANDARD as BASE64, DecodeError, Engine},
+    chrono::{DateTime, TimeZone, Utc},
+    sha2::{Digest, Sha256},
+    std::{
+        convert::TryInto,
+        error::Error,
+        fmt::{self, Display, Formatter},
+        fs::read_to_string,
+        path::{Path, PathBuf},
+    },
+    structopt::StructOpt,
+};
+
+const MAGIC: &[u8; 4] = b"PESS";
+const VERSION: u8 = 2;
+/// The first version recording retired problems.
+const RETIRED_VERSION: u8 = 2;
+const HEADER_LEN: usize = 4 + 1 + 8 + 4 + 4;
+/// How much longer the header is with a count of retired problems.
+const RETIRED_HEADER_LEN: usize = 4;
+const CHECKSUM_LEN: usize = 4;
+
+#[derive(Debug)]
+pub enum SolvedSetError {
+    Base64(DecodeError),
+    TooShort,
+    BadMagic,
+    UnsupportedVersion(u8),
+    ChecksumMismatch,
+    BadTimestamp(i64),
+    BitsetTooShort { total: usize, bits: usize },
+    UnevenBitsets,
+    UnpublishedSolved(usize),
+    UnpublishedRetired(usize),
+    SolvedRetired(usize),
+    CountMismatch { expected: usize, actual: usize },
+    RetiredCountMismatch { expected: usize, actual: usize },
+}
+
+impl Display for SolvedSetError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        use self::SolvedSetError::*;
+
+        match self {
+            Base64(e) => write!(f, "invalid base64: {}", e),
+            TooShort => write!(f, "too short to be a solved set"),
+            BadMagic => write!(f, "not a solved set"),
+            UnsupportedVersion(version) => write!(
+                f,
+                "version {} isn't supported by this version of cargo-euler, which only supports \
+                 up to version {}",
+                version, VERSION
+            ),
+            ChecksumMismatch => write!(f, "the checksum doesn't match; the file is corrupt"),
+            BadTimestamp(seconds) => write!(f, "invalid export time {}", seconds),
+            BitsetTooShort { total, bits } => write!(
+                f,
+                "the bitset covers {} problems, but {} are published",
+                bits, total
+            ),
+            UnevenBitsets => write!(
+                f,
+                "the bitsets of solved and retired problems aren't as long"
+            ),
+            UnpublishedSolved(number) => {
+                write!(f, "problem {} is solved, but wasn't published", number)
+            }
+            UnpublishedRetired(number) => {
+                write!(f, "problem {} is retired, but wasn't published", number)
+            }
+            SolvedRetired(number) => write!(f, "problem {} is both solved and retired", number),
+            CountMismatch { expected, actual } => write!(
+                f,
+                "{} problems should be solved, but {} are",
+                expected, actual
+            ),
+            RetiredCountMismatch { expected, actual } => write!(
+                f,
+                "{} problems should be retired, but {} are",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl Error for SolvedSetError {}
+
+fn checksum(bytes: &[u8]) -> [u8; CHECKSUM_LEN] {
+    Sha256::digest(bytes)[..CHECKSUM_LEN].try_into().unwrap()
+}
+
+/// The bitset of `set` over `total` problems.
+fn to_bitset(set: &ProblemSet, total: usize) -> Vec<u8> {
+    let mut bitset = vec![0u8; total.div_ceil(8)];
+    for &number in &set.0 {
+        let idx = number - 1;
+        bitset[idx / 8] |= 1 << (idx % 8);
+    }
+    bitset
+}
+
+fn from_bitset(bitset: &[u8]) -> ProblemSet {
+    ProblemSet(
+        (0..bitset.len() * 8)
+            .filter(|idx| bitset[idx / 8] & (1 << (idx % 8)) != 0)
+            .map(|idx| idx + 1)
+            .collect(),
+    )
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SolvedSet {
+    pub exported_at: DateTime<Utc>,
+    /// How many problems were published, counting retired ones.
+    pub total: usize,
+    pub solved: ProblemSet,
+    pub retired: ProblemSet,
+}
+
+impl SolvedSet {
+    pub fn of(snapshot: &Snapshot) -> Self {
+        Self {
+            exported_at: snapshot.timestamp,
+            total: snapshot.total,
+            solved: snapshot.solved.clone(),
+            retired: snapshot.retired.clone(),
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let version = if self.retired.is_empty() {
+            1
+        } else {
+            RETIRED_VERSION
+        };
+        let mut bytes = Vec::with_capacity(
+            HEADER_LEN + RETIRED_HEADER_LEN + 2 * self.total.div_ceil(8) + CHECKSUM_LEN,
+        );
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(version);
+        bytes.extend_from_slice(&self.exported_at.timestamp().to_le_bytes());
+        bytes.extend_from_slice(&(self.total as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.solved.0.len() as u32).to_le_bytes());
+        if version >= RETIRED_VERSION {
+            bytes.extend_from_slice(&(self.retired.0.len() as u32).to_le_bytes());
+        }
+        bytes.extend_from_slice(&to_bitset(&self.solved, self.total));
+        if version >= RETIRED_VERSION {
+            bytes.extend_from_slice(&to_bitset(&self.retired, self.total));
+        }
+        let checksum = checksum(&bytes);
+        bytes.extend_from_slice(&checksum);
+        BASE64.encode(bytes)
+    }
+
+    pub fn decode(encoded: &str) -> Result<Self, SolvedSetError> {
+        use self::SolvedSetError::*;
+
+        let bytes = BASE64.decode(encoded.trim()).map_err(Base64)?;
+        if bytes.len() < HEADER_LEN + CHECKSUM_LEN {
+            return Err(TooShort);
+        }
+        if &bytes[..4] != MAGIC {
+            return Err(BadMagic);
+        }
+        let version = bytes[4];
+        if version > VERSION {
+            return Err(UnsupportedVersion(version));
+        }
+        let header_len = if version >= RETIRED_VERSION {
+            HEADER_LEN + RETIRED_HEADER_LEN
+        } else {
+            HEADER_LEN
+        };
+        if bytes.len() < header_len + CHECKSUM_LEN {
+            return Err(TooShort);
+        }
+        let (contents, expected_checksum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+        if checksum(contents) != expected_checksum {
+            return Err(ChecksumMismatch);
+        }
+
+        let seconds = i64::from_le_bytes(contents[5..13].try_into().unwrap());
+        let exported_at = Utc
+            .timestamp_opt(seconds, 0)
+            .single()
+            .ok_or(BadTimestamp(seconds))?;
+        let total = u32::from_le_bytes(contents[13..17].try_into().unwrap()) as usize;
+        let count = u32::from_le_bytes(contents[17..21].try_into().unwrap()) as usize;
+        let bitsets = &contents[header_len..];
+        let (bitset, retired_bitset, retired_count) = if version >= RETIRED_VERSION {
+            if bitsets.len() % 2 != 0 {
+                return Err(UnevenBitsets);
+            }
+            let (bitset, retired_bitset) = bitsets.split_at(bitsets.len() / 2);
+            let retired_count = u32::from_le_bytes(contents[21..25].try_into().unwrap());
+            (bitset, retired_bitset, retired_count as usize)
+        } else {
+            (bitsets, &[][..], 0)
+        };
+        if bitset.len() * 8 < total {
+            return Err(BitsetTooShort {
+                total,
+                bits: bitset.len() * 8,
+            });
+        }
+        let solved = from_bitset(bitset);
+        let retired = from_bitset(retired_bitset);
+        if let Some(&number) = solved.0.iter().find(|&&number| number > total) {
+            return Err(UnpublishedSolved(number));
+        }
+        if let Some(&number) = retired.0.iter().find(|&&number| number > total) {
+            return Err(UnpublishedRetired(number));
+        }
+        if let Some(&number) = solved.0.intersection(&retired.0).next() {
+            return Err(SolvedRetired(number));
+        }
+        if solved.0.len() != count {
+            return Err(CountMismatch {
+                expected: count,
+                actual: solved.0.len(),
+            });
+        }
+        if retired.0.len() != retired_count {
+            return Err(RetiredCountMismatch {
+                expected: retired_count,
+                actual: retired.0.len(),
+            });
+        }
+        Ok(Self {
+            exported_at,
+            total,
+            solved,
+            retired,
+        })
+    }
+
+    pub fn read(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let contents = read_to_string(path)
+            .map_err(|e| format!("unable to read {}: {}", path.display(), e))?;
+        Self::decode(&contents)
+            .map_err(|e| format!("unable to parse {}: {}", path.display(), e).into())
+    }
+
+    /// The status of every problem published when the set was exported.
+    pub fn problems(&self) -> Problems {
+        Problems(
+            (1..=self.total)
+                .map(|number| {
+                    if self.solved.0.contains(&number) {
+                        ProblemStatus::Solved
+                    } else if self.retired.0.contains(&number) {
+                        ProblemStatus::Retired
+                    } else {
+                        ProblemStatus::Unsolved
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    pub fn to_snapshot(&self) -> Snapshot {
+        Snapshot {
+            timestamp: self.exported_at,
+            solved_count: self.solved.0.len(),
+            total: self.total,
+            level: self.solved.0.len() / PROBLEMS_PER_LEVEL,
+            solved: self.solved.clone(),
+            retired: self.retired.clone(),
+        }
+    }
+}
+
+/// Export progress to a file that's safe to commit.
+#[derive(Debug, StructOpt)]
+pub struct Export {
+    /// Write the set of solved problems to this file, to compare against with `--baseline`.
+    #[structopt(long, parse(from_os_str))]
+    solved_set: PathBuf,
+}
+
+impl Export {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self { solved_set } = self;
+
+        let snapshot = Snapshot::of(&context.progress()?, Utc::now());
+        write_atomically(&solved_set, SolvedSet::of(&snapshot).encode() + "\n")?;
+        println!(
+            "Exported {} solved problems to {}",
+            snapshot.solved_count,
+            solved_set.display()
+        );
+        Ok(())
+    }
+}
+
+/// Record a solved set exported with `cargo euler export` in the history, like on a new machine.
+#[derive(Debug, StructOpt)]
+pub struct Import {
+    /// The solved set to import.
+    #[structopt(parse(from_os_str))]
+    solved_set: PathBuf,
+}
+
+impl Import {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self { solved_set } = self;
+
+        let set = SolvedSet::read(&solved_set)?;
+        if let Some(latest) = read_history(&context.profile)?.last() {
+            if latest.timestamp > set.exported_at {
+                return Err(format!(
+                    "the history already has snapshots newer than {}; compare against it with \
+                     `--baseline` instead",
+                    solved_set.display()
+                )
+                .into());
+            }
+        }
+        append_snapshot(&context.profile, &set.to_snapshot())?;
+        println!(
+            "Recorded {} solved problems from {} in the history",
+            set.solved.0.len(),
+            solved_set.display()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::test_support::{gap_progress, noon},
+    };
+
+    fn set(total: usize, solved: &[usize], retired: &[usize]) -> SolvedSet {
+        SolvedSet {
+            exported_at: noon(3, 1),
+            total,
+            solved: ProblemSet(solved.iter().copied().collect()),
+            retired: ProblemSet(retired.iter().copied().collect()),
+        }
+    }
+
+    /// Decodes `encoded`, changes its bytes with `edit` and encodes them again with a checksum
+    /// that matches.
+    fn tamper(encoded: &str, edit: impl FnOnce(&mut Vec<u8>)) -> String {
+        let mut bytes = BASE64.decode(encoded).unwrap();
+        bytes.truncate(bytes.len() - CHECKSUM_LEN);
+        edit(&mut bytes);
+        let checksum = checksum(&bytes);
+        bytes.extend_from_slice(&checksum);
+        BASE64.encode(bytes)
+    }
+
+    #[test]
+    fn round_trips_solved_sets() {
+        for set in &[
+            set(0, &[], &[]),
+            set(10, &[1, 2, 3, 8, 9], &[]),
+            set(17, &[1, 17], &[4]),
+        ] {
+            assert_eq!(SolvedSet::decode(&set.encode()).unwrap(), *set);
+        }
+    }
+
+    #[test]
+    fn round_trips_progress_with_a_gap() {
+        let progress = gap_progress();
+        let set = SolvedSet::of(&Snapshot::of(&progress, noon(3, 1)));
+        assert_eq!(set.retired.0.iter().copied().collect::<Vec<_>>(), [12, 28]);
+
+        let decoded = SolvedSet::decode(&set.encode()).unwrap();
+        assert_eq!(decoded, set);
+        assert_eq!(decoded.problems().0, progress.problems.0);
+    }
+
+    #[test]
+    fn writes_version_1_without_retired_problems() {
+        let version = |set: &SolvedSet| BASE64.decode(set.encode()).unwrap()[4];
+        assert_eq!(version(&set(10, &[1], &[])), 1);
+        assert_eq!(version(&set(10, &[1], &[2])), RETIRED_VERSION);
+    }
+
+    #[test]
+    fn reads_padded_bitsets() {
+        let encoded = tamper(&set(10, &[1, 10], &[]).encode(), |bytes| {
+            bytes.extend_from_slice(&[0, 0])
+        });
+        assert_eq!(SolvedSet::decode(&encoded).unwrap(), set(10, &[1, 10], &[]));
+    }
+
+    #[test]
+    fn rejects_corrupt_solved_sets() {
+        let encoded = set(10, &[1, 2], &[]).encode();
+        let mut bytes = BASE64.decode(&encoded).unwrap();
+        bytes[HEADER_LEN] ^= 0b100;
+        assert!(matches!(
+            SolvedSet::decode(&BASE64.encode(&bytes)),
+            Err(SolvedSetError::ChecksumMismatch)
+        ));
+        assert!(matches!(
+            SolvedSet::decode("not base64!"),
+            Err(SolvedSetError::Base64(_))
+        ));
+        assert!(matches!(
+            SolvedSet::decode(&BASE64.encode(b"PESS")),
+            Err(SolvedSetError::TooShort)
+        ));
+        assert!(matches!(
+            SolvedSet::decode(&tamper(&encoded, |bytes| bytes[0] = b'X')),
+            Err(SolvedSetError::BadMagic)
+        ));
+        assert!(matches!(
+            SolvedSet::decode(&tamper(&encoded, |bytes| bytes[4] = VERSION + 1)),
+            Err(SolvedSetError::UnsupportedVersion(_))
+        ));
+        assert!(matches!(
+            SolvedSet::decode(&tamper(&encoded, |bytes| bytes[17] = 3)),
+            Err(SolvedSetError::CountMismatch {
+                expected: 3,
+                actual: 2
+            })
+        ));
+        assert!(matches!(
+            SolvedSet::decode(&tamper(&encoded, |bytes| bytes[13] = 20)),
+            Err(SolvedSetError::BitsetTooShort {
+                total: 20,
+                bits: 16
+            })
+        ));
+        assert!(matches!(
+            SolvedSet::decode(&tamper(&encoded, |bytes| bytes[13] = 1)),
+            Err(SolvedSetError::UnpublishedSolved(2))
+        ));
+    }
+
+    #[test]
+    fn rejects_problems_both_solved_and_retired() {
+        let encoded = tamper(&set(8, &[1], &[2]).encode(), |bytes| {
+            let retired = bytes.len() - 1;
+            bytes[retired] |= 1;
+            bytes[21] = 2;
+        });
+        assert!(matches!(
+            SolvedSet::decode(&encoded),
+            Err(SolvedSetError::SolvedRetired(1))
+        ));
+    }
+
+    #[test]
+    fn lists_the_status_of_every_problem() {
+        let problems = set(4, &[1, 3], &[2]).problems();
+        assert_eq!(
+            problems.0,
+            [
+                ProblemStatus::Solved,
+                ProblemStatus::Retired,
+                ProblemStatus::Solved,
+                ProblemStatus::Unsolved
+            ]
+        );
+    }
+}
@@ -0,0 +1,334 @@
+use {
+    crate::{client::fetch_problem_page, endpoints::base_url, storage, timings::PhaseTimer},
+    itertools::Itertools,
+    log::warn,
+    serde::{Deserialize, Serialize},
+    std::error::Error,
+    unhtml::scraper::{ElementRef, Html, Node, Selector},
+};
+
+/// Elements that start a new paragraph when flattening a statement into text.
+const BLOCK_ELEMENTS: &[&str] = &["blockquote", "div", "li", "ol", "p", "pre", "table", "ul"];
+
+/// A data file linked from a problem's statement, like `names.txt` for problem 22.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Attachment {
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Statement {
+    pub number: usize,
+    pub title: String,
+    /// The text of the statement, one entry per paragraph with whitespace collapsed.
+    pub paragraphs: Vec<String>,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+}
+
+pub fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().join(" ")
+}
+
+fn flush_paragraph(current: &mut String, paragraphs: &mut Vec<String>) {
+    let paragraph = collapse_whitespace(current);
+    if !paragraph.is_empty() {
+        paragraphs.push(paragraph);
+    }
+    current.clear();
+}
+
+pub fn flatten_paragraphs(el: ElementRef, paragraphs: &mut Vec<String>) {
+    let mut current = String::new();
+    for child in el.children() {
+        match child.value() {
+            Node::Text(text) => current.push_str(text),
+            Node::Element(child_el) if BLOCK_ELEMENTS.contains(&&*child_el.name.local) => {
+                flush_paragraph(&mut current, paragraphs);
+                flatten_paragraphs(ElementRef::wrap(child).unwrap(), paragraphs);
+            }
+            Node::Element(_) => {
+                current.extend(ElementRef::wrap(child).unwrap().text());
+            }
+            _ => (),
+        }
+        current.push(' ');
+    }
+    flush_paragraph(&mut current, paragraphs);
+}
+
+/// Phrases that mark a sentence as giving an example.
+const EXAMPLE_PHRASES: &[&str] = &["for example", "for instance", "e.g."];
+
+/// Words that questions are usually started with, which mark the sentence of a statement
+/// asking for the actual answer rather than giving an example.
+const QUESTION_WORDS: &[&str] = &[
+    "find",
+    "what",
+    "how",
+    "which",
+    "determine",
+    "calculate",
+    "evaluate",
+    "give",
+];
+
+/// Numbers with more than this many digits are more likely part of the question than an
+/// example.
+const MAX_EXAMPLE_DIGITS: usize = 6;
+
+/// Abbreviations whose final period doesn't end a sentence.
+const ABBREVIATIONS: &[&str] = &["e.g.", "i.e."];
+
+/// Splits `paragraph` into sentences at terminal punctuation followed by whitespace, which
+/// leaves decimals like `3.14` and abbreviations like `e.g.` alone.
+fn sentences(paragraph: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = paragraph.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        let end = idx + c.len_utf8();
+        let ends_sentence = matches!(c, '.' | '?' | '!')
+            && chars.peek().is_none_or(|(_, next)| next.is_whitespace())
+            && !ABBREVIATIONS
+                .iter()
+                .any(|abbreviation| paragraph[..end].to_lowercase().ends_with(abbreviation));
+        if ends_sentence {
+            sentences.push(paragraph[start..end].trim());
+            start = end;
+        }
+    }
+    let rest = paragraph[start..].trim();
+    if !rest.is_empty() {
+        sentences.push(rest);
+    }
+    sentences
+}
+
+fn is_example(sentence: &str) -> bool {
+    let lowercase = sentence.to_lowercase();
+    if EXAMPLE_PHRASES
+        .iter()
+        .any(|phrase| lowercase.contains(phrase))
+    {
+        return true;
+    }
+    let first_word = lowercase
+        .split(|c: char| !c.is_alphabetic())
+        .next()
+        .unwrap_or_default();
+    if sentence.ends_with('?') || QUESTION_WORDS.contains(&first_word) {
+        return false;
+    }
+    let mut numbers = sentence
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|digits| !digits.is_empty())
+        .peekable();
+    numbers.peek().is_some() && numbers.all(|digits| digits.len() <= MAX_EXAMPLE_DIGITS)
+}
+
+/// Strips the problem number that data files are prefixed with on the site, like the `p022_`
+/// of `p022_names.txt`.
+fn strip_problem_prefix(file_name: &str) -> &str {
+    match file_name.split_once('_') {
+        Some((prefix, rest)) => {
+            let digits = prefix.strip_prefix('p').unwrap_or(prefix);
+            if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+                rest
+            } else {
+                file_name
+            }
+        }
+        None => file_name,
+    }
+}
+
+impl Statement {
+    /// Finds the sentences of the statement that look like they give a worked example, like
+    /// "The sum of these multiples is 23." This is a best-effort guess.
+    pub fn examples(&self) -> Vec<&str> {
+        self.paragraphs
+            .iter()
+            .flat_map(|paragraph| sentences(paragraph))
+            .filter(|sentence| is_example(sentence))
+            .collect()
+    }
+
+    /// Extracts a statement from the HTML of a problem's page.
+    pub fn from_problem_page(number: usize, page: &str) -> Result<Self, Box<dyn Error>> {
+        let _timer = PhaseTimer::start("parse");
+        let html = Html::parse_document(page);
+        let title = html
+            .select(&Selector::parse("#content h2").unwrap())
+            .next()
+            .map(|h2| collapse_whitespace(&h2.text().collect::<String>()))
+            .ok_or_else(|| format!("unable to find the title of problem {}", number))?;
+        let content = html
+            .select(&Selector::parse("div.problem_content").unwrap())
+            .next()
+            .ok_or_else(|| format!("unable to find the statement of problem {}", number))?;
+        let mut paragraphs = Vec::new();
+        flatten_paragraphs(content, &mut paragraphs);
+        let attachments = content
+            .select(&Selector::parse("a[href]").unwrap())
+            .filter_map(|anchor| {
+                let href = anchor.value().attr("href")?;
+                if !href.contains("resources/") {
+                    return None;
+                }
+                let url = base_url().join(href).ok()?;
+                let name = url.path_segments()?.next_back()?;
+                Some(Attachment {
+                    name: strip_problem_prefix(name).to_owned(),
+                    url: url.into_string(),
+                })
+            })
+            .collect();
+        Ok(Self {
+            number,
+            title,
+            paragraphs,
+            attachments,
+        })
+    }
+
+    /// Reads the statement of problem `number` from the cache, if it's been cached.
+    pub fn cached(number: usize) -> Option<Self> {
+        match storage::open_shared().and_then(|store| store.statement(number)) {
+            Ok(statement) => statement,
+            Err(e) => {
+                warn!("unable to read cached statement {}: {}", number, e);
+                None
+            }
+        }
+    }
+
+    /// Reads the statement of problem `number` from the cache, fetching (and caching) it if it
+    /// hasn't been cached yet.
+    pub fn load(number: usize) -> Result<Self, Box<dyn Error>> {
+        if let Some(statement) = Self::cached(number) {
+            return Ok(statement);
+        }
+
+        let statement = Self::from_problem_page(number, &fetch_problem_page(number)?)?;
+        if let Err(e) = storage::open_shared().and_then(|store| store.cache_statement(&statement)) {
+            warn!("unable to cache statement {}: {}", number, e);
+        }
+        Ok(statement)
+    }
+}
+
+/// Greedily wraps `text` into lines no longer than `width` columns, except where a single word
+/// is longer than that.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            endpoints::set_base_url,
+            test_support::{with_each_backend, MockServer, Reply},
+        },
+    };
+
+    fn statement(paragraphs: &[&str]) -> Statement {
+        Statement {
+            number: 1,
+            title: "Multiples of 3 or 5".to_owned(),
+            paragraphs: paragraphs.iter().map(|&p| p.to_owned()).collect(),
+            attachments: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn finds_examples_introduced_as_such() {
+        let statement = statement(&[
+            "Some numbers are abundant. For example, the divisors of twelve sum past it.",
+            "Consider the numbers, e.g. those that are perfect.",
+        ]);
+        assert_eq!(
+            statement.examples(),
+            [
+                "For example, the divisors of twelve sum past it.",
+                "Consider the numbers, e.g. those that are perfect."
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_examples_by_their_small_numbers() {
+        let statement = statement(&[
+            "If we list all the natural numbers below 10 that are multiples of 3 or 5, we get 3, \
+             5, 6 and 9. The sum of these multiples is 23.",
+            "Find the sum of all the multiples of 3 or 5 below 1000.",
+            "The answer is smaller than 10000000000.",
+            "Is 3.14 close to 22/7?",
+        ]);
+        assert_eq!(
+            statement.examples(),
+            [
+                "If we list all the natural numbers below 10 that are multiples of 3 or 5, we get \
+                 3, 5, 6 and 9.",
+                "The sum of these multiples is 23."
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_no_examples_in_statements_without_any() {
+        let statement = statement(&[
+            "Consider the triangle numbers.",
+            "Find the first one with over five hundred divisors.",
+        ]);
+        assert!(statement.examples().is_empty());
+        assert!(Statement {
+            paragraphs: Vec::new(),
+            ..statement
+        }
+        .examples()
+        .is_empty());
+    }
+
+    #[test]
+    fn caches_statements_once_fetched() {
+        with_each_backend(|| {
+            let server = MockServer::start();
+            server.reply(
+                "GET",
+                "/problem=42",
+                Reply::ok(include_str!(
+                    "../tests/fixtures/pages/problem-submittable.html"
+                )),
+            );
+            set_base_url(Some(server.url().parse().unwrap()));
+
+            assert!(Statement::cached(42).is_none());
+            let fetched = Statement::load(42).unwrap();
+            assert_eq!(fetched.title, "Coded Triangle Numbers");
+            let cached = Statement::load(42).unwrap();
+            assert_eq!(
+                serde_json::to_value(&cached).unwrap(),
+                serde_json::to_value(&fetched).unwrap()
+            );
+            assert_eq!(server.received().len(), 1);
+        });
+    }
+}
@@ -0,0 +1,364 @@
+//! Statistics about the rate of solving, computed from the recorded history.
+
+use {
+    crate::{
+        account::AccountStats,
+        chart::{bucket_counts, Bucket},
+        context::Context,
+        history::{read_history, Snapshot},
+        output::{print_json, Format},
+    },
+    chrono::{DateTime, Duration, Utc},
+    log::warn,
+    serde::Serialize,
+    std::{collections::BTreeMap, error::Error},
+    structopt::StructOpt,
+};
+
+/// How many problems must be solved per level.
+pub const PROBLEMS_PER_LEVEL: usize = 25;
+
+/// How far back to look when projecting dates.
+const PROJECTION_WINDOW_DAYS: i64 = 90;
+
+/// The count of solved problems in effect at `time`: that of the last snapshot taken at or before
+/// it, or of the first snapshot if none were.
+pub fn solved_at(snapshots: &[Snapshot], time: DateTime<Utc>) -> usize {
+    snapshots
+        .iter()
+        .rev()
+        .find(|snapshot| snapshot.timestamp <= time)
+        .or_else(|| snapshots.first())
+        .map_or(0, |snapshot| snapshot.solved_count)
+}
+
+/// How many problems were solved between `now - days` and the latest snapshot.
+pub fn solved_in_last(snapshots: &[Snapshot], days: i64, now: DateTime<Utc>) -> usize {
+    let current = snapshots.last().map_or(0, |snapshot| snapshot.solved_count);
+    current.saturating_sub(solved_at(snapshots, now - Duration::days(days)))
+}
+
+/// The average number of problems solved per week over the whole history, if it spans any time.
+pub fn weekly_rate(snapshots: &[Snapshot]) -> Option<f64> {
+    let (first, last) = (snapshots.first()?, snapshots.last()?);
+    let weeks = (last.timestamp - first.timestamp).num_seconds() as f64 / (7.0 * 86400.0);
+    if weeks <= 0.0 {
+        return None;
+    }
+    Some(last.solved_count.saturating_sub(first.solved_count) as f64 / weeks)
+}
+
+/// How many consecutive weeks, up to the current one, had at least one problem solved. The
+/// current week doesn't break the streak before it's over.
+pub fn weekly_streak(snapshots: &[Snapshot], now: DateTime<Utc>) -> usize {
+    let weeks = bucket_counts(snapshots, Bucket::Week, now);
+    let solved_in_week = |idx: usize| idx > 0 && weeks[idx].1 > weeks[idx - 1].1;
+    let mut idx = match weeks.len().checked_sub(1) {
+        Some(current) if solved_in_week(current) => current,
+        Some(current) => match current.checked_sub(1) {
+            Some(previous) => previous,
+            None => return 0,
+        },
+        None => return 0,
+    };
+    let mut streak = 0;
+    while solved_in_week(idx) {
+        streak += 1;
+        idx -= 1;
+    }
+    streak
+}
+
+/// Naively projects when `target` problems will be solved at the rate of the trailing
+/// `PROJECTION_WINDOW_DAYS`, or `None` without recent activity to project from.
+pub fn project(snapshots: &[Snapshot], target: usize, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let current = snapshots.last()?.solved_count;
+    let recent = solved_in_last(snapshots, PROJECTION_WINDOW_DAYS, now);
+    if recent == 0 {
+        return None;
+    }
+    let remaining = target.saturating_sub(current) as f64;
+    let seconds = remaining / recent as f64 * (PROJECTION_WINDOW_DAYS * 86400) as f64;
+    Some(now + Duration::seconds(seconds as i64))
+}
+
+/// The milestones still ahead of `snapshot`, as a description and the solved count reaching it.
+pub fn milestones(snapshot: &Snapshot) -> Vec<(String, usize)> {
+    let mut milestones = vec![(
+        format!("level {}", snapshot.level + 1),
+        (snapshot.level + 1) * PROBLEMS_PER_LEVEL,
+    )];
+    milestones.push(("100 problems".to_owned(), 100));
+    milestones.push((
+        "25% of problems".to_owned(),
+        snapshot.available().div_ceil(4),
+    ));
+    milestones.retain(|(_, target)| *target > snapshot.solved_count);
+    milestones.sort_by_key(|(_, target)| *target);
+    milestones.dedup_by_key(|(_, target)| *target);
+    milestones
+}
+
+/// When a milestone would be reached at the recent rate.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Projection {
+    pub milestone: String,
+    /// The solved count reaching the milestone.
+    pub target: usize,
+    /// `None` without recent activity to project from.
+    pub date: Option<DateTime<Utc>>,
+}
+
+/// Statistics computed from the recorded history.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct HistoryStats {
+    pub solved: usize,
+    pub available: usize,
+    pub level: usize,
+    /// How many problems were solved in each of the last 7, 30 and 365 days.
+    pub solved_in_last_days: BTreeMap<i64, usize>,
+    /// `None` if the history doesn't span any time yet.
+    pub weekly_rate: Option<f64>,
+    pub weekly_streak: usize,
+    pub projections: Vec<Projection>,
+}
+
+impl HistoryStats {
+    /// Computes the statistics of `snapshots` (oldest first), or `None` if there are none.
+    pub fn of(snapshots: &[Snapshot], now: DateTime<Utc>) -> Option<Self> {
+        let latest = snapshots.last()?;
+        Some(Self {
+            solved: latest.solved_count,
+            available: latest.available(),
+            level: latest.level,
+            solved_in_last_days: [7, 30, 365]
+                .iter()
+                .map(|&days| (days, solved_in_last(snapshots, days, now)))
+                .collect(),
+            weekly_rate: weekly_rate(snapshots),
+            weekly_streak: weekly_streak(snapshots, now),
+            projections: milestones(latest)
+                .into_iter()
+                .map(|(milestone, target)| Projection {
+                    date: project(snapshots, target, now),
+                    milestone,
+                    target,
+                })
+                .collect(),
+        })
+    }
+
+    fn print(&self) {
+        println!(
+            "Solved {} of {} problems, level {}",
+            self.solved, self.available, self.level
+        );
+        for (days, solved) in &self.solved_in_last_days {
+            println!("Solved in the last {} days: {}", days, solved);
+        }
+        match self.weekly_rate {
+            Some(rate) => println!("Average per week: {:.1}", rate),
+            None => println!("Average per week: not enough history"),
+        }
+        println!(
+            "Weekly streak: {}",
+            match self.weekly_streak {
+                1 => "1 week".to_owned(),
+                weeks => format!("{} weeks", weeks),
+            }
+        );
+
+        println!(
+            "\nNaive projections, at the rate of the last {} days:",
+            PROJECTION_WINDOW_DAYS
+        );
+        for Projection {
+            milestone, date, ..
+        } in &self.projections
+        {
+            match date {
+                Some(date) => println!("  {}: {}", milestone, date.format("%Y-%m-%d")),
+                None => println!("  {}: no recent activity", milestone),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    history: Option<HistoryStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    account: Option<AccountStats>,
+}
+
+/// Show statistics about the rate of solving, from the recorded history.
+#[derive(Debug, StructOpt)]
+pub struct Stats {
+    /// Also show the statistics of the account page, like forum posts made and kudos earned.
+    #[structopt(long)]
+    account: bool,
+    /// The format to print the statistics in: `human` or `json`.
+    #[structopt(long, default_value = "human")]
+    format: Format,
+}
+
+/// Fetches the statistics of the account page, warning if its solved count disagrees with the
+/// progress page.
+fn fetch_account_stats(context: &Context) -> Result<AccountStats, Box<dyn Error>> {
+    let stats = AccountStats::fetch(&mut context.session()?)?;
+    if let Some(account_solved) = stats.problems_solved {
+        let parsed_solved = context.progress()?.problems.solved_count();
+        if account_solved != parsed_solved {
+            warn!(
+                "the account page counts {} problems solved, but {} were parsed from the progress \
+                 page; the progress page's parser is probably out of date, so please report this",
+                account_solved, parsed_solved
+            );
+        }
+    }
+    Ok(stats)
+}
+
+fn print_account_stats(stats: &AccountStats) {
+    let count =
+        |count: Option<usize>| count.map_or_else(|| "unknown".to_owned(), |c| c.to_string());
+    println!("Forum posts made: {}", count(stats.posts_made));
+    println!("Kudos earned: {}", count(stats.kudos_earned));
+}
+
+impl Stats {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self { account, format } = self;
+
+        let history = HistoryStats::of(&read_history(&context.profile)?, Utc::now());
+        if history.is_none() && !account {
+            return Err("no snapshots have been recorded yet".into());
+        }
+        let account = if account {
+            Some(fetch_account_stats(context)?)
+        } else {
+            None
+        };
+
+        match format {
+            Format::Human => {
+                if let Some(history) = &history {
+                    history.print();
+                }
+                if let Some(account) = &account {
+                    if history.is_some() {
+                        println!();
+                    }
+                    print_account_stats(account);
+                }
+            }
+            Format::Json => print_json(&Report { history, account })?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::test_support::{noon, snapshot},
+    };
+
+    #[test]
+    fn counts_solved_over_time() {
+        let snapshots = [snapshot(3, 1, 10), snapshot(3, 8, 14), snapshot(3, 15, 20)];
+        assert_eq!(solved_at(&snapshots, noon(2, 1)), 10);
+        assert_eq!(solved_at(&snapshots, noon(3, 8)), 14);
+        assert_eq!(solved_at(&snapshots, noon(3, 10)), 14);
+        assert_eq!(solved_in_last(&snapshots, 7, noon(3, 15)), 6);
+        assert_eq!(solved_in_last(&snapshots, 30, noon(3, 15)), 10);
+        assert_eq!(weekly_rate(&snapshots), Some(5.0));
+        assert_eq!(weekly_rate(&snapshots[..1]), None);
+        assert_eq!(solved_at(&[], noon(3, 1)), 0);
+    }
+
+    #[test]
+    fn counts_weekly_streaks() {
+        // Mondays of March 2024 are the 4th, 11th, 18th and 25th.
+        let snapshots = [
+            snapshot(3, 4, 10),
+            snapshot(3, 12, 11),
+            snapshot(3, 19, 12),
+            snapshot(3, 26, 13),
+        ];
+        assert_eq!(weekly_streak(&snapshots, noon(3, 26)), 3);
+        // Nothing solved yet in the current week doesn't break the streak.
+        assert_eq!(weekly_streak(&snapshots, noon(4, 2)), 3);
+        assert_eq!(weekly_streak(&snapshots, noon(4, 9)), 0);
+        assert_eq!(weekly_streak(&snapshots[..1], noon(3, 4)), 0);
+        assert_eq!(weekly_streak(&[], noon(3, 4)), 0);
+    }
+
+    #[test]
+    fn projects_at_the_recent_rate() {
+        let snapshots = [snapshot(1, 1, 0), snapshot(3, 31, 90)];
+        // 90 solved in the last 90 days is one a day.
+        assert_eq!(project(&snapshots, 100, noon(3, 31)), Some(noon(4, 10)));
+        assert_eq!(project(&snapshots, 50, noon(3, 31)), Some(noon(3, 31)));
+        let idle = [snapshot(1, 1, 90), snapshot(3, 31, 90)];
+        assert_eq!(project(&idle, 100, noon(3, 31)), None);
+    }
+
+    #[test]
+    fn lists_milestones_ahead() {
+        let milestones = milestones(&snapshot(3, 1, 20));
+        assert_eq!(
+            milestones,
+            [("level 1".to_owned(), 25), ("100 problems".to_owned(), 100)]
+        );
+        let milestones = super::milestones(&snapshot(3, 1, 90));
+        assert_eq!(milestones, [("level 4".to_owned(), 100)]);
+    }
+
+    #[test]
+    fn reports_history_statistics() {
+        let snapshots = [snapshot(1, 1, 0), snapshot(3, 31, 90)];
+        let stats = HistoryStats::of(&snapshots, noon(3, 31)).unwrap();
+        assert_eq!(stats.solved, 90);
+        assert_eq!(
+            stats.solved_in_last_days.into_iter().collect::<Vec<_>>(),
+            [(7, 90), (30, 90), (365, 90)]
+        );
+        assert_eq!(
+            stats.projections,
+            [Projection {
+                milestone: "level 4".to_owned(),
+                target: 100,
+                date: Some(noon(4, 10)),
+            }]
+        );
+        assert_eq!(HistoryStats::of(&[], noon(3, 31)), None);
+    }
+
+    #[test]
+    fn reports_statistics_as_json() {
+        let report = Report {
+            history: HistoryStats::of(&[snapshot(3, 1, 10)], noon(3, 1)),
+            account: Some(AccountStats {
+                posts_made: Some(3),
+                kudos_earned: None,
+                problems_solved: Some(10),
+            }),
+        };
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["history"]["solved"], 10);
+        assert_eq!(json["history"]["solved_in_last_days"]["30"], 0);
+        assert!(json["history"]["weekly_rate"].is_null());
+        assert_eq!(json["account"]["posts_made"], 3);
+        assert!(json["account"]["kudos_earned"].is_null());
+
+        let json = serde_json::to_value(&Report {
+            history: None,
+            account: None,
+        })
+        .unwrap();
+        assert_eq!(json, serde_json::json!({}));
+    }
+}
@@ -0,0 +1,319 @@
+use {
+    crate::{
+        context::Context,
+        history::first_seen_solved,
+        output::{print_json, Format},
+        progress::{ProblemStatus, Problems},
+        solved_set::SolvedSet,
+        workspace::Workspace,
+    },
+    chrono::{DateTime, Utc},
+    serde::Serialize,
+    std::{collections::BTreeMap, error::Error, path::PathBuf, str::FromStr},
+    structopt::StructOpt,
+};
+
+/// How a problem's local and remote states relate.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Category {
+    /// Solved on the site, with a local solution.
+    SolvedWithLocal,
+    /// Solved on the site, but without a local solution.
+    SolvedWithoutLocal,
+    /// Not solved on the site, but with a local solution, which is suspicious.
+    LocalUnsolved,
+    /// Neither solved on the site nor with a local solution.
+    Neither,
+}
+
+impl Category {
+    const ALL: &'static [Self] = &[
+        Self::SolvedWithLocal,
+        Self::SolvedWithoutLocal,
+        Self::LocalUnsolved,
+        Self::Neither,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::SolvedWithLocal => "solved-with-local",
+            Self::SolvedWithoutLocal => "solved-without-local",
+            Self::LocalUnsolved => "local-unsolved",
+            Self::Neither => "neither",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Self::SolvedWithLocal => "Solved, with a local solution",
+            Self::SolvedWithoutLocal => "Solved, but without a local solution",
+            Self::LocalUnsolved => "Not solved, but with a local solution (suspicious!)",
+            Self::Neither => "Not solved, without a local solution",
+        }
+    }
+}
+
+impl FromStr for Category {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|category| category.name() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown category {:?}; expected one of {}",
+                    s,
+                    Self::ALL
+                        .iter()
+                        .map(|category| category.name())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Entry {
+    pub number: usize,
+    pub paths: Vec<PathBuf>,
+    /// For solved problems, when they were first seen solved, which is `null` if they were
+    /// solved before the history began. Omitted for unsolved problems.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_seen_solved: Option<Option<DateTime<Utc>>>,
+}
+
+/// Every problem with known local or remote state, grouped by how they relate.
+pub type StatusReport = BTreeMap<Category, Vec<Entry>>;
+
+/// Sorts every problem that's published or has a local solution into a category.
+pub fn categorize(problems: &Problems, local: BTreeMap<usize, Vec<PathBuf>>) -> StatusReport {
+    let mut report = Category::ALL
+        .iter()
+        .map(|&category| (category, Vec::new()))
+        .collect::<StatusReport>();
+    let mut local = local;
+    for (idx, &status) in problems.0.iter().enumerate() {
+        let number = idx + 1;
+        let paths = local.remove(&number).unwrap_or_default();
+        let category = match (status, paths.is_empty()) {
+            (ProblemStatus::Solved, false) => Category::SolvedWithLocal,
+            (ProblemStatus::Solved, true) => Category::SolvedWithoutLocal,
+            // Retired problems can't be solved anymore, so they're only of note with a local
+            // solution, which can't be checked against the site.
+            (_, false) => Category::LocalUnsolved,
+            (ProblemStatus::Retired, true) => continue,
+            (ProblemStatus::Unsolved, true) => Category::Neither,
+        };
+        report.get_mut(&category).unwrap().push(Entry {
+            number,
+            paths,
+            first_seen_solved: None,
+        });
+    }
+    // Local solutions for problems that aren't even published can't have been solved.
+    for (number, paths) in local {
+        report
+            .get_mut(&Category::LocalUnsolved)
+            .unwrap()
+            .push(Entry {
+                number,
+                paths,
+                first_seen_solved: None,
+            });
+    }
+    report
+}
+
+/// Lists the problems of `report` with several local solutions, and which is used for each,
+/// if any.
+fn print_duplicates(workspace: &Workspace, report: &StatusReport) -> Result<(), Box<dyn Error>> {
+    let mut duplicates = Vec::new();
+    for Entry { number, paths, .. } in report.values().flatten() {
+        if paths.len() < 2 {
+            continue;
+        }
+        let candidates = workspace.candidates(*number)?;
+        if candidates.len() > 1 {
+            duplicates.push((*number, candidates));
+        }
+    }
+    if duplicates.is_empty() {
+        return Ok(());
+    }
+    duplicates.sort_by_key(|(number, _)| *number);
+    println!("With several local solutions ({}):", duplicates.len());
+    for (number, candidates) in &duplicates {
+        let paths = candidates
+            .iter()
+            .map(|path| workspace.display_path(path).to_string())
+            .collect::<Vec<_>>();
+        let used = match workspace.preferred(candidates) {
+            Some(preferred) => format!("using {}", workspace.display_path(preferred)),
+            None => "none used until one is preferred".to_owned(),
+        };
+        println!("  {} ({}; {})", number, paths.join(", "), used);
+    }
+    Ok(())
+}
+
+/// Compare local solutions to the problems solved on the site.
+#[derive(Debug, StructOpt)]
+pub struct Status {
+    /// The format to print the status in: `human` or `json`.
+    #[structopt(long, default_value = "human")]
+    format: Format,
+    /// Only show these categories: `solved-with-local`, `solved-without-local`,
+    /// `local-unsolved` or `neither`. Every category except `neither` is listed in full by
+    /// default.
+    #[structopt(long = "only")]
+    only: Vec<Category>,
+    /// Compare against a solved set exported with `cargo euler export` instead of the site.
+    #[structopt(long, parse(from_os_str))]
+    baseline: Option<PathBuf>,
+    /// Use the solution file matching this glob, like `src/bin/p*.rs`, for problems with
+    /// several. Tried before the `layout.prefer` globs in `euler.toml`.
+    #[structopt(long)]
+    prefer: Vec<String>,
+}
+
+impl Status {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self {
+            format,
+            only,
+            baseline,
+            prefer,
+        } = self;
+
+        let mut workspace = Workspace::discover()?;
+        workspace.prefer(prefer);
+        let local = workspace.scan_solutions()?;
+        let problems = match baseline {
+            Some(baseline) => SolvedSet::read(&baseline)?.problems(),
+            None => context.progress()?.problems,
+        };
+        let mut report = categorize(&problems, local);
+        if !only.is_empty() {
+            report.retain(|category, _| only.contains(category));
+        }
+        let first_seen = first_seen_solved(&context.profile)?;
+        for category in &[Category::SolvedWithLocal, Category::SolvedWithoutLocal] {
+            for entry in report.get_mut(category).into_iter().flatten() {
+                entry.first_seen_solved = Some(first_seen.get(&entry.number).copied());
+            }
+        }
+
+        match format {
+            Format::Json => print_json(&report)?,
+            Format::Human => {
+                for (category, entries) in &report {
+                    println!("{} ({}):", category.description(), entries.len());
+                    if *category == Category::Neither && !only.contains(category) {
+                        continue;
+                    }
+                    for Entry { number, paths, .. } in entries {
+                        let paths = paths
+                            .iter()
+                            .map(|path| workspace.display_path(path).to_string())
+                            .collect::<Vec<_>>();
+                        if paths.is_empty() {
+                            println!("  {}", number);
+                        } else {
+                            println!("  {} ({})", number, paths.join(", "));
+                        }
+                    }
+                }
+                print_duplicates(&workspace, &report)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ProblemStatus::*;
+
+    fn numbers(report: &StatusReport, category: Category) -> Vec<usize> {
+        report[&category].iter().map(|entry| entry.number).collect()
+    }
+
+    #[test]
+    fn categorizes_local_and_remote_state() {
+        let problems = Problems(vec![Solved, Solved, Unsolved, Unsolved, Retired, Retired]);
+        let local = [1, 3, 5, 9]
+            .iter()
+            .map(|&number| {
+                (
+                    number,
+                    vec![PathBuf::from(format!("src/bin/p{:03}.rs", number))],
+                )
+            })
+            .collect();
+
+        let report = categorize(&problems, local);
+        assert_eq!(numbers(&report, Category::SolvedWithLocal), [1]);
+        assert_eq!(numbers(&report, Category::SolvedWithoutLocal), [2]);
+        // Unpublished and retired problems can't be solved, so local solutions are suspicious.
+        assert_eq!(numbers(&report, Category::LocalUnsolved), [3, 5, 9]);
+        // Retired problems without local solutions aren't worth mentioning.
+        assert_eq!(numbers(&report, Category::Neither), [4]);
+        assert_eq!(
+            report[&Category::LocalUnsolved][2].paths,
+            [PathBuf::from("src/bin/p009.rs")]
+        );
+    }
+
+    #[test]
+    fn lists_every_category_even_when_empty() {
+        let report = categorize(&Problems(Vec::new()), BTreeMap::new());
+        assert_eq!(report.keys().copied().collect::<Vec<_>>(), Category::ALL);
+        assert!(report.values().all(Vec::is_empty));
+    }
+
+    #[test]
+    fn parses_category_names() {
+        for category in Category::ALL {
+            assert_eq!(category.name().parse::<Category>(), Ok(*category));
+        }
+        assert!("solved".parse::<Category>().is_err());
+    }
+
+    #[test]
+    fn renders_the_same_json_for_the_same_state() {
+        let render = || {
+            let local = vec![
+                (7, vec![PathBuf::from("src/bin/p007.rs")]),
+                (2, vec![PathBuf::from("src/bin/p002.rs")]),
+            ]
+            .into_iter()
+            .collect();
+            serde_json::to_string_pretty(&categorize(&Problems(vec![Solved, Unsolved]), local))
+                .unwrap()
+        };
+        let json = render();
+        assert_eq!(json, render());
+        // Categories are listed in the order they're declared in, and problems ascending.
+        let positions = [
+            "solved-with-local",
+            "solved-without-local",
+            "local-unsolved",
+            "neither",
+        ]
+        .iter()
+        .map(|name| json.find(&format!("\"{}\"", name)).unwrap())
+        .collect::<Vec<_>>();
+        assert!(
+            positions.windows(2).all(|pair| pair[0] < pair[1]),
+            "{}",
+            json
+        );
+        assert!(json.find("p002.rs").unwrap() < json.find("p007.rs").unwrap());
+    }
+}
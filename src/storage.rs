@@ -0,0 +1,297 @@
+//! Where data accumulated over time is kept: progress snapshots, submission attempts,
+//! benchmark results and cached statements. By default, each kind of data is kept in files of
+//! its own; with the `sqlite` feature, `storage = "sqlite"` in the user configuration keeps them
+//! all in a single database instead.
+
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use {
+    crate::{
+        bench::BenchRecord,
+        history::Snapshot,
+        paths::{cache_dir, create_cache_dir, profile_data_dir, write_atomically},
+        statement::Statement,
+        submissions::Attempt,
+        user_config::UserConfig,
+    },
+    chrono::{DateTime, Utc},
+    log::warn,
+    serde::{de::DeserializeOwned, Deserialize, Serialize},
+    std::{
+        collections::BTreeMap,
+        error::Error,
+        fs::{create_dir_all, read_to_string, OpenOptions},
+        io::{ErrorKind, Read, Seek, SeekFrom, Write},
+        path::{Path, PathBuf},
+    },
+    structopt::StructOpt,
+};
+
+const HISTORY_FILE_NAME: &str = "history.jsonl";
+const SUBMISSIONS_FILE_NAME: &str = "submissions.json";
+const BENCH_HISTORY_FILE_NAME: &str = "bench-history.json";
+const FIRST_SEEN_SOLVED_FILE_NAME: &str = "first-seen-solved.json";
+/// The directory of the cache directory that statements are cached in.
+pub const STATEMENTS_DIR_NAME: &str = "statements";
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum StorageKind {
+    #[default]
+    Files,
+    Sqlite,
+}
+
+/// A place to keep the data of a profile. Statements aren't specific to any profile, and are
+/// shared between them.
+pub trait Store {
+    /// Every progress snapshot recorded, oldest first.
+    fn snapshots(&self) -> Result<Vec<Snapshot>, Box<dyn Error>>;
+    /// Durably records a progress snapshot.
+    fn append_snapshot(&self, snapshot: &Snapshot) -> Result<(), Box<dyn Error>>;
+    /// Atomically replaces every snapshot recorded with `snapshots`, like after thinning them
+    /// out.
+    fn replace_snapshots(&self, snapshots: &[&Snapshot]) -> Result<(), Box<dyn Error>>;
+    /// When each problem seen being solved was first seen solved.
+    fn first_seen_solved(&self) -> Result<BTreeMap<usize, DateTime<Utc>>, Box<dyn Error>>;
+    /// Records that `problems` were first seen solved at `timestamp`, keeping any earlier record
+    /// of the same problem.
+    fn record_first_seen_solved(
+        &self,
+        problems: &[usize],
+        timestamp: DateTime<Utc>,
+    ) -> Result<(), Box<dyn Error>>;
+    /// Every submission attempt recorded, oldest first.
+    fn attempts(&self) -> Result<Vec<Attempt>, Box<dyn Error>>;
+    fn append_attempt(&self, attempt: &Attempt) -> Result<(), Box<dyn Error>>;
+    /// Every benchmark result recorded, oldest first.
+    fn bench_records(&self) -> Result<Vec<BenchRecord>, Box<dyn Error>>;
+    fn append_bench_records(&self, records: &[BenchRecord]) -> Result<(), Box<dyn Error>>;
+    /// The cached statement of problem `number`, if it's been cached.
+    fn statement(&self, number: usize) -> Result<Option<Statement>, Box<dyn Error>>;
+    fn cache_statement(&self, statement: &Statement) -> Result<(), Box<dyn Error>>;
+}
+
+/// Opens the store configured by the user for `profile`.
+pub fn open(profile: &str) -> Result<Box<dyn Store>, Box<dyn Error>> {
+    match UserConfig::load()?.storage {
+        StorageKind::Files => Ok(Box::new(FileStore::new(profile))),
+        #[cfg(feature = "sqlite")]
+        StorageKind::Sqlite => Ok(Box::new(sqlite::SqliteStore::open(profile)?)),
+        #[cfg(not(feature = "sqlite"))]
+        StorageKind::Sqlite => Err(format!(
+            "{} asks for `storage = \"sqlite\"`, but this build of cargo-euler doesn't have the \
+             `sqlite` feature",
+            UserConfig::path().display()
+        )
+        .into()),
+    }
+}
+
+/// Opens the store configured by the user for data shared between profiles, like statements.
+pub fn open_shared() -> Result<Box<dyn Store>, Box<dyn Error>> {
+    open("")
+}
+
+fn read_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T, Box<dyn Error>> {
+    match read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| format!("unable to parse {}: {}", path.display(), e).into()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(format!("unable to read {}: {}", path.display(), e).into()),
+    }
+}
+
+fn write_json(path: &Path, value: &impl Serialize) -> Result<(), Box<dyn Error>> {
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+    }
+    write_atomically(path, serde_json::to_string_pretty(value)?)?;
+    Ok(())
+}
+
+/// Reads JSON lines from `path`, skipping (with a warning) lines that can't be parsed, like one
+/// cut off by a crash.
+fn read_json_lines<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>, Box<dyn Error>> {
+    let contents = match read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("unable to read {}: {}", path.display(), e).into()),
+    };
+    let mut values = Vec::new();
+    for (idx, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(value) => values.push(value),
+            Err(e) => warn!(
+                "skipping corrupt entry on line {} of {}: {}",
+                idx + 1,
+                path.display(),
+                e
+            ),
+        }
+    }
+    Ok(values)
+}
+
+/// Durably appends `value` to `path` as a JSON line.
+fn append_json_line(path: &Path, value: &impl Serialize) -> Result<(), Box<dyn Error>> {
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new()
+        .read(true)
+        .create(true)
+        .append(true)
+        .open(path)?;
+    let mut line = String::new();
+    // If a crash cut the last line short, start a new line rather than corrupting this one too.
+    if file.metadata()?.len() > 0 {
+        let mut last = [0];
+        file.seek(SeekFrom::End(-1))?;
+        file.read_exact(&mut last)?;
+        if last != *b"\n" {
+            line.push('\n');
+        }
+    }
+    line.push_str(&serde_json::to_string(value)?);
+    line.push('\n');
+    file.write_all(line.as_bytes())?;
+    file.sync_data()?;
+    Ok(())
+}
+
+/// Keeps each kind of data in files of its own in the data directory of the profile, and
+/// statements in the cache directory.
+#[derive(Debug)]
+pub struct FileStore {
+    profile_dir: PathBuf,
+    statements_dir: PathBuf,
+}
+
+impl FileStore {
+    pub fn new(profile: &str) -> Self {
+        Self {
+            profile_dir: profile_data_dir(profile),
+            statements_dir: cache_dir().join(STATEMENTS_DIR_NAME),
+        }
+    }
+
+    fn statement_path(&self, number: usize) -> PathBuf {
+        self.statements_dir.join(format!("{}.json", number))
+    }
+}
+
+impl Store for FileStore {
+    fn snapshots(&self) -> Result<Vec<Snapshot>, Box<dyn Error>> {
+        read_json_lines(&self.profile_dir.join(HISTORY_FILE_NAME))
+    }
+
+    fn append_snapshot(&self, snapshot: &Snapshot) -> Result<(), Box<dyn Error>> {
+        append_json_line(&self.profile_dir.join(HISTORY_FILE_NAME), snapshot)
+    }
+
+    fn replace_snapshots(&self, snapshots: &[&Snapshot]) -> Result<(), Box<dyn Error>> {
+        let mut lines = String::new();
+        for snapshot in snapshots {
+            lines.push_str(&serde_json::to_string(snapshot)?);
+            lines.push('\n');
+        }
+        create_dir_all(&self.profile_dir)?;
+        write_atomically(&self.profile_dir.join(HISTORY_FILE_NAME), lines)?;
+        Ok(())
+    }
+
+    fn first_seen_solved(&self) -> Result<BTreeMap<usize, DateTime<Utc>>, Box<dyn Error>> {
+        read_json(&self.profile_dir.join(FIRST_SEEN_SOLVED_FILE_NAME))
+    }
+
+    fn record_first_seen_solved(
+        &self,
+        problems: &[usize],
+        timestamp: DateTime<Utc>,
+    ) -> Result<(), Box<dyn Error>> {
+        let path = self.profile_dir.join(FIRST_SEEN_SOLVED_FILE_NAME);
+        let mut first_seen = read_json::<BTreeMap<usize, DateTime<Utc>>>(&path)?;
+        for &problem in problems {
+            first_seen.entry(problem).or_insert(timestamp);
+        }
+        write_json(&path, &first_seen)
+    }
+
+    fn attempts(&self) -> Result<Vec<Attempt>, Box<dyn Error>> {
+        read_json(&self.profile_dir.join(SUBMISSIONS_FILE_NAME))
+    }
+
+    fn append_attempt(&self, attempt: &Attempt) -> Result<(), Box<dyn Error>> {
+        let path = self.profile_dir.join(SUBMISSIONS_FILE_NAME);
+        let mut attempts = read_json::<Vec<serde_json::Value>>(&path)?;
+        attempts.push(serde_json::to_value(attempt)?);
+        write_json(&path, &attempts)
+    }
+
+    fn bench_records(&self) -> Result<Vec<BenchRecord>, Box<dyn Error>> {
+        read_json(&self.profile_dir.join(BENCH_HISTORY_FILE_NAME))
+    }
+
+    fn append_bench_records(&self, records: &[BenchRecord]) -> Result<(), Box<dyn Error>> {
+        let path = self.profile_dir.join(BENCH_HISTORY_FILE_NAME);
+        let mut history = read_json::<Vec<serde_json::Value>>(&path)?;
+        for record in records {
+            history.push(serde_json::to_value(record)?);
+        }
+        write_json(&path, &history)
+    }
+
+    fn statement(&self, number: usize) -> Result<Option<Statement>, Box<dyn Error>> {
+        let path = self.statement_path(number);
+        match read_to_string(&path) {
+            Ok(cached) => match serde_json::from_str(&cached) {
+                Ok(statement) => Ok(Some(statement)),
+                Err(e) => {
+                    warn!(
+                        "ignoring corrupt cached statement {}: {}",
+                        path.display(),
+                        e
+                    );
+                    Ok(None)
+                }
+            },
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => {
+                Err(format!("unable to read cached statement {}: {}", path.display(), e).into())
+            }
+        }
+    }
+
+    fn cache_statement(&self, statement: &Statement) -> Result<(), Box<dyn Error>> {
+        let path = self.statement_path(statement.number);
+        create_cache_dir(&self.statements_dir)?;
+        std::fs::write(&path, serde_json::to_string(statement)?)?;
+        Ok(())
+    }
+}
+
+/// Manage where data is stored.
+#[derive(Debug, StructOpt)]
+pub enum StorageCommand {
+    /// Import the data kept in files into the SQLite database, for switching to
+    /// `storage = "sqlite"`. Profiles already in the database are left alone.
+    Migrate,
+}
+
+impl StorageCommand {
+    pub fn run(self) -> Result<(), Box<dyn Error>> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Self::Migrate => sqlite::migrate_from_files(),
+            #[cfg(not(feature = "sqlite"))]
+            Self::Migrate => {
+                Err("this build of cargo-euler doesn't have the `sqlite` feature".into())
+            }
+        }
+    }
+}
@@ -0,0 +1,461 @@
+//! Keeping all data in a single SQLite database, with JSON documents for the data itself and
+//! columns for what it's looked up by.
+
+use {
+    super::{FileStore, Store},
+    crate::{
+        bench::BenchRecord, history::Snapshot, paths::data_dir, statement::Statement,
+        submissions::Attempt,
+    },
+    chrono::{DateTime, Utc},
+    rusqlite::{params, Connection, OptionalExtension},
+    serde::de::DeserializeOwned,
+    std::{
+        collections::BTreeMap,
+        error::Error,
+        fs::{create_dir_all, read_dir},
+        io::ErrorKind,
+        path::PathBuf,
+    },
+};
+
+const DATABASE_FILE_NAME: &str = "cargo-euler.db";
+
+/// The statements bringing the schema from each version to the next, where the schema of
+/// version `n` is the result of running the first `n` of them. Released migrations must never
+/// change; add new ones to the end instead.
+const MIGRATIONS: &[&str] = &[
+    "
+    CREATE TABLE snapshots (
+        id INTEGER PRIMARY KEY,
+        profile TEXT NOT NULL,
+        timestamp TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX snapshots_by_profile ON snapshots (profile, timestamp);
+    CREATE TABLE attempts (
+        id INTEGER PRIMARY KEY,
+        profile TEXT NOT NULL,
+        problem INTEGER NOT NULL,
+        timestamp TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX attempts_by_profile ON attempts (profile, problem);
+    CREATE TABLE bench_records (
+        id INTEGER PRIMARY KEY,
+        profile TEXT NOT NULL,
+        problem INTEGER NOT NULL,
+        timestamp TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX bench_records_by_profile ON bench_records (profile, problem);
+    CREATE TABLE statements (
+        number INTEGER PRIMARY KEY,
+        title TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+",
+    "
+    CREATE TABLE first_seen_solved (
+        profile TEXT NOT NULL,
+        problem INTEGER NOT NULL,
+        timestamp TEXT NOT NULL,
+        PRIMARY KEY (profile, problem)
+    );
+",
+];
+
+pub fn database_path() -> PathBuf {
+    data_dir().join(DATABASE_FILE_NAME)
+}
+
+/// Brings the schema of the database up to date, refusing to touch databases from newer
+/// versions of cargo-euler.
+fn migrate_schema(connection: &mut Connection) -> Result<(), Box<dyn Error>> {
+    connection.execute_batch(
+        "CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);",
+    )?;
+    let version = connection
+        .query_row(
+            "SELECT value FROM meta WHERE key = 'schema_version'",
+            [],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()?
+        .map_or(Ok(0), |version| version.parse::<usize>())?;
+    if version > MIGRATIONS.len() {
+        return Err(format!(
+            "{} is from a newer version of cargo-euler (schema version {})",
+            database_path().display(),
+            version
+        )
+        .into());
+    }
+
+    let transaction = connection.transaction()?;
+    for migration in &MIGRATIONS[version..] {
+        transaction.execute_batch(migration)?;
+    }
+    transaction.execute(
+        "INSERT OR REPLACE INTO meta (key, value) VALUES ('schema_version', ?1)",
+        params![MIGRATIONS.len().to_string()],
+    )?;
+    transaction.commit()?;
+    Ok(())
+}
+
+fn parse_rows<T: DeserializeOwned>(rows: Vec<String>) -> Result<Vec<T>, Box<dyn Error>> {
+    rows.iter()
+        .map(|data| Ok(serde_json::from_str(data)?))
+        .collect()
+}
+
+#[derive(Debug)]
+pub struct SqliteStore {
+    connection: Connection,
+    profile: String,
+}
+
+impl SqliteStore {
+    pub fn open(profile: &str) -> Result<Self, Box<dyn Error>> {
+        let path = database_path();
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+        let mut connection = Connection::open(&path)
+            .map_err(|e| format!("unable to open {}: {}", path.display(), e))?;
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        migrate_schema(&mut connection)?;
+        Ok(Self {
+            connection,
+            profile: profile.to_owned(),
+        })
+    }
+
+    fn select(&self, table: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT data FROM {} WHERE profile = ?1 ORDER BY id",
+            table
+        ))?;
+        let rows = statement
+            .query_map(params![self.profile], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        Ok(rows)
+    }
+
+    fn has_profile_data(&self) -> Result<bool, Box<dyn Error>> {
+        Ok(self.connection.query_row(
+            "SELECT EXISTS (SELECT 1 FROM snapshots WHERE profile = ?1)
+                OR EXISTS (SELECT 1 FROM attempts WHERE profile = ?1)
+                OR EXISTS (SELECT 1 FROM bench_records WHERE profile = ?1)",
+            params![self.profile],
+            |row| row.get(0),
+        )?)
+    }
+}
+
+impl Store for SqliteStore {
+    fn snapshots(&self) -> Result<Vec<Snapshot>, Box<dyn Error>> {
+        parse_rows(self.select("snapshots")?)
+    }
+
+    fn append_snapshot(&self, snapshot: &Snapshot) -> Result<(), Box<dyn Error>> {
+        self.connection.execute(
+            "INSERT INTO snapshots (profile, timestamp, data) VALUES (?1, ?2, ?3)",
+            params![
+                self.profile,
+                snapshot.timestamp.to_rfc3339(),
+                serde_json::to_string(snapshot)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn replace_snapshots(&self, snapshots: &[&Snapshot]) -> Result<(), Box<dyn Error>> {
+        let transaction = self.connection.unchecked_transaction()?;
+        transaction.execute(
+            "DELETE FROM snapshots WHERE profile = ?1",
+            params![self.profile],
+        )?;
+        for snapshot in snapshots {
+            self.append_snapshot(snapshot)?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn first_seen_solved(&self) -> Result<BTreeMap<usize, DateTime<Utc>>, Box<dyn Error>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT problem, timestamp FROM first_seen_solved WHERE profile = ?1")?;
+        let rows = statement
+            .query_map(params![self.profile], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.into_iter()
+            .map(|(problem, timestamp)| {
+                Ok((
+                    problem as usize,
+                    DateTime::parse_from_rfc3339(&timestamp)?.with_timezone(&Utc),
+                ))
+            })
+            .collect()
+    }
+
+    fn record_first_seen_solved(
+        &self,
+        problems: &[usize],
+        timestamp: DateTime<Utc>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut statement = self.connection.prepare(
+            "INSERT OR IGNORE INTO first_seen_solved (profile, problem, timestamp) \
+             VALUES (?1, ?2, ?3)",
+        )?;
+        for &problem in problems {
+            statement.execute(params![
+                self.profile,
+                problem as i64,
+                timestamp.to_rfc3339()
+            ])?;
+        }
+        Ok(())
+    }
+
+    fn attempts(&self) -> Result<Vec<Attempt>, Box<dyn Error>> {
+        parse_rows(self.select("attempts")?)
+    }
+
+    fn append_attempt(&self, attempt: &Attempt) -> Result<(), Box<dyn Error>> {
+        self.connection.execute(
+            "INSERT INTO attempts (profile, problem, timestamp, data) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                self.profile,
+                attempt.problem as i64,
+                attempt.timestamp.to_rfc3339(),
+                serde_json::to_string(attempt)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn bench_records(&self) -> Result<Vec<BenchRecord>, Box<dyn Error>> {
+        parse_rows(self.select("bench_records")?)
+    }
+
+    fn append_bench_records(&self, records: &[BenchRecord]) -> Result<(), Box<dyn Error>> {
+        let mut statement = self.connection.prepare(
+            "INSERT INTO bench_records (profile, problem, timestamp, data) \
+             VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for record in records {
+            statement.execute(params![
+                self.profile,
+                record.problem as i64,
+                record.timestamp.to_rfc3339(),
+                serde_json::to_string(record)?
+            ])?;
+        }
+        Ok(())
+    }
+
+    fn statement(&self, number: usize) -> Result<Option<Statement>, Box<dyn Error>> {
+        let data = self
+            .connection
+            .query_row(
+                "SELECT data FROM statements WHERE number = ?1",
+                params![number as i64],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        Ok(data.map(|data| serde_json::from_str(&data)).transpose()?)
+    }
+
+    fn cache_statement(&self, statement: &Statement) -> Result<(), Box<dyn Error>> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO statements (number, title, data) VALUES (?1, ?2, ?3)",
+            params![
+                statement.number as i64,
+                statement.title,
+                serde_json::to_string(statement)?
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+/// Imports the data of every profile kept in files, and every statement cached in files.
+/// Profiles that already have data in the database are skipped, so this can be run again
+/// safely.
+pub fn migrate_from_files() -> Result<(), Box<dyn Error>> {
+    let entries = match read_dir(data_dir()) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut profiles = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            profiles.extend(entry.file_name().to_str().map(str::to_owned));
+        }
+    }
+    profiles.sort();
+
+    for profile in &profiles {
+        let store = SqliteStore::open(profile)?;
+        if store.has_profile_data()? {
+            println!(
+                "Skipping profile {:?}, which is already in the database",
+                profile
+            );
+            continue;
+        }
+        let files = FileStore::new(profile);
+        let snapshots = files.snapshots()?;
+        let attempts = files.attempts()?;
+        let records = files.bench_records()?;
+        let first_seen = files.first_seen_solved()?;
+
+        store.connection.execute_batch("BEGIN")?;
+        for (&problem, &timestamp) in &first_seen {
+            store.record_first_seen_solved(&[problem], timestamp)?;
+        }
+        for snapshot in &snapshots {
+            store.append_snapshot(snapshot)?;
+        }
+        for attempt in &attempts {
+            store.append_attempt(attempt)?;
+        }
+        store.append_bench_records(&records)?;
+        store.connection.execute_batch("COMMIT")?;
+        println!(
+            "Imported profile {:?}: {} snapshots, {} submissions and {} benchmark results",
+            profile,
+            snapshots.len(),
+            attempts.len(),
+            records.len()
+        );
+    }
+
+    let store = SqliteStore::open("")?;
+    let files = FileStore::new("");
+    let mut imported = 0;
+    let entries = match read_dir(&files.statements_dir) {
+        Ok(entries) => entries.collect::<Result<Vec<_>, _>>()?,
+        Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    store.connection.execute_batch("BEGIN")?;
+    for entry in entries {
+        let number = entry
+            .path()
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse().ok());
+        if let Some(statement) = number
+            .map(|number| files.statement(number))
+            .transpose()?
+            .flatten()
+        {
+            store.cache_statement(&statement)?;
+            imported += 1;
+        }
+    }
+    store.connection.execute_batch("COMMIT")?;
+    println!("Imported {} cached statements", imported);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            statement::Attachment,
+            submit::SubmissionOutcome,
+            test_support::{lock_globals, noon, snapshot},
+        },
+        serde::Serialize,
+        std::time::Duration,
+    };
+
+    fn json(value: &impl Serialize) -> serde_json::Value {
+        serde_json::to_value(value).unwrap()
+    }
+
+    /// Checks that `store` holds just what `files` does.
+    fn assert_same_data(store: &SqliteStore, files: &FileStore) {
+        assert_eq!(
+            json(&store.snapshots().unwrap()),
+            json(&files.snapshots().unwrap())
+        );
+        assert_eq!(
+            store.first_seen_solved().unwrap(),
+            files.first_seen_solved().unwrap()
+        );
+        assert_eq!(
+            json(&store.attempts().unwrap()),
+            json(&files.attempts().unwrap())
+        );
+        assert_eq!(
+            json(&store.bench_records().unwrap()),
+            json(&files.bench_records().unwrap())
+        );
+    }
+
+    #[test]
+    fn imports_data_kept_in_files() {
+        let _globals = lock_globals();
+        let files = FileStore::new("default");
+        for snapshot in &[snapshot(3, 1, 10), snapshot(3, 2, 12)] {
+            files.append_snapshot(snapshot).unwrap();
+        }
+        files
+            .record_first_seen_solved(&[11, 12], noon(3, 2))
+            .unwrap();
+        for outcome in &[SubmissionOutcome::Incorrect, SubmissionOutcome::Correct] {
+            files
+                .append_attempt(&Attempt {
+                    problem: 12,
+                    timestamp: noon(3, 2),
+                    outcome: outcome.clone(),
+                })
+                .unwrap();
+        }
+        files
+            .append_bench_records(&[BenchRecord {
+                problem: 12,
+                commit: Some("0123abc".to_owned()),
+                timestamp: noon(3, 2),
+                runs: 10,
+                min: Duration::from_micros(90),
+                median: Duration::from_micros(100),
+                max: Duration::from_micros(250),
+            }])
+            .unwrap();
+        let statement = Statement {
+            number: 22,
+            title: "Names Scores".to_owned(),
+            paragraphs: vec!["Using names.txt, a 46K text file…".to_owned()],
+            attachments: vec![Attachment {
+                name: "names.txt".to_owned(),
+                url: "https://projecteuler.net/resources/documents/0022_names.txt".to_owned(),
+            }],
+        };
+        FileStore::new("").cache_statement(&statement).unwrap();
+
+        migrate_from_files().unwrap();
+        let store = SqliteStore::open("default").unwrap();
+        assert_same_data(&store, &files);
+        assert_eq!(store.snapshots().unwrap().len(), 2);
+        assert_eq!(
+            json(&SqliteStore::open("").unwrap().statement(22).unwrap()),
+            json(&Some(statement))
+        );
+
+        // The profile's already in the database now, so migrating again duplicates nothing.
+        migrate_from_files().unwrap();
+        assert_same_data(&store, &files);
+    }
+}
@@ -0,0 +1,222 @@
+//! The local record of submitted answers, used to respect the wait the site enforces between
+//! incorrect submissions.
+
+use {
+    crate::{context::Context, storage, submit::SubmissionOutcome},
+    chrono::{DateTime, Utc},
+    log::warn,
+    serde::{Deserialize, Serialize},
+    std::{error::Error, time::Duration},
+    structopt::StructOpt,
+};
+
+/// How long to wait after an incorrect submission before submitting to the same problem again,
+/// when the site didn't say.
+pub const COOLDOWN: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Attempt {
+    pub problem: usize,
+    pub timestamp: DateTime<Utc>,
+    pub outcome: SubmissionOutcome,
+}
+
+impl Attempt {
+    /// How long submissions to the same problem should wait after this attempt.
+    fn cooldown(&self) -> Option<Duration> {
+        match self.outcome {
+            SubmissionOutcome::Incorrect => Some(COOLDOWN),
+            SubmissionOutcome::RateLimited(wait) => Some(wait.unwrap_or(COOLDOWN)),
+            SubmissionOutcome::Correct | SubmissionOutcome::WrongCaptcha => None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Submissions(pub Vec<Attempt>);
+
+impl Submissions {
+    pub fn load(profile: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Self(storage::open(profile)?.attempts()?))
+    }
+
+    /// Durably records `attempt` for `profile`.
+    pub fn record(profile: &str, attempt: &Attempt) -> Result<(), Box<dyn Error>> {
+        storage::open(profile)?.append_attempt(attempt)
+    }
+
+    pub fn for_problem(&self, problem: usize) -> impl Iterator<Item = &Attempt> {
+        self.0
+            .iter()
+            .filter(move |attempt| attempt.problem == problem)
+    }
+
+    /// How much longer to wait before submitting to `problem` again, if at all. That's up to the
+    /// last attempt the site checked the answer of, so a correct answer ends the wait, while a
+    /// wrong captcha leaves it be.
+    pub fn remaining_cooldown(&self, problem: usize, now: DateTime<Utc>) -> Option<Duration> {
+        let last = self
+            .for_problem(problem)
+            .filter(|attempt| attempt.outcome != SubmissionOutcome::WrongCaptcha)
+            .max_by_key(|attempt| attempt.timestamp)?;
+        let cooldown = last.cooldown()?;
+        if last.timestamp > now {
+            // Waiting out a cooldown that starts in the future could take forever if the clock was
+            // wrong when the attempt was recorded, so give the benefit of the doubt.
+            warn!(
+                "the last submission to problem {} is recorded in the future ({}); is your clock \
+                 right?",
+                problem, last.timestamp
+            );
+            return None;
+        }
+        let elapsed = (now - last.timestamp).to_std().unwrap_or_default();
+        cooldown
+            .checked_sub(elapsed)
+            .filter(|remaining| !remaining.is_zero())
+    }
+}
+
+impl SubmissionOutcome {
+    fn description(&self) -> String {
+        match self {
+            Self::Correct => "correct".to_owned(),
+            Self::Incorrect => "incorrect".to_owned(),
+            Self::WrongCaptcha => "wrong captcha".to_owned(),
+            Self::RateLimited(Some(wait)) => format!("rate limited ({} seconds)", wait.as_secs()),
+            Self::RateLimited(None) => "rate limited".to_owned(),
+        }
+    }
+}
+
+/// List the answers submitted to a problem.
+#[derive(Debug, StructOpt)]
+pub struct SubmissionsCommand {
+    /// The number of the problem whose submissions to list.
+    number: usize,
+}
+
+impl SubmissionsCommand {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self { number } = self;
+        let submissions = Submissions::load(&context.profile)?;
+        let mut any = false;
+        for attempt in submissions.for_problem(number) {
+            any = true;
+            println!(
+                "{}  {}",
+                attempt.timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
+                attempt.outcome.description()
+            );
+        }
+        if !any {
+            println!("No answers have been submitted to problem {}.", number);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::test_support::{noon, with_each_backend},
+        chrono::Duration as TimeDelta,
+    };
+
+    /// Attempts at problem 1 with `outcomes`, as many seconds before noon on 1 March as given.
+    fn submissions(attempts: &[(i64, SubmissionOutcome)]) -> Submissions {
+        Submissions(
+            attempts
+                .iter()
+                .map(|(seconds_ago, outcome)| Attempt {
+                    problem: 1,
+                    timestamp: noon(3, 1) - TimeDelta::seconds(*seconds_ago),
+                    outcome: outcome.clone(),
+                })
+                .collect(),
+        )
+    }
+
+    fn remaining(attempts: &[(i64, SubmissionOutcome)]) -> Option<Duration> {
+        submissions(attempts).remaining_cooldown(1, noon(3, 1))
+    }
+
+    #[test]
+    fn waits_out_the_cooldown_after_an_incorrect_answer() {
+        use self::SubmissionOutcome::*;
+
+        assert_eq!(remaining(&[]), None);
+        assert_eq!(remaining(&[(10, Incorrect)]), Some(Duration::from_secs(50)));
+        assert_eq!(remaining(&[(60, Incorrect)]), None);
+        assert_eq!(remaining(&[(3_600, Incorrect)]), None);
+        assert_eq!(
+            submissions(&[(10, Incorrect)]).remaining_cooldown(2, noon(3, 1)),
+            None
+        );
+    }
+
+    #[test]
+    fn waits_as_long_as_the_site_said() {
+        use self::SubmissionOutcome::*;
+
+        assert_eq!(
+            remaining(&[(100, RateLimited(Some(Duration::from_secs(300))))]),
+            Some(Duration::from_secs(200))
+        );
+        assert_eq!(
+            remaining(&[(15, RateLimited(None))]),
+            Some(Duration::from_secs(45))
+        );
+    }
+
+    #[test]
+    fn goes_by_the_last_checked_answer() {
+        use self::SubmissionOutcome::*;
+
+        // Solving the problem ends the wait.
+        assert_eq!(remaining(&[(20, Incorrect), (10, Correct)]), None);
+        assert_eq!(
+            remaining(&[(40, Correct), (20, Incorrect)]),
+            Some(Duration::from_secs(40))
+        );
+        // A wrong captcha doesn't get an answer checked, so the site still makes you wait.
+        assert_eq!(
+            remaining(&[(20, Incorrect), (10, WrongCaptcha)]),
+            Some(Duration::from_secs(40))
+        );
+        assert_eq!(remaining(&[(10, WrongCaptcha)]), None);
+    }
+
+    #[test]
+    fn gives_attempts_recorded_in_the_future_the_benefit_of_the_doubt() {
+        use self::SubmissionOutcome::*;
+
+        assert_eq!(remaining(&[(-30, Incorrect)]), None);
+        assert_eq!(remaining(&[(10, Incorrect), (-30, Incorrect)]), None);
+    }
+
+    #[test]
+    fn records_attempts_in_the_order_they_were_made() {
+        with_each_backend(|| {
+            assert!(Submissions::load("default").unwrap().0.is_empty());
+            let attempts = submissions(&[
+                (120, SubmissionOutcome::Incorrect),
+                (
+                    60,
+                    SubmissionOutcome::RateLimited(Some(Duration::from_secs(30))),
+                ),
+                (0, SubmissionOutcome::Correct),
+            ]);
+            for attempt in &attempts.0 {
+                Submissions::record("default", attempt).unwrap();
+            }
+            let loaded = Submissions::load("default").unwrap();
+            assert_eq!(
+                serde_json::to_value(&loaded.0).unwrap(),
+                serde_json::to_value(&attempts.0).unwrap()
+            );
+            assert!(Submissions::load("other").unwrap().0.is_empty());
+        });
+    }
+}
@@ -0,0 +1,638 @@
+//! Submitting answers through the form on a problem's page.
+
+use {
+    crate::{
+        answers::{store_hashed, Answers},
+        captcha::{self, CaptchaPrompt, TerminalPrompt},
+        client::Session,
+        context::Context,
+        endpoints::{problem_url, thread_url},
+        form,
+        output::confirm,
+        progress::Progress,
+        runner::{Solution, TimeLimit},
+        submissions::{Attempt, Submissions},
+        thread::Thread,
+        workspace::Workspace,
+        Failure,
+    },
+    chrono::Utc,
+    log::warn,
+    reqwest::Url,
+    serde::{Deserialize, Serialize},
+    std::{
+        error::Error,
+        fs::{create_dir_all, write},
+        io::{stdin, IsTerminal, Read},
+        time::Duration,
+    },
+    structopt::StructOpt,
+    unhtml::scraper::{Html, Selector},
+};
+
+/// How many times to try solving captchas before giving up on a submission.
+const CAPTCHA_ATTEMPTS: usize = 3;
+
+/// Submit an answer to a problem.
+#[derive(Debug, StructOpt)]
+pub struct Submit {
+    /// The number of the problem to submit an answer to.
+    number: usize,
+    /// The answer to submit, or `-` to read it from standard input.
+    #[structopt(required_unless = "from-run", conflicts_with = "from-run")]
+    answer: Option<String>,
+    /// Build and run the local solution with optimizations like `cargo euler run --release`,
+    /// and submit its answer after confirming it.
+    #[structopt(long)]
+    from_run: bool,
+    /// Don't ask for confirmation before submitting the answer of `--from-run`.
+    #[structopt(long)]
+    yes: bool,
+    /// Submit even if the problem is already solved, or the answer of `--from-run` disagrees
+    /// with the answer stored for the problem.
+    #[structopt(long)]
+    force: bool,
+    /// After a correct answer, open the discussion thread of the problem in the browser.
+    #[structopt(long)]
+    open_thread: bool,
+    /// After a correct answer, save the first page of the discussion thread of the problem to
+    /// `threads/` in the workspace.
+    #[structopt(long)]
+    save_thread: bool,
+    /// Submit even if an incorrect answer was submitted to the problem too recently.
+    #[structopt(long)]
+    ignore_cooldown: bool,
+    /// With `--dry-run`, show the answer that would be submitted instead of redacting it.
+    #[structopt(long)]
+    show_answer: bool,
+}
+
+/// The answer form of a problem's page, which is only there for signed-in users who haven't
+/// solved the problem yet.
+#[derive(Debug)]
+pub struct SubmissionForm {
+    /// Where the form is submitted to.
+    pub action: Url,
+    /// Hidden fields that must be submitted as-is, like CSRF tokens.
+    pub hidden: Vec<(String, String)>,
+    /// The name of the field the answer goes in.
+    pub answer_field: String,
+    /// The name of the field the solved captcha goes in, and the image to solve it from.
+    pub captcha: Option<(String, Url)>,
+}
+
+impl SubmissionForm {
+    pub fn from_problem_page(number: usize, page: &str) -> Result<Self, Box<dyn Error>> {
+        let page_url = Url::parse(&problem_url(number))?;
+        let html = Html::parse_document(page);
+
+        let is_answer_field = |name: &str| name.starts_with("guess");
+        let form = form::find(&html, is_answer_field).ok_or_else(|| {
+            format!(
+                "unable to find the answer form of problem {}; make sure your session is signed \
+                 in",
+                number
+            )
+        })?;
+        let answer_field = form::field_names(form)
+            .into_iter()
+            .find(|name| is_answer_field(name))
+            .unwrap()
+            .to_owned();
+
+        Ok(Self {
+            action: form::action(form, &page_url)?,
+            hidden: form::hidden_fields(form),
+            answer_field,
+            captcha: captcha::find_in_form(form, &page_url)?,
+        })
+    }
+}
+
+/// What the site made of a submitted answer.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SubmissionOutcome {
+    Correct,
+    Incorrect,
+    /// The captcha was solved wrong, so the answer wasn't checked.
+    WrongCaptcha,
+    /// Answers were submitted too recently for this one to be checked, with how long to wait
+    /// before trying again if the site said.
+    RateLimited(Option<Duration>),
+}
+
+/// Finds a duration like "30 seconds" or "2 minutes" after the word "wait" in `text`.
+fn parse_wait(text: &str) -> Option<Duration> {
+    let lowercase = text.to_lowercase();
+    let after_wait = &lowercase[lowercase.find("wait")? + "wait".len()..];
+    let mut words = after_wait
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '.')
+        .filter(|word| !word.is_empty())
+        .skip_while(|word| !word.starts_with(|c: char| c.is_ascii_digit()));
+    let amount = words.next()?.parse::<u64>().ok()?;
+    let unit = words.next()?;
+    let seconds = if unit.starts_with("sec") {
+        amount
+    } else if unit.starts_with("min") {
+        amount * 60
+    } else if unit.starts_with("hour") {
+        amount * 60 * 60
+    } else {
+        return None;
+    };
+    Some(Duration::from_secs(seconds))
+}
+
+impl SubmissionOutcome {
+    /// Classifies the page responded with after submitting an answer.
+    pub fn from_response(response: &str) -> Result<Self, Box<dyn Error>> {
+        let html = Html::parse_document(response);
+        let text = html
+            .select(&Selector::parse("#content").unwrap())
+            .next()
+            .map_or_else(String::new, |content| content.text().collect::<String>());
+        let lowercase = text.to_lowercase();
+        let outcome = if lowercase.contains("congratulations") || lowercase.contains("is correct") {
+            Self::Correct
+        } else if (lowercase.contains("confirmation code") || lowercase.contains("captcha"))
+            && ["not valid", "invalid", "wrong"]
+                .iter()
+                .any(|complaint| lowercase.contains(complaint))
+        {
+            Self::WrongCaptcha
+        } else if lowercase.contains("too recently") || lowercase.contains("must wait") {
+            Self::RateLimited(parse_wait(&text))
+        } else if lowercase.contains("incorrect") {
+            Self::Incorrect
+        } else {
+            return Err("unable to tell what became of the answer from the response".into());
+        };
+        Ok(outcome)
+    }
+}
+
+/// Submits `answer` to problem `number`, retrying with a new captcha if it's solved wrong.
+pub fn submit_answer(
+    session: &mut Session,
+    number: usize,
+    answer: &str,
+    prompt: &mut dyn CaptchaPrompt,
+) -> Result<SubmissionOutcome, Box<dyn Error>> {
+    let mut attempt = 1;
+    loop {
+        let outcome = submit_once(session, number, answer, prompt)?;
+        if outcome != SubmissionOutcome::WrongCaptcha || attempt == CAPTCHA_ATTEMPTS {
+            return Ok(outcome);
+        }
+        println!("The captcha was solved wrong; try this one.");
+        attempt += 1;
+    }
+}
+
+fn submit_once(
+    session: &mut Session,
+    number: usize,
+    answer: &str,
+    prompt: &mut dyn CaptchaPrompt,
+) -> Result<SubmissionOutcome, Box<dyn Error>> {
+    let page = session.fetch_page(&problem_url(number))?;
+    let form = SubmissionForm::from_problem_page(number, &page)?;
+    let mut fields = form.hidden;
+    fields.push((form.answer_field, answer.to_owned()));
+    if let Some((field, image_url)) = form.captcha {
+        let solution = captcha::solve(session, image_url.as_str(), prompt)?;
+        fields.push((field, solution));
+    }
+
+    println!("Submitting an answer to problem {}...", number);
+    let response = session.post_form(form.action.as_str(), &fields)?;
+    SubmissionOutcome::from_response(&response)
+}
+
+/// Makes sure `answer` is a single, non-empty line, returning it without surrounding
+/// whitespace.
+fn validate_answer(answer: &str) -> Result<&str, Box<dyn Error>> {
+    let answer = answer.trim();
+    if answer.is_empty() {
+        return Err("the answer is empty".into());
+    }
+    if answer.contains('\n') {
+        return Err("the answer must be a single line".into());
+    }
+    Ok(answer)
+}
+
+/// How an answer compares to the one stored for its problem.
+#[derive(Debug, PartialEq)]
+enum StoredAnswer {
+    Agrees,
+    Disagrees,
+    Missing,
+}
+
+impl StoredAnswer {
+    /// Compares `answer` to the answer to `number` in `answers`, by hash if only that is stored.
+    fn check(answers: &Answers, number: usize, answer: &str) -> Self {
+        match answers.get(number) {
+            Some(expected) if expected.matches(answer) => Self::Agrees,
+            Some(_) => Self::Disagrees,
+            None => Self::Missing,
+        }
+    }
+}
+
+/// Builds and runs the local solution to problem `number` with optimizations, returning its
+/// answer along with the workspace it's in.
+fn answer_from_run(number: usize) -> Result<(String, Workspace), Box<dyn Error>> {
+    let workspace = Workspace::discover()?;
+    let output = Solution::resolve(&workspace, number)?.run(
+        &workspace,
+        true,
+        &[],
+        workspace
+            .config
+            .run
+            .time_limit
+            .map(|TimeLimit(limit)| limit),
+    )?;
+    let (answer, _) = output.answer(&workspace.config.run);
+    let answer = answer
+        .ok_or_else(|| format!("the solution to problem {} didn't print an answer", number))?;
+    let answer = validate_answer(answer)?.to_owned();
+    Ok((answer, workspace))
+}
+
+impl Submit {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self {
+            number,
+            answer,
+            from_run,
+            yes,
+            force,
+            ignore_cooldown,
+            open_thread,
+            save_thread,
+            show_answer,
+        } = self;
+
+        // Where to offer storing the answer once it's confirmed correct, if it wasn't stored yet.
+        let mut unstored_in = None;
+        let answer = match answer.as_deref() {
+            Some("-") => {
+                let mut answer = String::new();
+                stdin().read_to_string(&mut answer)?;
+                validate_answer(&answer)?.to_owned()
+            }
+            Some(answer) => validate_answer(answer)?.to_owned(),
+            None => {
+                debug_assert!(from_run);
+                let (answer, workspace) = answer_from_run(number)?;
+                // A solution disagreeing with the stored answer is almost always broken, and
+                // wrong answers cost a cooldown.
+                match StoredAnswer::check(&Answers::load(&workspace, false)?, number, &answer) {
+                    StoredAnswer::Agrees => (),
+                    StoredAnswer::Disagrees if force => warn!(
+                        "the answer stored for problem {} disagrees, but submitting anyway",
+                        number
+                    ),
+                    StoredAnswer::Disagrees => {
+                        return Err(Failure(format!(
+                            "the answer stored for problem {} disagrees with the solution's \
+                             answer {}; use `--force` to submit anyway",
+                            number, answer
+                        ))
+                        .into())
+                    }
+                    StoredAnswer::Missing => unstored_in = Some(workspace),
+                }
+                if !yes
+                    && !confirm(&format!(
+                        "Submit {} as the answer to problem {}?",
+                        answer, number
+                    ))?
+                {
+                    return Err("not submitting".into());
+                }
+                answer
+            }
+        };
+
+        let submissions = Submissions::load(&context.profile)?;
+        if !ignore_cooldown {
+            if let Some(remaining) = submissions.remaining_cooldown(number, Utc::now()) {
+                return Err(format!(
+                    "an incorrect answer to problem {} was submitted recently; wait {} more \
+                     seconds, or pass `--ignore-cooldown`",
+                    number,
+                    remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0)
+                )
+                .into());
+            }
+        }
+
+        let mut session = context.session()?;
+        if !force && context.progress()?.problems.is_solved(number) {
+            return Err(format!(
+                "problem {} is already solved; pass `--force` to submit anyway",
+                number
+            )
+            .into());
+        }
+        if context.dry_run().is_some() {
+            println!(
+                "Would submit {} as the answer to problem {}",
+                if show_answer { &answer } else { "[redacted]" },
+                number
+            );
+            return Ok(());
+        }
+
+        let outcome = submit_answer(&mut session, number, &answer, &mut TerminalPrompt)?;
+        let attempt = Attempt {
+            problem: number,
+            timestamp: Utc::now(),
+            outcome,
+        };
+        if let Err(e) = Submissions::record(&context.profile, &attempt) {
+            warn!("unable to record the submission: {}", e);
+        }
+        match &attempt.outcome {
+            SubmissionOutcome::Correct => {
+                println!("Correct! Problem {} is solved. 🎉", number);
+                context.invalidate_progress_cache();
+                match context.progress() {
+                    Ok(Progress { problems, .. }) => println!(
+                        "You've now solved {} of {} problems.",
+                        problems.solved_count(),
+                        problems.available()
+                    ),
+                    Err(e) => warn!("unable to fetch the new progress: {}", e),
+                }
+
+                let url = thread_url(number);
+                println!("Read the discussion at <{}>", url);
+                if open_thread {
+                    if let Err(e) = webbrowser::open(&url) {
+                        warn!("unable to open the thread in a browser: {}", e);
+                    }
+                }
+                if save_thread {
+                    let workspace = Workspace::discover()?;
+                    let thread =
+                        Thread::from_page(number, url.clone(), &session.fetch_page(&url)?)?;
+                    let path = workspace.thread_path(number);
+                    if let Some(parent) = path.parent() {
+                        create_dir_all(parent)?;
+                    }
+                    write(&path, thread.to_markdown())?;
+                    println!("Saved the thread to {}", workspace.display_path(&path));
+                }
+                // Only offered interactively, so that scripts aren't stopped by the question.
+                if let Some(workspace) = unstored_in.filter(|_| stdin().is_terminal()) {
+                    if confirm(&format!(
+                        "Store the hash of the answer to problem {} to test the solution against?",
+                        number
+                    ))? {
+                        let path = store_hashed(&workspace, number, &answer)?;
+                        println!("Stored it in {}", workspace.display_path(&path));
+                    }
+                }
+                Ok(())
+            }
+            SubmissionOutcome::Incorrect => {
+                Err(Failure(format!("the answer to problem {} is incorrect", number)).into())
+            }
+            SubmissionOutcome::WrongCaptcha => Err(Failure(format!(
+                "the captcha was solved wrong {} times in a row; the answer wasn't checked",
+                CAPTCHA_ATTEMPTS
+            ))
+            .into()),
+            SubmissionOutcome::RateLimited(wait) => Err(Failure(match wait {
+                Some(wait) => format!(
+                    "answers were submitted too recently; try again in {} seconds",
+                    wait.as_secs()
+                ),
+                None => "answers were submitted too recently; try again later".to_owned(),
+            })
+            .into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            answers::{hash, PLAINTEXT_ANSWERS_FILE_NAME},
+            endpoints::set_base_url,
+            init::HASHED_ANSWERS_FILE_NAME,
+            test_support::{lock_globals, MockServer, Reply, ScriptedPrompt, TempDir},
+            workspace::Config,
+        },
+        std::fs::write,
+    };
+
+    #[test]
+    fn compares_answers_to_those_stored() {
+        let dir = TempDir::new();
+        write(dir.path().join(PLAINTEXT_ANSWERS_FILE_NAME), "1 = 233168\n").unwrap();
+        write(
+            dir.path().join(HASHED_ANSWERS_FILE_NAME),
+            format!("2 {}\n", hash("4613732")),
+        )
+        .unwrap();
+        let answers = Answers::load(
+            &Workspace::new(dir.path().to_owned(), Config::default()),
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(
+            StoredAnswer::check(&answers, 1, "233168"),
+            StoredAnswer::Agrees
+        );
+        assert_eq!(
+            StoredAnswer::check(&answers, 1, "233169"),
+            StoredAnswer::Disagrees
+        );
+        // Only the hash of this one is stored.
+        assert_eq!(
+            StoredAnswer::check(&answers, 2, "4613732"),
+            StoredAnswer::Agrees
+        );
+        assert_eq!(
+            StoredAnswer::check(&answers, 2, "4613733"),
+            StoredAnswer::Disagrees
+        );
+        assert_eq!(
+            StoredAnswer::check(&answers, 3, "6857"),
+            StoredAnswer::Missing
+        );
+    }
+
+    fn fixture(name: &str) -> String {
+        std::fs::read_to_string(format!(
+            "{}/tests/fixtures/pages/{}.html",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn extracts_the_submission_form() {
+        let _globals = lock_globals();
+        let form = SubmissionForm::from_problem_page(42, &fixture("problem-submittable")).unwrap();
+        assert_eq!(form.action.as_str(), "https://projecteuler.net/problem=42");
+        assert_eq!(
+            form.hidden,
+            [("csrf_token".to_owned(), "3f9a1c".to_owned())]
+        );
+        assert_eq!(form.answer_field, "guess_42");
+        let (captcha_field, captcha_url) = form.captcha.unwrap();
+        assert_eq!(captcha_field, "captcha");
+        assert_eq!(
+            captcha_url.as_str(),
+            "https://projecteuler.net/captcha/show_captcha.php?1697120000"
+        );
+    }
+
+    #[test]
+    fn finds_no_submission_form_for_solved_problems() {
+        let e = SubmissionForm::from_problem_page(42, &fixture("problem-solved")).unwrap_err();
+        assert!(e
+            .to_string()
+            .starts_with("unable to find the answer form of problem 42"));
+    }
+
+    #[test]
+    fn validates_answers() {
+        assert_eq!(validate_answer(" 162\n").unwrap(), "162");
+        assert!(validate_answer(" \n").is_err());
+        assert!(validate_answer("1\n2").is_err());
+    }
+
+    #[test]
+    fn classifies_submission_responses() {
+        for (name, outcome) in &[
+            ("submission-correct", SubmissionOutcome::Correct),
+            ("submission-incorrect", SubmissionOutcome::Incorrect),
+            ("submission-wrong-captcha", SubmissionOutcome::WrongCaptcha),
+            (
+                "submission-rate-limited",
+                SubmissionOutcome::RateLimited(Some(Duration::from_secs(27))),
+            ),
+        ] {
+            assert_eq!(
+                SubmissionOutcome::from_response(&fixture(name)).unwrap(),
+                *outcome,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn refuses_to_guess_what_became_of_answers() {
+        // The problem page, as if the answer had gone unnoticed.
+        assert!(SubmissionOutcome::from_response(&fixture("problem-submittable")).is_err());
+        assert!(SubmissionOutcome::from_response("").is_err());
+    }
+
+    #[test]
+    fn parses_how_long_to_wait() {
+        for (text, seconds) in &[
+            ("Please wait 27 seconds.", Some(27)),
+            ("You must wait 2 minutes, then try again", Some(120)),
+            ("Wait for 1 hour", Some(3600)),
+            ("Please wait a while", None),
+            ("Please wait 5 fortnights", None),
+            ("Try again in 30 seconds", None),
+        ] {
+            assert_eq!(
+                parse_wait(text),
+                seconds.map(Duration::from_secs),
+                "{}",
+                text
+            );
+        }
+    }
+
+    /// Serves problem 42 with a captcha, answering every submission with `responses` in turn.
+    fn serve_submissions(responses: &[&str]) -> MockServer {
+        let server = MockServer::start();
+        set_base_url(Some(Url::parse(server.url()).unwrap()));
+        server
+            .reply(
+                "GET",
+                "/problem=42",
+                Reply::ok(fixture("problem-submittable")),
+            )
+            .reply(
+                "GET",
+                "/captcha/show_captcha.php?1697120000",
+                Reply::ok("captcha"),
+            );
+        for response in responses {
+            server.reply("POST", "/problem=42", Reply::ok(fixture(response)));
+        }
+        server
+    }
+
+    fn submissions(server: &MockServer) -> Vec<String> {
+        server
+            .received()
+            .into_iter()
+            .filter(|request| request.method == "POST")
+            .map(|request| request.body)
+            .collect()
+    }
+
+    #[test]
+    fn retries_wrong_captchas() {
+        let _globals = lock_globals();
+        let server = serve_submissions(&["submission-wrong-captcha", "submission-correct"]);
+        let mut session = Session::new(Some("s3ss10n")).unwrap();
+        let mut prompt = ScriptedPrompt::new(&["wrong", "right"]);
+
+        let outcome = submit_answer(&mut session, 42, "162", &mut prompt).unwrap();
+        assert_eq!(outcome, SubmissionOutcome::Correct);
+        assert_eq!(
+            submissions(&server),
+            [
+                "csrf_token=3f9a1c&guess_42=162&captcha=wrong",
+                "csrf_token=3f9a1c&guess_42=162&captcha=right",
+            ]
+        );
+    }
+
+    #[test]
+    fn gives_up_after_three_wrong_captchas() {
+        let _globals = lock_globals();
+        let server = serve_submissions(&["submission-wrong-captcha"]);
+        let mut session = Session::new(Some("s3ss10n")).unwrap();
+        let mut prompt = ScriptedPrompt::new(&["a", "b", "c"]);
+
+        let outcome = submit_answer(&mut session, 42, "162", &mut prompt).unwrap();
+        assert_eq!(outcome, SubmissionOutcome::WrongCaptcha);
+        assert_eq!(submissions(&server).len(), CAPTCHA_ATTEMPTS);
+    }
+
+    #[test]
+    fn doesnt_retry_other_outcomes() {
+        let _globals = lock_globals();
+        let server = serve_submissions(&["submission-rate-limited"]);
+        let mut session = Session::new(Some("s3ss10n")).unwrap();
+        let mut prompt = ScriptedPrompt::new(&["right"]);
+
+        let outcome = submit_answer(&mut session, 42, "162", &mut prompt).unwrap();
+        assert_eq!(
+            outcome,
+            SubmissionOutcome::RateLimited(Some(Duration::from_secs(27)))
+        );
+        assert_eq!(submissions(&server).len(), 1);
+    }
+}
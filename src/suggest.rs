@@ -0,0 +1,281 @@
+use {
+    crate::{
+        context::Context,
+        enrichment::{Enrichment, ProblemInfo},
+        percentile::{describe_milestones, mainstream, max_solved_by, missing_solved_by},
+    },
+    log::warn,
+    std::{cmp::Ordering, error::Error},
+    structopt::StructOpt,
+};
+
+// Weights of each ranking criterion in a problem's score. Lower scores rank first.
+const DIFFICULTY_WEIGHT: f64 = 0.5;
+const SOLVED_BY_WEIGHT: f64 = 0.3;
+const NUMBER_WEIGHT: f64 = 0.2;
+
+#[derive(Debug)]
+pub struct Suggestion<'e> {
+    pub number: usize,
+    pub info: Option<&'e ProblemInfo>,
+    pub reason: &'static str,
+}
+
+/// Ranks `unsolved` problems by a blend of difficulty (ascending), solved-by count (descending)
+/// and problem number (ascending). Problems without cached metadata are ranked as if they were
+/// the hardest and least solved, so with no metadata at all this degrades to ordering by problem
+/// number.
+pub fn rank<'e>(unsolved: &[usize], enrichment: &'e Enrichment) -> Vec<Suggestion<'e>> {
+    let infos = unsolved
+        .iter()
+        .map(|&number| (number, enrichment.get(number)))
+        .collect::<Vec<_>>();
+
+    let max_number = unsolved.iter().copied().max().unwrap_or(0).max(1) as f64;
+    let max_solved_by = infos
+        .iter()
+        .filter_map(|(_, info)| info.and_then(|i| i.solved_by))
+        .max()
+        .unwrap_or(0)
+        .max(1) as f64;
+    let min_number = unsolved.iter().copied().min();
+    let min_difficulty = infos
+        .iter()
+        .filter_map(|(_, info)| info.and_then(|i| i.difficulty))
+        .min();
+    let max_solved_by_count = infos
+        .iter()
+        .filter_map(|(_, info)| info.and_then(|i| i.solved_by))
+        .max();
+
+    let score = |number: usize, info: Option<&ProblemInfo>| {
+        let difficulty = info
+            .and_then(|i| i.difficulty)
+            .map_or(1.0, |d| f64::from(d) / 100.0);
+        let obscurity = info
+            .and_then(|i| i.solved_by)
+            .map_or(1.0, |s| 1.0 - s as f64 / max_solved_by);
+        DIFFICULTY_WEIGHT * difficulty
+            + SOLVED_BY_WEIGHT * obscurity
+            + NUMBER_WEIGHT * (number as f64 / max_number)
+    };
+
+    let mut scored = infos
+        .into_iter()
+        .map(|(number, info)| (score(number, info), number, info))
+        .collect::<Vec<_>>();
+    scored.sort_by(|(a_score, a_number, _), (b_score, b_number, _)| {
+        a_score
+            .partial_cmp(b_score)
+            .unwrap_or(Ordering::Equal)
+            .then(a_number.cmp(b_number))
+    });
+
+    scored
+        .into_iter()
+        .map(|(_, number, info)| {
+            let difficulty = info.and_then(|i| i.difficulty);
+            let solved_by = info.and_then(|i| i.solved_by);
+            let reason = if Some(number) == min_number {
+                "lowest unsolved"
+            } else if difficulty.is_some() && difficulty == min_difficulty {
+                "easiest remaining"
+            } else if solved_by.is_some() && solved_by == max_solved_by_count {
+                "most solved remaining"
+            } else if difficulty.is_some() {
+                "next by rank"
+            } else {
+                "next in order"
+            };
+            Suggestion {
+                number,
+                info,
+                reason,
+            }
+        })
+        .collect()
+}
+
+/// Recommend unsolved problems to try next.
+#[derive(Debug, StructOpt)]
+pub struct Suggest {
+    /// How many problems to suggest.
+    #[structopt(long, default_value = "5")]
+    count: usize,
+    /// Also show how mainstream each problem is, as its solved-by count relative to the most
+    /// solved problem's, and which level milestones you've passed.
+    #[structopt(long)]
+    percentile: bool,
+}
+
+impl Suggest {
+    pub fn run(self, context: &Context) -> Result<(), Box<dyn Error>> {
+        let Self { count, percentile } = self;
+
+        let progress = context.progress()?;
+        let enrichment = Enrichment::load_cached();
+        let unsolved = progress.problems.unsolved().collect::<Vec<_>>();
+
+        let suggestions = rank(&unsolved, &enrichment);
+        if suggestions.is_empty() {
+            println!("Every problem has been solved!");
+            return Ok(());
+        }
+
+        let max_solved_by = max_solved_by(&enrichment).filter(|_| percentile);
+        if percentile {
+            println!(
+                "{}",
+                describe_milestones(&progress.levels, progress.problems.solved_count())
+            );
+            if max_solved_by.is_none() {
+                warn!("{}", missing_solved_by());
+            }
+            println!();
+        }
+
+        let rows = suggestions
+            .iter()
+            .take(count)
+            .map(
+                |Suggestion {
+                     number,
+                     info,
+                     reason,
+                 }| {
+                    (
+                        number.to_string(),
+                        info.map_or("", |i| &*i.title),
+                        info.and_then(|i| i.difficulty)
+                            .map_or_else(|| "-".to_owned(), |d| format!("{}%", d)),
+                        max_solved_by
+                            .and_then(|max| mainstream(&enrichment, *number, max))
+                            .map_or_else(|| "-".to_owned(), |m| format!("{:.0}%", m)),
+                        *reason,
+                    )
+                },
+            )
+            .collect::<Vec<_>>();
+        let number_width = rows.iter().map(|r| r.0.len()).max().unwrap_or(0).max(1);
+        let title_width = rows
+            .iter()
+            .map(|r| r.1.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max("Title".len());
+
+        let mainstream_heading = if max_solved_by.is_some() {
+            format!("  {:>10}", "Mainstream")
+        } else {
+            String::new()
+        };
+        println!(
+            "{:>nw$}  {:tw$}  {:>10}{}  Reason",
+            "#",
+            "Title",
+            "Difficulty",
+            mainstream_heading,
+            nw = number_width,
+            tw = title_width
+        );
+        for (number, title, difficulty, mainstream, reason) in rows {
+            let mainstream = if max_solved_by.is_some() {
+                format!("  {:>10}", mainstream)
+            } else {
+                String::new()
+            };
+            println!(
+                "{:>nw$}  {:tw$}  {:>10}{}  {}",
+                number,
+                title,
+                difficulty,
+                mainstream,
+                reason,
+                nw = number_width,
+                tw = title_width
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enrichment(infos: &[(usize, Option<u8>, Option<u64>)]) -> Enrichment {
+        let mut enrichment = Enrichment::default();
+        for &(number, difficulty, solved_by) in infos {
+            enrichment.insert(ProblemInfo {
+                number,
+                title: format!("Problem {}", number),
+                difficulty,
+                solved_by,
+            });
+        }
+        enrichment
+    }
+
+    fn ranked(unsolved: &[usize], enrichment: &Enrichment) -> Vec<(usize, &'static str)> {
+        rank(unsolved, enrichment)
+            .into_iter()
+            .map(|suggestion| (suggestion.number, suggestion.reason))
+            .collect()
+    }
+
+    #[test]
+    fn orders_by_number_without_metadata() {
+        assert_eq!(
+            ranked(&[30, 4, 12], &Enrichment::default()),
+            [
+                (4, "lowest unsolved"),
+                (12, "next in order"),
+                (30, "next in order")
+            ]
+        );
+    }
+
+    #[test]
+    fn prefers_easy_and_widely_solved_problems() {
+        let enrichment = enrichment(&[
+            (10, Some(50), Some(1_000)),
+            (20, Some(5), Some(90_000)),
+            (30, Some(5), Some(100_000)),
+            (40, Some(100), Some(10)),
+        ]);
+        assert_eq!(
+            ranked(&[10, 20, 30, 40], &enrichment),
+            [
+                (20, "easiest remaining"),
+                (30, "easiest remaining"),
+                (10, "lowest unsolved"),
+                (40, "next by rank"),
+            ]
+        );
+    }
+
+    #[test]
+    fn ranks_problems_without_metadata_last() {
+        let enrichment = enrichment(&[(50, Some(30), Some(5_000))]);
+        assert_eq!(
+            ranked(&[7, 50], &enrichment),
+            [(50, "easiest remaining"), (7, "lowest unsolved")]
+        );
+    }
+
+    #[test]
+    fn breaks_ties_by_number() {
+        let enrichment = enrichment(&[(8, Some(20), Some(100)), (3, Some(20), Some(100))]);
+        let numbers = rank(&[8, 3], &enrichment)
+            .into_iter()
+            .map(|suggestion| suggestion.number)
+            .collect::<Vec<_>>();
+        // The lower number also scores lower, but the order holds regardless.
+        assert_eq!(numbers, [3, 8]);
+    }
+
+    #[test]
+    fn suggests_nothing_with_nothing_unsolved() {
+        assert!(rank(&[], &Enrichment::default()).is_empty());
+    }
+}
@@ -0,0 +1,149 @@
+//! A tiny templating language for scaffolded files: `{{name}}` is replaced with the value of
+//! the variable `name`, and a line consisting of nothing but a variable that expands to nothing
+//! is dropped entirely.
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs::read_to_string,
+    path::Path,
+};
+
+#[derive(Debug)]
+pub enum TemplateError {
+    UnknownVariable {
+        source: String,
+        line: usize,
+        name: String,
+    },
+    Unclosed {
+        source: String,
+        line: usize,
+    },
+}
+
+impl Display for TemplateError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::UnknownVariable { source, line, name } => write!(
+                f,
+                "unknown template variable `{}` on line {} of {}",
+                name, line, source
+            ),
+            Self::Unclosed { source, line } => {
+                write!(f, "unclosed `{{{{` on line {} of {}", line, source)
+            }
+        }
+    }
+}
+
+impl Error for TemplateError {}
+
+#[derive(Debug)]
+pub struct Template {
+    /// Where the template came from, for error messages.
+    source: String,
+    text: String,
+}
+
+impl Template {
+    pub fn new(source: impl Into<String>, text: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            text: text.into(),
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let text = read_to_string(path)
+            .map_err(|e| format!("unable to read template {}: {}", path.display(), e))?;
+        Ok(Self::new(path.display().to_string(), text))
+    }
+
+    pub fn render(&self, vars: &[(&str, &str)]) -> Result<String, TemplateError> {
+        let mut rendered = String::with_capacity(self.text.len());
+        for (idx, line) in self.text.split_inclusive('\n').enumerate() {
+            let mut rendered_line = String::with_capacity(line.len());
+            let mut var_count = 0;
+            let mut only_empty_vars = true;
+            let mut rest = line;
+            while let Some(start) = rest.find("{{") {
+                let (before, after) = rest.split_at(start);
+                only_empty_vars &= before.trim().is_empty();
+                rendered_line.push_str(before);
+                let end = after.find("}}").ok_or_else(|| TemplateError::Unclosed {
+                    source: self.source.clone(),
+                    line: idx + 1,
+                })?;
+                let name = after[2..end].trim();
+                let value = vars
+                    .iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| *value)
+                    .ok_or_else(|| TemplateError::UnknownVariable {
+                        source: self.source.clone(),
+                        line: idx + 1,
+                        name: name.to_owned(),
+                    })?;
+                var_count += 1;
+                only_empty_vars &= value.is_empty();
+                rendered_line.push_str(value);
+                rest = &after[end + 2..];
+            }
+            if var_count > 0 && only_empty_vars && rest.trim().is_empty() {
+                continue;
+            }
+            rendered_line.push_str(rest);
+            rendered.push_str(&rendered_line);
+        }
+        Ok(rendered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(text: &str, vars: &[(&str, &str)]) -> Result<String, String> {
+        Template::new("test.hbs", text)
+            .render(vars)
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn replaces_variables() {
+        assert_eq!(
+            render(
+                "fn p{{ number }}() {}\n// {{title}}\n",
+                &[("number", "7"), ("title", "Primes")]
+            ),
+            Ok("fn p7() {}\n// Primes\n".to_owned())
+        );
+    }
+
+    #[test]
+    fn drops_lines_of_nothing_but_empty_variables() {
+        let text = "a\n  {{data}}\n{{data}}{{data}}\nb {{data}}\n";
+        assert_eq!(render(text, &[("data", "")]), Ok("a\nb \n".to_owned()));
+        assert_eq!(
+            render(text, &[("data", "x")]),
+            Ok("a\n  x\nxx\nb x\n".to_owned())
+        );
+    }
+
+    #[test]
+    fn reports_unknown_variables_by_line() {
+        assert_eq!(
+            render("a\n{{number}}\nb {{nope}}\n", &[("number", "1")]),
+            Err("unknown template variable `nope` on line 3 of test.hbs".to_owned())
+        );
+    }
+
+    #[test]
+    fn reports_unclosed_variables_by_line() {
+        assert_eq!(
+            render("a\n{{number\n}}\n", &[("number", "1")]),
+            Err("unclosed `{{` on line 2 of test.hbs".to_owned())
+        );
+    }
+}
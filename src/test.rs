@@ -0,0 +1,646 @@
+use {
+    crate::{
+        answers::{canonicalize, Answers, Expected},
+        github::{annotate, append_step_summary, Annotation},
+        new::escape_inline_markdown,
+        readme_table::render_table,
+        runner::{
+            build_all, format_duration, Cancelled, RunOutput, Solution, SolutionFailed, TimeLimit,
+            TimedOut,
+        },
+        workspace::Workspace,
+        Failure,
+    },
+    log::warn,
+    std::{
+        error::Error,
+        io::{stderr, IsTerminal},
+        num::NonZeroUsize,
+        path::Path,
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Mutex,
+        },
+        thread::{self, available_parallelism},
+        time::Duration,
+    },
+    structopt::StructOpt,
+};
+
+/// Verify local solutions against stored answers.
+#[derive(Debug, StructOpt)]
+pub struct Test {
+    /// The problems whose solutions to test. Every local solution is tested if omitted.
+    numbers: Vec<usize>,
+    /// Build solutions with optimizations.
+    #[structopt(long)]
+    release: bool,
+    /// Only warn about solutions without a stored answer, rather than failing.
+    #[structopt(long)]
+    allow_missing_answers: bool,
+    /// Only verify against hashed answers in `answers.sha256`, ignoring `answers.toml`.
+    #[structopt(long)]
+    hashed: bool,
+    /// How many solutions to run at once. Defaults to the number of logical CPUs.
+    #[structopt(long, short)]
+    jobs: Option<usize>,
+    /// Kill solutions that run longer than this, like `60s`, counting them as failures.
+    /// Defaults to the time limit in `euler.toml`, if any.
+    #[structopt(long)]
+    time_limit: Option<TimeLimit>,
+    /// Use the solution file matching this glob, like `src/bin/p*.rs`, for problems with
+    /// several. Tried before the `layout.prefer` globs in `euler.toml`.
+    #[structopt(long)]
+    prefer: Vec<String>,
+    /// Stop at the first failure, killing solutions still running and skipping the rest,
+    /// instead of testing every solution.
+    #[structopt(long)]
+    fail_fast: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Status {
+    Pass,
+    /// The answer didn't match; `expected` is only known for plaintext answers.
+    Fail {
+        actual: String,
+        expected: Option<String>,
+    },
+    MissingAnswer {
+        actual: String,
+    },
+    /// The solution ran longer than the time limit, and was killed.
+    TimedOut(Duration),
+    Error(String),
+    /// The solution wasn't tested to the end because of an earlier failure, with `--fail-fast`.
+    Skipped {
+        /// Whether the solution was already running, and was killed.
+        started: bool,
+    },
+}
+
+#[derive(Debug)]
+pub struct TestResult {
+    pub number: usize,
+    pub status: Status,
+    pub elapsed: Option<Duration>,
+    /// What the solution printed, if it ran to completion.
+    pub output: Option<RunOutput>,
+}
+
+impl TestResult {
+    fn is_failure(&self, allow_missing_answers: bool) -> bool {
+        match self.status {
+            Status::Pass | Status::Skipped { .. } => false,
+            Status::MissingAnswer { .. } => !allow_missing_answers,
+            Status::Fail { .. } | Status::TimedOut(_) | Status::Error(_) => true,
+        }
+    }
+}
+
+fn error_result(number: usize, e: Box<dyn Error>) -> TestResult {
+    let e = match e.downcast::<SolutionFailed>() {
+        Ok(failed) => {
+            let status = Status::Error(failed.to_string());
+            let output = failed.output;
+            return TestResult {
+                number,
+                status,
+                elapsed: Some(output.elapsed),
+                output: Some(output),
+            };
+        }
+        Err(e) => e,
+    };
+    let (status, elapsed) = if let Some(TimedOut { limit, .. }) = e.downcast_ref() {
+        (Status::TimedOut(*limit), Some(*limit))
+    } else if e.is::<Cancelled>() {
+        (Status::Skipped { started: true }, None)
+    } else {
+        (Status::Error(e.to_string()), None)
+    };
+    TestResult {
+        number,
+        status,
+        elapsed,
+        output: None,
+    }
+}
+
+fn test_one(
+    workspace: &Workspace,
+    answers: &Answers,
+    solution: &Solution,
+    executable: &Path,
+    time_limit: Option<Duration>,
+    cancel: &AtomicBool,
+) -> TestResult {
+    let number = solution.number;
+    let output = match solution.execute_captured(workspace, executable, time_limit, cancel) {
+        Ok(output) => output,
+        Err(e) => return error_result(number, e),
+    };
+    let actual = output
+        .answer(&workspace.config.run)
+        .0
+        .map(canonicalize)
+        .unwrap_or_default()
+        .to_owned();
+    let status = match answers.get(number) {
+        Some(expected) if expected.matches(&actual) => Status::Pass,
+        Some(expected) => Status::Fail {
+            actual,
+            expected: match expected {
+                Expected::Plaintext(expected) => Some(expected.to_owned()),
+                Expected::Hashed(_) => None,
+            },
+        },
+        None => Status::MissingAnswer { actual },
+    };
+    TestResult {
+        number,
+        status,
+        elapsed: Some(output.elapsed),
+        output: Some(output),
+    }
+}
+
+/// Prints what the solution of a failed test printed, as one uninterrupted block.
+fn print_output(number: usize, output: &RunOutput) {
+    for (stream, text) in [("stdout", &output.stdout), ("stderr", &output.stderr)] {
+        if text.trim().is_empty() {
+            continue;
+        }
+        println!();
+        println!("--- Problem {} {} ---", number, stream);
+        println!("{}", text.trim_end());
+    }
+}
+
+/// Tests the solutions to `numbers` with `test`, `jobs` at a time, adding their results to
+/// those already known. With `fail_fast`, the first failure stops the rest from starting, and
+/// `test` is expected to stop once the flag it's passed is set. Solutions left untested are
+/// counted as skipped, and every result is returned ordered by problem number, whatever order
+/// the solutions finished in.
+fn test_all(
+    numbers: &[usize],
+    results: Vec<TestResult>,
+    jobs: usize,
+    fail_fast: bool,
+    allow_missing_answers: bool,
+    progress: bool,
+    test: impl Fn(usize, &AtomicBool) -> TestResult + Sync,
+) -> Vec<TestResult> {
+    let total = results.len() + numbers.len();
+    let next = AtomicUsize::new(0);
+    // Set on the first failure with `--fail-fast`, to stop solutions from starting and kill
+    // those still running.
+    let cancel = AtomicBool::new(
+        fail_fast
+            && results
+                .iter()
+                .any(|result| result.is_failure(allow_missing_answers)),
+    );
+    let results = Mutex::new(results);
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, numbers.len().max(1)) {
+            scope.spawn(|| loop {
+                if cancel.load(Ordering::SeqCst) {
+                    break;
+                }
+                let number = match numbers.get(next.fetch_add(1, Ordering::SeqCst)) {
+                    Some(number) => *number,
+                    None => break,
+                };
+                let result = test(number, &cancel);
+                if fail_fast && result.is_failure(allow_missing_answers) {
+                    cancel.store(true, Ordering::SeqCst);
+                }
+                let mut results = results.lock().unwrap();
+                results.push(result);
+                if progress {
+                    let failed = results
+                        .iter()
+                        .filter(|result| result.is_failure(allow_missing_answers))
+                        .count();
+                    eprint!(
+                        "\r{}/{} done: {} passed, {} failed",
+                        results.len(),
+                        total,
+                        results.len() - failed,
+                        failed
+                    );
+                }
+            });
+        }
+    });
+    if progress {
+        eprint!("\r\x1b[2K");
+    }
+    let mut results = results.into_inner().unwrap();
+    for number in numbers {
+        if !results.iter().any(|result| result.number == *number) {
+            results.push(TestResult {
+                number: *number,
+                status: Status::Skipped { started: false },
+                elapsed: None,
+                output: None,
+            });
+        }
+    }
+    results.sort_by_key(|result| result.number);
+    results
+}
+
+/// The label of `status` in the table of results, and details to go with it.
+fn describe(status: &Status) -> (&'static str, String) {
+    match status {
+        Status::Pass => ("PASS", String::new()),
+        Status::Fail { actual, expected } => {
+            let details = match expected {
+                Some(expected) => format!("expected {}, got {}", expected, actual),
+                None => format!("got {}, which doesn't match the stored hash", actual),
+            };
+            ("FAIL", details)
+        }
+        Status::MissingAnswer { actual } => {
+            ("MISSING", format!("no stored answer; got {}", actual))
+        }
+        Status::TimedOut(limit) => (
+            "TIMEOUT",
+            format!("killed after the time limit of {}", format_duration(*limit)),
+        ),
+        Status::Error(e) => ("ERROR", e.clone()),
+        Status::Skipped { started: true } => {
+            ("SKIPPED", "killed after an earlier failure".to_owned())
+        }
+        Status::Skipped { started: false } => {
+            ("SKIPPED", "not run after an earlier failure".to_owned())
+        }
+    }
+}
+
+impl Test {
+    pub fn run(self) -> Result<(), Box<dyn Error>> {
+        let Self {
+            numbers,
+            release,
+            allow_missing_answers,
+            hashed,
+            jobs,
+            time_limit,
+            prefer,
+            fail_fast,
+        } = self;
+
+        let mut workspace = Workspace::discover()?;
+        workspace.prefer(prefer);
+        let time_limit = time_limit
+            .or(workspace.config.run.time_limit)
+            .map(|TimeLimit(limit)| limit);
+        let answers = Answers::load(&workspace, hashed)?;
+        let numbers = if numbers.is_empty() {
+            workspace.solutions()?
+        } else {
+            numbers
+        };
+        if numbers.is_empty() {
+            return Err("no solutions to test".into());
+        }
+        let jobs = jobs.unwrap_or_else(|| available_parallelism().map_or(1, NonZeroUsize::get));
+
+        let mut results = Vec::with_capacity(numbers.len());
+        let mut solutions = Vec::with_capacity(numbers.len());
+        for number in numbers {
+            match Solution::resolve(&workspace, number) {
+                Ok(solution) => solutions.push(solution),
+                Err(e) => results.push(error_result(number, e)),
+            }
+        }
+        // Building every solution at once saves cargo's overhead for each of them.
+        let executables = build_all(&workspace, &solutions, release)?;
+
+        let numbers = solutions
+            .iter()
+            .map(|solution| solution.number)
+            .collect::<Vec<_>>();
+        let results = test_all(
+            &numbers,
+            results,
+            jobs,
+            fail_fast,
+            allow_missing_answers,
+            stderr().is_terminal(),
+            |number, cancel| {
+                let solution = solutions
+                    .iter()
+                    .find(|solution| solution.number == number)
+                    .unwrap();
+                match executables.get(&number) {
+                    Some(executable) => test_one(
+                        &workspace, &answers, solution, executable, time_limit, cancel,
+                    ),
+                    None => error_result(
+                        number,
+                        format!("the solution to problem {} didn't build", number).into(),
+                    ),
+                }
+            },
+        );
+
+        println!("{:>7}  {:<7}  {:>10}  Details", "Problem", "Status", "Time");
+        let mut rows = Vec::with_capacity(results.len());
+        for result in &results {
+            let TestResult {
+                number,
+                status,
+                elapsed,
+                ..
+            } = result;
+            if allow_missing_answers && matches!(status, Status::MissingAnswer { .. }) {
+                warn!("no stored answer for problem {}", number);
+            }
+            let (label, details) = describe(status);
+            let elapsed = elapsed.map(format_duration).unwrap_or_default();
+            println!("{:>7}  {:<7}  {:>10}  {}", number, label, elapsed, details);
+            if result.is_failure(allow_missing_answers) {
+                annotate(
+                    Annotation::Error,
+                    &format!("problem {}: {} {}", number, label, details),
+                );
+            } else if let Status::MissingAnswer { .. } = status {
+                annotate(
+                    Annotation::Warning,
+                    &format!("problem {}: {} {}", number, label, details),
+                );
+            }
+            rows.push(vec![
+                number.to_string(),
+                label.to_owned(),
+                elapsed,
+                escape_inline_markdown(&details),
+            ]);
+        }
+        append_step_summary(&format!(
+            "### Solution tests\n\n{}",
+            render_table(&["Problem", "Status", "Time", "Details"], &rows)
+        ));
+        for result in &results {
+            if let (true, Some(output)) = (result.is_failure(allow_missing_answers), &result.output)
+            {
+                print_output(result.number, output);
+            }
+        }
+
+        let failed = results
+            .iter()
+            .filter(|result| result.is_failure(allow_missing_answers))
+            .map(|result| result.number.to_string())
+            .collect::<Vec<_>>();
+        let skipped = results
+            .iter()
+            .filter(|result| matches!(result.status, Status::Skipped { .. }))
+            .count();
+        if skipped > 0 {
+            println!();
+            println!(
+                "Skipped {} of {} solutions after the first failure",
+                skipped,
+                results.len()
+            );
+        }
+        if !failed.is_empty() {
+            return Err(Failure(format!(
+                "{} of {} solutions failed: {}",
+                failed.len(),
+                results.len(),
+                failed.join(", ")
+            ))
+            .into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::{thread::sleep, time::Instant},
+    };
+
+    fn result(number: usize, status: Status) -> TestResult {
+        TestResult {
+            number,
+            status,
+            elapsed: None,
+            output: None,
+        }
+    }
+
+    #[test]
+    fn runs_as_many_solutions_at_once_as_there_are_jobs() {
+        let (running, most_running) = (AtomicUsize::new(0), AtomicUsize::new(0));
+        let start = Instant::now();
+        let results = test_all(
+            &[1, 2, 3, 4, 5, 6],
+            Vec::new(),
+            3,
+            false,
+            false,
+            false,
+            |number, _| {
+                let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+                most_running.fetch_max(now_running, Ordering::SeqCst);
+                sleep(Duration::from_millis(100));
+                running.fetch_sub(1, Ordering::SeqCst);
+                result(number, Status::Pass)
+            },
+        );
+        assert_eq!(results.len(), 6);
+        assert_eq!(most_running.load(Ordering::SeqCst), 3);
+        // Two rounds of three, rather than six one after another.
+        assert!(
+            start.elapsed() < Duration::from_millis(500),
+            "{:?}",
+            start.elapsed()
+        );
+    }
+
+    #[test]
+    fn runs_solutions_one_at_a_time_with_a_job() {
+        let running = AtomicUsize::new(0);
+        test_all(
+            &[1, 2, 3],
+            Vec::new(),
+            1,
+            false,
+            false,
+            false,
+            |number, _| {
+                assert_eq!(running.fetch_add(1, Ordering::SeqCst), 0);
+                sleep(Duration::from_millis(10));
+                running.fetch_sub(1, Ordering::SeqCst);
+                result(number, Status::Pass)
+            },
+        );
+        // No more jobs than solutions are started, and zero jobs still make progress.
+        let results = test_all(&[1], Vec::new(), 0, false, false, false, |number, _| {
+            result(number, Status::Pass)
+        });
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    fn lists_results_by_number_whatever_order_they_finish_in() {
+        let results = test_all(
+            &[1, 2, 4, 5],
+            vec![result(3, Status::Error("failed to build".to_owned()))],
+            4,
+            false,
+            false,
+            false,
+            |number, _| {
+                // Later solutions finish first.
+                sleep(Duration::from_millis(20 * (6 - number) as u64));
+                result(number, Status::Pass)
+            },
+        );
+        let numbers = results
+            .iter()
+            .map(|result| result.number)
+            .collect::<Vec<_>>();
+        assert_eq!(numbers, [1, 2, 3, 4, 5]);
+    }
+
+    fn failed(number: usize) -> TestResult {
+        result(
+            number,
+            Status::Fail {
+                actual: "1".to_owned(),
+                expected: Some("2".to_owned()),
+            },
+        )
+    }
+
+    fn statuses(results: &[TestResult]) -> Vec<(usize, &Status)> {
+        results
+            .iter()
+            .map(|result| (result.number, &result.status))
+            .collect()
+    }
+
+    #[test]
+    fn keeps_going_after_failures_by_default() {
+        let results = test_all(
+            &[1, 2, 3],
+            Vec::new(),
+            1,
+            false,
+            false,
+            false,
+            |number, _| match number {
+                1 => failed(1),
+                _ => result(number, Status::Pass),
+            },
+        );
+        assert_eq!(
+            statuses(&results)[1..],
+            [(2, &Status::Pass), (3, &Status::Pass)]
+        );
+    }
+
+    #[test]
+    fn stops_starting_solutions_after_a_failure_with_fail_fast() {
+        let started = Mutex::new(Vec::new());
+        let results = test_all(
+            &[1, 2, 3],
+            Vec::new(),
+            1,
+            true,
+            false,
+            false,
+            |number, _| {
+                started.lock().unwrap().push(number);
+                match number {
+                    2 => failed(2),
+                    _ => result(number, Status::Pass),
+                }
+            },
+        );
+        assert_eq!(*started.lock().unwrap(), [1, 2]);
+        assert_eq!(statuses(&results)[0], (1, &Status::Pass));
+        assert!(results[1].is_failure(false));
+        assert_eq!(
+            statuses(&results)[2],
+            (3, &Status::Skipped { started: false })
+        );
+
+        // Failures known before any solution runs, like failed builds, stop them all.
+        let results = test_all(&[2], vec![failed(1)], 1, true, false, false, |_, _| {
+            panic!("no solution should run")
+        });
+        assert_eq!(
+            statuses(&results)[1],
+            (2, &Status::Skipped { started: false })
+        );
+    }
+
+    #[test]
+    fn cancels_running_solutions_after_a_failure_with_fail_fast() {
+        let results = test_all(
+            &[1, 2],
+            Vec::new(),
+            2,
+            true,
+            false,
+            false,
+            |number, cancel| {
+                if number == 1 {
+                    sleep(Duration::from_millis(50));
+                    return failed(1);
+                }
+                let start = Instant::now();
+                while !cancel.load(Ordering::SeqCst) {
+                    assert!(start.elapsed() < Duration::from_secs(10), "never cancelled");
+                    sleep(Duration::from_millis(5));
+                }
+                result(number, Status::Skipped { started: true })
+            },
+        );
+        assert_eq!(
+            statuses(&results)[1],
+            (2, &Status::Skipped { started: true })
+        );
+    }
+
+    #[test]
+    fn allows_missing_answers_only_when_asked_to() {
+        let missing = result(
+            1,
+            Status::MissingAnswer {
+                actual: "42".to_owned(),
+            },
+        );
+        assert!(missing.is_failure(false));
+        assert!(!missing.is_failure(true));
+        let results = test_all(
+            &[1, 2],
+            Vec::new(),
+            1,
+            true,
+            true,
+            false,
+            |number, _| match number {
+                1 => result(
+                    1,
+                    Status::MissingAnswer {
+                        actual: "42".to_owned(),
+                    },
+                ),
+                _ => result(number, Status::Pass),
+            },
+        );
+        assert_eq!(statuses(&results)[1], (2, &Status::Pass));
+    }
+}
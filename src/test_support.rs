@@ -0,0 +1,485 @@
+//! Helpers shared by the tests of several modules.
+
+use {
+    crate::{
+        captcha::CaptchaPrompt,
+        clipboard::Clipboard,
+        fixtures::Exchange,
+        history::{ProblemSet, Snapshot},
+        progress::{ProblemStatus, Problems, Progress},
+        user_config::UserConfig,
+    },
+    chrono::{DateTime, TimeZone, Utc},
+    std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+        collections::VecDeque,
+        env::{set_var, temp_dir},
+        error::Error,
+        fs::{create_dir_all, read_dir, read_to_string, remove_dir_all},
+        io::{self, BufRead, BufReader, Read, Write},
+        net::{TcpListener, TcpStream},
+        path::{Path, PathBuf},
+        process::{self, Command},
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Arc, Mutex, MutexGuard,
+        },
+        thread,
+    },
+    unhtml::FromHtml,
+};
+
+/// Counts the allocations made on each thread, so that tests can keep track of how many some
+/// code makes. Reallocations count too, since growing a collection is as costly.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count_allocation() {
+    // The count is gone while the thread is being torn down.
+    let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_allocation();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// What `f` returns, and how many allocations it made on the current thread.
+pub fn count_allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let returned = f();
+    (returned, ALLOCATIONS.with(Cell::get) - before)
+}
+
+/// A directory removed once it's dropped.
+#[derive(Debug)]
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+        let path = temp_dir().join(format!(
+            "cargo-euler-test-{}-{}",
+            process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = remove_dir_all(&path);
+        create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = remove_dir_all(&self.0);
+    }
+}
+
+/// Global state tests set up, like the directories of the tool and whether this is a dry run.
+/// Holding it keeps tests depending on it from running at the same time.
+#[derive(Debug)]
+pub struct Globals {
+    _dirs: TempDir,
+    _guard: MutexGuard<'static, ()>,
+}
+
+/// Takes exclusive use of global state, with the configuration, cache and data directories of
+/// the tool pointed at a fresh temporary directory.
+pub fn lock_globals() -> Globals {
+    static LOCK: Mutex<()> = Mutex::new(());
+
+    let guard = LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let dirs = TempDir::new();
+    for (var, dir) in &[
+        ("XDG_CONFIG_HOME", "config"),
+        ("XDG_CACHE_HOME", "cache"),
+        ("XDG_DATA_HOME", "data"),
+    ] {
+        set_var(var, dirs.path().join(dir));
+    }
+    crate::client::set_dry_run(None);
+    // Tests only talk to servers on localhost, which shouldn't be proxied.
+    crate::client::set_direct(true);
+    crate::client::clear_pause();
+    crate::fixtures::stop();
+    crate::anonymize::disable();
+    crate::endpoints::set_base_url(None);
+    Globals {
+        _dirs: dirs,
+        _guard: guard,
+    }
+}
+
+/// The directory of the fixtures recorded for the flow `name`, for replaying.
+pub fn flow(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/flows")
+        .join(name)
+}
+
+/// The page recorded as the response to the first `GET` of `path` in the flow `name`, for
+/// testing the parser of a page with what the flow saw.
+pub fn recorded_page(name: &str, path: &str) -> String {
+    let mut paths = read_dir(flow(name))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| serde_json::from_str::<Exchange>(&read_to_string(path).unwrap()).unwrap())
+        .find(|exchange| exchange.method == "GET" && exchange.path == path)
+        .and_then(|exchange| exchange.body)
+        .unwrap_or_else(|| panic!("no page of {} is recorded in the {} flow", path, name))
+}
+
+/// Runs `test` once with each storage backend built in, holding global state with nothing
+/// recorded yet, so that everything kept in a `Store` is known to work the same on each.
+pub fn with_each_backend(test: impl Fn()) {
+    #[allow(unused_mut)]
+    let mut backends = vec!["files"];
+    #[cfg(feature = "sqlite")]
+    backends.push("sqlite");
+    for backend in backends {
+        let _globals = lock_globals();
+        let config_path = UserConfig::path();
+        create_dir_all(config_path.parent().unwrap()).unwrap();
+        std::fs::write(&config_path, format!("storage = {:?}\n", backend)).unwrap();
+        test();
+    }
+}
+
+/// Builds the cargo project at `dir`, panicking with the compiler's output if it fails.
+pub fn assert_builds(dir: &Path) {
+    let output = Command::new(env!("CARGO"))
+        .args(["build", "--offline", "--quiet"])
+        .current_dir(dir)
+        .env("CARGO_TARGET_DIR", dir.join("target"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "building {} failed:\n{}",
+        dir.display(),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// A snapshot taken at noon UTC on a day of 2024, with the first `solved_count` of 100 problems
+/// solved.
+pub fn snapshot(month: u32, day: u32, solved_count: usize) -> Snapshot {
+    Snapshot {
+        timestamp: noon(month, day),
+        solved_count,
+        total: 100,
+        level: solved_count / 25,
+        solved: ProblemSet((1..=solved_count).collect()),
+        retired: ProblemSet::default(),
+    }
+}
+
+/// Noon UTC on a day of 2024.
+pub fn noon(month: u32, day: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, month, day, 12, 0, 0).unwrap()
+}
+
+/// Problems with the statuses spelled out by `statuses`, a character per problem: `S` for
+/// solved, `U` for unsolved and `R` for retired. Spaces are ignored, for grouping.
+pub fn problems(statuses: &str) -> Problems {
+    Problems(
+        statuses
+            .chars()
+            .filter(|c| *c != ' ')
+            .map(|c| match c {
+                'S' => ProblemStatus::Solved,
+                'U' => ProblemStatus::Unsolved,
+                'R' => ProblemStatus::Retired,
+                _ => panic!("unknown problem status {:?}", c),
+            })
+            .collect(),
+    )
+}
+
+/// The progress of an account that hasn't solved anything yet, whose progress page lists no
+/// levels.
+pub fn new_account() -> Progress {
+    Progress::from_html(include_str!(
+        "../tests/fixtures/pages/progress-new-account.html"
+    ))
+    .unwrap()
+}
+
+/// The progress of the account on the progress page fixture: 26 of 29 problems solved, and
+/// Level 1 of 3 completed.
+pub fn account_progress() -> Progress {
+    Progress::from_html(include_str!("../tests/fixtures/pages/progress.html")).unwrap()
+}
+
+/// The progress on the fixture of a progress page that leaves problem 12 out of its listing,
+/// as if it were taken down: 25 of 28 problems solved.
+pub fn gap_progress() -> Progress {
+    Progress::from_html(include_str!("../tests/fixtures/pages/progress-gap.html")).unwrap()
+}
+
+/// A request a `MockServer` received.
+#[derive(Clone, Debug)]
+pub struct Received {
+    pub method: String,
+    /// The path and query of the request, like `/problem=1`.
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Received {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| &**value)
+    }
+}
+
+/// A response a `MockServer` answers with.
+#[derive(Clone, Debug)]
+pub struct Reply {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Reply {
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: 200,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    pub fn status(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+}
+
+type Routes = Vec<(String, String, VecDeque<Reply>)>;
+
+/// An HTTP server on localhost answering requests with canned replies, and keeping track of
+/// what it received. Requests without a reply are answered with `404 Not Found`.
+#[derive(Debug)]
+pub struct MockServer {
+    url: String,
+    routes: Arc<Mutex<Routes>>,
+    received: Arc<Mutex<Vec<Received>>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl MockServer {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let routes = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let stopped = Arc::new(AtomicBool::new(false));
+        {
+            let (routes, received, stopped) = (routes.clone(), received.clone(), stopped.clone());
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stopped.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        let _ = Self::serve(stream, &routes, &received);
+                    }
+                }
+            });
+        }
+        Self {
+            url,
+            routes,
+            received,
+            stopped,
+        }
+    }
+
+    /// The base URL of the server, like `http://127.0.0.1:12345/`.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Answers the next `method` request to `path` with `reply`. Replies to the same request
+    /// are used up in the order they're added, and the last one is kept for later requests.
+    pub fn reply(&self, method: &str, path: &str, reply: Reply) -> &Self {
+        let mut routes = self.routes.lock().unwrap();
+        match routes
+            .iter_mut()
+            .find(|(route_method, route_path, _)| route_method == method && route_path == path)
+        {
+            Some((_, _, replies)) => replies.push_back(reply),
+            None => routes.push((method.to_owned(), path.to_owned(), vec![reply].into())),
+        }
+        self
+    }
+
+    /// Every request received so far, in the order they were received.
+    pub fn received(&self) -> Vec<Received> {
+        self.received.lock().unwrap().clone()
+    }
+
+    fn serve(
+        stream: TcpStream,
+        routes: &Mutex<Routes>,
+        received: &Mutex<Vec<Received>>,
+    ) -> io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_owned();
+        let path = parts.next().unwrap_or_default().to_owned();
+        let mut headers = Vec::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line)?;
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_owned(), value.trim().to_owned()));
+            }
+        }
+        let length = headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, value)| value.parse().ok())
+            .unwrap_or(0);
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body)?;
+
+        let reply = {
+            let mut routes = routes.lock().unwrap();
+            routes
+                .iter_mut()
+                .find(|(route_method, route_path, _)| {
+                    *route_method == method && *route_path == path
+                })
+                .map(|(_, _, replies)| {
+                    if replies.len() > 1 {
+                        replies.pop_front().unwrap()
+                    } else {
+                        replies[0].clone()
+                    }
+                })
+                .unwrap_or_else(|| Reply::status(404))
+        };
+        received.lock().unwrap().push(Received {
+            method,
+            path,
+            headers,
+            body: String::from_utf8_lossy(&body).into_owned(),
+        });
+
+        let mut stream = stream;
+        write!(stream, "HTTP/1.1 {} Mock\r\n", reply.status)?;
+        for (name, value) in &reply.headers {
+            write!(stream, "{}: {}\r\n", name, value)?;
+        }
+        write!(
+            stream,
+            "Content-Length: {}\r\nConnection: close\r\n\r\n",
+            reply.body.len()
+        )?;
+        stream.write_all(&reply.body)?;
+        stream.flush()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        // Wake the server up, so that it sees it's been stopped.
+        let _ = TcpStream::connect(self.url.trim_start_matches("http://").trim_end_matches('/'));
+    }
+}
+
+/// Solves captchas with canned answers, remembering the images it was shown.
+#[derive(Debug, Default)]
+pub struct ScriptedPrompt {
+    pub answers: Vec<&'static str>,
+    pub shown: Vec<Vec<u8>>,
+}
+
+impl ScriptedPrompt {
+    pub fn new(answers: &[&'static str]) -> Self {
+        Self {
+            answers: answers.to_vec(),
+            shown: Vec::new(),
+        }
+    }
+}
+
+impl CaptchaPrompt for ScriptedPrompt {
+    fn solve(&mut self, image: &[u8]) -> Result<String, Box<dyn Error>> {
+        self.shown.push(image.to_owned());
+        Ok(self.answers.remove(0).to_owned())
+    }
+}
+
+/// A clipboard that keeps track of what's copied to it, or fails to if it's unavailable.
+#[derive(Debug, Default)]
+pub struct FakeClipboard {
+    pub copied: Vec<String>,
+    pub unavailable: bool,
+}
+
+impl FakeClipboard {
+    pub fn unavailable() -> Self {
+        Self {
+            copied: Vec::new(),
+            unavailable: true,
+        }
+    }
+}
+
+impl Clipboard for FakeClipboard {
+    fn set_text(&mut self, text: &str) -> Result<(), Box<dyn Error>> {
+        if self.unavailable {
+            return Err("no clipboard".into());
+        }
+        self.copied.push(text.to_owned());
+        Ok(())
+    }
+}
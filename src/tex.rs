@@ -0,0 +1,400 @@
+//! A best-effort conversion of the TeX math in problem statements (rendered by MathJax on the
+//! site) into plain Unicode text.
+
+const SYMBOLS: &[(&str, &str)] = &[
+    ("alpha", "α"),
+    ("approx", "≈"),
+    ("beta", "β"),
+    ("cdot", "·"),
+    ("cdots", "⋯"),
+    ("circ", "∘"),
+    ("cup", "∪"),
+    ("cap", "∩"),
+    ("delta", "δ"),
+    ("Delta", "Δ"),
+    ("div", "÷"),
+    ("dots", "…"),
+    ("epsilon", "ε"),
+    ("equiv", "≡"),
+    ("exists", "∃"),
+    ("forall", "∀"),
+    ("gamma", "γ"),
+    ("ge", "≥"),
+    ("geq", "≥"),
+    ("gt", ">"),
+    ("in", "∈"),
+    ("infty", "∞"),
+    ("lambda", "λ"),
+    ("lceil", "⌈"),
+    ("ldots", "…"),
+    ("le", "≤"),
+    ("leq", "≤"),
+    ("lfloor", "⌊"),
+    ("lt", "<"),
+    ("mid", "∣"),
+    ("mu", "μ"),
+    ("ne", "≠"),
+    ("neq", "≠"),
+    ("notin", "∉"),
+    ("omega", "ω"),
+    ("Omega", "Ω"),
+    ("phi", "φ"),
+    ("Phi", "Φ"),
+    ("pi", "π"),
+    ("pm", "±"),
+    ("prod", "∏"),
+    ("rceil", "⌉"),
+    ("rfloor", "⌋"),
+    ("rho", "ρ"),
+    ("sigma", "σ"),
+    ("Sigma", "Σ"),
+    ("sim", "∼"),
+    ("subset", "⊂"),
+    ("subseteq", "⊆"),
+    ("sum", "∑"),
+    ("tau", "τ"),
+    ("theta", "θ"),
+    ("times", "×"),
+    ("to", "→"),
+    ("varphi", "φ"),
+    ("vert", "|"),
+    ("lvert", "|"),
+    ("rvert", "|"),
+    ("zeta", "ζ"),
+    ("bmod", " mod "),
+    ("mod", " mod "),
+    ("quad", " "),
+    ("qquad", " "),
+    ("lbrace", "{"),
+    ("rbrace", "}"),
+    ("{", "{"),
+    ("}", "}"),
+    ("$", "$"),
+    ("%", "%"),
+    ("#", "#"),
+    ("&", "&"),
+    ("_", "_"),
+    (",", " "),
+    (";", " "),
+    (":", " "),
+    ("!", ""),
+    (" ", " "),
+    ("\\", " "),
+    ("left", ""),
+    ("right", ""),
+    ("displaystyle", ""),
+    ("large", ""),
+    ("Large", ""),
+    ("small", ""),
+];
+
+/// Commands whose single argument is kept as plain text.
+const TEXT_COMMANDS: &[&str] = &[
+    "text",
+    "textrm",
+    "textit",
+    "textbf",
+    "mathrm",
+    "mathit",
+    "mathbf",
+    "mathbb",
+    "mathcal",
+    "operatorname",
+    "mbox",
+    "color",
+];
+
+const SUPERSCRIPTS: &[(char, char)] = &[
+    ('0', '⁰'),
+    ('1', '¹'),
+    ('2', '²'),
+    ('3', '³'),
+    ('4', '⁴'),
+    ('5', '⁵'),
+    ('6', '⁶'),
+    ('7', '⁷'),
+    ('8', '⁸'),
+    ('9', '⁹'),
+    ('+', '⁺'),
+    ('-', '⁻'),
+    ('=', '⁼'),
+    ('(', '⁽'),
+    (')', '⁾'),
+    ('n', 'ⁿ'),
+    ('i', 'ⁱ'),
+    ('k', 'ᵏ'),
+    ('x', 'ˣ'),
+];
+
+const SUBSCRIPTS: &[(char, char)] = &[
+    ('0', '₀'),
+    ('1', '₁'),
+    ('2', '₂'),
+    ('3', '₃'),
+    ('4', '₄'),
+    ('5', '₅'),
+    ('6', '₆'),
+    ('7', '₇'),
+    ('8', '₈'),
+    ('9', '₉'),
+    ('+', '₊'),
+    ('-', '₋'),
+    ('=', '₌'),
+    ('(', '₍'),
+    (')', '₎'),
+    ('a', 'ₐ'),
+    ('e', 'ₑ'),
+    ('i', 'ᵢ'),
+    ('j', 'ⱼ'),
+    ('k', 'ₖ'),
+    ('m', 'ₘ'),
+    ('n', 'ₙ'),
+    ('o', 'ₒ'),
+    ('p', 'ₚ'),
+    ('r', 'ᵣ'),
+    ('s', 'ₛ'),
+    ('t', 'ₜ'),
+    ('x', 'ₓ'),
+];
+
+struct Parser<'t> {
+    rest: &'t str,
+}
+
+impl<'t> Parser<'t> {
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.rest.chars().next()?;
+        self.rest = &self.rest[c.len_utf8()..];
+        Some(c)
+    }
+
+    /// Reads the name of a command, just after its backslash.
+    fn command_name(&mut self) -> &'t str {
+        let len = self
+            .rest
+            .char_indices()
+            .find(|(_, c)| !c.is_ascii_alphabetic())
+            .map_or(self.rest.len(), |(idx, _)| idx);
+        let len = if len == 0 {
+            self.rest.chars().next().map_or(0, char::len_utf8)
+        } else {
+            len
+        };
+        let (name, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        name
+    }
+
+    /// Reads a single argument: either a braced group or a single token.
+    fn argument(&mut self) -> String {
+        self.rest = self.rest.trim_start();
+        match self.rest.chars().next() {
+            Some('{') => {
+                self.next_char();
+                let mut depth = 1;
+                let end = self
+                    .rest
+                    .char_indices()
+                    .find(|&(_, c)| {
+                        match c {
+                            '{' => depth += 1,
+                            '}' => depth -= 1,
+                            _ => (),
+                        }
+                        depth == 0
+                    })
+                    .map_or(self.rest.len(), |(idx, _)| idx);
+                let (group, rest) = self.rest.split_at(end);
+                self.rest = rest.get(1..).unwrap_or("");
+                convert(group)
+            }
+            Some('\\') => {
+                self.next_char();
+                let name = self.command_name();
+                self.command(name)
+            }
+            Some(_) => self.next_char().map(String::from).unwrap_or_default(),
+            None => String::new(),
+        }
+    }
+
+    fn command(&mut self, name: &str) -> String {
+        if TEXT_COMMANDS.contains(&name) {
+            return self.argument();
+        }
+        match name {
+            "frac" | "dfrac" | "tfrac" => {
+                let numerator = self.argument();
+                let denominator = self.argument();
+                format!(
+                    "{}/{}",
+                    parenthesize(&numerator),
+                    parenthesize(&denominator)
+                )
+            }
+            "sqrt" => format!("√{}", parenthesize(&self.argument())),
+            "pmod" => format!(" (mod {})", self.argument()),
+            _ => SYMBOLS
+                .iter()
+                .find(|(symbol, _)| *symbol == name)
+                .map_or_else(|| name.to_owned(), |(_, unicode)| (*unicode).to_owned()),
+        }
+    }
+}
+
+/// Wraps `s` in parentheses unless it's a single "atom" that doesn't need them.
+fn parenthesize(s: &str) -> String {
+    if s.chars().all(|c| c.is_alphanumeric()) || s.chars().count() <= 1 {
+        s.to_owned()
+    } else {
+        format!("({})", s)
+    }
+}
+
+/// Maps every character of `s` with `table`, falling back to `fallback_prefix` and parentheses
+/// when some character has no mapping.
+fn script(s: &str, table: &[(char, char)], fallback_prefix: char) -> String {
+    let mapped = s
+        .chars()
+        .map(|c| table.iter().find(|(from, _)| *from == c).map(|(_, to)| *to))
+        .collect::<Option<String>>();
+    match mapped {
+        Some(mapped) if !mapped.is_empty() => mapped,
+        _ => format!("{}{}", fallback_prefix, parenthesize(s)),
+    }
+}
+
+/// Converts a TeX expression (without math delimiters) into Unicode text.
+fn convert(tex: &str) -> String {
+    let mut parser = Parser { rest: tex };
+    let mut converted = String::with_capacity(tex.len());
+    while let Some(c) = parser.next_char() {
+        match c {
+            '\\' => {
+                let name = parser.command_name();
+                converted.push_str(&parser.command(name));
+            }
+            '^' => converted.push_str(&script(&parser.argument(), SUPERSCRIPTS, '^')),
+            '_' => converted.push_str(&script(&parser.argument(), SUBSCRIPTS, '_')),
+            '{' | '}' => (),
+            '~' => converted.push(' '),
+            c => converted.push(c),
+        }
+    }
+    converted
+}
+
+/// Replaces every math span in `text` (delimited by `$…$`, `$$…$$`, `\(…\)` or `\[…\]`) with
+/// its Unicode approximation, leaving the surrounding text untouched.
+pub fn convert_math(text: &str) -> String {
+    const DELIMITERS: &[(&str, &str)] = &[("$$", "$$"), ("$", "$"), ("\\(", "\\)"), ("\\[", "\\]")];
+
+    let mut converted = String::with_capacity(text.len());
+    let mut rest = text;
+    loop {
+        let next = DELIMITERS
+            .iter()
+            .filter_map(|&(open, close)| rest.find(open).map(|idx| (idx, open, close)))
+            .min_by_key(|&(idx, open, _)| (idx, std::cmp::Reverse(open.len())));
+        let (start, open, close) = match next {
+            Some(next) => next,
+            None => break,
+        };
+        let after_open = &rest[start + open.len()..];
+        let end = match after_open.find(close) {
+            Some(end) => end,
+            None => break,
+        };
+        converted.push_str(&rest[..start]);
+        converted.push_str(convert(&after_open[..end]).trim());
+        rest = &after_open[end + close.len()..];
+    }
+    converted.push_str(rest);
+    converted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_converts(cases: &[(&str, &str)]) {
+        for &(tex, text) in cases {
+            assert_eq!(convert(tex), text, "{:?}", tex);
+        }
+    }
+
+    #[test]
+    fn converts_superscripts_and_subscripts() {
+        assert_converts(&[
+            ("n^2", "n²"),
+            ("2^{10}", "2¹⁰"),
+            ("x^{n+1}", "xⁿ⁺¹"),
+            ("a_1 + a_{k}", "a₁ + aₖ"),
+            ("p_{n-1}", "pₙ₋₁"),
+            // Characters without a Unicode superscript fall back to a caret.
+            ("2^y", "2^y"),
+            ("2^{a+b}", "2^(a+b)"),
+            ("x_{y}", "x_y"),
+            ("e^{-y/2}", "e^(-y/2)"),
+        ]);
+    }
+
+    #[test]
+    fn converts_symbols() {
+        assert_converts(&[
+            ("3 \\times 4", "3 × 4"),
+            ("n \\le 10", "n ≤ 10"),
+            ("a \\leq b \\ge c", "a ≤ b ≥ c"),
+            ("\\alpha\\beta", "αβ"),
+            ("10\\,000", "10 000"),
+            ("\\{1, 2\\}", "{1, 2}"),
+        ]);
+    }
+
+    #[test]
+    fn converts_fractions_and_roots() {
+        assert_converts(&[
+            ("\\frac{1}{2}", "1/2"),
+            ("\\dfrac{n+1}{2}", "(n+1)/2"),
+            ("\\frac 1 x", "1/x"),
+            ("\\frac{\\frac{1}{2}}{3}", "(1/2)/3"),
+            ("\\sqrt{5}", "√5"),
+            ("\\sqrt{n^2+1}", "√(n²+1)"),
+        ]);
+    }
+
+    #[test]
+    fn keeps_text_and_passes_unknown_commands_through() {
+        assert_converts(&[
+            ("\\text{for all } n", "for all  n"),
+            ("\\operatorname{lcm}(a, b)", "lcm(a, b)"),
+            ("\\unknown x", "unknown x"),
+            ("\\binom{n}{k}", "binomnk"),
+        ]);
+    }
+
+    #[test]
+    fn survives_unbalanced_braces() {
+        assert_converts(&[
+            ("2^{10", "2¹⁰"),
+            ("a}b", "ab"),
+            ("{{a}", "a"),
+            ("\\frac{1}{2", "1/2"),
+            ("\\frac{1", "1/"),
+            ("x^", "x^"),
+        ]);
+    }
+
+    #[test]
+    fn converts_only_math_spans() {
+        for &(text, converted) in &[
+            ("The sum $1 + 2^2$ is 5.", "The sum 1 + 2² is 5."),
+            ("$$\\frac{1}{2}$$ and \\(n_1\\)", "1/2 and n₁"),
+            ("\\[ 3 \\times 4 \\]", "3 × 4"),
+            ("It costs $5.", "It costs $5."),
+            ("No math here.", "No math here."),
+        ] {
+            assert_eq!(convert_math(text), converted, "{:?}", text);
+        }
+    }
+}
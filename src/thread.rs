@@ -0,0 +1,84 @@
+//! The discussion threads of problems, which open up to those who've solved them.
+
+use {
+    crate::statement::{collapse_whitespace, flatten_paragraphs},
+    std::error::Error,
+    unhtml::scraper::{ElementRef, Html, Selector},
+};
+
+/// Selectors for the elements of a thread's page holding whole posts, their authors and their
+/// text, tried in order.
+const POST_SELECTORS: &[&str] = &["div.post", "div.forum_post", "table.post"];
+const AUTHOR_SELECTORS: &[&str] = &[".post_author", ".author", ".username"];
+const TEXT_SELECTORS: &[&str] = &[".post_content", ".post_text", ".message"];
+
+#[derive(Debug)]
+pub struct Post {
+    pub author: String,
+    pub paragraphs: Vec<String>,
+}
+
+/// The first page of the discussion thread of a problem.
+#[derive(Debug)]
+pub struct Thread {
+    pub number: usize,
+    pub url: String,
+    pub posts: Vec<Post>,
+}
+
+fn select_first<'a>(el: ElementRef<'a>, selectors: &[&str]) -> Option<ElementRef<'a>> {
+    selectors
+        .iter()
+        .find_map(|selector| el.select(&Selector::parse(selector).unwrap()).next())
+}
+
+impl Thread {
+    pub fn from_page(number: usize, url: String, page: &str) -> Result<Self, Box<dyn Error>> {
+        let html = Html::parse_document(page);
+        let posts = POST_SELECTORS
+            .iter()
+            .map(|selector| {
+                html.select(&Selector::parse(selector).unwrap())
+                    .collect::<Vec<_>>()
+            })
+            .find(|posts| !posts.is_empty())
+            .ok_or_else(|| {
+                format!(
+                    "unable to find any posts in the thread of problem {}; make sure it's solved",
+                    number
+                )
+            })?
+            .into_iter()
+            .map(|post| {
+                let author = select_first(post, AUTHOR_SELECTORS)
+                    .map(|author| collapse_whitespace(&author.text().collect::<String>()))
+                    .unwrap_or_default();
+                let mut paragraphs = Vec::new();
+                flatten_paragraphs(
+                    select_first(post, TEXT_SELECTORS).unwrap_or(post),
+                    &mut paragraphs,
+                );
+                Post { author, paragraphs }
+            })
+            .collect();
+        Ok(Self { number, url, posts })
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("# Problem {} thread\n\n<{}>\n", self.number, self.url);
+        for post in &self.posts {
+            let author = if post.author.is_empty() {
+                "Unknown author"
+            } else {
+                &post.author
+            };
+            markdown.push_str(&format!("\n## {}\n", author));
+            for paragraph in &post.paragraphs {
+                markdown.push('\n');
+                markdown.push_str(paragraph);
+                markdown.push('\n');
+            }
+        }
+        markdown
+    }
+}
@@ -0,0 +1,136 @@
+//! Measuring how long the phases of a command take, like requests and parsing, for `--timings`.
+
+use {
+    serde::Serialize,
+    std::{
+        io::{stderr, Write},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Mutex,
+        },
+        time::Instant,
+    },
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// When timing was enabled, which the total is measured from.
+static STARTED: Mutex<Option<Instant>> = Mutex::new(None);
+/// Every phase measured so far, in the order each first finished.
+static PHASES: Mutex<Vec<PhaseTiming>> = Mutex::new(Vec::new());
+
+/// How long every occurrence of a phase took, all told.
+#[derive(Clone, Debug, Serialize)]
+pub struct PhaseTiming {
+    pub phase: &'static str,
+    /// How many times the phase happened, like the number of requests made.
+    pub count: usize,
+    pub total_ms: f64,
+}
+
+/// Starts measuring phases, which are otherwise not measured at all.
+pub fn enable() {
+    *STARTED.lock().unwrap() = Some(Instant::now());
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// Measures a phase from when it's started until it's dropped.
+pub struct PhaseTimer {
+    phase: &'static str,
+    start: Option<Instant>,
+}
+
+impl PhaseTimer {
+    pub fn start(phase: &'static str) -> Self {
+        Self {
+            phase,
+            start: is_enabled().then(Instant::now),
+        }
+    }
+}
+
+impl Drop for PhaseTimer {
+    fn drop(&mut self) {
+        let elapsed = match self.start {
+            Some(start) => start.elapsed(),
+            None => return,
+        };
+        let mut phases = PHASES.lock().unwrap();
+        let index = match phases.iter().position(|timing| timing.phase == self.phase) {
+            Some(index) => index,
+            None => {
+                phases.push(PhaseTiming {
+                    phase: self.phase,
+                    count: 0,
+                    total_ms: 0.0,
+                });
+                phases.len() - 1
+            }
+        };
+        let timing = &mut phases[index];
+        timing.count += 1;
+        timing.total_ms += elapsed.as_secs_f64() * 1000.0;
+    }
+}
+
+/// Measures how long `f` takes as an occurrence of `phase`.
+pub fn time<T>(phase: &'static str, f: impl FnOnce() -> T) -> T {
+    let _timer = PhaseTimer::start(phase);
+    f()
+}
+
+/// Every phase measured so far, followed by the total since timing was enabled. Empty if it
+/// wasn't.
+pub fn report() -> Vec<PhaseTiming> {
+    let started = match *STARTED.lock().unwrap() {
+        Some(started) => started,
+        None => return Vec::new(),
+    };
+    let mut report = PHASES.lock().unwrap().clone();
+    report.push(PhaseTiming {
+        phase: "total",
+        count: 1,
+        total_ms: started.elapsed().as_secs_f64() * 1000.0,
+    });
+    report
+}
+
+/// Prints the phases measured so far as a table on standard error, if timing is enabled.
+pub fn print_report() {
+    let report = report();
+    if report.is_empty() {
+        return;
+    }
+    let width = report
+        .iter()
+        .map(|timing| timing.phase.len())
+        .max()
+        .unwrap_or_default();
+    let mut stderr = stderr().lock();
+    let _ = writeln!(
+        stderr,
+        "{:<width$}  {:>5}  {:>10}",
+        "Phase",
+        "Count",
+        "Time",
+        width = width
+    );
+    for PhaseTiming {
+        phase,
+        count,
+        total_ms,
+    } in &report
+    {
+        let _ = writeln!(
+            stderr,
+            "{:<width$}  {:>5}  {:>10}",
+            phase,
+            count,
+            format!("{:.1}ms", total_ms),
+            width = width
+        );
+    }
+}
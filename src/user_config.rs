@@ -0,0 +1,42 @@
+//! Configuration of the user rather than of a workspace, kept in `config.toml` in the user's
+//! configuration directory.
+
+use {
+    crate::{notify::Webhook, paths::config_dir, storage::StorageKind},
+    serde::Deserialize,
+    std::{collections::BTreeMap, error::Error, fs::read_to_string, io::ErrorKind, path::PathBuf},
+};
+
+const USER_CONFIG_FILE_NAME: &str = "config.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct UserConfig {
+    /// Where history, submissions, benchmarks and cached statements are kept.
+    pub storage: StorageKind,
+    /// Webhooks for `cargo euler notify` to notify, by name.
+    pub webhooks: BTreeMap<String, Webhook>,
+    /// The longest wait, in seconds, to sleep through when the site rate limits a request with
+    /// `Retry-After`. Defaults to 120.
+    pub max_retry_after: Option<u64>,
+    /// The URL to use in place of `https://projecteuler.net`, like for a mirror. Overridden by
+    /// `--base-url` and `CARGO_EULER_BASE_URL`.
+    pub base_url: Option<String>,
+}
+
+impl UserConfig {
+    pub fn path() -> PathBuf {
+        config_dir().join(USER_CONFIG_FILE_NAME)
+    }
+
+    /// Reads the configuration, which is all defaults if there's no configuration file.
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        let path = Self::path();
+        match read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents)
+                .map_err(|e| format!("unable to parse {}: {}", path.display(), e).into()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("unable to read {}: {}", path.display(), e).into()),
+        }
+    }
+}
@@ -0,0 +1,1031 @@
+use {
+    crate::{
+        pattern::{detect, glob_matches, Detected, Pattern},
+        runner::TimeLimit,
+    },
+    log::warn,
+    once_cell::sync::OnceCell,
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::BTreeMap,
+        env::current_dir,
+        error::Error,
+        fmt::{self, Display, Formatter},
+        fs::{read_dir, read_to_string, write},
+        io::ErrorKind,
+        iter,
+        path::{Path, PathBuf},
+    },
+    toml_edit::{value, Array, DocumentMut, Item, Table, TomlError},
+};
+
+pub const MANIFEST_FILE_NAME: &str = "Cargo.toml";
+pub const CONFIG_FILE_NAME: &str = "euler.toml";
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LayoutKind {
+    /// Every solution is a binary target in `src/bin` of a single package.
+    BinPerProblem,
+    /// Every solution is its own package in the `problems` directory of a workspace.
+    CratePerProblem,
+}
+
+/// Preferences for where solutions live in a workspace and how they're named.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Layout {
+    pub kind: LayoutKind,
+    /// The minimum number of digits problem numbers are zero-padded to in file and binary names.
+    pub padding: usize,
+    /// What comes before the padded problem number in the names of solution binaries and
+    /// crates.
+    pub prefix: String,
+    /// The path of solution files relative to the workspace root, like `src/bin/euler{n:03}.rs`
+    /// or `solutions/{n:03}_{slug}.rs`, in place of the prefix and padding. Only bin-per-problem
+    /// layouts can have one. Each solution is the binary target named after its file stem, so
+    /// files outside of `src/bin` need `[[bin]]` entries in the manifest.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<Pattern>,
+    /// Globs of paths relative to the workspace root, like `src/bin/p*.rs`, picking which of
+    /// several solution files for the same problem to use. The first glob matching exactly one
+    /// of them wins.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub prefer: Vec<String>,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            kind: LayoutKind::BinPerProblem,
+            padding: 3,
+            prefix: "p".to_owned(),
+            pattern: None,
+            prefer: Vec::new(),
+        }
+    }
+}
+
+impl Layout {
+    pub fn padded_number(&self, number: usize) -> String {
+        format!("{:0width$}", number, width = self.padding)
+    }
+
+    /// The name of the binary target (and, in crate-per-problem layouts, the package) holding
+    /// the solution to problem `number`.
+    pub fn bin_name(&self, number: usize) -> String {
+        format!("{}{}", self.prefix, self.padded_number(number))
+    }
+}
+
+/// Where to find templates for scaffolded files, relative to the workspace root.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Templates {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub solution: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_message: Option<PathBuf>,
+}
+
+/// How solutions are run.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RunConfig {
+    /// If set, the answer of a solution is the last line of its output starting with this
+    /// prefix, rather than the last non-empty line.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub answer_prefix: Option<String>,
+    /// How long a solution may run before it's killed, like `60s`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_limit: Option<TimeLimit>,
+}
+
+/// How solutions are benchmarked.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BenchConfig {
+    /// How many seconds a solution may take before it's flagged as too slow.
+    pub budget: f64,
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        Self { budget: 60.0 }
+    }
+}
+
+/// The contents of a workspace's `euler.toml`.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub layout: Layout,
+    pub templates: Templates,
+    pub run: RunConfig,
+    pub bench: BenchConfig,
+}
+
+impl Config {
+    pub fn read(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let config: Self = toml::from_str(&read_to_string(path)?)
+            .map_err(|e| format!("unable to parse {}: {}", path.display(), e))?;
+        if config.layout.pattern.is_some() && config.layout.kind != LayoutKind::BinPerProblem {
+            return Err(format!(
+                "unable to use {}: only bin-per-problem layouts can have a `layout.pattern`",
+                path.display()
+            )
+            .into());
+        }
+        Ok(config)
+    }
+}
+
+/// Prefixes commonly put before problem numbers in the names of solution files.
+const SOLUTION_NAME_PREFIXES: &[&str] = &["problem", "euler", "prob", "pe", "p"];
+
+/// Names of files that are named after their directory instead, like `042/main.rs`.
+const DIRECTORY_NAMED_FILES: &[&str] = &["main", "lib", "mod"];
+
+/// Extracts the number of the problem a solution file is for from its path, tolerating common
+/// naming schemes like `p042.rs`, `problem_42.rs`, `042/main.rs` or `p042/src/main.rs`, as
+/// well as names starting with the `prefix` of the layout.
+pub fn problem_number_from_path(path: &Path, prefix: &str) -> Option<usize> {
+    if path.extension()? != "rs" {
+        return None;
+    }
+    let stem = path.file_stem()?.to_str()?;
+    let name = if DIRECTORY_NAMED_FILES.contains(&stem) {
+        path.parent()?
+            .iter()
+            .rev()
+            .find(|dir| *dir != "src" && *dir != "bin")?
+            .to_str()?
+    } else {
+        stem
+    };
+
+    let lowercase = name.to_ascii_lowercase();
+    let prefix = prefix.to_ascii_lowercase();
+    iter::once(&*prefix)
+        .chain(SOLUTION_NAME_PREFIXES.iter().copied())
+        .chain(iter::once(""))
+        .filter_map(|prefix| lowercase.strip_prefix(prefix))
+        .find_map(|rest| {
+            let rest = rest.trim_start_matches(['_', '-']);
+            let digits_len = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            // Allow a descriptive suffix like `042_coded_triangle_numbers`, but not things
+            // like `2d`.
+            let suffix = &rest[digits_len..];
+            if digits_len == 0 || !(suffix.is_empty() || suffix.starts_with(['_', '-'])) {
+                return None;
+            }
+            rest[..digits_len].parse().ok().filter(|&number| number > 0)
+        })
+}
+
+/// Finds every Rust file in `dir` and its subdirectories, in order of their paths. Hidden
+/// directories, `target`, symlinked directories and nested workspaces with an `euler.toml` of
+/// their own are skipped.
+fn rust_files(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    fn visit(dir: &Path, found: &mut Vec<PathBuf>) -> Result<(), Box<dyn Error>> {
+        let entries = match read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(format!("unable to read {}: {}", dir.display(), e).into()),
+        };
+        let mut paths = entries
+            .map(|entry| Ok(entry?.path()))
+            .collect::<Result<Vec<_>, std::io::Error>>()?;
+        paths.sort();
+        for path in paths {
+            let hidden = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with('.') || name == "target");
+            if hidden {
+                continue;
+            }
+            if path.is_dir() {
+                let symlink = path.symlink_metadata()?.file_type().is_symlink();
+                if !symlink && !path.join(CONFIG_FILE_NAME).is_file() {
+                    visit(&path, found)?;
+                }
+            } else if path.extension().is_some_and(|extension| extension == "rs") {
+                found.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    let mut found = Vec::new();
+    visit(dir, &mut found)?;
+    Ok(found)
+}
+
+/// The name of the cargo target a solution file is the root of: that of its directory for
+/// files like `p042/main.rs` or `p042/src/main.rs`, or else its file stem.
+fn target_name(path: &Path) -> String {
+    let stem = path.file_stem().unwrap_or_default();
+    let name = match stem.to_str() {
+        Some(name) if DIRECTORY_NAMED_FILES.contains(&name) => path
+            .parent()
+            .into_iter()
+            .flat_map(|dir| dir.iter().rev())
+            .find(|dir| *dir != "src" && *dir != "bin")
+            .unwrap_or(stem),
+        _ => stem,
+    };
+    name.to_string_lossy().into_owned()
+}
+
+/// Several files look like the solution to the same problem, and nothing picks one of them.
+#[derive(Debug)]
+pub struct DuplicateSolutions {
+    pub number: usize,
+    pub paths: Vec<String>,
+}
+
+impl Display for DuplicateSolutions {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "problem {} has {} local solutions: {}; pick one with `--prefer <glob>` or \
+             `layout.prefer` in {}",
+            self.number,
+            self.paths.len(),
+            self.paths.join(", "),
+            CONFIG_FILE_NAME
+        )
+    }
+}
+
+impl Error for DuplicateSolutions {}
+
+#[derive(Debug)]
+pub enum MemberRegistrationError {
+    Parse(TomlError),
+    /// The manifest has no `[workspace]` table, and converting it into a workspace wasn't
+    /// allowed.
+    NotAWorkspace,
+    /// The `members` key of the `[workspace]` table is something other than an array.
+    MembersNotAnArray,
+}
+
+impl Display for MemberRegistrationError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            MemberRegistrationError::Parse(e) => write!(f, "unable to parse manifest: {}", e),
+            MemberRegistrationError::NotAWorkspace => write!(f, "manifest isn't a workspace"),
+            MemberRegistrationError::MembersNotAnArray => {
+                write!(f, "`workspace.members` isn't an array")
+            }
+        }
+    }
+}
+
+impl Error for MemberRegistrationError {}
+
+/// How far to indent the members of a multi-line array that has none yet.
+const DEFAULT_MEMBER_INDENT: &str = "    ";
+
+/// Adds `member` to the `members` of the workspace defined by `manifest`, leaving everything
+/// else about its formatting untouched. Returns `None` if `member` is already listed. If
+/// `convert` is set, a manifest without a `[workspace]` table gets one.
+pub fn add_workspace_member(
+    manifest: &str,
+    member: &str,
+    convert: bool,
+) -> Result<Option<String>, MemberRegistrationError> {
+    use self::MemberRegistrationError::*;
+
+    let mut document = manifest.parse::<DocumentMut>().map_err(Parse)?;
+    if !document.contains_table("workspace") {
+        if !convert {
+            return Err(NotAWorkspace);
+        }
+        document.insert("workspace", Item::Table(Table::new()));
+    }
+    let workspace = document["workspace"].as_table_mut().ok_or(NotAWorkspace)?;
+    let members = workspace
+        .entry("members")
+        .or_insert_with(|| value(Array::new()))
+        .as_array_mut()
+        .ok_or(MembersNotAnArray)?;
+    if members
+        .iter()
+        .any(|existing| existing.as_str() == Some(member))
+    {
+        return Ok(None);
+    }
+
+    // Lay out the new member like the last existing one, so multi-line arrays stay
+    // multi-line, but without copying any comments before it. Comments after the last member
+    // stay with it.
+    let last_prefix = members
+        .len()
+        .checked_sub(1)
+        .and_then(|last| members.get(last))
+        .map(|last| last.decor().prefix().and_then(|prefix| prefix.as_str()))
+        .map(|prefix| prefix.unwrap_or("").to_owned());
+    let trailing = members.trailing().as_str().unwrap_or("").to_owned();
+    let prefix = if last_prefix
+        .as_ref()
+        .is_some_and(|prefix| prefix.contains('\n'))
+        || trailing.contains('\n')
+    {
+        let indent = last_prefix
+            .as_ref()
+            .map_or(DEFAULT_MEMBER_INDENT, |prefix| {
+                prefix.rsplit('\n').next().unwrap()
+            });
+        let (comments, closing) = match trailing.rfind('\n') {
+            Some(end) => (&trailing[..=end], format!("\n{}", &trailing[end + 1..])),
+            None => ("\n", trailing.clone()),
+        };
+        members.set_trailing(closing);
+        format!("{}{}", comments, indent)
+    } else if last_prefix.is_some() {
+        " ".to_owned()
+    } else {
+        String::new()
+    };
+    members.push(member);
+    let last = members.len() - 1;
+    members
+        .get_mut(last)
+        .unwrap()
+        .decor_mut()
+        .set_prefix(prefix);
+    Ok(Some(document.to_string()))
+}
+
+/// A cargo project holding solutions.
+#[derive(Debug)]
+pub struct Workspace {
+    pub root: PathBuf,
+    pub config: Config,
+    /// The files found by the first scan for solutions, which later lookups reuse rather than
+    /// walking the workspace again. Commands don't add solutions after looking for them, so it
+    /// doesn't go stale.
+    scanned: OnceCell<BTreeMap<usize, Vec<PathBuf>>>,
+}
+
+impl Workspace {
+    pub fn new(root: PathBuf, config: Config) -> Self {
+        Self {
+            root,
+            config,
+            scanned: OnceCell::new(),
+        }
+    }
+
+    /// Finds the workspace containing the current directory. The nearest ancestor with an
+    /// `euler.toml` wins, since solution packages in a crate-per-problem layout have manifests
+    /// of their own; otherwise, the nearest ancestor with a cargo manifest is used with the
+    /// default layout.
+    pub fn discover() -> Result<Self, Box<dyn Error>> {
+        let cwd = current_dir()?;
+        if let Some(root) = cwd
+            .ancestors()
+            .find(|dir| dir.join(CONFIG_FILE_NAME).is_file())
+        {
+            return Ok(Self::new(
+                root.to_owned(),
+                Config::read(&root.join(CONFIG_FILE_NAME))?,
+            ));
+        }
+
+        let root = cwd
+            .ancestors()
+            .find(|dir| dir.join(MANIFEST_FILE_NAME).is_file())
+            .ok_or_else(|| {
+                format!(
+                    "unable to find a {} or {} in {} or any of its parents",
+                    CONFIG_FILE_NAME,
+                    MANIFEST_FILE_NAME,
+                    cwd.display()
+                )
+            })?;
+        Ok(Self::new(root.to_owned(), Config::default()))
+    }
+
+    /// The directory of the package holding the solution to problem `number`.
+    pub fn package_dir(&self, number: usize) -> PathBuf {
+        match self.config.layout.kind {
+            LayoutKind::BinPerProblem => self.root.clone(),
+            LayoutKind::CratePerProblem => self
+                .root
+                .join("problems")
+                .join(self.config.layout.bin_name(number)),
+        }
+    }
+
+    /// Adds the package in `package_dir` to the `members` of the workspace manifest at the root.
+    /// Returns whether the manifest was changed.
+    pub fn register_member(
+        &self,
+        package_dir: &Path,
+        convert: bool,
+    ) -> Result<bool, Box<dyn Error>> {
+        let manifest_path = self.root.join(MANIFEST_FILE_NAME);
+        let member = self.relative_path(package_dir);
+        let manifest = read_to_string(&manifest_path)?;
+        match add_workspace_member(&manifest, &member, convert) {
+            Ok(Some(manifest)) => {
+                write(&manifest_path, manifest)?;
+                Ok(true)
+            }
+            Ok(None) => Ok(false),
+            Err(MemberRegistrationError::NotAWorkspace) => Err(format!(
+                "{} isn't a workspace manifest; pass `--yes` to add a `[workspace]` with {:?} \
+                 as a member",
+                self.display_path(&manifest_path),
+                member
+            )
+            .into()),
+            Err(e) => Err(format!(
+                "unable to update {}: {}",
+                self.display_path(&manifest_path),
+                e
+            )
+            .into()),
+        }
+    }
+
+    /// Finds the numbers of all problems with local solutions, in ascending order. Without a
+    /// pattern, only solutions where the layout expects them count.
+    pub fn solutions(&self) -> Result<Vec<usize>, Box<dyn Error>> {
+        let layout = &self.config.layout;
+        let scanned = self.scanned()?;
+        if layout.pattern.is_some() {
+            return Ok(scanned.keys().copied().collect());
+        }
+        let numbers = scanned
+            .iter()
+            .filter(|(&number, paths)| paths.contains(&self.solution_path(number)))
+            .map(|(&number, _)| number)
+            .collect::<Vec<_>>();
+        if numbers.is_empty() && layout.kind == LayoutKind::BinPerProblem {
+            if let Some(detected) = self.detect_pattern()? {
+                warn!(
+                    "no solutions follow the layout, but {} files look like `{}`; use it with \
+                     `cargo euler detect-layout`",
+                    detected.matched, detected.pattern
+                );
+            }
+        }
+        Ok(numbers)
+    }
+
+    /// Scans the directory solutions live in for anything that looks like a solution file,
+    /// grouped by problem number. With a pattern, only files matching it count.
+    pub fn scan_solutions(&self) -> Result<BTreeMap<usize, Vec<PathBuf>>, Box<dyn Error>> {
+        Ok(self.scanned()?.clone())
+    }
+
+    /// The files found by scanning for solutions, scanning on first use.
+    fn scanned(&self) -> Result<&BTreeMap<usize, Vec<PathBuf>>, Box<dyn Error>> {
+        self.scanned.get_or_try_init(|| {
+            let layout = &self.config.layout;
+            if let Some(pattern) = &layout.pattern {
+                return self.match_pattern(pattern);
+            }
+            let dir = match layout.kind {
+                LayoutKind::BinPerProblem => self.root.join("src").join("bin"),
+                LayoutKind::CratePerProblem => self.root.join("problems"),
+            };
+            let mut found = BTreeMap::<_, Vec<_>>::new();
+            for path in rust_files(&dir)? {
+                if let Some(number) = problem_number_from_path(&path, &layout.prefix) {
+                    found.entry(number).or_default().push(path);
+                }
+            }
+            Ok(found)
+        })
+    }
+
+    /// Every file that looks like the solution to problem `number`, the one the layout expects
+    /// first. Files symlinked to each other count once, as do files of the same target, like
+    /// the `main.rs` and `lib.rs` of a package.
+    pub fn candidates(&self, number: usize) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let mut candidates = self.scanned()?.get(&number).cloned().unwrap_or_default();
+        let expected = self.solution_path(number);
+        if expected.is_file() {
+            candidates.retain(|path| *path != expected);
+            candidates.insert(0, expected);
+        }
+        let mut seen = Vec::with_capacity(candidates.len());
+        candidates.retain(|path| {
+            let file = path.canonicalize().unwrap_or_else(|_| path.clone());
+            let target = self.target_name(path);
+            let first = !seen
+                .iter()
+                .any(|(seen_file, seen_target)| *seen_file == file || *seen_target == target);
+            seen.push((file, target));
+            first
+        });
+        Ok(candidates)
+    }
+
+    /// Tries `globs` before those of `layout.prefer` to pick between solution files.
+    pub fn prefer(&mut self, globs: Vec<String>) {
+        self.config.layout.prefer.splice(0..0, globs);
+    }
+
+    /// Picks the one of `candidates` to use, being the only one or the only one matching the
+    /// first glob of `layout.prefer` that matches any.
+    pub fn preferred<'c>(&self, candidates: &'c [PathBuf]) -> Option<&'c PathBuf> {
+        if let [only] = candidates {
+            return Some(only);
+        }
+        self.config.layout.prefer.iter().find_map(|glob| {
+            let mut matching = candidates
+                .iter()
+                .filter(|path| glob_matches(glob, &self.relative_path(path)));
+            match (matching.next(), matching.next()) {
+                (Some(preferred), None) => Some(preferred),
+                _ => None,
+            }
+        })
+    }
+
+    /// Finds the file of the local solution to problem `number`, if there is one, refusing to
+    /// pick between several unless `layout.prefer` does.
+    pub fn resolve_solution(&self, number: usize) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        let candidates = self.candidates(number)?;
+        if candidates.is_empty() {
+            return Ok(None);
+        }
+        match self.preferred(&candidates) {
+            Some(preferred) => Ok(Some(preferred.clone())),
+            None => Err(DuplicateSolutions {
+                number,
+                paths: candidates
+                    .iter()
+                    .map(|path| self.relative_path(path))
+                    .collect(),
+            }
+            .into()),
+        }
+    }
+
+    /// The name of the binary target (and, in crate-per-problem layouts, the package) with the
+    /// root at `path`.
+    pub fn target_name(&self, path: &Path) -> String {
+        match self.config.layout.kind {
+            LayoutKind::CratePerProblem => target_name(path),
+            LayoutKind::BinPerProblem => match &self.config.layout.pattern {
+                Some(_) => path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                None => target_name(path),
+            },
+        }
+    }
+
+    /// Renders `path` relative to the workspace root with `/` separating directories, the way
+    /// patterns are written.
+    pub fn relative_path(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .iter()
+            .map(|component| component.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Finds the files matching `pattern`, grouped by problem number.
+    fn match_pattern(
+        &self,
+        pattern: &Pattern,
+    ) -> Result<BTreeMap<usize, Vec<PathBuf>>, Box<dyn Error>> {
+        let mut found = BTreeMap::<_, Vec<_>>::new();
+        for path in rust_files(&self.root.join(pattern.base_dir()))? {
+            if let Some(number) = pattern.matches(&self.relative_path(&path)) {
+                found.entry(number).or_default().push(path);
+            }
+        }
+        Ok(found)
+    }
+
+    /// Guesses the pattern the Rust files of the workspace follow, for a workspace whose
+    /// solutions aren't where its layout says.
+    pub fn detect_pattern(&self) -> Result<Option<Detected>, Box<dyn Error>> {
+        let paths = rust_files(&self.root)?
+            .iter()
+            .map(|path| self.relative_path(path))
+            .collect::<Vec<_>>();
+        Ok(detect(paths.iter().map(|path| &**path)))
+    }
+
+    /// The path of the solution to problem `number`: that of the existing file if there is one,
+    /// or else where `cargo euler new` would put it, described by `title` if the pattern has a
+    /// `{slug}`.
+    pub fn new_solution_path(&self, number: usize, title: Option<&str>) -> PathBuf {
+        let existing = self.solution_path(number);
+        match &self.config.layout.pattern {
+            Some(pattern) if !existing.is_file() => self.root.join(pattern.render(number, title)),
+            _ => existing,
+        }
+    }
+
+    /// The name of the binary target (and, in crate-per-problem layouts, the package) holding
+    /// the solution to problem `number`. With a pattern, that's the stem of its file.
+    pub fn bin_name(&self, number: usize) -> String {
+        match &self.config.layout.pattern {
+            Some(_) => self.target_name(&self.solution_path(number)),
+            None => self.config.layout.bin_name(number),
+        }
+    }
+
+    /// The path of the solution to problem `number`, whether or not it exists. With a pattern
+    /// that has a `{slug}`, that's the first matching file, if any.
+    pub fn solution_path(&self, number: usize) -> PathBuf {
+        if let Some(pattern) = &self.config.layout.pattern {
+            let existing = if pattern.has_slug() {
+                self.scanned()
+                    .ok()
+                    .and_then(|found| found.get(&number))
+                    .and_then(|paths| paths.first().cloned())
+            } else {
+                None
+            };
+            return existing.unwrap_or_else(|| self.root.join(pattern.render(number, None)));
+        }
+        let package_dir = self.package_dir(number);
+        match self.config.layout.kind {
+            LayoutKind::BinPerProblem => package_dir
+                .join("src")
+                .join("bin")
+                .join(self.config.layout.bin_name(number))
+                .with_extension("rs"),
+            LayoutKind::CratePerProblem => package_dir.join("src").join("main.rs"),
+        }
+    }
+
+    /// The directory that data files for problem `number` are downloaded into.
+    pub fn data_dir(&self, number: usize) -> PathBuf {
+        self.root
+            .join("data")
+            .join(self.config.layout.bin_name(number))
+    }
+
+    /// Where the discussion thread of problem `number` is saved.
+    pub fn thread_path(&self, number: usize) -> PathBuf {
+        self.root
+            .join("threads")
+            .join(self.config.layout.bin_name(number))
+            .with_extension("md")
+    }
+
+    /// The path of the data file `name` for problem `number`, relative to the directory of the
+    /// package holding its solution, in the form expected after `env!("CARGO_MANIFEST_DIR")`.
+    pub fn data_include_path(&self, number: usize, name: &str) -> String {
+        let package_depth = self
+            .package_dir(number)
+            .strip_prefix(&self.root)
+            .map_or(0, |relative| relative.components().count());
+        let mut include_path = "/..".repeat(package_depth);
+        include_path.push_str("/data/");
+        include_path.push_str(&self.config.layout.bin_name(number));
+        include_path.push('/');
+        include_path.push_str(name);
+        include_path
+    }
+
+    /// Renders `path` relative to the workspace root, if it's inside of it.
+    pub fn display_path<'p>(&self, path: &'p Path) -> std::path::Display<'p> {
+        path.strip_prefix(&self.root).unwrap_or(path).display()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::test_support::TempDir, std::fs::create_dir_all};
+
+    #[test]
+    fn finds_problem_numbers_in_common_file_names() {
+        for (path, number) in &[
+            ("src/bin/p042.rs", 42),
+            ("src/bin/p42.rs", 42),
+            ("src/bin/P042.rs", 42),
+            ("src/bin/problem_42.rs", 42),
+            ("src/bin/problem-42.rs", 42),
+            ("src/bin/euler042.rs", 42),
+            ("src/bin/pe42.rs", 42),
+            ("src/bin/prob7.rs", 7),
+            ("src/bin/042.rs", 42),
+            ("src/bin/042_coded_triangle_numbers.rs", 42),
+            ("042/main.rs", 42),
+            ("problems/p042/src/main.rs", 42),
+            ("problems/042/src/bin/main.rs", 42),
+            ("problems/problem_101/src/lib.rs", 101),
+        ] {
+            assert_eq!(
+                problem_number_from_path(Path::new(path), "p"),
+                Some(*number),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn ignores_files_that_arent_solutions() {
+        for path in &[
+            "src/bin/p042.txt",
+            "src/bin/p042",
+            "src/bin/utils.rs",
+            "src/bin/p2d.rs",
+            "src/bin/p000.rs",
+            "src/bin/problem.rs",
+            "src/main.rs",
+            "src/lib.rs",
+        ] {
+            assert_eq!(
+                problem_number_from_path(Path::new(path), "p"),
+                None,
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn finds_problem_numbers_after_the_layout_prefix() {
+        let path = Path::new("src/bin/sol042.rs");
+        assert_eq!(problem_number_from_path(path, "sol"), Some(42));
+        assert_eq!(problem_number_from_path(path, "p"), None);
+        // The common prefixes still work alongside it.
+        assert_eq!(
+            problem_number_from_path(Path::new("src/bin/p042.rs"), "sol"),
+            Some(42)
+        );
+    }
+
+    /// A bin-per-problem workspace in `dir` with the given files, relative to its root.
+    fn bin_workspace(dir: &TempDir, config: Config, files: &[&str]) -> Workspace {
+        for file in files {
+            let path = dir.path().join(file);
+            create_dir_all(path.parent().unwrap()).unwrap();
+            write(path, "fn main() {}\n").unwrap();
+        }
+        Workspace::new(dir.path().to_owned(), config)
+    }
+
+    #[test]
+    fn lists_solutions_where_the_layout_expects_them() {
+        let dir = TempDir::new();
+        let workspace = bin_workspace(
+            &dir,
+            Config::default(),
+            &[
+                "src/bin/p002.rs",
+                "src/bin/p001.rs",
+                "src/bin/problem_3.rs",
+                "src/bin/p10.rs",
+                "src/bin/utils.rs",
+            ],
+        );
+        assert_eq!(workspace.solutions().unwrap(), [1, 2]);
+        assert_eq!(
+            workspace
+                .scan_solutions()
+                .unwrap()
+                .into_keys()
+                .collect::<Vec<_>>(),
+            [1, 2, 3, 10]
+        );
+
+        let dir = TempDir::new();
+        let mut config = Config::default();
+        config.layout.prefix = "sol".to_owned();
+        let prefixed = bin_workspace(&dir, config, &["src/bin/sol007.rs"]);
+        assert_eq!(prefixed.solutions().unwrap(), [7]);
+    }
+
+    #[test]
+    fn lists_the_expected_candidate_first() {
+        let dir = TempDir::new();
+        let workspace = bin_workspace(
+            &dir,
+            Config::default(),
+            &["src/bin/euler004.rs", "src/bin/p004.rs", "src/bin/p005.rs"],
+        );
+        assert_eq!(
+            workspace.candidates(4).unwrap(),
+            [
+                dir.path().join("src/bin/p004.rs"),
+                dir.path().join("src/bin/euler004.rs")
+            ]
+        );
+        assert!(workspace.candidates(6).unwrap().is_empty());
+    }
+
+    #[test]
+    fn scans_the_workspace_once() {
+        let dir = TempDir::new();
+        let workspace = bin_workspace(&dir, Config::default(), &["src/bin/p001.rs"]);
+        assert_eq!(workspace.candidates(1).unwrap().len(), 1);
+        write(dir.path().join("src/bin/euler002.rs"), "").unwrap();
+        assert!(workspace.candidates(2).unwrap().is_empty());
+        assert_eq!(
+            Workspace::new(dir.path().to_owned(), Config::default())
+                .candidates(2)
+                .unwrap(),
+            [dir.path().join("src/bin/euler002.rs")]
+        );
+    }
+
+    #[test]
+    fn refuses_to_pick_between_duplicate_solutions() {
+        let dir = TempDir::new();
+        let workspace = bin_workspace(
+            &dir,
+            Config::default(),
+            &["src/bin/p042.rs", "src/bin/problem42/main.rs"],
+        );
+        let e = workspace.resolve_solution(42).unwrap_err();
+        let duplicates = e.downcast_ref::<DuplicateSolutions>().unwrap();
+        assert_eq!(duplicates.number, 42);
+        assert_eq!(
+            duplicates.paths,
+            ["src/bin/p042.rs", "src/bin/problem42/main.rs"]
+        );
+        assert_eq!(workspace.resolve_solution(43).unwrap(), None);
+    }
+
+    #[test]
+    fn picks_solutions_by_the_first_glob_matching_exactly_one() {
+        let dir = TempDir::new();
+        let mut config = Config::default();
+        config.layout.prefer = vec![
+            // Matches both, so it can't pick either.
+            "src/bin/**/*.rs".to_owned(),
+            "src/bin/*/main.rs".to_owned(),
+        ];
+        let mut workspace = bin_workspace(
+            &dir,
+            config,
+            &["src/bin/p042.rs", "src/bin/problem42/main.rs"],
+        );
+        assert_eq!(
+            workspace.resolve_solution(42).unwrap(),
+            Some(dir.path().join("src/bin/problem42/main.rs"))
+        );
+        // Globs given on the command line go first.
+        workspace.prefer(vec!["src/bin/p*.rs".to_owned()]);
+        assert_eq!(
+            workspace.resolve_solution(42).unwrap(),
+            Some(dir.path().join("src/bin/p042.rs"))
+        );
+    }
+
+    #[test]
+    fn counts_files_of_the_same_target_once() {
+        let dir = TempDir::new();
+        let mut config = Config::default();
+        config.layout.kind = LayoutKind::CratePerProblem;
+        let workspace = bin_workspace(
+            &dir,
+            config,
+            &["problems/p008/src/lib.rs", "problems/p008/src/main.rs"],
+        );
+        assert_eq!(
+            workspace.resolve_solution(8).unwrap(),
+            Some(dir.path().join("problems/p008/src/main.rs"))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn counts_symlinked_files_once() {
+        let dir = TempDir::new();
+        let workspace = bin_workspace(&dir, Config::default(), &["src/bin/euler007.rs"]);
+        std::os::unix::fs::symlink(
+            dir.path().join("src/bin/euler007.rs"),
+            dir.path().join("src/bin/p007.rs"),
+        )
+        .unwrap();
+        assert_eq!(
+            workspace.resolve_solution(7).unwrap(),
+            Some(dir.path().join("src/bin/p007.rs"))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn skips_symlinked_directories_and_nested_workspaces() {
+        let dir = TempDir::new();
+        let workspace = bin_workspace(
+            &dir,
+            Config::default(),
+            &[
+                "elsewhere/p010.rs",
+                "src/bin/nested/p011.rs",
+                "src/bin/nested/euler.toml",
+                "src/bin/p012.rs",
+            ],
+        );
+        std::os::unix::fs::symlink(
+            dir.path().join("elsewhere"),
+            dir.path().join("src/bin/linked"),
+        )
+        .unwrap();
+        assert_eq!(
+            workspace
+                .scan_solutions()
+                .unwrap()
+                .into_keys()
+                .collect::<Vec<_>>(),
+            [12]
+        );
+    }
+
+    const MANIFEST: &str = r#"# Solutions to Project Euler problems.
+[workspace]
+# Every problem is its own crate.
+members = [
+    # The first few, solved by hand.
+    "problems/p001",
+    "problems/p002", # Fibonacci!
+]
+
+[profile.release]
+debug = true   # for profiling
+"#;
+
+    #[test]
+    fn adds_workspace_members_preserving_everything_else() {
+        let updated = add_workspace_member(MANIFEST, "problems/p101", false)
+            .unwrap()
+            .unwrap();
+        let inserted = "    \"problems/p101\",\n";
+        let at = MANIFEST.find("]\n\n[profile").unwrap();
+        assert_eq!(
+            updated,
+            format!("{}{}{}", &MANIFEST[..at], inserted, &MANIFEST[at..])
+        );
+    }
+
+    #[test]
+    fn adds_workspace_members_to_single_line_arrays() {
+        let manifest = "[workspace] # all of them\nmembers = [\"a\"] # so far\n";
+        assert_eq!(
+            add_workspace_member(manifest, "b", false).unwrap().unwrap(),
+            "[workspace] # all of them\nmembers = [\"a\", \"b\"] # so far\n"
+        );
+    }
+
+    #[test]
+    fn adds_workspace_members_to_empty_arrays() {
+        for (manifest, updated) in &[
+            (
+                "[workspace]\nmembers = []\n",
+                "[workspace]\nmembers = [\"a\"]\n",
+            ),
+            (
+                "[workspace]\nmembers = [\n]\n",
+                "[workspace]\nmembers = [\n    \"a\"\n]\n",
+            ),
+            ("[workspace]\n", "[workspace]\nmembers = [\"a\"]\n"),
+        ] {
+            assert_eq!(
+                add_workspace_member(manifest, "a", false).unwrap().unwrap(),
+                *updated
+            );
+        }
+    }
+
+    #[test]
+    fn leaves_existing_workspace_members_alone() {
+        assert!(add_workspace_member(MANIFEST, "problems/p002", false)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn converts_packages_into_workspaces_only_if_asked() {
+        let manifest = "# Mine\n[package]\nname = \"solutions\"\n";
+        assert!(matches!(
+            add_workspace_member(manifest, "problems/p001", false),
+            Err(MemberRegistrationError::NotAWorkspace)
+        ));
+        let converted = add_workspace_member(manifest, "problems/p001", true)
+            .unwrap()
+            .unwrap();
+        assert!(converted.starts_with(manifest));
+        assert!(converted.ends_with("[workspace]\nmembers = [\"problems/p001\"]\n"));
+    }
+
+    #[test]
+    fn refuses_workspace_members_that_arent_an_array() {
+        assert!(matches!(
+            add_workspace_member("[workspace]\nmembers = \"a\"\n", "b", false),
+            Err(MemberRegistrationError::MembersNotAnArray)
+        ));
+    }
+}